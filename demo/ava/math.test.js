@@ -0,0 +1,13 @@
+import test from "ava";
+
+test("adds", (t) => {
+  t.is(1 + 1, 2);
+});
+
+test.serial("subtracts", (t) => {
+  t.is(1 - 1, 1);
+});
+
+test("resolves", async (t) => {
+  t.is(await Promise.resolve(3), 3);
+});
@@ -0,0 +1,3 @@
+add() {
+    echo $(($1 + $2))
+}
@@ -0,0 +1,18 @@
+#!/usr/bin/env bats
+
+setup() {
+    source "$BATS_TEST_DIRNAME/../src/math.sh"
+}
+
+@test "adds numbers" {
+    [ "$(add 1 1)" -eq 2 ]
+}
+
+@test "fails on purpose" {
+    result="$(add 2 2)"
+    [ "$result" -eq 5 ]
+}
+
+@test "later" {
+    skip "not ready"
+}
@@ -0,0 +1,11 @@
+import { describe, expect, test } from "bun:test";
+
+describe("math", () => {
+  test("adds", () => {
+    expect(1 + 1).toBe(2);
+  });
+
+  test("subtracts", () => {
+    expect(1 - 1).toBe(1);
+  });
+});
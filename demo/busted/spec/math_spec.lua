@@ -0,0 +1,22 @@
+local math_lib = require("src.math")
+
+describe("math", function()
+  it("adds", function()
+    assert.are.equal(3, math_lib.add(1, 2))
+  end)
+
+  it("fails on purpose", function()
+    assert.are.equal(4, math_lib.add(1, 2))
+  end)
+
+  describe("large numbers", function()
+    it('adds large numbers', function()
+      -- it("is not a test in a comment")
+      assert.are.equal(2000000, math_lib.add(1000000, 1000000))
+    end)
+  end)
+end)
+
+it("runs outside of a describe", function()
+  assert.are.equal(1, math_lib.sub(2, 1))
+end)
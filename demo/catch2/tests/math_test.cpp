@@ -0,0 +1,16 @@
+#include <catch2/catch_test_macros.hpp>
+
+int add(int a, int b) { return a + b; }
+
+TEST_CASE("adds", "[math]") {
+    SECTION("small numbers") {
+        REQUIRE(add(1, 1) == 2);
+    }
+    SECTION("fails on purpose") {
+        REQUIRE(add(2, 2) == 5);
+    }
+}
+
+SCENARIO("adding zero") {
+    REQUIRE(add(0, 1) == 1);
+}
@@ -0,0 +1,32 @@
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+fn fibonacci(n: u64) -> u64 {
+    match n {
+        0 | 1 => 1,
+        n => fibonacci(n - 1) + fibonacci(n - 2),
+    }
+}
+
+fn bench_fib(c: &mut Criterion) {
+    c.bench_function("fib 20", |b| b.iter(|| fibonacci(black_box(20))));
+}
+
+fn sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sizes");
+    for size in [10, 15] {
+        group.bench_with_input(BenchmarkId::new("fib", size), &size, |b, &size| {
+            b.iter(|| fibonacci(size));
+        });
+    }
+    group.finish();
+}
+
+fn bench_other(c: &mut Criterion) {
+    let name = String::from("dynamic");
+    c.bench_function(&name, |b| b.iter(|| fibonacci(5)));
+}
+
+criterion_group!(benches, bench_fib, sizes, bench_other);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#include <gtest/gtest.h>
+
+int add(int a, int b) { return a + b; }
+
+TEST(Math, Adds) {
+    EXPECT_EQ(add(2, 2), 4);
+}
+
+TEST(Math, Fails) {
+    EXPECT_EQ(add(2, 2), 5);
+}
@@ -0,0 +1,21 @@
+import 'package:demo/math.dart';
+import 'package:test/test.dart';
+
+void main() {
+  test('adds', () {
+    expect(add(1, 2), equals(3));
+  });
+
+  group('math', () {
+    test('fails on purpose', () {
+      expect(add(1, 2), equals(4));
+    });
+
+    group('large numbers', () {
+      // Doesn't overflow
+      test('adds large numbers (1 + 1000000)', () {
+        expect(add(1000000, 1), equals(1000001));
+      });
+    });
+  });
+}
@@ -0,0 +1,11 @@
+let add a b = a + b
+
+let%test "adds" = add 1 2 = 3
+
+let%test "fails on purpose" = add 1 2 = 4
+
+let%test_unit "unit adds" = assert (add 2 2 = 4)
+
+let%expect_test "prints sum" =
+  print_int (add 1 2);
+  [%expect {| 4 |}]
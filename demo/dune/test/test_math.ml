@@ -0,0 +1,13 @@
+let test_adds () = Alcotest.(check int) "sum" 3 (Math.add 1 2)
+
+let test_fails () = Alcotest.(check int) "sum" 4 (Math.add 1 2)
+
+let () =
+  Alcotest.run "math"
+    [
+      ( "math",
+        [
+          Alcotest.test_case "adds" `Quick test_adds;
+          Alcotest.test_case "fails with alcotest" `Quick test_fails;
+        ] );
+    ]
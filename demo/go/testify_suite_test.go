@@ -0,0 +1,35 @@
+package main
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/suite"
+)
+
+type MySuite struct {
+	suite.Suite
+	value int
+}
+
+func (s *MySuite) SetupTest() {
+	s.value = 1
+}
+
+func (s *MySuite) TestFoo() {
+	s.Equal(1, s.value)
+}
+
+func (s MySuite) TestBar() {
+	s.Equal(2, s.value)
+}
+
+// Not run by any test function in this file
+type OrphanSuite struct {
+	suite.Suite
+}
+
+func (s *OrphanSuite) TestOrphan() {}
+
+func TestMySuite(t *testing.T) {
+	suite.Run(t, &MySuite{})
+}
@@ -0,0 +1,3 @@
+package com.example
+
+fun add(a: Int, b: Int): Int = a + b
@@ -0,0 +1,43 @@
+package com.example;
+
+import static org.junit.jupiter.api.Assertions.assertEquals;
+
+import org.junit.jupiter.api.Nested;
+import org.junit.jupiter.api.Tag;
+import org.junit.jupiter.api.Test;
+
+class CalculatorTest {
+    @Test
+    void adds() {
+        assertEquals(3, MathKt.add(1, 2));
+    }
+
+    @Tag("slow")
+    @Test
+    public void addsManyNumbers() {
+        int sum = 0;
+        for (int i = 0; i < 1000; i++) {
+            sum = MathKt.add(sum, 1);
+        }
+        assertEquals(1000, sum);
+    }
+
+    void helper() {}
+
+    @Nested
+    class Division {
+        @Test
+        @Tag("fast")
+        void dividesEvenly() {
+            assertEquals(2, 4 / 2);
+        }
+
+        @Nested
+        class Remainder {
+            @Test
+            void keepsRemainder() {
+                assertEquals(2, 7 % 4);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+package com.example
+
+import org.junit.jupiter.api.Nested
+import org.junit.jupiter.api.Test
+import kotlin.test.assertEquals
+
+data class Pair(val a: Int, val b: Int)
+
+class MathTest {
+    @Test
+    fun adds() {
+        assertEquals(3, add(1, 2))
+    }
+
+    @Test
+    fun `fails on purpose`() {
+        assertEquals(4, add(1, 2))
+    }
+
+    fun helper() {}
+
+    @Nested
+    inner class LargeNumbers {
+        @Test
+        fun addsLargeNumbers() {
+            assertEquals(1_000_001, add(1_000_000, 1))
+        }
+    }
+}
@@ -0,0 +1,7 @@
+const config = require("./missing-config.json");
+
+describe("import error", () => {
+  test("never runs", () => {
+    expect(config).toBeDefined();
+  });
+});
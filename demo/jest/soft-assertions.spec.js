@@ -0,0 +1,6 @@
+describe("soft", () => {
+  test("checks totals", () => {
+    expect.soft(1 + 1).toBe(3);
+    expect.soft(2 * 2).toBe(5);
+  });
+});
@@ -0,0 +1,7 @@
+module Demo
+
+export add
+
+add(a, b) = a + b
+
+end
@@ -0,0 +1,13 @@
+using Demo
+using Test
+
+@testset "Demo" begin
+    @testset "adds" begin
+        @test add(1, 1) == 2
+    end
+
+    @testset "math" begin
+        @test add(2, 2) == 5
+        @test [1, 2][3] == 1
+    end
+end
@@ -0,0 +1,13 @@
+package com.example;
+
+public final class Calculator {
+    private Calculator() {}
+
+    public static int add(int a, int b) {
+        return a + b;
+    }
+
+    public static int divide(int a, int b) {
+        return a / b;
+    }
+}
@@ -0,0 +1,26 @@
+package com.example;
+
+import static org.junit.jupiter.api.Assertions.assertEquals;
+
+import org.junit.jupiter.api.Nested;
+import org.junit.jupiter.api.Test;
+
+class CalculatorTest {
+    @Test
+    void adds() {
+        assertEquals(3, Calculator.add(1, 2));
+    }
+
+    @Test
+    void failsOnPurpose() {
+        assertEquals(4, Calculator.add(1, 2));
+    }
+
+    @Nested
+    class Division {
+        @Test
+        void dividesEvenly() {
+            assertEquals(2, Calculator.divide(4, 2));
+        }
+    }
+}
@@ -0,0 +1,13 @@
+import unittest
+import demo
+
+test "adds":
+  check add(1, 1) == 2
+
+suite "math":
+  test "fails on purpose":
+    check add(2, 2) == 5
+
+  test "raises":
+    let values = @[1, 2]
+    discard values[add(1, 2)]
@@ -0,0 +1,25 @@
+<?php
+
+namespace Tests;
+
+use App\Calculator;
+use PHPUnit\Framework\Attributes\Test;
+
+trait AddsNumbers
+{
+    #[Test]
+    public function addsZero(): void
+    {
+        $this->assertSame(2, $this->calculator()->add(2, 0));
+    }
+
+    public function testAddsOne(): void
+    {
+        $this->assertSame(3, $this->calculator()->add(2, 1));
+    }
+
+    private function calculator(): Calculator
+    {
+        return new Calculator();
+    }
+}
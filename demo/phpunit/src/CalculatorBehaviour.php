@@ -0,0 +1,20 @@
+<?php
+
+namespace Tests;
+
+use App\Calculator;
+use PHPUnit\Framework\Attributes\CoversClass;
+use PHPUnit\Framework\Attributes\Test;
+use PHPUnit\Framework\TestCase;
+
+#[CoversClass(Calculator::class)]
+final class CalculatorBehaviour extends TestCase
+{
+    use AddsNumbers;
+
+    #[Test]
+    public function subtracts(): void
+    {
+        $this->assertSame(1, (new Calculator())->subtract(3, 2));
+    }
+}
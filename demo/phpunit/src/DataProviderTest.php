@@ -0,0 +1,35 @@
+<?php
+
+namespace Tests;
+
+use App\Calculator;
+use PHPUnit\Framework\Attributes\DataProvider;
+use PHPUnit\Framework\TestCase;
+
+class DataProviderTest extends TestCase
+{
+    public static function additionProvider(): array
+    {
+        return [
+            [0, 0, 0],
+            [0, 1, 1],
+            [1, 1, 3],
+        ];
+    }
+
+    #[DataProvider('additionProvider')]
+    public function testAdd(int $a, int $b, int $expected): void
+    {
+        $calculator = new Calculator();
+        $this->assertSame($expected, $calculator->add($a, $b));
+    }
+
+    /**
+     * @dataProvider additionProvider
+     */
+    public function adds(int $a, int $b, int $expected): void
+    {
+        $calculator = new Calculator();
+        $this->assertSame($expected, $calculator->add($a, $b));
+    }
+}
@@ -0,0 +1,20 @@
+<?php
+
+namespace Tests;
+
+use App\Calculator;
+use PHPUnit\Framework\TestCase;
+
+class ErrorTest extends TestCase
+{
+    public function testDivideByZero()
+    {
+        $calculator = new Calculator();
+        $calculator->divide(1, 0);
+    }
+
+    public function testNotReady()
+    {
+        $this->markTestSkipped('Not implemented yet');
+    }
+}
@@ -0,0 +1,18 @@
+<?php
+
+namespace Tests;
+
+use PHPUnit\Framework\TestCase;
+
+class PendingTest extends TestCase
+{
+    public function testSubtract()
+    {
+        $this->markTestIncomplete('Subtraction is not written yet');
+    }
+
+    public function testNothing()
+    {
+        $calculator = new \App\Calculator();
+    }
+}
@@ -0,0 +1,17 @@
+import { expect, test } from "@playwright/test";
+
+test("has title", async ({ page }) => {
+  await page.setContent("<title>Demo</title>");
+  await expect(page).toHaveTitle("Demo");
+});
+
+test.describe("navigation", () => {
+  test("shows heading", async ({ page }) => {
+    await page.setContent("<h1>Welcome</h1>");
+    await expect(page.getByRole("heading")).toHaveText("Goodbye");
+  });
+
+  test.skip("not ready", async ({ page }) => {
+    await page.goto("/later");
+  });
+});
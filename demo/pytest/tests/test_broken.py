@@ -0,0 +1,5 @@
+import missing
+
+
+def test_never_collected():
+    assert missing.value == 1
@@ -0,0 +1,21 @@
+import pytest
+
+from calc import add, divide, subtract
+
+
+@pytest.mark.parametrize("a, b, expected", [(1, 1, 2), (2, 3, 6)])
+def test_adds(a, b, expected):
+    assert add(a, b) == expected
+
+
+def test_divides():
+    assert divide(6, 3) == 2
+
+
+class TestCalc:
+    def test_subtracts(self):
+        assert subtract(3, 1) == 2
+
+    class TestNegative:
+        def test_keeps_sign(self):
+            assert subtract(1, 3) == 2
@@ -0,0 +1,5 @@
+package com.example
+
+object Math {
+  def add(a: Int, b: Int): Int = a + b
+}
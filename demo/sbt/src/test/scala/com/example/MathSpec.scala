@@ -0,0 +1,31 @@
+package com.example
+
+import org.scalatest.flatspec.AnyFlatSpec
+import org.scalatest.funsuite.AnyFunSuite
+import org.scalatest.wordspec.AnyWordSpec
+
+class MathSpec extends AnyWordSpec {
+  "add" should {
+    "add two numbers" in {
+      assert(Math.add(1, 2) == 3)
+    }
+    "fail on purpose" in {
+      assert(Math.add(1, 2) == 4)
+    }
+  }
+}
+
+class MathFlatSpec extends AnyFlatSpec {
+  "add" should "be commutative" in {
+    assert(Math.add(1, 2) == Math.add(2, 1))
+  }
+  it should "keep zero neutral" in {
+    assert(Math.add(0, 5) == 5)
+  }
+}
+
+class MathSuite extends AnyFunSuite {
+  test("adds") {
+    assert(Math.add(2, 2) == 4)
+  }
+}
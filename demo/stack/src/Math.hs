@@ -0,0 +1,4 @@
+module Math (add) where
+
+add :: Int -> Int -> Int
+add x y = x + y
@@ -0,0 +1,17 @@
+module MathSpec (spec) where
+
+import Test.Hspec
+
+import Math (add)
+
+spec :: Spec
+spec =
+  describe "add" $ do
+    it "adds" $
+      add 1 2 `shouldBe` 3
+    it "fails on purpose" $
+      add 1 2 `shouldBe` 4
+
+    describe "negative" $
+      it "keeps the sign" $
+        add (-1) (-2) `shouldBe` (-3)
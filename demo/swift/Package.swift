@@ -0,0 +1,10 @@
+// swift-tools-version:5.9
+import PackageDescription
+
+let package = Package(
+    name: "Math",
+    targets: [
+        .target(name: "Math"),
+        .testTarget(name: "MathTests", dependencies: ["Math"]),
+    ]
+)
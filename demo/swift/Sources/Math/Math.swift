@@ -0,0 +1,3 @@
+public func add(_ a: Int, _ b: Int) -> Int {
+    a + b
+}
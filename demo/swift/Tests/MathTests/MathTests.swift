@@ -0,0 +1,20 @@
+import XCTest
+@testable import Math
+
+final class MathTests: XCTestCase {
+    func testAdds() {
+        XCTAssertEqual(add(1, 2), 3)
+    }
+
+    func testFails() {
+        XCTAssertEqual(add(1, 2), 4)
+    }
+
+    func helper() {}
+}
+
+class SlowMathTests: XCTestCase {
+    func testLargeNumbers() throws {
+        XCTAssertEqual(add(1_000_000, 1), 1_000_001)
+    }
+}
@@ -0,0 +1,35 @@
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(1, 1 => 2 ; "one plus one")]
+    #[test_case(2, 2 => 5 ; "two plus two")]
+    fn adds(a: u32, b: u32) -> u32 {
+        add(a, b)
+    }
+
+    #[test]
+    fn adds_zero() {
+        assert_eq!(add(0, 0), 0);
+    }
+
+    macro_rules! add_tests {
+        ($($name:ident: $a:expr, $b:expr => $expected:expr;)*) => {
+            $(paste::paste! {
+                #[test]
+                fn [<test_add_ $name>]() {
+                    assert_eq!(add($a, $b), $expected);
+                }
+            })*
+        };
+    }
+
+    add_tests! {
+        small: 1, 2 => 3;
+    }
+}
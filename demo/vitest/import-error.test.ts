@@ -0,0 +1,8 @@
+import { describe, expect, test } from "vitest";
+import config from "./missing-config";
+
+describe("import error", () => {
+  test("never runs", () => {
+    expect(config).toBeDefined();
+  });
+});
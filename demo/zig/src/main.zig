@@ -0,0 +1,23 @@
+const std = @import("std");
+
+pub fn add(a: i32, b: i32) i32 {
+    return a + b;
+}
+
+test "adds" {
+    try std.testing.expectEqual(@as(i32, 3), add(1, 2));
+}
+
+test "fails on purpose" {
+    try std.testing.expectEqual(@as(i32, 4), add(1, 2));
+}
+
+const Nested = struct {
+    test "nested adds" {
+        try std.testing.expect(add(2, 2) == 4);
+    }
+};
+
+test add {
+    try std.testing.expect(add(0, 0) == 0);
+}
@@ -0,0 +1,30 @@
+use std::process::{Command, Output};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, name_filter_args, test_command},
+};
+
+/// Command running the given test files with `bats --formatter tap`.
+pub fn bats_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut bats = test_command(context, command, &["bats", "--formatter", "tap"])?;
+    bats.args(name_filter_args("--filter", filter))
+        .args(extra_args)
+        .args(file_paths);
+    Ok(bats)
+}
+
+/// Run a `bats` command.
+pub fn run_bats(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("bats.log", &output)?;
+    Ok(output)
+}
@@ -0,0 +1,207 @@
+//! Shell tests of bats, the Bash Automated Testing System.
+//!
+//! Tests are the `@test "name" {` blocks of `.bats` files. No Bash grammar is
+//! bundled, so they are found by scanning the source, taking a block to end
+//! at the first `}` indented like its header.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+};
+
+/// Tests of a file, with their name as id.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let test_re = Regex::new(r#"^(\s*)@test\s+(?:"([^"]*)"|'([^']*)')\s*\{"#).unwrap();
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut items = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = test_re.captures(line) else {
+            continue;
+        };
+        let indent = m[1].len();
+        let Some(name) = m.get(2).or_else(|| m.get(3)) else {
+            continue;
+        };
+        let name = name.as_str();
+        let end = if line.trim_end().ends_with('}') {
+            i
+        } else {
+            lines
+                .iter()
+                .enumerate()
+                .skip(i + 1)
+                .find(|(_, line)| {
+                    line.len() - line.trim_start().len() == indent && line.trim() == "}"
+                })
+                .map_or(i, |(end, _)| end)
+        };
+        let line_number = i as u32;
+        let end_line = end as u32;
+        items.push(TestItem {
+            id: name.to_string(),
+            name: name.to_string(),
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line_number, indent as u32),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(
+                Position::new(end_line, 0),
+                Position::new(end_line, lines[end].len() as u32),
+            ),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Directory the tests of a file are run in: the project above the `test`
+/// or `tests` directory holding it, or else the directory of the file.
+fn bats_project_dir(file_path: &str) -> Option<&Path> {
+    let dir = Path::new(file_path).parent()?;
+    match dir.file_name() {
+        Some(name) if name == "test" || name == "tests" => dir.parent(),
+        _ => Some(dir),
+    }
+}
+
+fn diagnostics(output: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        output,
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct BatsRunner;
+
+impl Runner for BatsRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::bats_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_bats(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        Ok(diagnostics(
+            &String::from_utf8_lossy(&output.stdout),
+            context.workspace,
+            file_paths,
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(project) = bats_project_dir(file_path) {
+                map.entry(project.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/bats/test/math.bats").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["adds numbers", "fails on purpose", "later"]);
+        assert_eq!(items[1].start_position.start, Position::new(10, 0));
+        assert_eq!(items[1].end_position.end, Position::new(13, 1));
+
+        let source = "@test 'one line' { true; }\n  @test \"nested\" {\n    false\n  }\n";
+        let items = discover_in_source("t.bats", source);
+        assert_eq!(items[0].id, "one line");
+        assert_eq!(items[0].end_position.end.line, 0);
+        assert_eq!(items[1].end_position.end, Position::new(3, 3));
+
+        assert_eq!(
+            bats_project_dir("/project/test/math.bats"),
+            Some(Path::new("/project"))
+        );
+        assert_eq!(
+            bats_project_dir("/project/spec/math.bats"),
+            Some(Path::new("/project/spec"))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    tap::{self, TapResult},
+    workspace::{resolve_path, same_path},
+};
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("bats".to_string()),
+        code: Some(NumberOrString::String("bats-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Message of a failed test: the comments bats printed after it, like the
+/// failed command and the output of the test, without the parenthesized
+/// location.
+fn bats_message(failure: &TapResult) -> String {
+    let mut lines = vec![];
+    let mut in_location = false;
+    for comment in &failure.comments {
+        if comment.starts_with('(') {
+            in_location = true;
+        }
+        if in_location {
+            in_location = !comment.ends_with(')');
+            continue;
+        }
+        lines.push(comment.as_str());
+    }
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let message = lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    match message.trim() {
+        "" => "test failed".to_string(),
+        message => message.to_string(),
+    }
+}
+
+/// Diagnostics for the failed tests of the TAP output of bats.
+///
+/// Failures are placed at the line given by the `in test file test.bats,
+/// line 9` comment when it resolves against `root` to one of `file_paths`,
+/// and linked to the definition of the test. Other failures are placed at
+/// the definition, found by the test name.
+#[must_use]
+pub fn to_diagnostics(
+    contents: &str,
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let location_re = Regex::new(r"in test file (.+?), line (\d+)").unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in tap::parse_tap(contents)
+        .iter()
+        .flat_map(TapResult::failures)
+    {
+        let test_item = test_items
+            .iter()
+            .find(|item| item.id == failure.description);
+        let message = bats_message(failure);
+        let location = location_re
+            .captures(&failure.comments.join("\n"))
+            .and_then(|m| {
+                let resolved = resolve_path(root, &m[1]).to_string_lossy().to_string();
+                let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+                Some((path.clone(), m[2].parse::<u32>().ok()?.saturating_sub(1)))
+            });
+
+        if let Some((path, line_number)) = location {
+            let range = Range::new(
+                Position::new(line_number, 0),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map.entry(path).or_default().push(Diagnostic {
+                related_information: test_item
+                    .and_then(TestItem::definition_info)
+                    .map(|info| vec![info]),
+                ..error_diagnostic(range, message)
+            });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!("`{}` failed\n{message}", failure.description),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bats_tap() {
+        let contents = std::fs::read_to_string("demo/bats/tap-output.txt").unwrap();
+        let root = Path::new("/home/demo/bats");
+        let file_path = "/home/demo/bats/test/math.bats".to_string();
+        let test_item = TestItem {
+            id: "fails on purpose".to_string(),
+            name: "fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(10, 0), Position::new(10, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(13, 0), Position::new(13, 1)),
+        };
+        let diagnostics = to_diagnostics(
+            &contents,
+            root,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostics.files[0].diagnostics.len(), 1);
+        assert_eq!(diagnostic.range.start, Position::new(12, 0));
+        assert_eq!(diagnostic.message, "`[ \"$result\" -eq 5 ]' failed");
+        assert!(diagnostic.related_information.is_some());
+
+        // A failure in a helper spans two location lines, and one outside the
+        // tested files is placed at the test
+        let contents = "1..1\nnot ok 1 fails on purpose\n# (from function `assert_equal' in file \
+                        test/test_helper/assert.bash, line 40,\n#  in test file \
+                        /elsewhere/math.bats, line 13)\n#   `assert_equal 4 5' failed\n#\n# \
+                        expected : 5\n";
+        let diagnostics = to_diagnostics(contents, root, &[file_path], &[test_item]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(10, 0));
+        assert_eq!(
+            diagnostic.message,
+            "`fails on purpose` failed\n`assert_equal 4 5' failed\n\nexpected : 5"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use crate::{
+    config,
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Test binary run when the adapter configures no `command`, relative to the
+/// workspace.
+const DEFAULT_BINARY: &str = "build/tests";
+
+/// JUnit report written by the test binary.
+#[must_use]
+pub fn results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("catch2.xml")
+}
+
+/// Whether any of the files includes the doctest header rather than Catch2.
+fn uses_doctest(file_paths: &[String]) -> bool {
+    file_paths
+        .iter()
+        .any(|path| std::fs::read_to_string(path).is_ok_and(|source| source.contains("doctest.h")))
+}
+
+/// Command running an already built Catch2 or doctest binary, the adapter's
+/// `command`, writing a JUnit report to the cache directory. A filter selects
+/// the tests whose name contains it.
+pub fn catch2_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut binary = test_command(context, command, &[DEFAULT_BINARY])?;
+    let results_path = results_path().to_string_lossy().to_string();
+    if uses_doctest(file_paths) {
+        binary
+            .arg("--reporters=junit")
+            .arg(format!("--out={results_path}"));
+        if let Some(filter) = filter {
+            binary.arg(format!("--test-case=*{filter}*"));
+        }
+    } else {
+        binary
+            .args(["--reporter", "junit", "--out"])
+            .arg(results_path);
+        if let Some(filter) = filter {
+            binary.arg(format!("*{filter}*"));
+        }
+    }
+    binary.args(extra_args);
+    Ok(binary)
+}
+
+/// Run a Catch2 or doctest binary.
+pub fn run_catch2(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("catch2.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch2_command() {
+        let sh = ["sh".to_string()];
+        let args = |file_paths: &[String]| {
+            let command = catch2_command(
+                &RunContext::new("/"),
+                file_paths,
+                &[],
+                Some(&sh),
+                Some("adds"),
+            )
+            .unwrap();
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+        let results_path = results_path().to_string_lossy().to_string();
+        assert_eq!(
+            args(&["demo/catch2/tests/math_test.cpp".to_string()]),
+            vec!["--reporter", "junit", "--out", &results_path, "*adds*"]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let doctest_file = dir.path().join("math_test.cpp");
+        std::fs::write(&doctest_file, "#include <doctest/doctest.h>\n").unwrap();
+        assert_eq!(
+            args(&[doctest_file.to_string_lossy().to_string()]),
+            vec![
+                "--reporters=junit".to_string(),
+                format!("--out={results_path}"),
+                "--test-case=*adds*".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! C++ tests of Catch2 or doctest, run by calling the built test binary.
+//!
+//! Tests are the `TEST_CASE(...)`, `SCENARIO(...)` and fixture test case
+//! macros of a file, and the `SECTION(...)` or `SUBCASE(...)` blocks in them,
+//! named like Catch2 names them in its JUnit report: the test case and its
+//! sections joined by slashes. No C++ grammar is bundled, so macros are found
+//! by scanning the source, balancing braces to find where their blocks end.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    cmake::{build_dir, cmake_root},
+    error::LSError,
+    junit::parse_junit,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+};
+
+/// Tests of a file, with ids like `adds`, `adds/small numbers` for a
+/// section, or `Scenario: adding zero`.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let macro_re = Regex::new(
+        r#"\b(TEST_CASE|SCENARIO|TEST_CASE_METHOD|TEST_CASE_FIXTURE|SECTION|SUBCASE)\s*\(\s*(?:\w+\s*,\s*)?"([^"]*)""#,
+    )
+    .unwrap();
+
+    // Name, start and end of each macro
+    let macros: Vec<(String, usize, usize)> = macro_re
+        .captures_iter(source)
+        .map(|m| {
+            let found = m.get(0).unwrap();
+            let name = match &m[1] {
+                "SCENARIO" => format!("Scenario: {}", &m[2]),
+                _ => m[2].to_string(),
+            };
+            (name, found.start(), block_end(source, found.end()))
+        })
+        .collect();
+
+    let mut items = vec![];
+    for (name, start, end) in &macros {
+        let mut names: Vec<&str> = macros
+            .iter()
+            .filter(|(_, outer_start, outer_end)| outer_start < start && end <= outer_end)
+            .map(|(name, _, _)| name.as_str())
+            .collect();
+        names.push(name);
+        let id = names.join("/");
+        let (line, column) = position_at(source, *start);
+        let end = position_at(source, *end);
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct Catch2Runner;
+
+impl Runner for Catch2Runner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::catch2_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_catch2(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(&call::results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = parse_junit(&std::fs::read_to_string(results_path)?)?;
+        let build_dir = build_dir(workspace);
+        Ok(parse::to_diagnostics(
+            &failures,
+            &[Path::new(workspace), &build_dir],
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    /// Files are grouped by their CMake project, or by their directory when
+    /// they aren't in one and the adapter's `command` names the binary.
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            let root = cmake_root(file_path)
+                .or_else(|| Path::new(file_path).parent().map(Path::to_path_buf));
+            if let Some(root) = root {
+                map.entry(root.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/catch2/tests/math_test.cpp").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "adds",
+                "adds/small numbers",
+                "adds/fails on purpose",
+                "Scenario: adding zero"
+            ]
+        );
+        assert_eq!(items[2].start_position.start, Position::new(8, 4));
+        assert_eq!(items[2].end_position.end, Position::new(10, 5));
+        assert_eq!(items[0].end_position.end.line, 11);
+
+        let source = "TEST_CASE_FIXTURE(Db, \"reads\") {\n    SUBCASE(\"empty\") {}\n}\n";
+        let items = discover_in_source("db_test.cpp", source);
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["reads", "reads/empty"]);
+
+        let workspaces = Catch2Runner.detect_workspaces(&[std::env::current_dir()
+            .unwrap()
+            .join("demo/catch2/tests/math_test.cpp")
+            .to_string_lossy()
+            .to_string()]);
+        assert!(
+            workspaces
+                .map
+                .keys()
+                .all(|root| root.ends_with("demo/catch2"))
+        );
+    }
+}
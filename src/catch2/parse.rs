@@ -0,0 +1,175 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    junit::JUnitFailure,
+    workspace::{contains_path, resolve_path, same_path},
+};
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("catch2".to_string()),
+        code: Some(NumberOrString::String("catch2-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for the failed tests of a JUnit report of Catch2 or doctest.
+///
+/// Failures are placed at the assertion, given as `at file:line` by Catch2
+/// and as `file(line):` by doctest, when it resolves against one of `roots`
+/// to one of `file_paths`, and linked to the definition of the test. Other
+/// failures are placed at the definition, found by the test name, which
+/// Catch2 joins with its sections like `adds/small numbers`.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[JUnitFailure],
+    roots: &[&Path],
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let location_re =
+        Regex::new(r"^\s*(?:at\s+)?(\S+\.(?:c|cc|cpp|cxx|h|hh|hpp|hxx))(?::(\d+)|\((\d+)\)):?\s*$")
+            .unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        // JUnit names are cut at a parenthesis when parsed, so are the ids
+        let test_item = test_items
+            .iter()
+            .find(|item| item.id.split('(').next() == Some(failure.name.as_str()));
+        let mut location = None;
+        let mut message_lines = vec![];
+        for line in failure.stack_trace.lines() {
+            let Some(m) = location_re.captures(line) else {
+                if !line.trim().is_empty() {
+                    message_lines.push(line.trim_end());
+                }
+                continue;
+            };
+            let found = roots
+                .iter()
+                .map(|root| resolve_path(root, &m[1]).to_string_lossy().to_string())
+                .find(|path| contains_path(file_paths, path));
+            let line_number = m
+                .get(2)
+                .or_else(|| m.get(3))
+                .and_then(|line| line.as_str().parse::<u32>().ok());
+            if let (None, Some(path), Some(line_number)) = (&location, found, line_number) {
+                location = Some((path, line_number.saturating_sub(1)));
+            }
+        }
+        let message = if message_lines.is_empty() {
+            failure.message.clone()
+        } else {
+            dedent(&message_lines)
+        };
+
+        if let Some((path, line_number)) = location {
+            let range = Range::new(
+                Position::new(line_number, 0),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map.entry(path).or_default().push(Diagnostic {
+                related_information: test_item
+                    .and_then(TestItem::definition_info)
+                    .map(|info| vec![info]),
+                ..error_diagnostic(range, message)
+            });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!("`{}` failed\n{message}", failure.name),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+/// Lines with the indentation they all share removed.
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| &line[indent..])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::junit::parse_junit;
+
+    #[test]
+    fn test_parse_catch2_junit() {
+        let contents = std::fs::read_to_string("demo/catch2/junit.xml").unwrap();
+        let failures = parse_junit(&contents).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "adds/fails on purpose");
+
+        let root = Path::new("/home/demo/catch2");
+        let build_dir = root.join("build");
+        let file_path = "/home/demo/catch2/tests/math_test.cpp".to_string();
+        let test_item = TestItem {
+            id: "adds/fails on purpose".to_string(),
+            name: "adds/fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(8, 4), Position::new(8, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(10, 0), Position::new(10, 5)),
+        };
+        let diagnostics = to_diagnostics(
+            &failures,
+            &[root, &build_dir],
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(9, 0));
+        assert_eq!(
+            diagnostic.message,
+            "FAILED:\n  REQUIRE( add(2, 2) == 5 )\nwith expansion:\n  4 == 5"
+        );
+        assert!(diagnostic.related_information.is_some());
+
+        // doctest gives the location as `file(line):`, here one not found
+        let failures = [JUnitFailure {
+            stack_trace: "/elsewhere/math_test.cpp(10):\nCHECK( 4 == 5 ) is NOT correct!\n"
+                .to_string(),
+            ..failures[0].clone()
+        }];
+        let diagnostics = to_diagnostics(&failures, &[root], &[file_path], &[test_item]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(8, 4));
+        assert_eq!(
+            diagnostic.message,
+            "`adds/fails on purpose` failed\nCHECK( 4 == 5 ) is NOT correct!"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+//! CMake projects: their root and the build directory their tests are
+//! configured in, shared by the runners of C and C++ tests.
+
+use std::path::{Path, PathBuf};
+
+/// Usual CMake build directories, searched after the workspace itself.
+const BUILD_DIRS: &[&str] = &[
+    "build",
+    "cmake-build-debug",
+    "cmake-build-release",
+    "out/build",
+];
+
+/// Outermost directory of the CMake project containing a file, found by
+/// walking up through directories with a `CMakeLists.txt`.
+#[must_use]
+pub fn cmake_root(file_path: &str) -> Option<PathBuf> {
+    let nearest = Path::new(file_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("CMakeLists.txt").exists())?;
+    nearest
+        .ancestors()
+        .take_while(|dir| dir.join("CMakeLists.txt").exists())
+        .last()
+        .map(Path::to_path_buf)
+}
+
+/// Directory CTest runs in: the first of the workspace and its usual build
+/// directories that CMake configured for testing.
+#[must_use]
+pub fn build_dir(workspace: &str) -> PathBuf {
+    let workspace = Path::new(workspace);
+    std::iter::once(workspace.to_path_buf())
+        .chain(BUILD_DIRS.iter().map(|dir| workspace.join(dir)))
+        .find(|dir| dir.join("CTestTestfile.cmake").exists())
+        .unwrap_or_else(|| workspace.join("build"))
+}
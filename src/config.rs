@@ -1,24 +1,82 @@
-use std::{collections::HashMap, path::PathBuf, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
 use clap::Parser;
+use ignore::overrides::OverrideBuilder;
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
 use serde::{Deserialize, Serialize};
 
-use crate::AdapterId;
+use crate::{AdapterId, coverage, dotenv, runner, workspace};
 
-pub static CONFIG: LazyLock<Config> = LazyLock::new(Config::parse);
+/// Configuration from the command line. Unit tests use the defaults, as the
+/// arguments of the test binary, like a test name filter, would otherwise be
+/// taken as the cache directory runners write their logs and reports in.
+pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
+    if cfg!(test) {
+        Config::default()
+    } else {
+        Config::parse()
+    }
+});
 
 /// Configuration for a test adapter.
 #[derive(Debug, Deserialize, Clone, Serialize, Default)]
 pub struct AdapterConfig {
-    /// Test runner kind (e.g., "cargo-test", "cargo-nextest", "jest", "vitest",
-    /// "go-test", "phpunit", "node-test", "deno")
+    /// Test runner kind (e.g., "cargo-test", "cargo-nextest",
+    /// "cargo-criterion", "jest", "vitest", "go-test", "phpunit",
+    /// "node-test", "deno", "bun-test", "ava", "ctest", "catch2", "playwright",
+    /// "swift-test", "gradle-test", "maven-test", "dart-test", "zig-test",
+    /// "dune-test", "busted", "stack-test", "cabal-test", "sbt-test",
+    /// "nim-test", "julia-test", "bats", "pytest")
     pub test_kind: String,
+    /// Program and leading arguments replacing the default test command (e.g.
+    /// `["./node_modules/.bin/jest"]` or `["cargo", "+nightly", "test"]`)
+    pub command: Option<Vec<String>>,
     /// Extra arguments passed to the test command
     #[serde(default)]
     pub extra_arg: Vec<String>,
+    /// Cargo features enabled for the test run (cargo runners)
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// Disable the default cargo features (cargo runners)
+    #[serde(default)]
+    pub no_default_features: bool,
+    /// Build profile the tests run in (e.g. "release"), for cargo-test,
+    /// cargo-nextest, swift-test (`-c`) and ctest (`-C`)
+    pub profile: Option<String>,
+    /// Cargo target the tests run in, for cargo-test and cargo-nextest:
+    /// "lib", "bins", "tests", "bin:<name>" or "test:<name>"
+    pub cargo_target: Option<String>,
+    /// Directory cargo builds in, set as `CARGO_TARGET_DIR` of cargo runners.
+    /// A relative directory is resolved against the project directory.
+    pub target_dir: Option<String>,
+    /// Change in percent beyond which cargo-criterion reports a benchmark as
+    /// regressed, passed to criterion as its noise threshold
+    pub regression_threshold: Option<f64>,
+    /// Number of tests run in parallel, passed as the flag of the runner
+    /// (e.g. `--test-threads` or `--maxWorkers`). 0 keeps its default.
+    pub jobs: Option<usize>,
+    /// Seconds after which go-test stops a test run, passed as `-timeout`
+    /// (60 by default)
+    pub timeout_secs: Option<u64>,
+    /// Run cargo-test under `cargo llvm-cov`, or deno with `--coverage`, and
+    /// send the coverage of each run in a `$/coverage` notification
+    #[serde(default)]
+    pub coverage: bool,
+    /// Publish warnings the test run printed to stderr, like compiler
+    /// warnings of cargo runners, as warning diagnostics
+    #[serde(default)]
+    pub surface_warnings: bool,
     /// Environment variables for the test process
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Dotenv file with more environment variables for the test process,
+    /// relative to the project directory. Variables in `env` take precedence.
+    pub env_file: Option<String>,
     /// Glob patterns for files to include
     #[serde(default)]
     pub include: Vec<String>,
@@ -27,23 +85,440 @@ pub struct AdapterConfig {
     pub exclude: Vec<String>,
     /// Override workspace directory
     pub workspace_dir: Option<String>,
+    /// Directory the test command runs in instead of the workspace, with the
+    /// placeholders `{workspace}` and `{file_dir}`, the directory of the first
+    /// file run. A relative directory is resolved against the workspace.
+    pub run_dir_template: Option<String>,
+    /// Severity of the diagnostics of a test status ("failed", "ignored" or
+    /// "skipped"), by name ("error", "warning", "information" or "hint")
+    #[serde(default)]
+    pub severity_overrides: HashMap<String, String>,
+    /// Source of the adapter's diagnostics instead of the runner's own, like
+    /// "cargo-test", to tell adapters of the same kind apart
+    pub source_label: Option<String>,
+}
+
+/// Test kinds run by cargo, which take its feature selection.
+const CARGO_KINDS: &[&str] = &["cargo-test", "cargo-nextest", "cargo-criterion"];
+
+/// Test kinds whose build profile can be chosen.
+const PROFILE_KINDS: &[&str] = &["cargo-test", "cargo-nextest", "swift-test", "ctest"];
+
+/// Test kinds taking a number of parallel jobs, with the flag it follows.
+const JOBS_FLAGS: &[(&str, &str)] = &[
+    ("cargo-test", "--test-threads="),
+    ("cargo-nextest", "--test-threads="),
+    ("jest", "--maxWorkers="),
+    ("vitest", "--maxWorkers="),
+    ("go-test", "-p="),
+    ("ctest", "-j"),
+    ("dune-test", "-j"),
+    ("playwright", "--workers="),
+    ("ava", "--concurrency="),
+    ("node-test", "--test-concurrency="),
+];
+
+/// Cargo target selections taking no name, and those taking one after a
+/// colon, like `test:integration`.
+const CARGO_TARGETS: &[&str] = &["lib", "bins", "tests"];
+const NAMED_CARGO_TARGETS: &[&str] = &["bin", "test"];
+
+/// Cargo arguments selecting a target, like `--test integration` for
+/// `test:integration`.
+fn cargo_target_args(target: &str) -> Option<Vec<String>> {
+    match target.split_once(':') {
+        None if CARGO_TARGETS.contains(&target) => Some(vec![format!("--{target}")]),
+        Some((kind, name)) if NAMED_CARGO_TARGETS.contains(&kind) && !name.is_empty() => {
+            Some(vec![format!("--{kind}"), name.to_string()])
+        }
+        _ => None,
+    }
+}
+
+/// Test statuses whose diagnostic severity can be overridden.
+const SEVERITY_STATUSES: &[&str] = &["failed", "ignored", "skipped"];
+
+fn severity_from_name(name: &str) -> Option<DiagnosticSeverity> {
+    match name.to_ascii_lowercase().as_str() {
+        "error" => Some(DiagnosticSeverity::ERROR),
+        "warning" => Some(DiagnosticSeverity::WARNING),
+        "information" | "info" => Some(DiagnosticSeverity::INFORMATION),
+        "hint" => Some(DiagnosticSeverity::HINT),
+        _ => None,
+    }
+}
+
+/// Test status reported by a diagnostic, judged by its code for ignored and
+/// skipped tests, and otherwise by its severity.
+fn diagnostic_status(diagnostic: &Diagnostic) -> Option<&'static str> {
+    let code = match &diagnostic.code {
+        Some(NumberOrString::String(code)) => code.as_str(),
+        _ => "",
+    };
+    if code.ends_with("-skipped") {
+        Some("skipped")
+    } else if code.ends_with("-ignored") {
+        Some("ignored")
+    } else if diagnostic.severity == Some(DiagnosticSeverity::ERROR) {
+        Some("failed")
+    } else {
+        None
+    }
 }
 
 impl AdapterConfig {
-    /// Validate configuration and return warnings.
+    /// Arguments passed to the test command: the cargo feature and target
+    /// selection of cargo runners, the build profile and the number of
+    /// parallel jobs, followed by `extra_arg`, the criterion noise threshold
+    /// and, for cargo-test, whose test harness takes it, the number of jobs.
+    #[must_use]
+    pub fn test_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if CARGO_KINDS.contains(&self.test_kind.as_str()) {
+            if !self.features.is_empty() {
+                args.push("--features".to_string());
+                args.push(self.features.join(","));
+            }
+            if self.no_default_features {
+                args.push("--no-default-features".to_string());
+            }
+        }
+        if let Some(target) = &self.cargo_target
+            && matches!(self.test_kind.as_str(), "cargo-test" | "cargo-nextest")
+        {
+            args.extend(cargo_target_args(target).unwrap_or_default());
+        }
+        if let Some(profile) = &self.profile {
+            match self.test_kind.as_str() {
+                "cargo-test" | "cargo-nextest" if profile == "release" => {
+                    args.push("--release".to_string());
+                }
+                "cargo-test" | "cargo-nextest" => {
+                    args.extend(["--profile".to_string(), profile.clone()]);
+                }
+                "swift-test" => args.extend(["-c".to_string(), profile.clone()]),
+                "ctest" => args.extend(["-C".to_string(), profile.clone()]),
+                _ => {}
+            }
+        }
+        if let Some(timeout) = self.timeout_secs
+            && self.test_kind == "go-test"
+        {
+            args.push(format!("-timeout={timeout}s"));
+        }
+        let jobs_arg = self.jobs.filter(|jobs| *jobs > 0).and_then(|jobs| {
+            JOBS_FLAGS
+                .iter()
+                .find(|(test_kind, _)| *test_kind == self.test_kind)
+                .map(|(_, flag)| format!("{flag}{jobs}"))
+        });
+        if self.test_kind != "cargo-test" {
+            args.extend(jobs_arg.clone());
+        }
+        args.extend(self.extra_arg.iter().cloned());
+        if let Some(threshold) = self.regression_threshold
+            && self.test_kind == "cargo-criterion"
+        {
+            if !args.iter().any(|arg| arg == "--") {
+                args.push("--".to_string());
+            }
+            args.push("--noise-threshold".to_string());
+            args.push((threshold / 100.0).to_string());
+        }
+        if let Some(jobs_arg) = jobs_arg
+            && self.test_kind == "cargo-test"
+        {
+            if !args.iter().any(|arg| arg == "--") {
+                args.push("--".to_string());
+            }
+            args.push(jobs_arg);
+        }
+        args
+    }
+
+    /// Command replacing the default one to collect coverage into an LCOV
+    /// report at `lcov_path`, or into the raw coverage directory next to it
+    /// for runners converting it afterwards, when coverage is enabled.
+    #[must_use]
+    pub fn coverage_command(&self, lcov_path: &Path) -> Option<Vec<String>> {
+        if !self.coverage {
+            return None;
+        }
+        match self.test_kind.as_str() {
+            "cargo-test" => Some(vec![
+                "cargo".to_string(),
+                "llvm-cov".to_string(),
+                "--lcov".to_string(),
+                "--output-path".to_string(),
+                lcov_path.to_string_lossy().to_string(),
+            ]),
+            "deno" => Some(vec![
+                "deno".to_string(),
+                "test".to_string(),
+                format!("--coverage={}", coverage::raw_dir(lcov_path).display()),
+            ]),
+            _ => None,
+        }
+    }
+
+    /// Command converting the raw coverage of a run into the LCOV report at
+    /// `lcov_path`, for runners that don't write LCOV themselves.
+    #[must_use]
+    pub fn coverage_report_command(&self, lcov_path: &Path) -> Option<Vec<String>> {
+        (self.coverage && self.test_kind == "deno").then(|| {
+            vec![
+                "deno".to_string(),
+                "coverage".to_string(),
+                "--lcov".to_string(),
+                format!("--output={}", lcov_path.display()),
+                coverage::raw_dir(lcov_path).to_string_lossy().to_string(),
+            ]
+        })
+    }
+
+    /// Environment variables of the test process: those of `env_file`, when
+    /// it can be read, overridden by `env`, and the `CARGO_TARGET_DIR` of
+    /// `target_dir`.
+    #[must_use]
+    pub fn test_env(&self, project_dir: &Path) -> HashMap<String, String> {
+        let mut env = self
+            .env_file
+            .as_ref()
+            .and_then(|env_file| {
+                std::fs::read_to_string(workspace::resolve_path(project_dir, env_file)).ok()
+            })
+            .map(|contents| dotenv::parse(&contents))
+            .unwrap_or_default();
+        env.extend(self.env.clone());
+        if let Some(target_dir) = &self.target_dir
+            && CARGO_KINDS.contains(&self.test_kind.as_str())
+        {
+            env.insert(
+                "CARGO_TARGET_DIR".to_string(),
+                workspace::resolve_path(project_dir, target_dir)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+        env
+    }
+
+    /// Directory of `run_dir_template` for running `file_paths` of
+    /// `workspace`, when one is configured.
+    #[must_use]
+    pub fn run_dir(&self, workspace: &str, file_paths: &[String]) -> Option<PathBuf> {
+        let template = self.run_dir_template.as_ref()?;
+        let file_dir = file_paths
+            .first()
+            .and_then(|path| Path::new(path).parent())
+            .map_or_else(
+                || workspace.to_string(),
+                |dir| dir.to_string_lossy().to_string(),
+            );
+        let dir = template
+            .replace("{workspace}", workspace)
+            .replace("{file_dir}", &file_dir);
+        Some(workspace::resolve_path(Path::new(workspace), &dir))
+    }
+
+    /// Context the test commands of `file_paths` of `workspace` run in: the
+    /// directory of [`Self::run_dir`] and the variables of [`Self::test_env`].
+    #[must_use]
+    pub fn run_context<'a>(
+        &self,
+        workspace: &'a str,
+        file_paths: &[String],
+        project_dir: &Path,
+    ) -> runner::RunContext<'a> {
+        runner::RunContext {
+            workspace,
+            run_dir: self.run_dir(workspace, file_paths),
+            env: self.test_env(project_dir),
+            test_ids: None,
+        }
+    }
+
+    /// Leave out the diagnostics of stderr warnings unless they're surfaced.
+    #[must_use]
+    pub fn filter_warnings(&self, mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        if !self.surface_warnings {
+            diagnostics.retain(|diagnostic| {
+                diagnostic.code
+                    != Some(NumberOrString::String(
+                        runner::STDERR_WARNING_CODE.to_string(),
+                    ))
+            });
+        }
+        diagnostics
+    }
+
+    /// Change the severity of diagnostics whose test status has an override.
     #[must_use]
-    pub fn validate(&self, adapter_id: &str) -> Vec<String> {
+    pub fn apply_severity_overrides(&self, mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        if self.severity_overrides.is_empty() {
+            return diagnostics;
+        }
+        for diagnostic in &mut diagnostics {
+            let severity = diagnostic_status(diagnostic)
+                .and_then(|status| self.severity_overrides.get(status))
+                .and_then(|name| severity_from_name(name));
+            if let Some(severity) = severity {
+                diagnostic.severity = Some(severity);
+            }
+        }
+        diagnostics
+    }
+
+    /// Apply the severity overrides and the source label to diagnostics.
+    #[must_use]
+    pub fn apply_overrides(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        self.apply_source_label(self.apply_severity_overrides(diagnostics))
+    }
+
+    /// Give diagnostics the configured `source_label` as their source.
+    #[must_use]
+    pub fn apply_source_label(&self, mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        if let Some(label) = &self.source_label {
+            for diagnostic in &mut diagnostics {
+                diagnostic.source = Some(label.clone());
+            }
+        }
+        diagnostics
+    }
+
+    /// Warnings for options set for a test kind they don't apply to, or set
+    /// to invalid values.
+    fn kind_option_warnings(&self, adapter_id: &str) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        let is_cargo = CARGO_KINDS.contains(&self.test_kind.as_str());
+        if !is_cargo && (!self.features.is_empty() || self.no_default_features) {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': features and no_default_features only apply to {}, not \
+                 '{}'",
+                CARGO_KINDS.join(", "),
+                self.test_kind
+            ));
+        }
+        if !is_cargo && self.target_dir.is_some() {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': target_dir only applies to {}, not '{}'",
+                CARGO_KINDS.join(", "),
+                self.test_kind
+            ));
+        }
+
+        match self.regression_threshold {
+            Some(_) if self.test_kind != "cargo-criterion" => warnings.push(format!(
+                "Adapter '{adapter_id}': regression_threshold only applies to cargo-criterion, \
+                 not '{}'",
+                self.test_kind
+            )),
+            Some(threshold) if threshold < 0.0 => warnings.push(format!(
+                "Adapter '{adapter_id}': regression_threshold must be a non-negative percentage, \
+                 not {threshold}"
+            )),
+            _ => {}
+        }
+
+        if self.timeout_secs.is_some() && self.test_kind != "go-test" {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': timeout_secs only applies to go-test, not '{}'",
+                self.test_kind
+            ));
+        }
+
+        if self.coverage && !matches!(self.test_kind.as_str(), "cargo-test" | "deno") {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': coverage only applies to cargo-test, deno, not '{}'",
+                self.test_kind
+            ));
+        } else if self.coverage && self.command.is_some() {
+            let runs = if self.test_kind == "deno" {
+                "`deno test --coverage`"
+            } else {
+                "`cargo llvm-cov`"
+            };
+            warnings.push(format!(
+                "Adapter '{adapter_id}': coverage runs {runs}, so command is ignored"
+            ));
+        }
+
+        match &self.cargo_target {
+            Some(_) if !matches!(self.test_kind.as_str(), "cargo-test" | "cargo-nextest") => {
+                warnings.push(format!(
+                    "Adapter '{adapter_id}': cargo_target only applies to cargo-test, \
+                     cargo-nextest, not '{}'",
+                    self.test_kind
+                ));
+            }
+            Some(target) if cargo_target_args(target).is_none() => warnings.push(format!(
+                "Adapter '{adapter_id}': invalid cargo_target '{target}', expected one of lib, \
+                 bins, tests, bin:<name> or test:<name>"
+            )),
+            _ => {}
+        }
+
+        if self.profile.is_some() && !PROFILE_KINDS.contains(&self.test_kind.as_str()) {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': profile only applies to {}, not '{}'",
+                PROFILE_KINDS.join(", "),
+                self.test_kind
+            ));
+        }
+
+        if self.jobs.is_some_and(|jobs| jobs > 0)
+            && !JOBS_FLAGS
+                .iter()
+                .any(|(test_kind, _)| *test_kind == self.test_kind)
+        {
+            let kinds: Vec<&str> = JOBS_FLAGS.iter().map(|(test_kind, _)| *test_kind).collect();
+            warnings.push(format!(
+                "Adapter '{adapter_id}': jobs only applies to {}, not '{}'",
+                kinds.join(", "),
+                self.test_kind
+            ));
+        }
+
+        warnings
+    }
+
+    /// Validate configuration and return warnings. A relative `workspace_dir`
+    /// is resolved against `project_dir`.
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn validate(&self, adapter_id: &str, project_dir: &Path) -> Vec<String> {
         let mut warnings = Vec::new();
 
         let valid_kinds = [
             "cargo-test",
             "cargo-nextest",
+            "cargo-criterion",
             "jest",
             "vitest",
             "go-test",
             "phpunit",
             "node-test",
             "deno",
+            "bun-test",
+            "ava",
+            "ctest",
+            "catch2",
+            "playwright",
+            "swift-test",
+            "gradle-test",
+            "maven-test",
+            "dart-test",
+            "zig-test",
+            "dune-test",
+            "busted",
+            "stack-test",
+            "cabal-test",
+            "sbt-test",
+            "nim-test",
+            "julia-test",
+            "bats",
+            "pytest",
         ];
         if !valid_kinds.contains(&self.test_kind.as_str()) {
             warnings.push(format!(
@@ -53,10 +528,142 @@ impl AdapterConfig {
             ));
         }
 
+        for include in &self.include {
+            if let Some(exclude) = self
+                .exclude
+                .iter()
+                .find(|exclude| glob_covers(exclude, include))
+            {
+                warnings.push(format!(
+                    "Adapter '{adapter_id}': include pattern '{include}' is shadowed by exclude \
+                     pattern '{exclude}', so it can never match"
+                ));
+            }
+        }
+
+        if let Some(workspace_dir) = &self.workspace_dir
+            && !workspace::resolve_path(project_dir, workspace_dir).is_dir()
+        {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': workspace_dir '{workspace_dir}' is not an existing \
+                 directory"
+            ));
+        }
+
+        if let Some(template) = &self.run_dir_template {
+            let placeholder_re = regex::Regex::new(r"\{([^{}]*)\}").unwrap();
+            for m in placeholder_re.captures_iter(template) {
+                if !matches!(&m[1], "workspace" | "file_dir") {
+                    warnings.push(format!(
+                        "Adapter '{adapter_id}': run_dir_template has unknown placeholder '{}', \
+                         use {{workspace}} or {{file_dir}}",
+                        &m[0]
+                    ));
+                }
+            }
+        }
+
+        if let Some(env_file) = &self.env_file
+            && !workspace::resolve_path(project_dir, env_file).is_file()
+        {
+            warnings.push(format!(
+                "Adapter '{adapter_id}': env_file '{env_file}' is not an existing file"
+            ));
+        }
+
+        if let Some(command) = &self.command {
+            let working_dir = self.workspace_dir.as_ref().map_or_else(
+                || project_dir.to_path_buf(),
+                |workspace_dir| workspace::resolve_path(project_dir, workspace_dir),
+            );
+            match command.first() {
+                None => warnings.push(format!("Adapter '{adapter_id}': command is empty")),
+                Some(program) if runner::require_binary(program, &working_dir).is_err() => {
+                    warnings.push(format!(
+                        "Adapter '{adapter_id}': command '{program}' is not an executable on PATH \
+                         or relative to the workspace"
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        warnings.extend(self.kind_option_warnings(adapter_id));
+
+        let mut overrides: Vec<(&String, &String)> = self.severity_overrides.iter().collect();
+        overrides.sort();
+        for (status, severity) in overrides {
+            if !SEVERITY_STATUSES.contains(&status.as_str()) {
+                warnings.push(format!(
+                    "Adapter '{adapter_id}': unknown status '{status}' in severity_overrides. \
+                     Valid values are: {}",
+                    SEVERITY_STATUSES.join(", ")
+                ));
+            } else if severity_from_name(severity).is_none() {
+                warnings.push(format!(
+                    "Adapter '{adapter_id}': unknown severity '{severity}' for '{status}'. Valid \
+                     values are: error, warning, information, hint"
+                ));
+            }
+        }
+
         warnings
     }
 }
 
+/// Whether every path matched by the `inner` glob is also matched by `outer`,
+/// judged by matching `inner` literally against `outer`.
+fn glob_covers(outer: &str, inner: &str) -> bool {
+    let mut builder = OverrideBuilder::new("/");
+    if builder.add(outer).is_err() {
+        return false;
+    }
+    builder
+        .build()
+        .is_ok_and(|matcher| matcher.matched(inner, false).is_whitelist())
+}
+
+/// Validate all adapter configurations, including conflicts between adapters,
+/// and return warnings.
+#[must_use]
+pub fn validate_adapters<S: BuildHasher>(
+    adapters: &HashMap<AdapterId, AdapterConfig, S>,
+    project_dir: &Path,
+) -> Vec<String> {
+    let mut adapter_ids: Vec<&AdapterId> = adapters.keys().collect();
+    adapter_ids.sort();
+
+    let mut warnings: Vec<String> = adapter_ids
+        .iter()
+        .flat_map(|id| adapters[*id].validate(id, project_dir))
+        .collect();
+
+    for (i, first_id) in adapter_ids.iter().enumerate() {
+        for second_id in &adapter_ids[i + 1..] {
+            let (first, second) = (&adapters[*first_id], &adapters[*second_id]);
+            if first.test_kind == second.test_kind {
+                continue;
+            }
+            let second_extensions = workspace::extensions_for_test_kind(&second.test_kind);
+            let shared: Vec<&str> = workspace::extensions_for_test_kind(&first.test_kind)
+                .into_iter()
+                .filter(|extension| second_extensions.contains(extension))
+                .collect();
+            if !shared.is_empty() {
+                warnings.push(format!(
+                    "Adapters '{first_id}' ({}) and '{second_id}' ({}) both claim files with \
+                     extensions: {}",
+                    first.test_kind,
+                    second.test_kind,
+                    shared.join(", ")
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
 /// Main configuration struct for assert-lsp.
 /// Can be loaded from CLI arguments, TOML file, or LSP initialization options.
 #[derive(Parser, Debug, Clone, Deserialize, Serialize)]
@@ -73,17 +680,665 @@ pub struct Config {
     #[arg(skip)]
     #[serde(default)]
     pub adapter_command: HashMap<AdapterId, AdapterConfig>,
+
+    /// Maximum directory depth when walking the project for test files
+    /// (unlimited by default)
+    #[arg(long)]
+    #[serde(default)]
+    pub max_walk_depth: Option<usize>,
+
+    /// Follow symbolic links when walking the project for test files
+    #[arg(long)]
+    #[serde(default)]
+    pub follow_symlinks: bool,
+
+    /// Length in characters beyond which diagnostic messages are cut off
+    /// (unlimited by default). Full messages are kept for `$/testOutput`.
+    #[arg(long)]
+    #[serde(default)]
+    pub max_message_len: Option<usize>,
+
+    /// Events that run tests: "startup" (`initialized`), "open" (the first
+    /// opened file, when the workspace hasn't run yet), "save" and
+    /// "diagnostic" (pull diagnostic requests), or "manual" for only
+    /// `$/runFileTest` and `$/runWorkspaceTest`
+    #[arg(long, value_delimiter = ',', default_values_t = default_run_on())]
+    #[serde(default = "default_run_on")]
+    pub run_on: Vec<String>,
+
+    /// Send `$/progress` notifications while tests run. Turn off for clients
+    /// that don't support `window/workDoneProgress/create`.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    #[serde(default = "default_show_progress")]
+    pub show_progress: bool,
 }
 
+/// Values of `run_on`.
+const RUN_ON_EVENTS: &[&str] = &["startup", "open", "save", "diagnostic", "manual"];
+
 fn default_cache_dir() -> PathBuf {
     std::env::temp_dir().join("assert-lsp")
 }
 
+const fn default_show_progress() -> bool {
+    true
+}
+
+fn default_run_on() -> Vec<String> {
+    ["startup", "open", "save", "diagnostic"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl Config {
+    /// Whether `event`, one of the values of `run_on` other than "manual",
+    /// runs tests.
+    #[must_use]
+    pub fn runs_on(&self, event: &str) -> bool {
+        !self.run_on.iter().any(|value| value == "manual")
+            && self.run_on.iter().any(|value| value == event)
+    }
+
+    /// Warnings for unknown values of `run_on`, and for "manual" combined
+    /// with events it turns off.
+    #[must_use]
+    pub fn run_on_warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .run_on
+            .iter()
+            .filter(|value| !RUN_ON_EVENTS.contains(&value.as_str()))
+            .map(|value| {
+                format!(
+                    "unknown run_on event '{value}'. Valid values are: {}",
+                    RUN_ON_EVENTS.join(", ")
+                )
+            })
+            .collect();
+        if self.run_on.len() > 1 && self.run_on.iter().any(|value| value == "manual") {
+            warnings.push(
+                "run_on: \"manual\" turns off the other events, tests run only when requested"
+                    .to_string(),
+            );
+        }
+        warnings
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             cache_dir: default_cache_dir(),
             adapter_command: HashMap::new(),
+            max_walk_depth: None,
+            follow_symlinks: false,
+            max_message_len: None,
+            run_on: default_run_on(),
+            show_progress: default_show_progress(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(test_kind: &str) -> AdapterConfig {
+        AdapterConfig {
+            test_kind: test_kind.to_string(),
+            ..AdapterConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_run_on() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.runs_on("startup") && config.runs_on("save"));
+        assert!(config.run_on_warnings().is_empty());
+
+        let config: Config = toml::from_str(r#"run_on = ["open"]"#).unwrap();
+        assert!(config.runs_on("open"));
+        assert!(!config.runs_on("save"));
+
+        let config: Config = toml::from_str(r#"run_on = ["manual", "save", "edit"]"#).unwrap();
+        assert!(!config.runs_on("save"));
+        let warnings = config.run_on_warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("unknown run_on event 'edit'"));
+        assert!(warnings[1].contains("\"manual\" turns off"));
+    }
+
+    #[test]
+    fn test_validate_shadowed_include() {
+        let project_dir = std::env::current_dir().unwrap();
+        let config = AdapterConfig {
+            include: vec!["tests/**/*.rs".to_string(), "src/**/*.rs".to_string()],
+            exclude: vec!["tests/**".to_string(), "**/target/**".to_string()],
+            ..adapter("cargo-test")
+        };
+
+        let warnings = config.validate("cargo", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'tests/**/*.rs'"));
+        assert!(warnings[0].contains("'tests/**'"));
+    }
+
+    #[test]
+    fn test_validate_missing_workspace_dir() {
+        let project_dir = std::env::current_dir().unwrap();
+        let existing = AdapterConfig {
+            workspace_dir: Some("demo/rust".to_string()),
+            ..adapter("cargo-test")
+        };
+        let missing = AdapterConfig {
+            workspace_dir: Some("demo/missing".to_string()),
+            ..adapter("cargo-test")
+        };
+
+        assert!(existing.validate("cargo", &project_dir).is_empty());
+        let warnings = missing.validate("cargo", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("demo/missing"));
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let project_dir = std::env::current_dir().unwrap();
+        let command = |command: &[&str]| AdapterConfig {
+            command: Some(command.iter().map(ToString::to_string).collect()),
+            ..adapter("cargo-test")
+        };
+
+        assert!(
+            command(&["cargo", "+nightly", "test"])
+                .validate("cargo", &project_dir)
+                .is_empty()
+        );
+        let warnings = command(&["./node_modules/.bin/jest"]).validate("cargo", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'./node_modules/.bin/jest'"));
+        assert_eq!(command(&[]).validate("cargo", &project_dir).len(), 1);
+    }
+
+    #[test]
+    fn test_feature_args() {
+        let project_dir = std::env::current_dir().unwrap();
+        let config = AdapterConfig {
+            features: vec!["serde".to_string(), "async".to_string()],
+            no_default_features: true,
+            extra_arg: vec!["--workspace".to_string()],
+            ..adapter("cargo-test")
+        };
+        assert_eq!(
+            config.test_args(),
+            vec![
+                "--features",
+                "serde,async",
+                "--no-default-features",
+                "--workspace"
+            ]
+        );
+        assert!(config.validate("cargo", &project_dir).is_empty());
+
+        let jest = AdapterConfig {
+            test_kind: "jest".to_string(),
+            ..config
+        };
+        assert_eq!(jest.test_args(), vec!["--workspace"]);
+        let warnings = jest.validate("jest", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("only apply to cargo-test, cargo-nextest"));
+    }
+
+    #[test]
+    fn test_cargo_target_args() {
+        let project_dir = std::env::current_dir().unwrap();
+        let target = |test_kind: &str, target: &str| AdapterConfig {
+            cargo_target: Some(target.to_string()),
+            ..adapter(test_kind)
+        };
+        let integration = target("cargo-test", "test:integration");
+        assert_eq!(integration.test_args(), vec!["--test", "integration"]);
+        assert!(integration.validate("cargo", &project_dir).is_empty());
+        let cargo = runner::get("cargo-test")
+            .unwrap()
+            .build_command(
+                &[],
+                &runner::RunContext::new("/project"),
+                &integration.test_args(),
+                Some(&["sh".to_string()]),
+                None,
+            )
+            .unwrap();
+        let args: Vec<String> = cargo
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        let separator = args.iter().position(|arg| arg == "--").unwrap();
+        assert_eq!(args[separator - 2..separator], ["--test", "integration"]);
+
+        assert_eq!(target("cargo-nextest", "lib").test_args(), vec!["--lib"]);
+        assert_eq!(
+            target("cargo-test", "bin:cli").test_args(),
+            vec!["--bin", "cli"]
+        );
+
+        let invalid = target("cargo-test", "test:");
+        assert!(invalid.test_args().is_empty());
+        let warnings = invalid.validate("cargo", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("invalid cargo_target 'test:'"));
+        let warnings = target("cargo-criterion", "lib").validate("cargo", &project_dir);
+        assert!(warnings[0].contains("cargo_target only applies to"));
+    }
+
+    #[test]
+    fn test_jobs_args() {
+        let project_dir = std::env::current_dir().unwrap();
+        let jobs = |test_kind: &str, jobs| AdapterConfig {
+            jobs: Some(jobs),
+            extra_arg: vec!["--workspace".to_string()],
+            ..adapter(test_kind)
+        };
+
+        let cargo = jobs("cargo-test", 4);
+        assert_eq!(
+            cargo.test_args(),
+            vec!["--workspace", "--", "--test-threads=4"]
+        );
+        assert!(cargo.validate("cargo", &project_dir).is_empty());
+        // The number of jobs is an argument of the test harness, after the
+        // arguments of cargo and its own
+        let command = runner::get("cargo-test")
+            .unwrap()
+            .build_command(
+                &[],
+                &runner::RunContext::new("."),
+                &cargo.test_args(),
+                None,
+                None,
+            )
+            .unwrap();
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(args.iter().filter(|arg| *arg == "--").count(), 1);
+        let separator = args.iter().position(|arg| arg == "--").unwrap();
+        assert!(args[..separator].contains(&"--workspace".to_string()));
+        assert!(args[separator..].contains(&"--test-threads=4".to_string()));
+
+        assert_eq!(
+            jobs("jest", 2).test_args(),
+            vec!["--maxWorkers=2", "--workspace"]
+        );
+        assert_eq!(jobs("jest", 0).test_args(), vec!["--workspace"]);
+
+        let warnings = jobs("phpunit", 2).validate("php", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("jobs only applies to"));
+        assert!(jobs("phpunit", 0).validate("php", &project_dir).is_empty());
+    }
+
+    #[test]
+    fn test_profile_args() {
+        let project_dir = std::env::current_dir().unwrap();
+        let profile = |test_kind: &str, profile: &str| AdapterConfig {
+            profile: Some(profile.to_string()),
+            extra_arg: vec!["--workspace".to_string()],
+            ..adapter(test_kind)
+        };
+
+        let release = profile("cargo-test", "release");
+        assert_eq!(release.test_args(), vec!["--release", "--workspace"]);
+        assert!(release.validate("cargo", &project_dir).is_empty());
+        // The profile is a cargo argument, not one of the test binary
+        let command = runner::get("cargo-test")
+            .unwrap()
+            .build_command(
+                &[],
+                &runner::RunContext::new("."),
+                &release.test_args(),
+                None,
+                None,
+            )
+            .unwrap();
+        let args: Vec<_> = command.get_args().collect();
+        let separator = args.iter().position(|arg| *arg == "--").unwrap();
+        assert!(args[..separator].contains(&std::ffi::OsStr::new("--release")));
+        assert_eq!(
+            profile("cargo-nextest", "bench").test_args(),
+            vec!["--profile", "bench", "--workspace"]
+        );
+        assert_eq!(
+            profile("swift-test", "release").test_args(),
+            vec!["-c", "release", "--workspace"]
+        );
+        assert_eq!(
+            profile("ctest", "Release").test_args(),
+            vec!["-C", "Release", "--workspace"]
+        );
+
+        let jest = profile("jest", "release");
+        assert_eq!(jest.test_args(), vec!["--workspace"]);
+        let warnings = jest.validate("jest", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("profile only applies to"));
+    }
+
+    #[test]
+    fn test_timeout_secs() {
+        let project_dir = std::env::current_dir().unwrap();
+        let config = AdapterConfig {
+            timeout_secs: Some(300),
+            extra_arg: vec!["-race".to_string()],
+            ..adapter("go-test")
+        };
+        assert_eq!(config.test_args(), vec!["-timeout=300s", "-race"]);
+        assert!(config.validate("go", &project_dir).is_empty());
+
+        let jest = AdapterConfig {
+            test_kind: "jest".to_string(),
+            ..config
+        };
+        assert_eq!(jest.test_args(), vec!["-race"]);
+        let warnings = jest.validate("jest", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("timeout_secs only applies to go-test"));
+    }
+
+    #[test]
+    fn test_regression_threshold() {
+        let project_dir = std::env::current_dir().unwrap();
+        let config = AdapterConfig {
+            regression_threshold: Some(5.0),
+            extra_arg: vec!["--all-features".to_string()],
+            ..adapter("cargo-criterion")
+        };
+        assert_eq!(
+            config.test_args(),
+            vec!["--all-features", "--", "--noise-threshold", "0.05"]
+        );
+        assert!(config.validate("bench", &project_dir).is_empty());
+
+        let negative = AdapterConfig {
+            regression_threshold: Some(-1.0),
+            ..config.clone()
+        };
+        assert_eq!(negative.validate("bench", &project_dir).len(), 1);
+        let cargo_test = AdapterConfig {
+            test_kind: "cargo-test".to_string(),
+            ..config
+        };
+        assert_eq!(cargo_test.test_args(), vec!["--all-features"]);
+        let warnings = cargo_test.validate("cargo", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("only applies to cargo-criterion"));
+    }
+
+    #[test]
+    fn test_coverage_command() {
+        let project_dir = std::env::current_dir().unwrap();
+        let lcov_path = Path::new("/cache/coverage/demo.lcov");
+        let config = AdapterConfig {
+            coverage: true,
+            ..adapter("cargo-test")
+        };
+        assert!(config.validate("rust", &project_dir).is_empty());
+        let command = config.coverage_command(lcov_path).unwrap();
+        let cargo = runner::get("cargo-test")
+            .unwrap()
+            .build_command(
+                &[],
+                &runner::RunContext::new("/project"),
+                &[],
+                Some(&command),
+                None,
+            )
+            .unwrap();
+        let args: Vec<String> = cargo
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            args[..4],
+            [
+                "llvm-cov",
+                "--lcov",
+                "--output-path",
+                "/cache/coverage/demo.lcov"
+            ]
+        );
+        assert!(args.contains(&"json".to_string()));
+
+        assert_eq!(adapter("cargo-test").coverage_command(lcov_path), None);
+        let nextest = AdapterConfig {
+            test_kind: "cargo-nextest".to_string(),
+            ..config
+        };
+        assert_eq!(nextest.coverage_command(lcov_path), None);
+        let warnings = nextest.validate("rust", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("coverage only applies to cargo-test"));
+    }
+
+    #[test]
+    fn test_deno_coverage_commands() {
+        let lcov_path = Path::new("/cache/coverage/demo.lcov");
+        let config = AdapterConfig {
+            coverage: true,
+            ..adapter("deno")
+        };
+        assert!(
+            config
+                .validate("deno", &std::env::current_dir().unwrap())
+                .is_empty()
+        );
+        assert_eq!(
+            config.coverage_command(lcov_path).unwrap(),
+            ["deno", "test", "--coverage=/cache/coverage/demo"]
+        );
+        assert_eq!(
+            config.coverage_report_command(lcov_path).unwrap(),
+            [
+                "deno",
+                "coverage",
+                "--lcov",
+                "--output=/cache/coverage/demo.lcov",
+                "/cache/coverage/demo"
+            ]
+        );
+        assert_eq!(
+            adapter("cargo-test").coverage_report_command(lcov_path),
+            None
+        );
+    }
+
+    #[test]
+    fn test_run_dir_template() {
+        let config = AdapterConfig {
+            run_dir_template: Some("{file_dir}/../fixtures".to_string()),
+            ..adapter("jest")
+        };
+        let files = ["/repo/web/src/app.test.ts".to_string()];
+        assert_eq!(
+            config.run_dir("/repo/web", &files),
+            Some(PathBuf::from("/repo/web/fixtures"))
+        );
+        let config = AdapterConfig {
+            run_dir_template: Some("frontend".to_string()),
+            ..config
+        };
+        assert_eq!(
+            config.run_dir("/repo/web", &files),
+            Some(PathBuf::from("/repo/web/frontend"))
+        );
+        assert_eq!(adapter("jest").run_dir("/repo/web", &files), None);
+
+        let project_dir = std::env::current_dir().unwrap();
+        assert!(config.validate("web", &project_dir).is_empty());
+        let config = AdapterConfig {
+            run_dir_template: Some("{workspace}/{package}".to_string()),
+            ..config
+        };
+        let warnings = config.validate("web", &project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("unknown placeholder '{package}'"));
+    }
+
+    #[test]
+    fn test_target_dir() {
+        let project_dir = Path::new("/home/demo/rust");
+        let config = AdapterConfig {
+            target_dir: Some("../cargo-target".to_string()),
+            env: HashMap::from([("CARGO_TARGET_DIR".to_string(), "ignored".to_string())]),
+            ..adapter("cargo-nextest")
+        };
+        assert_eq!(
+            config.test_env(project_dir)["CARGO_TARGET_DIR"],
+            "/home/demo/cargo-target"
+        );
+        assert!(config.validate("rust", project_dir).is_empty());
+
+        let jest = AdapterConfig {
+            target_dir: Some("target".to_string()),
+            ..adapter("jest")
+        };
+        assert!(!jest.test_env(project_dir).contains_key("CARGO_TARGET_DIR"));
+        let warnings = jest.validate("js", project_dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("target_dir only applies to"));
+    }
+
+    #[test]
+    fn test_env_file() {
+        let project_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project_dir.path().join(".env.test"),
+            "DATABASE_URL=postgres://localhost/test\nLOG=info\n",
+        )
+        .unwrap();
+        let config = AdapterConfig {
+            env: HashMap::from([("LOG".to_string(), "debug".to_string())]),
+            env_file: Some(".env.test".to_string()),
+            ..adapter("cargo-test")
+        };
+        assert!(config.validate("rust", project_dir.path()).is_empty());
+        let env = config.test_env(project_dir.path());
+        assert_eq!(env["DATABASE_URL"], "postgres://localhost/test");
+        assert_eq!(env["LOG"], "debug");
+
+        let missing = AdapterConfig {
+            env_file: Some(".env.missing".to_string()),
+            ..config
+        };
+        assert_eq!(missing.test_env(project_dir.path())["LOG"], "debug");
+        let warnings = missing.validate("rust", project_dir.path());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("env_file '.env.missing' is not an existing file"));
+    }
+
+    #[test]
+    fn test_filter_warnings() {
+        let diagnostic = |code: &str| Diagnostic {
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Diagnostic::default()
+        };
+        let diagnostics = vec![
+            diagnostic("cargo-test-failed"),
+            diagnostic(runner::STDERR_WARNING_CODE),
+        ];
+        assert_eq!(
+            adapter("cargo-test").filter_warnings(diagnostics.clone()),
+            diagnostics[..1]
+        );
+        let config = AdapterConfig {
+            surface_warnings: true,
+            ..adapter("cargo-test")
+        };
+        assert_eq!(config.filter_warnings(diagnostics.clone()), diagnostics);
+    }
+
+    #[test]
+    fn test_severity_overrides() {
+        let project_dir = std::env::current_dir().unwrap();
+        let config = AdapterConfig {
+            severity_overrides: HashMap::from([
+                ("failed".to_string(), "warning".to_string()),
+                ("skipped".to_string(), "hint".to_string()),
+            ]),
+            ..adapter("phpunit")
+        };
+        assert!(config.validate("php", &project_dir).is_empty());
+
+        let diagnostic = |severity, code: &str| Diagnostic {
+            severity: Some(severity),
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Diagnostic::default()
+        };
+        let diagnostics = config.apply_severity_overrides(vec![
+            diagnostic(DiagnosticSeverity::ERROR, "phpunit-failed"),
+            diagnostic(DiagnosticSeverity::INFORMATION, "phpunit-skipped"),
+            diagnostic(DiagnosticSeverity::WARNING, "nextest-leak"),
+        ]);
+        let severities: Vec<_> = diagnostics.iter().map(|d| d.severity.unwrap()).collect();
+        assert_eq!(
+            severities,
+            vec![
+                DiagnosticSeverity::WARNING,
+                DiagnosticSeverity::HINT,
+                DiagnosticSeverity::WARNING
+            ]
+        );
+
+        let invalid = AdapterConfig {
+            severity_overrides: HashMap::from([
+                ("failed".to_string(), "fatal".to_string()),
+                ("passed".to_string(), "hint".to_string()),
+            ]),
+            ..adapter("phpunit")
+        };
+        let warnings = invalid.validate("php", &project_dir);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("unknown severity 'fatal'"));
+        assert!(warnings[1].contains("unknown status 'passed'"));
+    }
+
+    #[test]
+    fn test_source_label() {
+        let diagnostics = vec![Diagnostic {
+            source: Some("cargo-test".to_string()),
+            ..Diagnostic::default()
+        }];
+        let config = adapter("cargo-test");
+        assert_eq!(config.apply_source_label(diagnostics.clone()), diagnostics);
+
+        let config = AdapterConfig {
+            source_label: Some("cargo-test (backend)".to_string()),
+            ..adapter("cargo-test")
+        };
+        let diagnostics = config.apply_source_label(diagnostics);
+        assert_eq!(
+            diagnostics[0].source.as_deref(),
+            Some("cargo-test (backend)")
+        );
+    }
+
+    #[test]
+    fn test_validate_overlapping_adapters() {
+        let project_dir = std::env::current_dir().unwrap();
+        let adapters = HashMap::from([
+            ("cargo".to_string(), adapter("cargo-test")),
+            ("nextest".to_string(), adapter("cargo-nextest")),
+            ("go".to_string(), adapter("go-test")),
+        ]);
+
+        let warnings = validate_adapters(&adapters, &project_dir);
+        assert_eq!(
+            warnings,
+            vec![
+                "Adapters 'cargo' (cargo-test) and 'nextest' (cargo-nextest) both claim files \
+                 with extensions: rs"
+            ]
+        );
+    }
+}
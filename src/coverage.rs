@@ -0,0 +1,161 @@
+//! Code coverage of a test run, read from the LCOV report of
+//! `cargo llvm-cov` or `deno coverage` and sent to the client as a
+//! `$/coverage` notification.
+
+use std::path::{Path, PathBuf};
+
+use lsp_types::{Position, Range, Url};
+use serde::Serialize;
+
+use crate::{MAX_CHAR_LENGTH, error::LSError, runner, workspace::resolve_path};
+
+/// Covered and uncovered lines of a file, as ranges of consecutive lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCoverage {
+    pub uri: Url,
+    pub covered: Vec<Range>,
+    pub uncovered: Vec<Range>,
+}
+
+/// Parameters of the `$/coverage` notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageParams {
+    pub workspace: String,
+    pub files: Vec<FileCoverage>,
+}
+
+/// Where the LCOV report of a workspace is written, in `cache_dir`.
+#[must_use]
+pub fn lcov_path(cache_dir: &Path, workspace: &str) -> PathBuf {
+    let name: String = workspace
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    cache_dir.join("coverage").join(format!("{name}.lcov"))
+}
+
+/// Where runners that convert their coverage afterwards, like `deno test
+/// --coverage`, write it before it becomes the report at `lcov_path`.
+#[must_use]
+pub fn raw_dir(lcov_path: &Path) -> PathBuf {
+    lcov_path.with_extension("")
+}
+
+/// Run the command converting the raw coverage of a run into its LCOV
+/// report, like `deno coverage --lcov`.
+pub fn convert_report(workspace: &str, command: &[String]) -> Result<(), LSError> {
+    let output =
+        runner::test_command(&runner::RunContext::new(workspace), Some(command), &[])?.output()?;
+    if !output.status.success() {
+        return Err(LSError::CommandSpawn(format!(
+            "`{}` failed: {}",
+            command.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Ranges of runs of consecutive lines, given as sorted zero-based numbers.
+fn line_ranges(lines: &[u32]) -> Vec<Range> {
+    let mut ranges: Vec<Range> = vec![];
+    for &line in lines {
+        match ranges.last_mut() {
+            Some(range) if range.end.line + 1 == line => range.end.line = line,
+            _ => ranges.push(Range::new(
+                Position::new(line, 0),
+                Position::new(line, MAX_CHAR_LENGTH),
+            )),
+        }
+    }
+    ranges
+}
+
+fn file_coverage(path: &Path, mut lines: Vec<(u32, bool)>) -> Option<FileCoverage> {
+    lines.sort_unstable();
+    lines.dedup_by_key(|(line, _)| *line);
+    let (mut covered, mut uncovered) = (vec![], vec![]);
+    for (line, hit) in lines {
+        if hit {
+            covered.push(line);
+        } else {
+            uncovered.push(line);
+        }
+    }
+    Some(FileCoverage {
+        uri: Url::from_file_path(path).ok()?,
+        covered: line_ranges(&covered),
+        uncovered: line_ranges(&uncovered),
+    })
+}
+
+/// Parse the line coverage of an LCOV report: the `DA:<line>,<hits>` records
+/// of each `SF:<path>` section. Relative paths are resolved against
+/// `workspace_root`, and `file://` URLs, as Deno writes, are read as paths.
+#[must_use]
+pub fn parse_lcov(contents: &str, workspace_root: &Path) -> Vec<FileCoverage> {
+    let mut files = vec![];
+    let mut current: Option<(PathBuf, Vec<(u32, bool)>)> = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("SF:") {
+            let path = Url::parse(path)
+                .ok()
+                .filter(|url| url.scheme() == "file")
+                .and_then(|url| url.to_file_path().ok())
+                .unwrap_or_else(|| resolve_path(workspace_root, path));
+            current = Some((path, vec![]));
+        } else if let Some(record) = line.strip_prefix("DA:") {
+            let mut fields = record.split(',');
+            let (Some((_, lines)), Some(Ok(line)), Some(Ok(hits))) = (
+                current.as_mut(),
+                fields.next().map(str::parse::<u32>),
+                fields.next().map(str::parse::<u64>),
+            ) else {
+                continue;
+            };
+            lines.push((line.saturating_sub(1), hits > 0));
+        } else if line == "end_of_record"
+            && let Some((path, lines)) = current.take()
+        {
+            files.extend(file_coverage(&path, lines));
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lcov() {
+        let contents = std::fs::read_to_string("tests/coverage.lcov").unwrap();
+        let files = parse_lcov(&contents, Path::new("/home/demo/rust"));
+        assert_eq!(files.len(), 2);
+
+        assert_eq!(files[0].uri.as_str(), "file:///home/demo/rust/src/lib.rs");
+        let lines = |ranges: &[Range]| -> Vec<(u32, u32)> {
+            ranges
+                .iter()
+                .map(|range| (range.start.line, range.end.line))
+                .collect()
+        };
+        assert_eq!(lines(&files[0].covered), vec![(0, 2), (5, 5)]);
+        assert_eq!(lines(&files[0].uncovered), vec![(6, 7)]);
+        assert_eq!(files[1].uri.as_str(), "file:///home/demo/rust/src/util.rs");
+        assert!(files[1].covered.is_empty());
+
+        assert_eq!(
+            lcov_path(Path::new("/cache"), "/home/demo/rust"),
+            Path::new("/cache/coverage/_home_demo_rust.lcov")
+        );
+
+        let deno = "SF:file:///home/demo/deno/mod.ts\nDA:1,1\nDA:2,0\nend_of_record\n";
+        let files = parse_lcov(deno, Path::new("/home/demo/deno"));
+        assert_eq!(files[0].uri.as_str(), "file:///home/demo/deno/mod.ts");
+        assert_eq!(lines(&files[0].uncovered), vec![(1, 1)]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use crate::{
+    cmake::build_dir,
+    config,
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, name_filter_args, test_command},
+};
+
+/// List the tests known to CTest as JSON, without running them.
+pub fn list_ctest(workspace: &str) -> Result<Output, LSError> {
+    let output = test_command(&RunContext::new(workspace), None, &["ctest"])?
+        .arg("--test-dir")
+        .arg(build_dir(workspace))
+        .arg("--show-only=json-v1")
+        .output()?;
+    Ok(output)
+}
+
+/// JUnit report written by the CTest command.
+#[must_use]
+pub fn results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("ctest.xml")
+}
+
+/// Command running the tests of an already built project whose name matches
+/// `filter`, writing JUnit results to the cache directory.
+pub fn ctest_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut ctest = test_command(context, command, &["ctest"])?;
+    ctest
+        .arg("--test-dir")
+        .arg(build_dir(context.workspace))
+        .arg("--output-on-failure")
+        .arg("--output-junit")
+        .arg(results_path())
+        .args(name_filter_args("-R", filter))
+        .args(extra_args);
+    Ok(ctest)
+}
+
+/// Run a CTest command.
+pub fn run_ctest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("ctest.log", &output)?;
+    Ok(output)
+}
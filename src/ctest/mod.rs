@@ -0,0 +1,232 @@
+//! C and C++ tests run by CTest.
+//!
+//! Tests are listed by CTest itself, so a project must be configured and
+//! built before its tests are discovered. Tests added with `add_test` are
+//! found at that call in `CMakeLists.txt`; GoogleTest and Catch2 tests are
+//! also found at their `TEST(...)` or `TEST_CASE(...)` macro in the source.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, FileTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    cmake::{build_dir, cmake_root},
+    error::LSError,
+    runner::{RunContext, Runner},
+    source::{block_end, position_at},
+};
+
+/// Tests known to CTest in the project of `workspace`.
+fn list_tests(workspace: &str) -> Result<Vec<parse::CTestTest>, LSError> {
+    let output = call::list_ctest(workspace)?;
+    if !output.status.success() {
+        log::warn!(
+            "ctest --show-only failed in {workspace}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(LSError::AdapterError);
+    }
+    parse::parse_show_only_json(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn test_item(file_path: &str, name: &str, start: (u32, u32), end: (u32, u32)) -> TestItem {
+    TestItem {
+        id: name.to_string(),
+        name: name.to_string(),
+        path: file_path.to_string(),
+        start_position: Range::new(
+            Position::new(start.0, start.1),
+            Position::new(start.0, MAX_CHAR_LENGTH),
+        ),
+        end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+    }
+}
+
+/// Tests of a file: CTest tests added in it, and tests whose GoogleTest or
+/// Catch2 macro in its source matches a CTest test name.
+fn discover_in_source(file_path: &str, source: &str, tests: &[parse::CTestTest]) -> Vec<TestItem> {
+    let gtest_re = Regex::new(
+        r"\b(?:TEST|TEST_F|TEST_P|TYPED_TEST|TYPED_TEST_P)\s*\(\s*(\w+)\s*,\s*(\w+)\s*\)",
+    )
+    .unwrap();
+    let catch_re = Regex::new(r#"\b(?:TEST_CASE|SCENARIO)\s*\(\s*"([^"]+)""#).unwrap();
+
+    let mut items: Vec<TestItem> = tests
+        .iter()
+        .filter_map(|test| {
+            let (path, line) = test.location.as_ref()?;
+            (path == file_path).then(|| {
+                let line = line.saturating_sub(1);
+                test_item(file_path, &test.name, (line, 0), (line, MAX_CHAR_LENGTH))
+            })
+        })
+        .collect();
+
+    let macros = gtest_re
+        .captures_iter(source)
+        .map(|m| (m.get(0).unwrap(), format!("{}.{}", &m[1], &m[2])))
+        .chain(
+            catch_re
+                .captures_iter(source)
+                .map(|m| (m.get(0).unwrap(), m[1].to_string())),
+        );
+    for (found, source_name) in macros {
+        // Parameterized GoogleTest names get a prefix and an index
+        let matching = tests.iter().filter(|test| {
+            test.name == source_name
+                || test.name.ends_with(&format!("/{source_name}"))
+                || test.name.starts_with(&format!("{source_name}/"))
+                || test.name.contains(&format!("/{source_name}/"))
+        });
+        let start = position_at(source, found.start());
+        let end = position_at(source, block_end(source, found.end()));
+        for test in matching {
+            items.push(test_item(file_path, &test.name, start, end));
+        }
+    }
+
+    items.sort_by_key(|item| item.start_position.start);
+    items
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct CTestRunner;
+
+impl Runner for CTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        let workspaces = self.detect_workspaces(file_paths);
+        let mut files = vec![];
+        for (workspace, paths) in workspaces.map {
+            let tests = list_tests(&workspace).unwrap_or_else(|e| {
+                log::warn!("Failed to list CTest tests in {workspace}: {e}");
+                vec![]
+            });
+            for path in paths {
+                let source = std::fs::read_to_string(&path).unwrap_or_default();
+                files.push(FileTests {
+                    tests: discover_in_source(&path, &source, &tests),
+                    path,
+                    test_kind: None,
+                    adapter_id: None,
+                });
+            }
+        }
+        Ok(DiscoveredTests { files })
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        let workspace = cmake_root(file_path).ok_or(LSError::NoWorkspaceFolders)?;
+        let tests = list_tests(&workspace.to_string_lossy())?;
+        Ok(discover_in_source(file_path, source, &tests))
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::ctest_command(context, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_ctest(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(&call::results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = parse::parse_ctest_junit(&std::fs::read_to_string(results_path)?)?;
+
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .collect();
+        let build_dir = build_dir(workspace);
+        Ok(parse::to_diagnostics(
+            &failures,
+            &[Path::new(workspace), &build_dir],
+            file_paths,
+            &test_items,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(root) = cmake_root(file_path) {
+                map.entry(root.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+
+    // Tests are listed from the build directory, which changes without the
+    // source files changing
+    fn caches_discovery(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let contents = std::fs::read_to_string("demo/ctest/show-only.json").unwrap();
+        let tests = parse::parse_show_only_json(&contents).unwrap();
+
+        let file_path = "demo/ctest/math_test.cpp";
+        let source = std::fs::read_to_string(file_path).unwrap();
+        let items = discover_in_source(file_path, &source, &tests);
+        let names: Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, vec!["Math.Adds", "Math.Fails"]);
+        assert_eq!(items[1].start_position.start, Position::new(8, 0));
+        assert_eq!(items[1].end_position.end, Position::new(10, 1));
+
+        let cmake_lists = "/home/demo/ctest/CMakeLists.txt";
+        let items = discover_in_source(cmake_lists, "", &tests);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "smoke");
+        assert_eq!(items[0].start_position.start.line, 12);
+    }
+}
@@ -0,0 +1,244 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    error::LSError,
+    junit::parse_testcases,
+    log::clean_ansi,
+    workspace::{contains_path, resolve_path},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShowOnly {
+    backtrace_graph: BacktraceGraph,
+    tests: Vec<ShowOnlyTest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BacktraceGraph {
+    files: Vec<String>,
+    nodes: Vec<BacktraceNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BacktraceNode {
+    file: usize,
+    line: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShowOnlyTest {
+    name: String,
+    backtrace: Option<usize>,
+}
+
+/// A test known to CTest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CTestTest {
+    pub name: String,
+    /// File and 1-based line of the CMake command that added the test
+    pub location: Option<(String, u32)>,
+}
+
+/// Parse the output of `ctest --show-only=json-v1`.
+pub fn parse_show_only_json(contents: &str) -> Result<Vec<CTestTest>, LSError> {
+    let show_only: ShowOnly = serde_json::from_str(contents)?;
+    let graph = &show_only.backtrace_graph;
+    Ok(show_only
+        .tests
+        .into_iter()
+        .map(|test| {
+            let location = test
+                .backtrace
+                .and_then(|index| graph.nodes.get(index))
+                .and_then(|node| Some((graph.files.get(node.file)?.clone(), node.line?)));
+            CTestTest {
+                name: test.name,
+                location,
+            }
+        })
+        .collect())
+}
+
+/// A failed test with the output CTest captured from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CTestFailure {
+    pub name: String,
+    pub output: String,
+}
+
+/// Parse the failed tests from the report of `ctest --output-junit`.
+pub fn parse_ctest_junit(contents: &str) -> Result<Vec<CTestFailure>, LSError> {
+    Ok(parse_testcases(contents)?
+        .into_iter()
+        .filter(|testcase| testcase.attribute("status") == "fail")
+        .map(|testcase| CTestFailure {
+            name: testcase.attribute("name").to_string(),
+            output: clean_ansi(&testcase.output),
+        })
+        .collect())
+}
+
+fn error_diagnostic(line: u32, character: u32, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position { line, character },
+            end: Position {
+                line,
+                character: MAX_CHAR_LENGTH,
+            },
+        },
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("ctest".to_string()),
+        code: Some(NumberOrString::String("ctest-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests.
+///
+/// CTest doesn't know where a test is defined in the source, so failures are
+/// placed at each `file:line` (or MSVC style `file(line)`) in the test output
+/// that resolves against one of `roots` to a target file, and at the
+/// definition of the test when it was discovered.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[CTestFailure],
+    roots: &[&Path],
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let location_re = Regex::new(
+        r"^\s*([^\s:()]+\.(?:c|cc|cpp|cxx|h|hh|hpp|hxx))(?::(\d+)(?::(\d+))?|\((\d+)\))",
+    )
+    .unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let lines: Vec<&str> = failure.output.lines().collect();
+        let mut first_message = None;
+
+        for (i, line) in lines.iter().enumerate() {
+            let Some(m) = location_re.captures(line) else {
+                continue;
+            };
+            let Some(path) = roots
+                .iter()
+                .map(|root| resolve_path(root, &m[1]).to_string_lossy().to_string())
+                .find(|path| contains_path(file_paths, path))
+            else {
+                continue;
+            };
+            let line_number: u32 = m
+                .get(2)
+                .or_else(|| m.get(4))
+                .and_then(|line| line.as_str().parse().ok())
+                .unwrap_or(1);
+            let column: u32 = m
+                .get(3)
+                .and_then(|column| column.as_str().parse().ok())
+                .unwrap_or(1);
+
+            // The failure message runs until a blank line or the next status line
+            let message = lines[i..]
+                .iter()
+                .take_while(|line| !line.trim().is_empty())
+                .enumerate()
+                .take_while(|(j, line)| *j == 0 || !line.starts_with('['))
+                .map(|(_, line)| *line)
+                .collect::<Vec<_>>()
+                .join("\n");
+            first_message.get_or_insert_with(|| message.clone());
+            result_map.entry(path).or_default().push(error_diagnostic(
+                line_number.saturating_sub(1),
+                column.saturating_sub(1),
+                message,
+            ));
+        }
+
+        for test_item in test_items.iter().filter(|item| item.id == failure.name) {
+            let detail = first_message.clone().unwrap_or_else(|| {
+                let tail = lines.len().saturating_sub(20);
+                lines[tail..].join("\n")
+            });
+            let diagnostic = Diagnostic {
+                range: test_item.start_position,
+                ..error_diagnostic(
+                    0,
+                    0,
+                    format!("`{}` failed\n{}", failure.name, detail.trim_end()),
+                )
+            };
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(diagnostic);
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_show_only_json() {
+        let contents = std::fs::read_to_string("demo/ctest/show-only.json").unwrap();
+        let tests = parse_show_only_json(&contents).unwrap();
+
+        assert_eq!(tests.len(), 3);
+        assert_eq!(tests[0].name, "Math.Adds");
+        assert_eq!(
+            tests[2].location,
+            Some(("/home/demo/ctest/CMakeLists.txt".to_string(), 13))
+        );
+    }
+
+    #[test]
+    fn test_parse_ctest_junit() {
+        let contents = std::fs::read_to_string("demo/ctest/junit.xml").unwrap();
+        let failures = parse_ctest_junit(&contents).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "Math.Fails");
+
+        let file_path = "/home/demo/ctest/math_test.cpp".to_string();
+        let test_item = TestItem {
+            id: "Math.Fails".to_string(),
+            name: "Math.Fails".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(8, 0), Position::new(8, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(10, 0), Position::new(10, 1)),
+        };
+        let diagnostics = to_diagnostics(
+            &failures,
+            &[
+                Path::new("/home/demo/ctest"),
+                Path::new("/home/demo/ctest/build"),
+            ],
+            std::slice::from_ref(&file_path),
+            &[test_item],
+        );
+
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(9, 0));
+        assert!(diagnostics[0].message.ends_with("  5"));
+        assert_eq!(diagnostics[1].range.start.line, 8);
+        assert!(diagnostics[1].message.starts_with("`Math.Fails` failed\n"));
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, name_filter_args, test_command},
+};
+
+/// Whether the package in `workspace` depends on the Flutter SDK.
+fn is_flutter_package(workspace: &str) -> bool {
+    std::fs::read_to_string(Path::new(workspace).join("pubspec.yaml"))
+        .is_ok_and(|pubspec| pubspec.contains("sdk: flutter"))
+}
+
+/// Command running the given test files, with `flutter test --machine` in
+/// Flutter packages and `dart test --reporter json` otherwise. Both print
+/// JSON reporter events.
+pub fn dart_test_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut dart = if is_flutter_package(context.workspace) {
+        test_command(context, command, &["flutter", "test", "--machine"])?
+    } else {
+        test_command(context, command, &["dart", "test", "--reporter", "json"])?
+    };
+    dart.args(name_filter_args("--name", filter))
+        .args(extra_args)
+        .args(file_paths);
+    Ok(dart)
+}
+
+/// Run a `dart test` or `flutter test` command.
+pub fn run_dart_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("dart_test.log", &output)?;
+    Ok(output)
+}
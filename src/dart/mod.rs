@@ -0,0 +1,221 @@
+//! Dart and Flutter tests run by `dart test` or `flutter test`.
+//!
+//! Tests are the `test(...)` and `testWidgets(...)` calls of a file, named
+//! after the `group(...)` calls around them. No Dart grammar is bundled, so
+//! they are found by scanning the source, balancing parentheses to find where
+//! calls end.
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::position_at,
+};
+
+/// Byte offset just past the call whose argument list opens after `start`,
+/// judged by balancing parentheses outside of strings and line comments.
+fn call_end(source: &str, start: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = source[start..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => {
+                chars.find(|(_, c)| *c == '\n');
+            }
+            '(' => depth += 1,
+            ')' if depth == 1 => return start + i + 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Tests of a file, with ids like `math fails on purpose` as the JSON
+/// reporter names them: the names of the enclosing groups and the test,
+/// joined by spaces.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let call_re =
+        Regex::new(r#"(?m)(?:^|[^\w.$])(test|testWidgets|group)\s*\(\s*r?(?:'([^']*)'|"([^"]*)")"#)
+            .unwrap();
+
+    // Kind, name, start and end of each call
+    let calls: Vec<(&str, &str, usize, usize)> = call_re
+        .captures_iter(source)
+        .map(|m| {
+            let keyword = m.get(1).unwrap();
+            let name = m.get(2).or_else(|| m.get(3)).unwrap().as_str();
+            (
+                keyword.as_str(),
+                name,
+                keyword.start(),
+                call_end(source, keyword.end()),
+            )
+        })
+        .collect();
+
+    let mut items = vec![];
+    for &(kind, name, start, end) in &calls {
+        if kind == "group" {
+            continue;
+        }
+        let mut names: Vec<&str> = calls
+            .iter()
+            .filter(|(kind, _, group_start, group_end)| {
+                *kind == "group" && *group_start < start && end <= *group_end
+            })
+            .map(|(_, name, _, _)| *name)
+            .collect();
+        names.push(name);
+        let id = names.join(" ");
+        let (line, column) = position_at(source, start);
+        let end = position_at(source, end);
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct DartTestRunner;
+
+impl Runner for DartTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::dart_test_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_dart_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        let failures = parse::parse_dart_json(&String::from_utf8_lossy(&output.stdout));
+        Ok(parse::to_diagnostics(
+            &failures,
+            Path::new(context.workspace),
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = parse::parse_dart_json(&std::fs::read_to_string(results_path)?);
+        Ok(parse::to_diagnostics(
+            &failures,
+            Path::new(workspace),
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["pubspec.yaml"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/dart/test/math_test.dart").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "adds",
+                "math fails on purpose",
+                "math large numbers adds large numbers (1 + 1000000)"
+            ]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(9, 4));
+        assert_eq!(items[1].end_position.end, Position::new(11, 6));
+        assert_eq!(items[2].end_position.end.line, 17);
+
+        let items = discover_in_source("a_test.dart", "group('g', () => list.test('x'));");
+        assert!(items.is_empty());
+    }
+}
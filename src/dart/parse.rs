@@ -0,0 +1,222 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    workspace::{resolve_path, same_path},
+};
+
+#[derive(Debug, Deserialize)]
+struct TestInfo {
+    id: u64,
+    name: String,
+}
+
+/// Event of the JSON reporter of `dart test` and `flutter test --machine`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Event {
+    TestStart {
+        test: TestInfo,
+    },
+    Error {
+        #[serde(rename = "testID")]
+        test_id: u64,
+        error: String,
+        #[serde(rename = "stackTrace", default)]
+        stack_trace: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// A test that reported errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DartTestFailure {
+    /// Full name, the names of the enclosing groups and the test joined by
+    /// spaces
+    pub name: String,
+    pub message: String,
+    pub stack_trace: String,
+}
+
+/// Parse the failed tests from the newline-delimited events of the JSON
+/// reporter. Lines that aren't events, like Flutter's build output, are
+/// skipped.
+#[must_use]
+pub fn parse_dart_json(contents: &str) -> Vec<DartTestFailure> {
+    let mut names: HashMap<u64, String> = HashMap::new();
+    let mut failures: Vec<(u64, DartTestFailure)> = vec![];
+
+    for line in contents.lines() {
+        let Ok(event) = serde_json::from_str::<Event>(line) else {
+            continue;
+        };
+        match event {
+            Event::TestStart { test } => {
+                names.insert(test.id, test.name);
+            }
+            Event::Error {
+                test_id,
+                error,
+                stack_trace,
+            } => {
+                // A test can report several errors, e.g. from its tear-down
+                if let Some((_, failure)) = failures.iter_mut().find(|(id, _)| *id == test_id) {
+                    failure.message.push('\n');
+                    failure.message.push_str(error.trim_end());
+                    continue;
+                }
+                failures.push((
+                    test_id,
+                    DartTestFailure {
+                        name: names.get(&test_id).cloned().unwrap_or_default(),
+                        message: error.trim_end().to_string(),
+                        stack_trace,
+                    },
+                ));
+            }
+            Event::Other => {}
+        }
+    }
+
+    failures.into_iter().map(|(_, failure)| failure).collect()
+}
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("dart-test".to_string()),
+        code: Some(NumberOrString::String("dart-test-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests.
+///
+/// Failures are placed at the innermost stack frame, like
+/// `test/math_test.dart 11:5  main.<fn>.<fn>` (or `file:line:col` in
+/// compilation errors), in one of `file_paths`, and linked to the definition
+/// of the test. A failure without such a frame is placed at the definition.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[DartTestFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let frame_re = Regex::new(r"^(?:file://)?(\S+\.dart)(?: +|:)(\d+):(\d+)").unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let test_item = test_items.iter().find(|item| item.id == failure.name);
+
+        let frame = failure
+            .stack_trace
+            .lines()
+            .chain(failure.message.lines())
+            .find_map(|line| {
+                let m = frame_re.captures(line.trim_start())?;
+                let resolved = resolve_path(root, &m[1]).to_string_lossy().to_string();
+                let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+                let line_number = m[2].parse::<u32>().ok()?.saturating_sub(1);
+                let column = m[3].parse::<u32>().ok()?.saturating_sub(1);
+                Some((path, line_number, column))
+            });
+        if let Some((path, line_number, column)) = frame {
+            let range = Range::new(
+                Position::new(line_number, column),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, failure.message.clone())
+                });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!("`{}` failed\n{}", failure.name, failure.message),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dart_json() {
+        let contents = std::fs::read_to_string("demo/dart/test-results.json").unwrap();
+        let failures = parse_dart_json(&contents);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "math fails on purpose");
+        assert_eq!(failures[0].message, "Expected: <4>\n  Actual: <3>");
+
+        let root = Path::new("/home/demo/dart");
+        let file_path = "/home/demo/dart/test/math_test.dart".to_string();
+        let test_item = TestItem {
+            id: "math fails on purpose".to_string(),
+            name: "math fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(9, 4), Position::new(9, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(11, 0), Position::new(11, 6)),
+        };
+        let diagnostics = to_diagnostics(
+            &failures,
+            root,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(10, 6));
+        assert_eq!(
+            diagnostic.related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            test_item.start_position
+        );
+
+        // Without a frame in the file, the failure is placed at the test
+        let failures = [DartTestFailure {
+            stack_trace: String::new(),
+            ..failures[0].clone()
+        }];
+        let diagnostics = to_diagnostics(&failures, root, &[file_path], &[test_item]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(9, 4));
+        assert!(
+            diagnostic
+                .message
+                .starts_with("`math fails on purpose` failed\n")
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Discovered tests persisted across server restarts.
+
+use std::{collections::HashMap, fs, path::Path, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{FilePath, TestItem, WorkspacePath, error::LSError};
+
+/// File in the cache directory holding the discovered tests of every project.
+const CACHE_FILE_NAME: &str = "discovery.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    /// Modification time of the file when its tests were discovered
+    modified: SystemTime,
+    tests: Vec<TestItem>,
+}
+
+/// Tests discovered in the files of a project, keyed by test kind and file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DiscoveryCache {
+    files: HashMap<String, HashMap<FilePath, CachedFile>>,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Cached projects in `cache_dir`, keyed by their root.
+fn read_projects(cache_dir: &Path) -> HashMap<WorkspacePath, Value> {
+    let Ok(contents) = fs::read_to_string(cache_dir.join(CACHE_FILE_NAME)) else {
+        return HashMap::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        log::warn!("Ignoring unreadable discovery cache: {e}");
+        HashMap::new()
+    })
+}
+
+impl DiscoveryCache {
+    /// Cached tests of the project at `root`, or an empty cache if none were
+    /// saved.
+    #[must_use]
+    pub fn load(cache_dir: &Path, root: &str) -> Self {
+        let mut cache: Self = read_projects(cache_dir)
+            .remove(root)
+            .and_then(|project| serde_json::from_value(project).ok())
+            .unwrap_or_default();
+        for files in cache.files.values_mut() {
+            files.retain(|path, _| Path::new(path).exists());
+        }
+        cache
+    }
+
+    /// Saves the cache as the tests of the project at `root`, keeping the
+    /// cached tests of other projects.
+    pub fn save(&self, cache_dir: &Path, root: &str) -> Result<(), LSError> {
+        let mut projects = read_projects(cache_dir);
+        projects.insert(root.to_string(), serde_json::to_value(self)?);
+        fs::create_dir_all(cache_dir)?;
+        fs::write(
+            cache_dir.join(CACHE_FILE_NAME),
+            serde_json::to_string(&projects)?,
+        )?;
+        Ok(())
+    }
+
+    /// Tests of a file, unless it was modified since they were discovered.
+    #[must_use]
+    pub fn get(&self, test_kind: &str, path: &str) -> Option<&[TestItem]> {
+        let cached = self.files.get(test_kind)?.get(path)?;
+        (modified(path)? == cached.modified).then_some(cached.tests.as_slice())
+    }
+
+    /// Remembers the tests just discovered in a file.
+    pub fn insert(&mut self, test_kind: &str, path: &str, tests: Vec<TestItem>) {
+        let Some(modified) = modified(path) else {
+            return;
+        };
+        self.files
+            .entry(test_kind.to_string())
+            .or_default()
+            .insert(path.to_string(), CachedFile { modified, tests });
+    }
+}
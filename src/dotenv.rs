@@ -0,0 +1,71 @@
+//! Variables of a dotenv (`.env`) file, set for test runs with `env_file`.
+
+use std::collections::HashMap;
+
+/// Value after the `=`: up to the closing quote when quoted, with escapes in
+/// double quotes, and otherwise up to a ` #` comment.
+fn parse_value(value: &str) -> String {
+    let value = value.trim();
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest.split('\'').next().unwrap_or_default().to_string();
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => parsed.push('\n'),
+                    Some('t') => parsed.push('\t'),
+                    Some(other) => parsed.push(other),
+                    None => parsed.push('\\'),
+                },
+                _ => parsed.push(c),
+            }
+        }
+        return parsed;
+    }
+    value
+        .split_once(" #")
+        .map_or(value, |(value, _)| value)
+        .trim_end()
+        .to_string()
+}
+
+/// Parse `KEY=VALUE` lines, optionally prefixed with `export`. Blank lines,
+/// `#` comments and lines without `=` are skipped; a later key overrides an
+/// earlier one.
+#[must_use]
+pub fn parse(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            (!key.is_empty()).then(|| (key.to_string(), parse_value(value)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let env = parse(
+            "# test settings\nDATABASE_URL=postgres://localhost/test # local\nexport API_KEY = \
+             'se#cret'\nGREETING=\"hello \\\"world\\\"\\nbye\"\nEMPTY=\nnot a \
+             variable\nAPI_KEY=override\n",
+        );
+        assert_eq!(env.len(), 4);
+        assert_eq!(env["DATABASE_URL"], "postgres://localhost/test");
+        assert_eq!(env["API_KEY"], "override");
+        assert_eq!(env["GREETING"], "hello \"world\"\nbye");
+        assert_eq!(env["EMPTY"], "");
+    }
+}
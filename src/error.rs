@@ -49,12 +49,27 @@ pub enum LSError {
     #[error("Command spawn failed: {0}")]
     CommandSpawn(String),
 
+    #[error("{0} not found on PATH; install it or set a path")]
+    AdapterBinaryMissing(String),
+
     #[error("Adapter produced no output")]
     AdapterNoOutput,
 
     #[error("Adapter returned error output")]
     AdapterError,
 
+    #[error("Debugging a single test isn't supported by this test kind")]
+    DebugUnsupported,
+
+    #[error("{0} isn't in a detected workspace of the test kind")]
+    NotInWorkspace(String),
+
+    #[error("No test executable built for {0}")]
+    TestExecutableNotFound(String),
+
+    #[error("Run directory {0} is not an existing directory")]
+    RunDirNotFound(PathBuf),
+
     // Configuration errors
     #[error("No workspace folders found")]
     NoWorkspaceFolders,
@@ -68,3 +83,32 @@ pub enum LSError {
     #[error("XML parse error")]
     XmlParse,
 }
+
+impl LSError {
+    /// Machine-readable kind of the error, stable across releases, for
+    /// clients that react to failed runs.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::IO(_) => "io",
+            Self::Json(_) | Self::JsonSerde(_) => "json",
+            Self::Protocol(_) | Self::ChannelSend(_) => "protocol",
+            Self::Utf8(_) | Self::StringUtf8(_) => "utf8",
+            Self::Toml(_) | Self::ConfigNotFound(_) => "config",
+            Self::TreeSitterLanguage(_) | Self::TreeSitterQuery(_) | Self::TreeSitterParse => {
+                "parse"
+            }
+            Self::UnknownTestKind(_) | Self::MissingTestKind => "unknown-test-kind",
+            Self::CommandSpawn(_) => "command-spawn",
+            Self::AdapterBinaryMissing(_) => "binary-missing",
+            Self::AdapterNoOutput => "no-output",
+            Self::AdapterError => "adapter-error",
+            Self::DebugUnsupported => "debug-unsupported",
+            Self::NotInWorkspace(_) => "not-in-workspace",
+            Self::TestExecutableNotFound(_) => "test-executable-not-found",
+            Self::RunDirNotFound(_) => "run-dir-not-found",
+            Self::NoWorkspaceFolders | Self::NoHomeDirectory => "no-workspace",
+            Self::XmlParse => "xml",
+        }
+    }
+}
@@ -1,15 +1,93 @@
 use std::process::{Command, Output};
 
-use crate::{error::LSError, log::write_result_log};
-
-pub fn run_go_test(workspace: &str, extra_args: &[String]) -> Result<Output, LSError> {
-    let default_args = ["-v", "-json", "", "-count=1", "-timeout=60s"];
-    let output = Command::new("go")
-        .current_dir(workspace)
-        .arg("test")
-        .args(default_args)
-        .args(extra_args)
-        .output()?;
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, name_selection_args, stream_output, test_command},
+};
+
+/// Timeout of a go test run unless the arguments set one, like the
+/// `timeout_secs` of the adapter.
+const DEFAULT_TIMEOUT: &str = "-timeout=60s";
+
+/// Arguments of go test after the program: JSON output without caching, the
+/// default timeout, the selection of tests and `extra_args`. Tests selected
+/// by id are run by their top-level test, as a subtest can't be selected
+/// without leaving out the subtests of other tests.
+fn go_test_args(context: &RunContext, extra_args: &[String], filter: Option<&str>) -> Vec<String> {
+    let mut args: Vec<String> = ["-v", "-json", "-count=1"].map(String::from).to_vec();
+    if !extra_args.iter().any(|arg| arg.starts_with("-timeout")) {
+        args.push(DEFAULT_TIMEOUT.to_string());
+    }
+    args.extend(name_selection_args("-run", context, filter, |id| {
+        id.split('/').next().unwrap_or(id).to_string()
+    }));
+    args.extend(extra_args.iter().cloned());
+    args
+}
+
+/// Command running go test with JSON output, limited to the tests whose name
+/// contains `filter`.
+pub fn go_test_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut go = test_command(context, command, &["go", "test"])?;
+    go.args(go_test_args(context, extra_args, filter));
+    Ok(go)
+}
+
+/// Arguments of go test building with the build tags `tags`, like
+/// `integration` or `integration,slow`, so that test files constrained to
+/// them are compiled in.
+#[must_use]
+pub fn go_tags_args(tags: &str) -> Vec<String> {
+    vec![format!("-tags={tags}")]
+}
+
+/// Run a go test command, passing each line of output to `on_line` as it is
+/// printed.
+pub fn run_go_test(command: &mut Command, on_line: impl FnMut(&str)) -> Result<Output, LSError> {
+    let output = stream_output(command, on_line)?;
     write_result_log("go.log", &output)?;
     Ok(output)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_go_test_args() {
+        let context = RunContext::default();
+        let args = go_test_args(&context, &[], Some("TestAdd"));
+        assert!(args.iter().all(|arg| !arg.is_empty()));
+        assert_eq!(
+            args,
+            vec!["-v", "-json", "-count=1", "-timeout=60s", "-run", "TestAdd"]
+        );
+
+        let args = go_test_args(&context, &["-timeout=5s".to_string()], None);
+        assert_eq!(args, vec!["-v", "-json", "-count=1", "-timeout=5s"]);
+
+        let context = RunContext {
+            test_ids: Some(vec![
+                "TestAdd".to_string(),
+                "MathSuite/TestDivide".to_string(),
+            ]),
+            ..RunContext::default()
+        };
+        let args = go_test_args(&context, &[], Some("TestAdd"));
+        assert_eq!(args[4..], ["-run", "^(MathSuite|TestAdd)$"]);
+    }
+
+    #[test]
+    fn test_go_tags_args() {
+        assert_eq!(
+            go_tags_args("integration,slow"),
+            vec!["-tags=integration,slow"]
+        );
+    }
+}
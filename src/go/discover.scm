@@ -7,7 +7,14 @@
   (#match? @test.name "^(Test|Example)"))
   @test.definition
 
+;; testify suite methods, reported as subtests of the suite's runner function
 (method_declaration
+  receiver: (parameter_list
+    (parameter_declaration
+      type: [
+        (pointer_type (type_identifier) @test.receiver)
+        (type_identifier) @test.receiver
+      ]))
   name: (field_identifier) @test.name
   (#match? @test.name "^(Test|Example)")) @test.definition
 
@@ -1,29 +1,42 @@
 pub mod call;
 pub mod parse;
 
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use lsp_types::{Position, Range};
-use tree_sitter::{Query, QueryCursor};
+use tree_sitter::{Language, Query, QueryCursor, Tree};
 
 use crate::{
-    Diagnostics, DiscoveredTests, FileTests, MAX_CHAR_LENGTH, TestItem, Workspaces, error::LSError,
-    runner::Runner, workspace::detect_from_files,
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{CompletionTracker, RunContext, RunEvent, Runner, discover_files},
+    workspace::detect_from_files,
 };
 
 const DISCOVER_QUERY: &str = include_str!("discover.scm");
+const SUITE_QUERY: &str = include_str!("suite.scm");
 
 fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     let source_code = std::fs::read_to_string(file_path)?;
     let mut parser = tree_sitter::Parser::new();
-    let language = tree_sitter_go::language();
-    parser.set_language(&language)?;
+    parser.set_language(&tree_sitter_go::language())?;
 
     let tree = parser
         .parse(&source_code, None)
         .ok_or(LSError::TreeSitterParse)?;
+    discover_tests_in_tree(file_path, &source_code, &tree)
+}
 
-    let query = Query::new(&language, DISCOVER_QUERY)?;
+fn discover_tests_in_tree(
+    file_path: &str,
+    source_code: &str,
+    tree: &Tree,
+) -> Result<Vec<TestItem>, LSError> {
+    let query = Query::new(&tree_sitter_go::language(), DISCOVER_QUERY)?;
     let mut cursor = QueryCursor::new();
     let matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
 
@@ -34,13 +47,21 @@ fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     let def_idx = query
         .capture_index_for_name("test.definition")
         .ok_or(LSError::TreeSitterParse)?;
+    let receiver_idx = query
+        .capture_index_for_name("test.receiver")
+        .ok_or(LSError::TreeSitterParse)?;
+    let suite_runners = discover_suite_runners(source_code, tree)?;
 
     for m in matches {
         let mut name: Option<String> = None;
+        let mut receiver: Option<&str> = None;
         let mut start_point = None;
         let mut end_point = None;
 
         for capture in m.captures {
+            if capture.index == receiver_idx {
+                receiver = capture.node.utf8_text(source_code.as_bytes()).ok();
+            }
             if capture.index == name_idx {
                 let text = capture.node.utf8_text(source_code.as_bytes()).unwrap_or("");
                 // Remove quotes from string literals
@@ -53,8 +74,16 @@ fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
         }
 
         if let (Some(test_name), Some(start), Some(end)) = (name, start_point, end_point) {
+            // Suite methods run as subtests of the function calling `suite.Run`
+            let id = match receiver {
+                Some(receiver) => {
+                    let runner = suite_runners.get(receiver).map_or(receiver, String::as_str);
+                    format!("{runner}/{test_name}")
+                }
+                None => test_name.clone(),
+            };
             tests.push(TestItem {
-                id: test_name.clone(),
+                id,
                 name: test_name,
                 path: file_path.to_string(),
                 start_position: Range {
@@ -84,45 +113,187 @@ fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     Ok(tests)
 }
 
+/// Map testify suite types to the test functions that run them.
+fn discover_suite_runners(
+    source_code: &str,
+    tree: &Tree,
+) -> Result<HashMap<String, String>, LSError> {
+    let query = Query::new(&tree_sitter_go::language(), SUITE_QUERY)?;
+    let type_idx = query
+        .capture_index_for_name("suite.type")
+        .ok_or(LSError::TreeSitterParse)?;
+    let run_idx = query
+        .capture_index_for_name("suite.run")
+        .ok_or(LSError::TreeSitterParse)?;
+
+    let mut runners = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    for m in cursor.matches(&query, tree.root_node(), source_code.as_bytes()) {
+        let suite_type = m.nodes_for_capture_index(type_idx).next();
+        let run_call = m.nodes_for_capture_index(run_idx).next();
+        let (Some(suite_type), Some(run_call)) = (suite_type, run_call) else {
+            continue;
+        };
+
+        let mut function = run_call.parent();
+        while let Some(node) = function
+            && node.kind() != "function_declaration"
+        {
+            function = node.parent();
+        }
+        let runner_name = function
+            .and_then(|f| f.child_by_field_name("name"))
+            .and_then(|n| n.utf8_text(source_code.as_bytes()).ok());
+
+        if let (Ok(suite_type), Some(runner_name)) =
+            (suite_type.utf8_text(source_code.as_bytes()), runner_name)
+        {
+            runners.insert(suite_type.to_string(), runner_name.to_string());
+        }
+    }
+    Ok(runners)
+}
+
 #[derive(Eq, PartialEq, Hash, Debug)]
 pub struct GoTestRunner;
 
 impl Runner for GoTestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
-        let mut files = Vec::new();
-        for file_path in file_paths {
-            let tests = discover_tests(file_path)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.clone(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_go::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_tests_in_tree(file_path, source, tree)
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        self.run_tests_streaming(
+            file_paths,
+            context,
+            extra_args,
+            command,
+            filter,
+            &mut |_| {},
+        )
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::go_test_command(context, extra_args, command, filter)
+    }
+
+    fn run_tests_streaming(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+        on_event: &mut dyn FnMut(RunEvent),
     ) -> Result<Diagnostics, LSError> {
-        let output = call::run_go_test(workspace, extra_args)?;
+        let test_items: Vec<TestItem> = file_paths
+            .iter()
+            .filter_map(|path| discover_tests(path).ok())
+            .flatten()
+            .collect();
+        // Only top-level test functions report their own results
+        let discovered_tests: Vec<TestItem> = test_items
+            .iter()
+            .filter(|item| item.id.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            .filter(|item| filter.is_none_or(|filter| item.name.contains(filter)))
+            .filter(|item| {
+                context.test_ids.as_ref().is_none_or(|test_ids| {
+                    test_ids
+                        .iter()
+                        .any(|id| id.split('/').next() == Some(item.id.as_str()))
+                })
+            })
+            .cloned()
+            .collect();
+        let workspace_root = PathBuf::from(context.workspace);
+        let mut parser = parse::GoTestParser::new(&workspace_root, file_paths, &test_items);
+        let mut tracker = CompletionTracker::new(&discovered_tests);
+        let mut command = call::go_test_command(context, extra_args, command, filter)?;
+        let output = call::run_go_test(&mut command, |line| {
+            let Some(outcome) = parser.parse_line(line) else {
+                return;
+            };
+            let (progress, finished_files) = tracker.finish(&outcome.id);
+            on_event(RunEvent::TestCompleted(outcome));
+            if let Some(progress) = progress {
+                on_event(progress);
+            }
+            for path in finished_files {
+                on_event(RunEvent::FileFinished(parser.file_diagnostics(&path)));
+            }
+        })?;
+
+        // Compiler errors go to stderr unless Go reports them as JSON
+        let mut has_build_errors = false;
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            has_build_errors |= parser.parse_build_error(line);
+        }
 
-        if output.stdout.is_empty() && !output.stderr.is_empty() {
+        if output.stdout.is_empty() && !output.stderr.is_empty() && !has_build_errors {
             return Err(LSError::AdapterError);
         }
 
-        let json_output = String::from_utf8(output.stdout)?;
+        Ok(parser.finish())
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .collect();
         parse::parse_go_test_json(
-            &json_output,
-            &PathBuf::from_str(workspace).unwrap(),
+            &std::fs::read_to_string(results_path)?,
+            Path::new(workspace),
             file_paths,
+            &test_items,
         )
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
         detect_from_files(file_paths, &["go.mod"])
     }
+
+    fn tag_args(&self, tags: &str) -> Option<Vec<String>> {
+        Some(call::go_tags_args(tags))
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
+    }
 }
 
 #[cfg(test)]
@@ -135,4 +306,20 @@ mod tests {
         let test_items = discover_tests(file_path).unwrap();
         assert!(!test_items.is_empty());
     }
+
+    #[test]
+    fn test_discover_testify_suite() {
+        let test_items = discover_tests("demo/go/testify_suite_test.go").unwrap();
+        let ids: Vec<&str> = test_items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "TestMySuite/TestFoo",
+                "TestMySuite/TestBar",
+                "OrphanSuite/TestOrphan",
+                "TestMySuite"
+            ]
+        );
+        assert_eq!(test_items[0].name, "TestFoo");
+    }
 }
@@ -1,10 +1,18 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, time::Duration};
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
 use regex::Regex;
 use serde::Deserialize;
 
-use crate::{Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, error::LSError};
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    error::LSError,
+    runner::{TestOutcome, TestStatus},
+    workspace,
+};
 
 #[derive(Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -14,17 +22,29 @@ enum Action {
     Output,
     Fail,
     Pass,
+    Skip,
+    Pause,
+    Cont,
+    Bench,
+    #[serde(rename = "build-output")]
+    BuildOutput,
+    #[serde(rename = "build-fail")]
+    BuildFail,
 }
 
 #[allow(dead_code)]
 #[derive(Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct TestResultLine {
+    #[serde(default)]
     time: String,
     action: Action,
+    #[serde(default)]
     package: String,
     test: Option<String>,
     output: Option<String>,
+    /// Seconds, reported when a test passes or fails
+    elapsed: Option<f64>,
 }
 
 fn get_position_from_output(output: &str) -> Option<(String, u32)> {
@@ -41,52 +61,147 @@ fn get_position_from_output(output: &str) -> Option<(String, u32)> {
     None
 }
 
+/// Parse a compiler error like `./foo.go:10:2: undefined: Bar`.
+fn get_build_error(line: &str) -> Option<(String, u32, u32, String)> {
+    let re = Regex::new(r"^(\S+\.go):(\d+):(\d+): (.+)$").unwrap();
+    let captures = re.captures(line.trim_end())?;
+    let lnum = captures[2].parse::<u32>().ok()?;
+    let col = captures[3].parse::<u32>().ok()?;
+    Some((
+        captures[1].to_string(),
+        lnum.saturating_sub(1),
+        col.saturating_sub(1),
+        captures[4].to_string(),
+    ))
+}
+
+/// Source locations of the stack frames in a data race report, like
+/// `      /path/to/counter.go:9 +0x44`.
+fn get_race_locations(report: &str) -> Vec<(String, u32)> {
+    let re = Regex::new(r"^\s+(\S+\.go):(\d+)(?: \+0x[0-9a-f]+)?$").unwrap();
+    let mut locations: Vec<(String, u32)> = vec![];
+    for line in report.lines() {
+        let Some(captures) = re.captures(line) else {
+            continue;
+        };
+        let Ok(lnum) = captures[2].parse::<u32>() else {
+            continue;
+        };
+        let location = (captures[1].to_string(), lnum.saturating_sub(1));
+        if !locations.contains(&location) {
+            locations.push(location);
+        }
+    }
+    locations
+}
+
 fn get_log_from_output(output: &str) -> String {
     output.replace("        ", "")
 }
 
-pub fn parse_go_test_json(
-    contents: &str,
-    workspace_root: &Path,
-    file_paths: &[String],
-) -> Result<Diagnostics, LSError> {
-    let contents = contents.replace("\r\n", "\n");
-    let lines = contents.lines();
-    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
-    let mut file_name: Option<String> = None;
-    let mut lnum: Option<u32> = None;
-    let mut message = String::new();
-    let mut last_action: Option<Action> = None;
-
-    for line in lines {
-        let value: TestResultLine = serde_json::from_str(line)?;
+/// Incremental parser for `go test -json` lines, collecting diagnostics for
+/// failures as they are reported.
+pub struct GoTestParser<'a> {
+    workspace_root: &'a Path,
+    file_paths: &'a [String],
+    test_items: &'a [TestItem],
+    result_map: HashMap<String, Vec<Diagnostic>>,
+    file_name: Option<String>,
+    lnum: Option<u32>,
+    /// Test that printed the failure at `file_name` and `lnum`
+    test_name: Option<String>,
+    message: String,
+    last_action: Option<Action>,
+    /// Data race report being printed by `go test -race`
+    race_report: Option<String>,
+    /// Output printed so far by each running test
+    outputs: HashMap<String, String>,
+    /// Output printed by each package outside of its tests
+    package_outputs: HashMap<String, String>,
+}
+
+impl<'a> GoTestParser<'a> {
+    #[must_use]
+    pub fn new(
+        workspace_root: &'a Path,
+        file_paths: &'a [String],
+        test_items: &'a [TestItem],
+    ) -> Self {
+        Self {
+            workspace_root,
+            file_paths,
+            test_items,
+            result_map: HashMap::new(),
+            file_name: None,
+            lnum: None,
+            test_name: None,
+            message: String::new(),
+            last_action: None,
+            race_report: None,
+            outputs: HashMap::new(),
+            package_outputs: HashMap::new(),
+        }
+    }
+
+    /// Parse one line of output. Returns the outcome of the test or subtest
+    /// that finished on this line, if any.
+    ///
+    /// Lines that aren't JSON are checked for compiler errors, which older Go
+    /// versions print as plain text.
+    pub fn parse_line(&mut self, line: &str) -> Option<TestOutcome> {
+        let value: TestResultLine = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => {
+                if !self.parse_build_error(line) {
+                    log::debug!("Failed to parse go test JSON: {line}, error: {e}");
+                }
+                return None;
+            }
+        };
         match value.action {
             Action::Run => {
-                file_name = None;
-                message = String::new();
+                self.file_name = None;
+                self.message = String::new();
+            }
+            Action::Output => self.parse_output(&value),
+            Action::BuildOutput => {
+                self.parse_build_error(value.output.as_deref().unwrap_or_default());
             }
-            Action::Output => {
-                let output = &value.output.unwrap();
-                if let Some((detected_file_name, detected_lnum)) = get_position_from_output(output)
-                {
-                    file_name = Some(detected_file_name);
-                    lnum = Some(detected_lnum);
-                    message = String::new();
-                } else {
-                    message += &get_log_from_output(output);
+            Action::Fail if value.test.is_none() => {
+                if let Some(output) = self.package_outputs.remove(&value.package) {
+                    self.parse_package_panic(&value.package, &output);
                 }
             }
             _ => {}
         }
+
+        let status = match value.action {
+            Action::Pass => Some(TestStatus::Passed),
+            Action::Fail => Some(TestStatus::Failed),
+            Action::Skip => Some(TestStatus::Ignored),
+            _ => None,
+        };
+        let finished_test = status.zip(value.test).map(|(status, id)| {
+            let output = self.outputs.remove(&id);
+            TestOutcome {
+                id,
+                status,
+                duration: value
+                    .elapsed
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+                output: output.filter(|_| status == TestStatus::Failed),
+            }
+        });
+
         let current_action = value.action;
-        let is_action_changed = last_action.as_ref() != Some(&current_action);
-        if is_action_changed {
-            last_action = Some(current_action);
-        } else {
-            continue;
+        let is_action_changed = self.last_action.as_ref() != Some(&current_action);
+        if !is_action_changed {
+            return finished_test;
         }
+        self.last_action = Some(current_action);
 
-        if let (Some(detected_fn), Some(detected_lnum)) = (&file_name, lnum) {
+        if let (Some(detected_fn), Some(detected_lnum)) = (self.file_name.take(), self.lnum.take())
+        {
             let diagnostic = Diagnostic {
                 range: Range {
                     start: Position {
@@ -98,32 +213,253 @@ pub fn parse_go_test_json(
                         character: MAX_CHAR_LENGTH,
                     },
                 },
-                message: message.clone(),
+                message: self.message.clone(),
                 severity: Some(DiagnosticSeverity::ERROR),
                 source: Some("go-test".to_string()),
+                related_information: self
+                    .test_name
+                    .take()
+                    .and_then(|name| self.test_item(&name))
+                    .and_then(TestItem::definition_info)
+                    .map(|info| vec![info]),
                 code: Some(NumberOrString::String("go-test-failed".to_string())),
                 ..Diagnostic::default()
             };
-            let file_path = workspace_root
+            let file_path = self
+                .workspace_root
                 .join(detected_fn)
-                .to_str()
-                .unwrap()
-                .to_owned();
-            if file_paths.contains(&file_path) {
-                result_map.entry(file_path).or_default().push(diagnostic);
+                .to_string_lossy()
+                .into_owned();
+            if workspace::contains_path(self.file_paths, &file_path) {
+                self.result_map
+                    .entry(file_path)
+                    .or_default()
+                    .push(diagnostic);
             }
-            file_name = None;
-            lnum = None;
+        }
+        finished_test
+    }
+
+    /// Collect a line a test or package printed, and the failure location and
+    /// message it reports.
+    fn parse_output(&mut self, value: &TestResultLine) {
+        let output = value.output.as_deref().unwrap_or_default();
+        match &value.test {
+            Some(test) => self.outputs.entry(test.clone()).or_default(),
+            None => self
+                .package_outputs
+                .entry(value.package.clone())
+                .or_default(),
+        }
+        .push_str(output);
+        if self.parse_race_output(output, value.test.as_deref()) {
+            // Part of a data race report
+        } else if let Some((detected_file_name, detected_lnum)) = get_position_from_output(output) {
+            self.file_name = Some(detected_file_name);
+            self.lnum = Some(detected_lnum);
+            self.test_name.clone_from(&value.test);
+            self.message = String::new();
+        } else {
+            self.message += &get_log_from_output(output);
         }
     }
 
-    Ok(Diagnostics {
-        files: result_map
-            .into_iter()
-            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
-            .collect(),
-        messages: vec![],
-    })
+    /// Discovered test a test or subtest name belongs to. Subtests of table
+    /// driven tests aren't discovered, so they fall back to their parent.
+    fn test_item(&self, name: &str) -> Option<&'a TestItem> {
+        let mut name = name;
+        loop {
+            if let Some(item) = self.test_items.iter().find(|item| item.id == name) {
+                return Some(item);
+            }
+            name = &name[..name.rfind('/')?];
+        }
+    }
+
+    /// Collect the lines of a data race report, which starts with `WARNING:
+    /// DATA RACE` and ends with a line of `=`, and record a diagnostic at
+    /// every frame of the report in `file_paths`. Returns whether the output
+    /// belonged to a report.
+    fn parse_race_output(&mut self, output: &str, test_name: Option<&str>) -> bool {
+        if output.trim_end() == "WARNING: DATA RACE" {
+            self.race_report = Some(String::new());
+            return true;
+        }
+        let Some(report) = self.race_report.as_mut() else {
+            return false;
+        };
+        let line = output.trim_end();
+        if line.is_empty() || !line.chars().all(|c| c == '=') {
+            report.push_str(output);
+            return true;
+        }
+        let report = self.race_report.take().unwrap_or_default();
+
+        let test_item = test_name.and_then(|name| self.test_item(name));
+        let message = match test_name {
+            Some(name) => format!("data race in `{name}`\n{}", report.trim_end()),
+            None => format!("data race\n{}", report.trim_end()),
+        };
+        for (file_name, lnum) in get_race_locations(&report) {
+            let file_path = workspace::resolve_path(self.workspace_root, &file_name)
+                .to_string_lossy()
+                .into_owned();
+            if !workspace::contains_path(self.file_paths, &file_path) {
+                continue;
+            }
+            self.result_map
+                .entry(file_path)
+                .or_default()
+                .push(Diagnostic {
+                    range: Range::new(Position::new(lnum, 1), Position::new(lnum, MAX_CHAR_LENGTH)),
+                    message: message.clone(),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("go-race".to_string()),
+                    code: Some(NumberOrString::String("go-data-race".to_string())),
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..Diagnostic::default()
+                });
+        }
+        true
+    }
+
+    /// Record a diagnostic for a package that panicked outside of its tests,
+    /// like in an `init` function or `TestMain`. It's placed at the
+    /// innermost frame of the panic in `file_paths` or, when the panic is in
+    /// another file of the workspace, at the top of the test files of that
+    /// file's package and linked to where it panicked.
+    fn parse_package_panic(&mut self, package: &str, output: &str) {
+        let Some(start) = output.find("panic: ") else {
+            return;
+        };
+        let panic = &output[start..];
+        let message = format!(
+            "package `{package}` failed outside of its tests\n{}",
+            panic.split("\n\n").next().unwrap_or(panic).trim_end()
+        );
+        let frame_re = Regex::new(r"(?m)^\t(\S+\.go):(\d+)").unwrap();
+        let Some((file_path, lnum)) = frame_re.captures_iter(panic).find_map(|m| {
+            let file_path = workspace::resolve_path(self.workspace_root, &m[1]);
+            let lnum = m[2].parse::<u32>().ok()?.saturating_sub(1);
+            file_path
+                .starts_with(self.workspace_root)
+                .then_some((file_path, lnum))
+        }) else {
+            return;
+        };
+        let diagnostic = |range: Range| Diagnostic {
+            range,
+            message: message.clone(),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("go-test".to_string()),
+            code: Some(NumberOrString::String("go-package-failed".to_string())),
+            ..Diagnostic::default()
+        };
+        let panic_range = Range::new(Position::new(lnum, 0), Position::new(lnum, MAX_CHAR_LENGTH));
+        let file_name = file_path.to_string_lossy().into_owned();
+        if workspace::contains_path(self.file_paths, &file_name) {
+            self.result_map
+                .entry(file_name)
+                .or_default()
+                .push(diagnostic(panic_range));
+            return;
+        }
+        let related_information = Url::from_file_path(&file_path).ok().map(|uri| {
+            vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri,
+                    range: panic_range,
+                },
+                message: "panicked here".to_string(),
+            }]
+        });
+        for test_file in self
+            .file_paths
+            .iter()
+            .filter(|path| Path::new(path).parent() == file_path.parent())
+        {
+            self.result_map
+                .entry(test_file.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: related_information.clone(),
+                    ..diagnostic(Range::new(
+                        Position::new(0, 0),
+                        Position::new(0, MAX_CHAR_LENGTH),
+                    ))
+                });
+        }
+    }
+
+    /// Record a diagnostic if the line is a compiler error. Returns whether it
+    /// was one.
+    pub fn parse_build_error(&mut self, line: &str) -> bool {
+        let Some((file_name, lnum, col, message)) = get_build_error(line) else {
+            return false;
+        };
+        let diagnostic = Diagnostic {
+            range: Range {
+                start: Position {
+                    line: lnum,
+                    character: col,
+                },
+                end: Position {
+                    line: lnum,
+                    character: MAX_CHAR_LENGTH,
+                },
+            },
+            message,
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("go-build".to_string()),
+            code: Some(NumberOrString::String("go-build-failed".to_string())),
+            ..Diagnostic::default()
+        };
+        let file_path = workspace::resolve_path(self.workspace_root, &file_name)
+            .to_string_lossy()
+            .into_owned();
+        let diagnostics = self.result_map.entry(file_path).or_default();
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+        true
+    }
+
+    /// Diagnostics collected so far for a file.
+    #[must_use]
+    pub fn file_diagnostics(&self, path: &str) -> FileDiagnostics {
+        FileDiagnostics {
+            path: path.to_string(),
+            diagnostics: self.result_map.get(path).cloned().unwrap_or_default(),
+        }
+    }
+
+    #[must_use]
+    pub fn finish(self) -> Diagnostics {
+        Diagnostics {
+            files: self
+                .result_map
+                .into_iter()
+                .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+                .collect(),
+            messages: vec![],
+        }
+    }
+}
+
+pub fn parse_go_test_json(
+    contents: &str,
+    workspace_root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Result<Diagnostics, LSError> {
+    let contents = contents.replace("\r\n", "\n");
+    let mut parser = GoTestParser::new(workspace_root, file_paths, test_items);
+    for line in contents.lines() {
+        parser.parse_line(line);
+    }
+    Ok(parser.finish())
 }
 
 #[cfg(test)]
@@ -140,7 +476,8 @@ mod tests {
         let workspace = PathBuf::from_str("/home/demo/test/go/src/test").unwrap();
         let target_file_path = "/home/demo/test/go/src/test/cases_test.go";
         let result =
-            parse_go_test_json(&contents, &workspace, &[target_file_path.to_string()]).unwrap();
+            parse_go_test_json(&contents, &workspace, &[target_file_path.to_string()], &[])
+                .unwrap();
         let result = result.files.first().unwrap();
         assert_eq!(result.path, target_file_path);
         let diagnostic = result.diagnostics.first().unwrap();
@@ -148,4 +485,151 @@ mod tests {
         assert_eq!(diagnostic.range.start.character, 1);
         assert_eq!(diagnostic.range.end.line, 30);
     }
+
+    #[test]
+    fn test_go_test_parser_reports_finished_tests() {
+        let current_dir = std::env::current_dir().unwrap();
+        let contents = read_to_string(current_dir.join("tests/go-test.txt")).unwrap();
+        let workspace = PathBuf::from_str("/home/demo/test/go/src/test").unwrap();
+        let file_paths = ["/home/demo/test/go/src/test/cases_test.go".to_string()];
+        let mut parser = GoTestParser::new(&workspace, &file_paths, &[]);
+
+        let finished: Vec<TestOutcome> = contents
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .collect();
+
+        let failed = finished.iter().find(|outcome| outcome.id == "TestFail");
+        assert_eq!(
+            failed.map(|outcome| outcome.status),
+            Some(TestStatus::Failed)
+        );
+        assert_eq!(
+            failed.and_then(|outcome| outcome.output.as_deref()),
+            Some(
+                "=== RUN   TestFail\n    cases_test.go:31: Expected 1 but got 2\n--- FAIL: \
+                 TestFail (0.00s)\n"
+            )
+        );
+        assert_eq!(
+            parser.file_diagnostics(&file_paths[0]).diagnostics.len(),
+            parser.finish().files[0].diagnostics.len()
+        );
+    }
+
+    #[test]
+    fn test_parse_go_init_panic() {
+        let contents = read_to_string("tests/go-init-panic.txt").unwrap();
+        let workspace = PathBuf::from("/home/demo/go");
+        let file_paths = [
+            "/home/demo/go/config/config_test.go".to_string(),
+            "/home/demo/go/server/server_test.go".to_string(),
+        ];
+        let result = parse_go_test_json(&contents, &workspace, &file_paths, &[]).unwrap();
+        assert_eq!(result.files.len(), 1);
+        assert_eq!(result.files[0].path, file_paths[0]);
+        let diagnostic = &result.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(0, 0));
+        assert_eq!(
+            diagnostic.message,
+            "package `example.com/demo/config` failed outside of its tests\npanic: DEMO_CONFIG is \
+             not set"
+        );
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(
+            related[0].location.uri.as_str(),
+            "file:///home/demo/go/config/config.go"
+        );
+        assert_eq!(related[0].location.range.start.line, 11);
+    }
+
+    #[test]
+    fn test_parse_go_build_failure() {
+        let current_dir = std::env::current_dir().unwrap();
+        let contents = read_to_string(current_dir.join("tests/go-build-failure.txt")).unwrap();
+        let workspace = PathBuf::from_str("/home/demo/test/go/src/test").unwrap();
+        let target_file_path = "/home/demo/test/go/src/test/cases.go";
+        let result =
+            parse_go_test_json(&contents, &workspace, &[target_file_path.to_string()], &[])
+                .unwrap();
+
+        assert_eq!(result.files.len(), 1);
+        let result = result.files.first().unwrap();
+        assert_eq!(result.path, target_file_path);
+        let diagnostic = result.diagnostics.first().unwrap();
+        assert_eq!(diagnostic.source.as_deref(), Some("go-build"));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostic.range.start.line, 9);
+        assert_eq!(diagnostic.range.start.character, 1);
+        assert_eq!(diagnostic.message, "undefined: Bar");
+    }
+
+    #[test]
+    fn test_parse_go_race() {
+        let contents = read_to_string("tests/go-race.txt").unwrap();
+        let workspace = PathBuf::from_str("/home/demo/test/go/src/test").unwrap();
+        let race_go = "/home/demo/test/go/src/test/race.go".to_string();
+        let race_test_go = "/home/demo/test/go/src/test/race_test.go".to_string();
+        let result = parse_go_test_json(
+            &contents,
+            &workspace,
+            &[race_go.clone(), race_test_go.clone()],
+            &[],
+        )
+        .unwrap();
+
+        let lines = |path: &str| -> Vec<u32> {
+            let file = result.files.iter().find(|file| file.path == path).unwrap();
+            assert!(file.diagnostics.iter().all(|diagnostic| {
+                diagnostic.source.as_deref() == Some("go-race")
+                    && diagnostic.severity == Some(DiagnosticSeverity::ERROR)
+            }));
+            file.diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.range.start.line)
+                .collect()
+        };
+        assert_eq!(lines(&race_go), vec![8]);
+        assert_eq!(lines(&race_test_go), vec![12, 10]);
+
+        let diagnostic = &result.files[0].diagnostics[0];
+        assert!(diagnostic.message.starts_with("data race in `TestRace`\n"));
+        assert!(
+            diagnostic
+                .message
+                .contains("Write at 0x00c00001c1d8 by goroutine 8:")
+        );
+        assert!(
+            !diagnostic
+                .message
+                .contains("race detected during execution")
+        );
+    }
+
+    #[test]
+    fn test_parse_go_test_json_links_test_definition() {
+        let contents = read_to_string("tests/go-test.txt").unwrap();
+        let workspace = PathBuf::from_str("/home/demo/test/go/src/test").unwrap();
+        let target_file_path = "/home/demo/test/go/src/test/cases_test.go".to_string();
+        let test_item = TestItem {
+            id: "TestFail".to_string(),
+            name: "TestFail".to_string(),
+            path: target_file_path.clone(),
+            start_position: Range::new(Position::new(28, 0), Position::new(28, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(32, 0), Position::new(32, 1)),
+        };
+        let result = parse_go_test_json(
+            &contents,
+            &workspace,
+            std::slice::from_ref(&target_file_path),
+            std::slice::from_ref(&test_item),
+        )
+        .unwrap();
+
+        let diagnostic = &result.files[0].diagnostics[0];
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.range, test_item.start_position);
+        assert_eq!(related[0].message, "test `TestFail` defined here");
+    }
 }
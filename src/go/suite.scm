@@ -0,0 +1,16 @@
+;; testify suite runners: `suite.Run(t, new(MySuite))` or `suite.Run(t, &MySuite{})`
+(call_expression
+  function: (selector_expression
+    field: (field_identifier) @suite.method
+    (#eq? @suite.method "Run"))
+  arguments: (argument_list
+    (identifier)
+    [
+      (call_expression
+        function: (identifier) @suite.new
+        (#eq? @suite.new "new")
+        arguments: (argument_list (type_identifier) @suite.type))
+      (unary_expression
+        operand: (composite_literal
+          type: (type_identifier) @suite.type))
+    ])) @suite.run
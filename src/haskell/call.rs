@@ -0,0 +1,90 @@
+use std::process::{Command, Output};
+
+use super::Framework;
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Arguments of the test suite selecting the tests whose name contains
+/// `filter`: hspec's `--match` or tasty's `-p`.
+#[must_use]
+pub fn filter_args(framework: Framework, filter: Option<&str>) -> Vec<String> {
+    let Some(filter) = filter else {
+        return vec![];
+    };
+    let option = match framework {
+        Framework::Hspec => "--match",
+        Framework::Tasty => "-p",
+    };
+    vec![option.to_string(), filter.to_string()]
+}
+
+/// `arg` quoted as one argument of `stack test --test-arguments`.
+fn stack_quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Command running the test suites of the project with `stack test`, passing
+/// `test_args` on to them.
+pub fn stack_test_command(
+    context: &RunContext,
+    test_args: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut stack = test_command(context, command, &["stack", "test"])?;
+    if !test_args.is_empty() {
+        let quoted: Vec<String> = test_args.iter().map(|arg| stack_quote(arg)).collect();
+        stack.arg("--test-arguments").arg(quoted.join(" "));
+    }
+    stack.args(extra_args);
+    Ok(stack)
+}
+
+/// Command running the test suites of the package with `cabal test`,
+/// printing their output as they run and passing `test_args` on to them.
+pub fn cabal_test_command(
+    context: &RunContext,
+    test_args: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut cabal = test_command(
+        context,
+        command,
+        &["cabal", "test", "--test-show-details=direct"],
+    )?;
+    cabal
+        .args(test_args.iter().map(|arg| format!("--test-option={arg}")))
+        .args(extra_args);
+    Ok(cabal)
+}
+
+/// Run a `stack test` or `cabal test` command.
+pub fn run_haskell_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("haskell_test.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_args() {
+        assert_eq!(
+            filter_args(Framework::Hspec, Some("fails on purpose")),
+            vec!["--match", "fails on purpose"]
+        );
+        assert_eq!(
+            filter_args(Framework::Tasty, Some("adds")),
+            vec!["-p", "adds"]
+        );
+        assert!(filter_args(Framework::Tasty, None).is_empty());
+        assert_eq!(stack_quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+}
@@ -0,0 +1,323 @@
+//! Haskell tests run by `stack test` or `cabal test`, with hspec or tasty.
+//!
+//! Tests are the hspec `it` (or `specify`, `prop`) calls, named after the
+//! `describe` (or `context`) calls around them, and the tasty `testCase` (or
+//! `testProperty`, ...) calls, named after the `testGroup` calls around them.
+//! No Haskell grammar is bundled, so they are found by scanning the source; a
+//! call ends before the next line indented no deeper than its own.
+//!
+//! A test suite runs as a whole, so every test of the package runs unless a
+//! filter selects some by name.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::{indented_end, position_at},
+};
+
+/// Test framework of a test suite, which decides how tests are named and
+/// selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    /// Ids join names with `/`, like the paths `--match` takes
+    Hspec,
+    /// Ids join names with `.`, like the paths `-p` patterns are matched
+    /// against
+    Tasty,
+}
+
+impl Framework {
+    fn separator(self) -> char {
+        match self {
+            Self::Hspec => '/',
+            Self::Tasty => '.',
+        }
+    }
+
+    /// Framework of a call, and whether the call is a group of tests.
+    fn of_call(keyword: &str) -> (Self, bool) {
+        match keyword {
+            "describe" | "context" => (Self::Hspec, true),
+            "it" | "specify" | "prop" => (Self::Hspec, false),
+            "testGroup" => (Self::Tasty, true),
+            _ => (Self::Tasty, false),
+        }
+    }
+}
+
+/// Whether `offset` is in a `--` line comment.
+fn in_comment(source: &str, offset: usize) -> bool {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..offset].contains("--")
+}
+
+/// Tests of a file, with ids like `Math/add/fails on purpose` for hspec and
+/// `Tests.Math.adds` for tasty: the names of the enclosing groups and the
+/// test.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let call_re = Regex::new(
+        r#"(?m)(?:^|[^\w'.])(describe|context|it|specify|prop|testGroup|testCase|testCaseInfo|testCaseSteps|testProperty)\s+"((?:[^"\\\n]|\\.)*)""#,
+    )
+    .unwrap();
+
+    // Framework, whether it's a group, name, start and last line of each call
+    let calls: Vec<(Framework, bool, &str, usize, u32)> = call_re
+        .captures_iter(source)
+        .filter_map(|m| {
+            let keyword = m.get(1).unwrap();
+            if in_comment(source, keyword.start()) {
+                return None;
+            }
+            let (framework, is_group) = Framework::of_call(keyword.as_str());
+            let (line, _) = position_at(source, keyword.start());
+            let text = source.lines().nth(line as usize).unwrap_or_default();
+            let indent = text.len() - text.trim_start().len();
+            let (end, _) = indented_end(source, line, u32::try_from(indent).unwrap_or(0));
+            Some((
+                framework,
+                is_group,
+                m.get(2).unwrap().as_str(),
+                keyword.start(),
+                end,
+            ))
+        })
+        .collect();
+
+    let mut items = vec![];
+    for &(framework, is_group, name, start, end) in &calls {
+        if is_group {
+            continue;
+        }
+        let (line, column) = position_at(source, start);
+        let mut names: Vec<&str> = calls
+            .iter()
+            .filter(|(group_framework, is_group, _, group_start, group_end)| {
+                *is_group
+                    && *group_framework == framework
+                    && *group_start < start
+                    && line <= *group_end
+            })
+            .map(|(_, _, name, _, _)| *name)
+            .collect();
+        names.push(name);
+        let id = names.join(&framework.separator().to_string());
+        let end_length = source.lines().nth(end as usize).map_or(0, |text| {
+            u32::try_from(text.len()).unwrap_or(MAX_CHAR_LENGTH)
+        });
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end, 0), Position::new(end, end_length)),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Framework of the test suite the files belong to: tasty when one of them
+/// imports it, hspec otherwise.
+fn framework_of(file_paths: &[String]) -> Framework {
+    let uses_tasty = file_paths.iter().any(|path| {
+        std::fs::read_to_string(path).is_ok_and(|source| source.contains("import Test.Tasty"))
+    });
+    if uses_tasty {
+        Framework::Tasty
+    } else {
+        Framework::Hspec
+    }
+}
+
+/// Directory of the Cabal package a file belongs to: the nearest directory
+/// with a `.cabal` file.
+fn cabal_package_dir(file_path: &str) -> Option<&Path> {
+    Path::new(file_path).ancestors().skip(1).find(|dir| {
+        std::fs::read_dir(dir).is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "cabal"))
+            })
+        })
+    })
+}
+
+fn diagnostics(output: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_haskell_output(output),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+/// Runner of Haskell test suites, with the build tool of the project.
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub enum HaskellTestRunner {
+    Stack,
+    Cabal,
+}
+
+impl Runner for HaskellTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let filter_args = call::filter_args(framework_of(file_paths), filter);
+        match self {
+            Self::Stack => call::stack_test_command(context, &filter_args, extra_args, command),
+            Self::Cabal => call::cabal_test_command(context, &filter_args, extra_args, command),
+        }
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_haskell_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        // Build tools print the test output on stdout and their own on stderr
+        let output = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        );
+        Ok(diagnostics(&output, context.workspace, file_paths))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        match self {
+            Self::Stack => crate::workspace::detect_from_files(file_paths, &["stack.yaml"]),
+            Self::Cabal => {
+                let mut map: HashMap<String, Vec<String>> = HashMap::new();
+                for file_path in file_paths {
+                    if let Some(package) = cabal_package_dir(file_path) {
+                        map.entry(package.to_string_lossy().to_string())
+                            .or_default()
+                            .push(file_path.clone());
+                    }
+                }
+                Workspaces { map }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_hspec() {
+        let items = discover_tests("demo/stack/test/MathSpec.hs").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "add/adds",
+                "add/fails on purpose",
+                "add/negative/keeps the sign"
+            ]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(11, 4));
+        assert_eq!(items[1].end_position.end.line, 12);
+        assert_eq!(items[2].end_position.end.line, 16);
+    }
+
+    #[test]
+    fn test_discover_tasty() {
+        let source = r#"import Test.Tasty
+import Test.Tasty.HUnit
+
+main = defaultMain tests
+
+tests = testGroup "Tests"
+  [ testGroup "Math"
+      [ testCase "adds" $ 1 + 1 @?= 2
+      -- , testCase "skipped" $ ...
+      , testCase "fails on purpose" $
+          1 + 2 @?= 4
+      ]
+  , testProperty "commutes" $ \x y -> x + y == y + (x :: Int)
+  ]
+"#;
+        let items = discover_in_source("test/Main.hs", source);
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "Tests.Math.adds",
+                "Tests.Math.fails on purpose",
+                "Tests.commutes"
+            ]
+        );
+        assert_eq!(items[1].end_position.end.line, 10);
+    }
+}
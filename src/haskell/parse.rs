@@ -0,0 +1,305 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use super::Framework;
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    workspace::{resolve_path, same_path},
+};
+
+/// A failed hspec or tasty test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HaskellFailure {
+    pub framework: Framework,
+    /// Path of the test, like `Math/add/fails on purpose` for hspec and
+    /// `Tests.Math.adds` for tasty
+    pub id: String,
+    pub message: String,
+    /// File, one-based line and column the failure is reported at
+    pub location: Option<(String, u32, u32)>,
+}
+
+fn location_regex() -> Regex {
+    Regex::new(r"^\s*(\S+\.l?hs):(\d+):(?:(\d+):)?\s*$").unwrap()
+}
+
+fn location(m: &regex::Captures) -> Option<(String, u32, u32)> {
+    Some((
+        m[1].to_string(),
+        m[2].parse().ok()?,
+        m.get(3)
+            .map_or(Some(1), |column| column.as_str().parse().ok())?,
+    ))
+}
+
+/// Lines with their common indentation removed, joined.
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default().trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Failures of the `Failures:` summary of hspec, each a location, a numbered
+/// name, the message and a `To rerun use: --match "/path/"` line.
+fn parse_hspec(output: &str) -> Vec<HaskellFailure> {
+    let location_re = location_regex();
+    let header_re = Regex::new(r"^\s*\d+\) (.+)$").unwrap();
+    let rerun_re = Regex::new(r#"To rerun use: --match "([^"]*)""#).unwrap();
+
+    let mut failures = vec![];
+    let Some(start) = output.lines().position(|line| line.trim() == "Failures:") else {
+        return failures;
+    };
+    let mut pending_location = None;
+    let mut current: Option<(HaskellFailure, Vec<&str>)> = None;
+    let mut finish = |current: Option<(HaskellFailure, Vec<&str>)>| {
+        if let Some((mut failure, lines)) = current {
+            failure.message = dedent(&lines);
+            failures.push(failure);
+        }
+    };
+    for line in output.lines().skip(start + 1) {
+        if let Some(m) = location_re.captures(line) {
+            finish(current.take());
+            pending_location = location(&m);
+        } else if let Some(m) = header_re.captures(line) {
+            finish(current.take());
+            current = Some((
+                HaskellFailure {
+                    framework: Framework::Hspec,
+                    id: m[1].to_string(),
+                    message: String::new(),
+                    location: pending_location.take(),
+                },
+                vec![],
+            ));
+        } else if let Some(m) = rerun_re.captures(line) {
+            if let Some((failure, _)) = current.as_mut() {
+                failure.id = m[1].trim_matches('/').to_string();
+            }
+            finish(current.take());
+        } else if line.starts_with("Randomized with seed") {
+            break;
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    finish(current);
+    failures
+}
+
+/// Failures of the test tree tasty prints, where tests are lines like
+/// `adds: FAIL` nested by indentation in their groups, followed by the
+/// message indented deeper. Tasty's tests are all in the group of its `main`,
+/// which tells them from build tool lines like `Test suite tests: FAIL`.
+fn parse_tasty(output: &str) -> Vec<HaskellFailure> {
+    let location_re = location_regex();
+    let status_re = Regex::new(r"^(\s*)(.+?):\s+(OK|FAIL)\b").unwrap();
+
+    let mut failures = vec![];
+    let mut groups: Vec<(usize, &str)> = vec![];
+    // The failing test, its indentation and message lines
+    let mut current: Option<(HaskellFailure, usize, Vec<&str>)> = None;
+    let mut finish = |current: Option<(HaskellFailure, usize, Vec<&str>)>| {
+        if let Some((mut failure, _, lines)) = current {
+            failure.message = dedent(&lines);
+            failures.push(failure);
+        }
+    };
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if let Some((failure, test_indent, lines)) = current.as_mut()
+            && indent > *test_indent
+        {
+            if let Some(m) = location_re.captures(line) {
+                failure.location = failure.location.take().or_else(|| location(&m));
+            } else if !line.trim_start().starts_with("Use -p ") {
+                lines.push(line);
+            }
+            continue;
+        }
+        finish(current.take());
+        groups.retain(|(group_indent, _)| *group_indent < indent);
+        match status_re.captures(line) {
+            Some(m) if &m[3] == "FAIL" && !groups.is_empty() => {
+                let mut names: Vec<&str> = groups.iter().map(|(_, name)| *name).collect();
+                names.push(m.get(2).unwrap().as_str());
+                current = Some((
+                    HaskellFailure {
+                        framework: Framework::Tasty,
+                        id: names.join("."),
+                        message: String::new(),
+                        location: None,
+                    },
+                    indent,
+                    vec![],
+                ));
+            }
+            Some(_) => {}
+            None => groups.push((indent, line.trim())),
+        }
+    }
+    finish(current);
+    failures
+}
+
+/// Parse the failed tests from the output of an hspec or tasty test suite.
+#[must_use]
+pub fn parse_haskell_output(output: &str) -> Vec<HaskellFailure> {
+    let mut failures = parse_hspec(output);
+    failures.extend(parse_tasty(output));
+    failures
+}
+
+fn error_diagnostic(framework: Framework, range: Range, message: String) -> Diagnostic {
+    let source = match framework {
+        Framework::Hspec => "hspec",
+        Framework::Tasty => "tasty",
+    };
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(source.to_string()),
+        code: Some(NumberOrString::String("haskell-test-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests.
+///
+/// A failure's test is the discovered test whose id ends its path, since
+/// `hspec-discover` and a tasty `main` add groups around the tests of a
+/// file. Failures are placed at their reported location, like
+/// `test/MathSpec.hs:12:26`, in one of `file_paths`, and linked to the
+/// definition of the test. A failure without such a location is placed at
+/// the definition.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[HaskellFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let separator = failure.framework.separator();
+        let test_item = test_items
+            .iter()
+            .filter(|item| {
+                failure.id == item.id || failure.id.ends_with(&format!("{separator}{}", item.id))
+            })
+            .max_by_key(|item| item.id.len());
+
+        let location = failure.location.as_ref().and_then(|(file, line, column)| {
+            let resolved = resolve_path(root, file).to_string_lossy().to_string();
+            let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+            Some((path, line.saturating_sub(1), column.saturating_sub(1)))
+        });
+        if let Some((path, line, column)) = location {
+            let range = Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(failure.framework, range, failure.message.clone())
+                });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    failure.framework,
+                    test_item.start_position,
+                    format!("`{}` failed\n{}", failure.id, failure.message),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hspec_output() {
+        let output = std::fs::read_to_string("demo/stack/test-output.txt").unwrap();
+        let failures = parse_haskell_output(&output);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].framework, Framework::Hspec);
+        assert_eq!(failures[0].id, "Math/add/fails on purpose");
+        assert_eq!(failures[0].message, "expected: 4\n but got: 3");
+        assert_eq!(
+            failures[0].location,
+            Some(("test/MathSpec.hs".to_string(), 13, 15))
+        );
+
+        let root = std::env::current_dir().unwrap().join("demo/stack");
+        let file_path = root.join("test/MathSpec.hs").to_string_lossy().to_string();
+        let test_items = super::super::discover_tests(&file_path).unwrap();
+        let diagnostics = to_diagnostics(
+            &failures,
+            &root,
+            std::slice::from_ref(&file_path),
+            &test_items,
+        );
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(12, 14));
+        assert_eq!(
+            diagnostic.related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            test_items[1].start_position
+        );
+    }
+
+    #[test]
+    fn test_parse_tasty_output() {
+        let output = std::fs::read_to_string("tests/tasty-output.txt").unwrap();
+        let failures = parse_haskell_output(&output);
+        let ids: Vec<&str> = failures.iter().map(|failure| failure.id.as_str()).collect();
+        assert_eq!(ids, vec!["Tests.Math.fails on purpose", "Tests.commutes"]);
+        assert_eq!(failures[0].message, "expected: 4\n but got: 3");
+        assert_eq!(
+            failures[0].location,
+            Some(("test/Main.hs".to_string(), 11, 1))
+        );
+        assert_eq!(failures[1].location, None);
+        assert!(failures[1].message.starts_with("*** Failed! Falsified"));
+    }
+}
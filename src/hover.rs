@@ -0,0 +1,168 @@
+//! Hover over a test to show the result of its last run.
+
+use std::{fmt::Write, time::Duration};
+
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, Hover, HoverContents, MarkupContent, MarkupKind, Position,
+};
+
+use crate::{
+    TestItem,
+    runner::{TestOutcome, TestStatus},
+};
+
+/// Last known result of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastResult {
+    pub status: TestStatus,
+    pub duration: Option<Duration>,
+    pub message: Option<String>,
+    /// Output captured while the test failed, as returned by `$/testOutput`
+    pub output: Option<String>,
+}
+
+impl LastResult {
+    /// Result of a test after a run, from the outcome reported by the runner
+    /// if any, otherwise from the diagnostics published for its file.
+    #[must_use]
+    pub fn new(
+        test_item: &TestItem,
+        outcome: Option<&TestOutcome>,
+        diagnostics: &[Diagnostic],
+    ) -> Self {
+        let failure = diagnostics
+            .iter()
+            .find(|diagnostic| is_failure_of(diagnostic, test_item));
+        let status = match (outcome, failure) {
+            (Some(outcome), _) => outcome.status,
+            (None, Some(_)) => TestStatus::Failed,
+            (None, None) => TestStatus::Passed,
+        };
+        Self {
+            status,
+            duration: outcome.and_then(|outcome| outcome.duration),
+            message: failure
+                .filter(|_| status == TestStatus::Failed)
+                .map(|diagnostic| diagnostic.message.clone()),
+            output: outcome
+                .filter(|_| status == TestStatus::Failed)
+                .and_then(|outcome| outcome.output.clone()),
+        }
+    }
+
+    fn to_markdown(&self, test_name: &str) -> String {
+        let status = match self.status {
+            TestStatus::Passed => "passed",
+            TestStatus::Failed => "failed",
+            TestStatus::Ignored => "ignored",
+        };
+        let mut markdown = format!("`{test_name}` **{status}**");
+        if let Some(duration) = self.duration {
+            let _ = write!(markdown, " in {duration:.2?}");
+        }
+        if let Some(message) = &self.message {
+            let _ = write!(markdown, "\n\n```text\n{}\n```", message.trim_end());
+        }
+        markdown
+    }
+}
+
+/// Whether a diagnostic reports a failure of the test, either by lying within
+/// its definition or by pointing back at it. Informational diagnostics, like
+/// skipped tests, aren't failures.
+fn is_failure_of(diagnostic: &Diagnostic, test_item: &TestItem) -> bool {
+    if matches!(
+        diagnostic.severity,
+        Some(DiagnosticSeverity::INFORMATION | DiagnosticSeverity::HINT)
+    ) {
+        return false;
+    }
+    let line = diagnostic.range.start.line;
+    let within =
+        (test_item.start_position.start.line..=test_item.end_position.end.line).contains(&line);
+    let points_back = diagnostic
+        .related_information
+        .iter()
+        .flatten()
+        .any(|info| info.location.range == test_item.start_position);
+    within || points_back
+}
+
+/// The innermost test whose definition contains the position.
+#[must_use]
+pub fn test_at(tests: &[TestItem], position: Position) -> Option<&TestItem> {
+    tests
+        .iter()
+        .filter(|test| {
+            (test.start_position.start.line..=test.end_position.end.line).contains(&position.line)
+        })
+        .max_by_key(|test| test.start_position.start.line)
+}
+
+/// Hover for a test, showing its last result or that it hasn't run yet.
+#[must_use]
+pub fn hover(test_item: &TestItem, last_result: Option<&LastResult>) -> Hover {
+    let value = last_result.map_or_else(
+        || format!("`{}` has not run yet", test_item.name),
+        |result| result.to_markdown(&test_item.name),
+    );
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(test_item.start_position),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn test_item(name: &str, start: u32, end: u32) -> TestItem {
+        TestItem {
+            id: name.to_string(),
+            name: name.to_string(),
+            path: "lib.rs".to_string(),
+            start_position: Range::new(Position::new(start, 0), Position::new(start, 10)),
+            end_position: Range::new(Position::new(end, 0), Position::new(end, 1)),
+        }
+    }
+
+    #[test]
+    fn test_test_at_prefers_innermost() {
+        let tests = [test_item("outer", 0, 20), test_item("inner", 5, 8)];
+        assert_eq!(test_at(&tests, Position::new(6, 3)).unwrap().name, "inner");
+        assert_eq!(test_at(&tests, Position::new(12, 0)).unwrap().name, "outer");
+        assert!(test_at(&tests, Position::new(30, 0)).is_none());
+    }
+
+    #[test]
+    fn test_last_result_markdown() {
+        let item = test_item("adds", 3, 6);
+        let diagnostic = Diagnostic {
+            range: Range::new(Position::new(4, 4), Position::new(4, 10)),
+            message: "assertion failed".to_string(),
+            ..Diagnostic::default()
+        };
+        let outcome = TestOutcome {
+            id: "adds".to_string(),
+            status: TestStatus::Failed,
+            duration: Some(Duration::from_millis(12)),
+            output: Some("running adds\n".to_string()),
+        };
+
+        let failed = LastResult::new(&item, Some(&outcome), &[diagnostic.clone()]);
+        assert_eq!(
+            failed.to_markdown("adds"),
+            "`adds` **failed** in 12.00ms\n\n```text\nassertion failed\n```"
+        );
+        assert_eq!(failed.output.as_deref(), Some("running adds\n"));
+
+        let passed = LastResult::new(&test_item("other", 10, 12), None, &[diagnostic]);
+        assert_eq!(passed.status, TestStatus::Passed);
+        assert_eq!(passed.message, None);
+    }
+}
@@ -3,67 +3,233 @@ use std::{
     process::{Command, Output},
 };
 
-use crate::{config, error::LSError, log::write_result_log};
+use crate::{
+    config,
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
 
-pub fn run_jest(workspace: &str) -> Result<(Output, PathBuf), LSError> {
-    let log_path = PathBuf::from(&config::CONFIG.cache_dir).join("jest.json");
+/// JSON report written by the Jest command.
+#[must_use]
+pub fn jest_results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("jest.json")
+}
 
-    let output = Command::new("jest")
-        .current_dir(workspace)
-        .args([
-            "--testLocationInResults",
-            "--forceExit",
-            "--no-coverage",
-            "--verbose",
-            "--json",
-            "--outputFile",
-            log_path.to_str().unwrap(),
-        ])
-        .output()?;
+pub fn jest_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut jest = test_command(context, command, &["jest"])?;
+    jest.args([
+        "--testLocationInResults",
+        "--forceExit",
+        "--no-coverage",
+        "--verbose",
+        "--json",
+        "--outputFile",
+    ])
+    .arg(jest_results_path())
+    .args(extra_args);
+    Ok(jest)
+}
+
+pub fn run_jest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
 
     write_result_log("jest.log", &output)?;
-    Ok((output, log_path))
+    Ok(output)
 }
 
-pub fn run_vitest(workspace: &str) -> Result<(Output, PathBuf), LSError> {
-    let log_path = PathBuf::from(&config::CONFIG.cache_dir).join("vitest.json");
+/// JSON report written by the Vitest command.
+#[must_use]
+pub fn vitest_results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("vitest.json")
+}
 
-    let output = Command::new("vitest")
-        .current_dir(workspace)
+pub fn vitest_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut vitest = test_command(context, command, &["vitest"])?;
+    vitest
         .args([
             "--watch=false",
             "--reporter=json",
-            &format!("--outputFile={}", log_path.display()),
+            &format!("--outputFile={}", vitest_results_path().display()),
         ])
-        .output()?;
+        .args(extra_args);
+    Ok(vitest)
+}
+
+pub fn run_vitest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
 
     write_result_log("vitest.log", &output)?;
-    Ok((output, log_path))
+    Ok(output)
+}
+
+/// JUnit report written by the `bun test` command.
+#[must_use]
+pub fn bun_results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("bun.xml")
+}
+
+pub fn bun_test_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut bun = test_command(context, command, &["bun", "test"])?;
+    bun.args([
+        "--reporter=junit",
+        &format!("--reporter-outfile={}", bun_results_path().display()),
+    ])
+    .args(extra_args)
+    .args(file_paths);
+    Ok(bun)
+}
+
+pub fn run_bun_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("bun.log", &output)?;
+    Ok(output)
+}
+
+pub fn ava_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut ava = test_command(context, command, &["ava"])?;
+    ava.arg("--tap").args(extra_args).args(file_paths);
+    Ok(ava)
+}
+
+pub fn run_ava(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("ava.log", &output)?;
+    Ok(output)
+}
+
+pub fn deno_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut deno = test_command(context, command, &["deno", "test"])?;
+    deno.arg("--no-prompt").args(extra_args).args(file_paths);
+    Ok(deno)
 }
 
-pub fn run_deno(workspace: &str, file_paths: &[String]) -> Result<Output, LSError> {
-    let output = Command::new("deno")
-        .current_dir(workspace)
-        .args(["test", "--no-prompt"])
-        .args(file_paths)
-        .output()?;
+pub fn run_deno(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
 
     write_result_log("deno.log", &output)?;
     Ok(output)
 }
 
-pub fn run_node_test(
-    workspace: &str,
+/// Major and minor version of a `node --version` output like `v20.11.1`.
+fn parse_node_version(output: &str) -> Option<(u32, u32)> {
+    let mut parts = output.trim().trim_start_matches('v').split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+/// Reporter arguments for a Node version: JUnit from Node 20.11 and 21.3,
+/// otherwise TAP. Unknown versions are assumed to be recent.
+fn node_reporter_args(version: Option<(u32, u32)>) -> &'static [&'static str] {
+    match version {
+        Some((major, minor))
+            if major < 20 || (major == 20 && minor < 11) || (major == 21 && minor < 3) =>
+        {
+            // The reporter can't be chosen before Node 18.15 and 19.6, but
+            // TAP is the default there
+            if major < 18 || (major == 18 && minor < 15) || (major == 19 && minor < 6) {
+                &[]
+            } else {
+                &["--test-reporter", "tap"]
+            }
+        }
+        _ => &["--test-reporter", "junit"],
+    }
+}
+
+/// Command running `node --test`. The installed Node version is checked to
+/// pick a reporter it supports.
+pub fn node_test_command(
+    context: &RunContext,
     file_paths: &[String],
     extra_args: &[String],
-) -> Result<Output, LSError> {
-    let output = Command::new("node")
-        .current_dir(workspace)
-        .args(["--test", "--test-reporter", "junit"])
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let version = test_command(context, command, &["node"])?
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| parse_node_version(&String::from_utf8_lossy(&output.stdout)));
+
+    let mut node = test_command(context, command, &["node"])?;
+    node.arg("--test")
+        .args(node_reporter_args(version))
         .args(extra_args)
-        .args(file_paths)
-        .output()?;
+        .args(file_paths);
+    Ok(node)
+}
+
+pub fn run_node_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
 
     write_result_log("node-test.xml", &output)?;
     Ok(output)
 }
+
+/// JSON report written by the Playwright command.
+#[must_use]
+pub fn playwright_results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("playwright.json")
+}
+
+pub fn playwright_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut playwright = test_command(context, command, &["playwright", "test"])?;
+    playwright
+        .env("PLAYWRIGHT_JSON_OUTPUT_NAME", playwright_results_path())
+        .arg("--reporter=json")
+        .args(extra_args)
+        .args(file_paths);
+    Ok(playwright)
+}
+
+pub fn run_playwright(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("playwright.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_reporter_args() {
+        let reporter = |output: &str| node_reporter_args(parse_node_version(output));
+        assert_eq!(reporter("v22.3.0\n"), ["--test-reporter", "junit"]);
+        assert_eq!(reporter("v20.11.1"), ["--test-reporter", "junit"]);
+        assert_eq!(reporter("v21.2.0"), ["--test-reporter", "tap"]);
+        assert_eq!(reporter("v18.19.0"), ["--test-reporter", "tap"]);
+        assert!(reporter("v18.12.1").is_empty());
+        assert_eq!(reporter(""), ["--test-reporter", "junit"]);
+    }
+}
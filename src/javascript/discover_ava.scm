@@ -0,0 +1,28 @@
+; AVA discovery query
+; AVA has no `describe`, tests are flat calls to `test`
+
+; -- Tests --
+; Matches: `test('name', t => {})` or `test('name', macro, ...args)`
+((call_expression
+  function: (identifier) @func_name (#eq? @func_name "test")
+  arguments: (arguments . (string (string_fragment) @test.name) . (_))
+)) @test.definition
+; Matches: `test.serial('name', t => {})`, also `only`, `skip` and `failing`
+((call_expression
+  function: (member_expression
+    object: (identifier) @func_name (#eq? @func_name "test")
+    property: (property_identifier) @modifier (#any-of? @modifier "serial" "only" "skip" "failing")
+  )
+  arguments: (arguments . (string (string_fragment) @test.name) . (_))
+)) @test.definition
+; Matches: `test.serial.only('name', t => {})`
+((call_expression
+  function: (member_expression
+    object: (member_expression
+      object: (identifier) @func_name (#eq? @func_name "test")
+      property: (property_identifier) @modifier (#eq? @modifier "serial")
+    )
+    property: (property_identifier) @modifier2 (#any-of? @modifier2 "only" "skip" "failing")
+  )
+  arguments: (arguments . (string (string_fragment) @test.name) . (_))
+)) @test.definition
@@ -0,0 +1,61 @@
+; Playwright test discovery query
+; Same shape as the Jest query, with groups declared by `test.describe`
+
+; -- Namespaces --
+; Alternatives for the callback are separate patterns, so that each group is
+; matched before the tests in it
+; Matches: `test.describe('group', () => {})`
+((call_expression
+  function: (member_expression
+    object: (identifier) @func_name (#eq? @func_name "test")
+    property: (property_identifier) @describe (#eq? @describe "describe")
+  )
+  arguments: (arguments (string (string_fragment) @namespace.name) (arrow_function))
+)) @namespace.definition
+; Matches: `test.describe('group', function() {})`
+((call_expression
+  function: (member_expression
+    object: (identifier) @func_name (#eq? @func_name "test")
+    property: (property_identifier) @describe (#eq? @describe "describe")
+  )
+  arguments: (arguments (string (string_fragment) @namespace.name) (function_expression))
+)) @namespace.definition
+; Matches: `test.describe.serial('group', () => {})`, also `parallel`, `only`,
+; `skip` and `fixme`
+((call_expression
+  function: (member_expression
+    object: (member_expression
+      object: (identifier) @func_name (#eq? @func_name "test")
+      property: (property_identifier) @describe (#eq? @describe "describe")
+    )
+    property: (property_identifier) @modifier (#any-of? @modifier "serial" "parallel" "only" "skip" "fixme")
+  )
+  arguments: (arguments (string (string_fragment) @namespace.name) (arrow_function))
+)) @namespace.definition
+; Matches: `test.describe.serial('group', function() {})`
+((call_expression
+  function: (member_expression
+    object: (member_expression
+      object: (identifier) @func_name (#eq? @func_name "test")
+      property: (property_identifier) @describe (#eq? @describe "describe")
+    )
+    property: (property_identifier) @modifier (#any-of? @modifier "serial" "parallel" "only" "skip" "fixme")
+  )
+  arguments: (arguments (string (string_fragment) @namespace.name) (function_expression))
+)) @namespace.definition
+
+; -- Tests --
+; Matches: `test('name', async ({ page }) => {})`
+((call_expression
+  function: (identifier) @func_name (#eq? @func_name "test")
+  arguments: (arguments (string (string_fragment) @test.name) [(arrow_function) (function_expression)])
+)) @test.definition
+; Matches: `test.only('name', async ({ page }) => {})`, also `skip`, `fixme`,
+; `fail` and `slow`
+((call_expression
+  function: (member_expression
+    object: (identifier) @func_name (#eq? @func_name "test")
+    property: (property_identifier) @modifier (#any-of? @modifier "only" "skip" "fixme" "fail" "slow")
+  )
+  arguments: (arguments (string (string_fragment) @test.name) [(arrow_function) (function_expression)])
+)) @test.definition
@@ -1,19 +1,64 @@
 pub mod call;
 pub mod parse;
 
-use std::{collections::HashSet, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
 
 use lsp_types::{Position, Range};
-use tree_sitter::{Language, Point, Query, QueryCursor};
+use tree_sitter::{Language, Point, Query, QueryCursor, Tree};
 
 use crate::{
-    Diagnostics, DiscoveredTests, FileDiagnostics, FileTests, MAX_CHAR_LENGTH, TestItem,
-    Workspaces, error::LSError, runner::Runner,
+    Diagnostics, DiscoveredTests, FileDiagnostics, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files, name_filter_args, name_selection_args},
+    workspace::detect_package_workspaces,
 };
 
 const DISCOVER_JEST_QUERY: &str = include_str!("discover_jest.scm");
 const DISCOVER_DENO_QUERY: &str = include_str!("discover_deno.scm");
 const DISCOVER_NODE_TEST_QUERY: &str = include_str!("discover_node_test.scm");
+const DISCOVER_AVA_QUERY: &str = include_str!("discover_ava.scm");
+const DISCOVER_PLAYWRIGHT_QUERY: &str = include_str!("discover_playwright.scm");
+
+pub(crate) const PLAYWRIGHT_CONFIG_FILES: &[&str] = &[
+    "playwright.config.ts",
+    "playwright.config.js",
+    "playwright.config.mts",
+    "playwright.config.mjs",
+];
+
+const VITEST_MARKER_FILES: &[&str] = &[
+    "package.json",
+    "vitest.config.ts",
+    "vitest.config.js",
+    "vite.config.ts",
+    "vite.config.js",
+    "vitest.config.mts",
+    "vitest.config.mjs",
+    "vite.config.mts",
+    "vite.config.mjs",
+];
+/// Multi-project Vitest configs, run from their own directory
+const VITEST_WORKSPACE_FILES: &[&str] = &[
+    "vitest.workspace.ts",
+    "vitest.workspace.js",
+    "vitest.workspace.mts",
+    "vitest.workspace.mjs",
+    "vitest.workspace.json",
+];
+
+/// Nearest directory above a file containing one of the marker files.
+fn find_marker_dir(file_path: &str, marker_files: &[&str]) -> Option<PathBuf> {
+    Path::new(file_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| marker_files.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
 
 fn discover_with_treesitter(
     file_path: &str,
@@ -21,12 +66,23 @@ fn discover_with_treesitter(
     query: &str,
 ) -> Result<Vec<TestItem>, LSError> {
     let mut parser = tree_sitter::Parser::new();
-    let mut test_items: Vec<TestItem> = vec![];
     parser.set_language(language)?;
     let source_code = std::fs::read_to_string(file_path)?;
     let tree = parser
         .parse(&source_code, None)
         .ok_or(LSError::TreeSitterParse)?;
+    discover_in_tree(file_path, &source_code, &tree, language, query)
+}
+
+/// Run a discovery query over an already parsed syntax tree.
+fn discover_in_tree(
+    file_path: &str,
+    source_code: &str,
+    tree: &Tree,
+    language: &Language,
+    query: &str,
+) -> Result<Vec<TestItem>, LSError> {
+    let mut test_items: Vec<TestItem> = vec![];
     let query = Query::new(language, query)?;
 
     let mut cursor = QueryCursor::new();
@@ -126,6 +182,12 @@ fn discover_with_treesitter(
     Ok(test_items)
 }
 
+/// Name of the test `id` that Jest, Vitest and Bun match `-t` against: the
+/// names of its `describe` block and of the test, joined by a space.
+fn full_name(id: &str) -> String {
+    id.replace("::", " ")
+}
+
 // --- Jest Runner ---
 
 #[derive(Eq, PartialEq, Hash, Debug)]
@@ -134,31 +196,79 @@ pub struct JestRunner;
 impl Runner for JestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
         let language = tree_sitter_javascript::language();
-        let mut files = Vec::new();
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_JEST_QUERY)
+        }))
+    }
 
-        for file_path in file_paths {
-            let tests = discover_with_treesitter(file_path, &language, DISCOVER_JEST_QUERY)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.clone(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_JEST_QUERY,
+        )
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        _extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::jest_command(
+            context,
+            &name_selection_args("-t", context, filter, full_name),
+            command,
+        )
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
-        _extra_args: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_jest(&mut self.build_command(file_paths, context, extra_args, command, filter)?)?;
+        self.parse_results(&call::jest_results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
     ) -> Result<Diagnostics, LSError> {
-        let (_, log_path) = call::run_jest(workspace)?;
-        let test_result = std::fs::read_to_string(log_path)?;
-        parse::parse_jest_json(&test_result, file_paths)
+        let test_result = std::fs::read_to_string(results_path)?;
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .collect();
+        parse::parse_jest_json(&test_result, file_paths, &test_items)
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
-        crate::workspace::detect_from_files(file_paths, &["package.json"])
+        detect_package_workspaces(file_paths, &["package.json"])
     }
 }
 
@@ -170,46 +280,293 @@ pub struct VitestRunner;
 impl Runner for VitestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
         let language = tree_sitter_javascript::language();
-        let mut files = Vec::new();
+        // Vitest uses the same query as Jest
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_JEST_QUERY)
+        }))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_JEST_QUERY,
+        )
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        _extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::vitest_command(
+            context,
+            &name_selection_args("-t", context, filter, full_name),
+            command,
+        )
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_vitest(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(&call::vitest_results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let test_result = std::fs::read_to_string(results_path)?;
+        let mut project_dirs: Vec<PathBuf> = file_paths
+            .iter()
+            .filter_map(|path| find_marker_dir(path, VITEST_MARKER_FILES))
+            .collect();
+        project_dirs.sort();
+        project_dirs.dedup();
+        parse::parse_vitest_json(
+            &test_result,
+            Path::new(workspace),
+            &project_dirs,
+            file_paths,
+        )
+    }
 
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        // A workspace config runs all of its projects at once
+        let mut workspace_config_roots: HashMap<String, Vec<String>> = HashMap::new();
+        let mut other_paths = vec![];
         for file_path in file_paths {
-            // Vitest uses the same query as Jest
-            let tests = discover_with_treesitter(file_path, &language, DISCOVER_JEST_QUERY)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.clone(),
-            });
+            match find_marker_dir(file_path, VITEST_WORKSPACE_FILES) {
+                Some(root) => workspace_config_roots
+                    .entry(root.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone()),
+                None => other_paths.push(file_path.clone()),
+            }
         }
-        Ok(DiscoveredTests { files })
+
+        let mut workspaces = detect_package_workspaces(&other_paths, VITEST_MARKER_FILES);
+        workspaces.map.extend(workspace_config_roots);
+        workspaces
+    }
+}
+
+// --- Bun Runner ---
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct BunTestRunner;
+
+impl Runner for BunTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        let language = tree_sitter_javascript::language();
+        // Bun's test API is Jest-compatible
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_JEST_QUERY)
+        }))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_JEST_QUERY,
+        )
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let extra_args = [
+            name_selection_args("-t", context, filter, full_name),
+            extra_args.to_vec(),
+        ]
+        .concat();
+        call::bun_test_command(context, file_paths, &extra_args, command)
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_bun_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(&call::bun_results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
         workspace: &str,
-        _extra_args: &[String],
     ) -> Result<Diagnostics, LSError> {
-        let (_, log_path) = call::run_vitest(workspace)?;
-        let test_result = std::fs::read_to_string(log_path)?;
-        parse::parse_vitest_json(&test_result, file_paths.to_vec())
+        let test_result = std::fs::read_to_string(results_path)?;
+        parse::parse_bun_output(&test_result, &PathBuf::from(workspace), file_paths)
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
-        crate::workspace::detect_from_files(
+        detect_package_workspaces(file_paths, &["package.json", "bunfig.toml"])
+    }
+}
+
+// --- AVA Runner ---
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct AvaRunner;
+
+impl AvaRunner {
+    fn parse_ava(
+        &self,
+        tap: &str,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .collect();
+        Ok(parse::parse_ava_tap(
+            tap,
+            &PathBuf::from(workspace),
             file_paths,
-            &[
-                "package.json",
-                "vitest.config.ts",
-                "vitest.config.js",
-                "vite.config.ts",
-                "vite.config.js",
-                "vitest.config.mts",
-                "vitest.config.mjs",
-                "vite.config.mts",
-                "vite.config.mjs",
-            ],
+            &test_items,
+        ))
+    }
+}
+
+impl Runner for AvaRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        let language = tree_sitter_javascript::language();
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_AVA_QUERY)
+        }))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_AVA_QUERY,
         )
     }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        // AVA matches titles against wildcard patterns rather than regexes
+        let match_args = filter.map_or_else(Vec::new, |filter| {
+            vec!["--match".to_string(), format!("*{filter}*")]
+        });
+        let extra_args = [match_args, extra_args.to_vec()].concat();
+        call::ava_command(context, file_paths, &extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_ava(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+
+        if output.stdout.is_empty() && !output.stderr.is_empty() {
+            return Err(LSError::AdapterError);
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        self.parse_ava(&stdout, file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        self.parse_ava(
+            &std::fs::read_to_string(results_path)?,
+            file_paths,
+            workspace,
+        )
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        detect_package_workspaces(file_paths, &["package.json"])
+    }
 }
 
 // --- Deno Runner ---
@@ -220,25 +577,56 @@ pub struct DenoRunner;
 impl Runner for DenoRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
         let language = tree_sitter_javascript::language();
-        let mut files = Vec::new();
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_DENO_QUERY)
+        }))
+    }
 
-        for file_path in file_paths {
-            let tests = discover_with_treesitter(file_path, &language, DISCOVER_DENO_QUERY)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.clone(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
     }
 
-    fn run_tests(
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_DENO_QUERY,
+        )
+    }
+
+    fn build_command(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         _extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        // Deno matches a plain filter as a substring
+        let filter_args = filter.map_or_else(Vec::new, |filter| {
+            vec!["--filter".to_string(), filter.to_string()]
+        });
+        call::deno_command(context, file_paths, &filter_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
     ) -> Result<Diagnostics, LSError> {
-        let output = call::run_deno(workspace, file_paths)?;
+        let output = call::run_deno(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
 
         if output.stdout.is_empty() {
             return Err(LSError::AdapterError);
@@ -247,7 +635,20 @@ impl Runner for DenoRunner {
         let test_result = String::from_utf8(output.stdout)?;
         parse::parse_deno_output(
             &test_result,
-            PathBuf::from_str(workspace).unwrap(),
+            PathBuf::from_str(context.workspace).unwrap(),
+            file_paths,
+        )
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        parse::parse_deno_output(
+            &std::fs::read_to_string(results_path)?,
+            PathBuf::from(workspace),
             file_paths,
         )
     }
@@ -262,41 +663,96 @@ impl Runner for DenoRunner {
 #[derive(Eq, PartialEq, Hash, Debug)]
 pub struct NodeTestRunner;
 
+/// Diagnostics of the JUnit or, from Node versions without a JUnit reporter,
+/// TAP output of a run.
+fn node_test_diagnostics(output: &str, file_paths: &[String]) -> Diagnostics {
+    let results = if output.trim_start().starts_with('<') {
+        parse::parse_node_test_xml(output, file_paths)
+    } else {
+        parse::parse_node_test_tap(output, file_paths)
+    };
+    Diagnostics {
+        files: results
+            .into_iter()
+            .map(Into::<FileDiagnostics>::into)
+            .collect(),
+        messages: vec![],
+    }
+}
+
 impl Runner for NodeTestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
         let language = tree_sitter_javascript::language();
-        let mut files = Vec::new();
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_NODE_TEST_QUERY)
+        }))
+    }
 
-        for file_path in file_paths {
-            let tests = discover_with_treesitter(file_path, &language, DISCOVER_NODE_TEST_QUERY)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.to_string(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_NODE_TEST_QUERY,
+        )
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let extra_args = [
+            name_filter_args("--test-name-pattern", filter),
+            extra_args.to_vec(),
+        ]
+        .concat();
+        call::node_test_command(context, file_paths, &extra_args, command)
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
     ) -> Result<Diagnostics, LSError> {
-        let output = call::run_node_test(workspace, file_paths, extra_args)?;
+        let output = call::run_node_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
 
         if output.stdout.is_empty() && !output.stderr.is_empty() {
             return Err(LSError::AdapterError);
         }
 
         let stdout = String::from_utf8(output.stdout)?;
-        let results = parse::parse_node_test_xml(&stdout, file_paths);
-        let result_item: Vec<FileDiagnostics> = results.into_iter().map(Into::into).collect();
+        Ok(node_test_diagnostics(&stdout, file_paths))
+    }
 
-        Ok(Diagnostics {
-            files: result_item,
-            messages: vec![],
-        })
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(node_test_diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            file_paths,
+        ))
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
@@ -304,6 +760,83 @@ impl Runner for NodeTestRunner {
     }
 }
 
+// --- Playwright Runner ---
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct PlaywrightRunner;
+
+impl Runner for PlaywrightRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        let language = tree_sitter_javascript::language();
+        Ok(discover_files(file_paths, |file_path| {
+            discover_with_treesitter(file_path, &language, DISCOVER_PLAYWRIGHT_QUERY)
+        }))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_javascript::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_in_tree(
+            file_path,
+            source,
+            tree,
+            &tree_sitter_javascript::language(),
+            DISCOVER_PLAYWRIGHT_QUERY,
+        )
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let extra_args = [name_filter_args("-g", filter), extra_args.to_vec()].concat();
+        call::playwright_command(context, file_paths, &extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_playwright(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(
+            &call::playwright_results_path(),
+            file_paths,
+            context.workspace,
+        )
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let test_result = std::fs::read_to_string(results_path)?;
+        parse::parse_playwright_json(&test_result, Path::new(workspace), file_paths)
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, PLAYWRIGHT_CONFIG_FILES)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -326,6 +859,70 @@ mod tests {
         assert!(!test_items.is_empty());
     }
 
+    #[test]
+    fn test_discover_bun() {
+        let discovered = BunTestRunner
+            .discover(&["demo/bun/index.test.ts".to_string()])
+            .unwrap();
+        let ids: Vec<_> = discovered.files[0]
+            .tests
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["math::adds", "math::subtracts"]);
+    }
+
+    #[test]
+    fn test_discover_ava() {
+        let discovered = AvaRunner
+            .discover(&["demo/ava/math.test.js".to_string()])
+            .unwrap();
+        let ids: Vec<_> = discovered.files[0]
+            .tests
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["adds", "subtracts", "resolves"]);
+    }
+
+    #[test]
+    fn test_discover_playwright() {
+        let discovered = PlaywrightRunner
+            .discover(&["demo/playwright/tests/example.spec.ts".to_string()])
+            .unwrap();
+        let ids: Vec<_> = discovered.files[0]
+            .tests
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "has title",
+                "navigation::shows heading",
+                "navigation::not ready"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_vitest_workspace_config() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::create_dir_all(root.join("packages/a")).unwrap();
+        std::fs::create_dir_all(root.join("standalone")).unwrap();
+        std::fs::write(root.join("vitest.workspace.ts"), "").unwrap();
+        std::fs::write(root.join("packages/a/vite.config.ts"), "").unwrap();
+        let member = root.join("packages/a/sum.test.ts");
+        let file_paths = vec![member.to_string_lossy().to_string()];
+
+        let workspaces = VitestRunner.detect_workspaces(&file_paths);
+        assert_eq!(
+            workspaces.map.get(root.to_str().unwrap()),
+            Some(&file_paths)
+        );
+    }
+
     #[test]
     fn test_discover_deno() {
         let file_path = "demo/deno/main_test.ts";
@@ -1,11 +1,46 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
 use regex::Regex;
+use serde::Deserialize;
 use serde_json::Value;
 use xml::{ParserConfig, reader::XmlEvent};
 
-use crate::{Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, error::LSError};
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    error::LSError,
+    junit::parse_testcases,
+    tap::{self, TapResult},
+    workspace::{contains_path, resolve_path, same_file, same_path},
+};
+
+/// The one of `file_paths` a test file reported by the runner is: the same
+/// file, possibly through a symlink, or else the only file with its name.
+fn reported_file<'a>(file_paths: &'a [String], reported: &str) -> Option<&'a String> {
+    if let Some(path) = file_paths
+        .iter()
+        .find(|path| same_path(path, reported))
+        .or_else(|| file_paths.iter().find(|path| same_file(path, reported)))
+    {
+        return Some(path);
+    }
+    let file_name = |path: &str| {
+        Path::new(path)
+            .file_name()
+            .map(std::ffi::OsStr::to_ascii_lowercase)
+    };
+    let name = file_name(reported)?;
+    let mut same_name = file_paths
+        .iter()
+        .filter(|path| file_name(path).as_ref() == Some(&name));
+    match (same_name.next(), same_name.next()) {
+        (Some(path), None) => Some(path),
+        _ => None,
+    }
+}
 
 /// Clean ANSI escape sequences from text
 pub fn clean_ansi(input: &str) -> String {
@@ -13,77 +48,117 @@ pub fn clean_ansi(input: &str) -> String {
     re.replace_all(input, "").to_string()
 }
 
-/// Resolve a relative path against a base directory
-pub fn resolve_path(base_dir: &std::path::Path, relative_path: &str) -> PathBuf {
-    let absolute = if std::path::Path::new(relative_path).is_absolute() {
-        PathBuf::from(relative_path)
-    } else {
-        base_dir.join(relative_path)
-    };
-
-    let mut components = Vec::new();
-    for component in absolute.components() {
-        match component {
-            std::path::Component::ParentDir => {
-                components.pop();
-            }
-            std::path::Component::Normal(_) | std::path::Component::RootDir => {
-                components.push(component);
-            }
-            _ => {}
-        }
+fn jest_diagnostic(line: u32, character: u32, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position { line, character },
+            end: Position {
+                line,
+                character: MAX_CHAR_LENGTH,
+            },
+        },
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("jest".to_string()),
+        code: Some(NumberOrString::String("jest-failed".to_string())),
+        ..Diagnostic::default()
     }
+}
 
-    PathBuf::from_iter(components)
+/// Zero-based line and column of the first `file:line:column` stack frame
+/// of `message` in the file Jest reported as `reported`.
+fn reported_frame(message: &str, reported: &str) -> Option<(u32, u32)> {
+    let re = Regex::new(r"([^\s()]+):(\d+):(\d+)").unwrap();
+    re.captures_iter(message).find_map(|caps| {
+        if !same_path(caps.get(1)?.as_str(), reported) {
+            return None;
+        }
+        Some((
+            caps.get(2)?.as_str().parse::<u32>().ok()?.saturating_sub(1),
+            caps.get(3)?.as_str().parse::<u32>().ok()?.saturating_sub(1),
+        ))
+    })
 }
 
 /// Parse Jest JSON output format
-pub fn parse_jest_json(test_result: &str, file_paths: &[String]) -> Result<Diagnostics, LSError> {
+///
+/// Each failure message, like those of several `expect.soft` assertions of a
+/// test, is placed at its first stack frame in the test file, falling back to
+/// the location of the test. Failures are linked to the discovered test with
+/// the same `describe` and test titles. A test file that failed without running
+/// any test, e.g. one throwing when imported, gets a single diagnostic at its
+/// first line.
+pub fn parse_jest_json(
+    test_result: &str,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Result<Diagnostics, LSError> {
     let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
     let json: Value = serde_json::from_str(test_result)?;
-    let test_results = json["testResults"].as_array().unwrap();
+    let test_results = json["testResults"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
 
     for test_result in test_results {
-        let file_path = test_result["name"].as_str().unwrap();
-        if !file_paths.iter().any(|path| path.contains(file_path)) {
+        let reported = test_result["name"].as_str().unwrap_or_default();
+        let Some(file_path) = reported_file(file_paths, reported) else {
+            continue;
+        };
+        let assertion_results = test_result["assertionResults"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+
+        if assertion_results.is_empty() && test_result["status"].as_str() == Some("failed") {
+            let message = [&test_result["message"], &test_result["failureMessage"]]
+                .into_iter()
+                .filter_map(Value::as_str)
+                .find(|message| !message.trim().is_empty())
+                .unwrap_or("Test suite failed to run");
+            result_map
+                .entry(file_path.clone())
+                .or_default()
+                .push(jest_diagnostic(0, 0, clean_ansi(message.trim())));
             continue;
         }
-        let assertion_results = test_result["assertionResults"].as_array().unwrap();
 
-        'assertion: for assertion_result in assertion_results {
-            let status = assertion_result["status"].as_str().unwrap();
-            if status != "failed" {
-                continue 'assertion;
+        for assertion_result in assertion_results {
+            if assertion_result["status"].as_str() != Some("failed") {
+                continue;
             }
-            let location = assertion_result["location"].as_object().unwrap();
-            let failure_messages = assertion_result["failureMessages"].as_array().unwrap();
-            let line = location["line"].as_u64().unwrap() - 1;
-            let column = location["column"].as_u64().unwrap() - 1;
+            let location = &assertion_result["location"];
+            let line = location["line"].as_u64().unwrap_or(1).saturating_sub(1);
+            let column = location["column"].as_u64().unwrap_or(1).saturating_sub(1);
+            let test_id = assertion_result["ancestorTitles"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .chain(std::iter::once(&assertion_result["title"]))
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("::");
+            let related_information = test_items
+                .iter()
+                .find(|item| item.id == test_id && same_path(&item.path, file_path))
+                .and_then(TestItem::definition_info)
+                .map(|info| vec![info]);
 
-            failure_messages.iter().for_each(|message| {
-                let message = clean_ansi(message.as_str().unwrap());
+            let failure_messages = assertion_result["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str);
+            for message in failure_messages {
+                let (line, column) =
+                    reported_frame(message, reported).unwrap_or((line as u32, column as u32));
                 let diagnostic = Diagnostic {
-                    range: Range {
-                        start: Position {
-                            line: line as u32,
-                            character: column as u32,
-                        },
-                        end: Position {
-                            line: line as u32,
-                            character: MAX_CHAR_LENGTH,
-                        },
-                    },
-                    message,
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    source: Some("jest".to_string()),
-                    code: Some(NumberOrString::String("jest-failed".to_string())),
-                    ..Diagnostic::default()
+                    related_information: related_information.clone(),
+                    ..jest_diagnostic(line, column, clean_ansi(message))
                 };
                 result_map
-                    .entry(file_path.to_string())
+                    .entry(file_path.clone())
                     .or_default()
                     .push(diagnostic);
-            })
+            }
         }
     }
 
@@ -96,58 +171,87 @@ pub fn parse_jest_json(test_result: &str, file_paths: &[String]) -> Result<Diagn
     })
 }
 
+fn vitest_diagnostic(line: u32, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position { line, character: 0 },
+            end: Position {
+                line,
+                character: MAX_CHAR_LENGTH,
+            },
+        },
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("vitest".to_string()),
+        code: Some(NumberOrString::String("vitest-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
 /// Parse Vitest JSON output format (similar to Jest but slightly different
 /// column handling)
+///
+/// With a Vitest workspace config, test file names may be relative to the
+/// project that ran them, so they are resolved against the workspace root and
+/// then each of `project_dirs`.
 pub fn parse_vitest_json(
     test_result: &str,
-    file_paths: Vec<String>,
+    workspace_root: &Path,
+    project_dirs: &[PathBuf],
+    file_paths: &[String],
 ) -> Result<Diagnostics, LSError> {
     let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
     let json: Value = serde_json::from_str(test_result)?;
-    let test_results = json["testResults"].as_array().unwrap();
+    let test_results = json["testResults"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
 
     for test_result in test_results {
-        let file_path = test_result["name"].as_str().unwrap();
-        if !file_paths.iter().any(|path| path.contains(file_path)) {
+        let name = test_result["name"].as_str().unwrap_or_default();
+        let Some(file_path) = std::iter::once(workspace_root)
+            .chain(project_dirs.iter().map(PathBuf::as_path))
+            .map(|root| resolve_path(root, name).to_string_lossy().to_string())
+            .find(|path| contains_path(file_paths, path))
+        else {
+            continue;
+        };
+        let assertion_results = test_result["assertionResults"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice);
+
+        if assertion_results.is_empty() && test_result["status"].as_str() == Some("failed") {
+            let message = test_result["message"]
+                .as_str()
+                .filter(|message| !message.trim().is_empty())
+                .unwrap_or("Test suite failed to run");
+            result_map
+                .entry(file_path)
+                .or_default()
+                .push(vitest_diagnostic(0, clean_ansi(message.trim())));
             continue;
         }
-        let assertion_results = test_result["assertionResults"].as_array().unwrap();
 
-        'assertion: for assertion_result in assertion_results {
-            let status = assertion_result["status"].as_str().unwrap();
-            if status != "failed" {
-                continue 'assertion;
+        for assertion_result in assertion_results {
+            if assertion_result["status"].as_str() != Some("failed") {
+                continue;
             }
-            let location = assertion_result["location"].as_object().unwrap();
-            let failure_messages = assertion_result["failureMessages"].as_array().unwrap();
-            let line = location["line"].as_u64().unwrap() - 1;
-
-            failure_messages.iter().for_each(|message| {
-                let message = clean_ansi(message.as_str().unwrap());
-                let diagnostic = Diagnostic {
-                    range: Range {
-                        start: Position {
-                            line: line as u32,
-                            // Line and column number is slightly incorrect.
-                            // Bug in json reporter: https://github.com/vitest-dev/vitest/discussions/5350
-                            character: 0,
-                        },
-                        end: Position {
-                            line: line as u32,
-                            character: MAX_CHAR_LENGTH,
-                        },
-                    },
-                    message,
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    source: Some("vitest".to_string()),
-                    code: Some(NumberOrString::String("vitest-failed".to_string())),
-                    ..Diagnostic::default()
-                };
+            // Line and column number is slightly incorrect.
+            // Bug in json reporter: https://github.com/vitest-dev/vitest/discussions/5350
+            let line = assertion_result["location"]["line"]
+                .as_u64()
+                .unwrap_or(1)
+                .saturating_sub(1);
+            let failure_messages = assertion_result["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str);
+            for message in failure_messages {
                 result_map
-                    .entry(file_path.to_string())
+                    .entry(file_path.clone())
                     .or_default()
-                    .push(diagnostic);
-            })
+                    .push(vitest_diagnostic(line as u32, clean_ansi(message)));
+            }
         }
     }
 
@@ -218,7 +322,7 @@ pub fn parse_deno_output(
                     .to_str()
                     .unwrap()
                     .to_string();
-                if file_paths.contains(&file_path) {
+                if contains_path(file_paths, &file_path) {
                     result_map.entry(file_path).or_default().push(diagnostic);
                 }
             }
@@ -238,6 +342,87 @@ pub fn parse_deno_output(
     })
 }
 
+/// Find the first `file:line:column` stack location pointing into a target
+/// file.
+fn find_stack_location(
+    text: &str,
+    workspace_root: &Path,
+    file_paths: &[String],
+) -> Option<(String, u32, u32)> {
+    let re = Regex::new(r"([^\s()]+):(\d+):(\d+)").unwrap();
+    re.captures_iter(text).find_map(|caps| {
+        let path = resolve_path(workspace_root, caps.get(1)?.as_str())
+            .to_string_lossy()
+            .to_string();
+        if !contains_path(file_paths, &path) {
+            return None;
+        }
+        Some((
+            path,
+            caps.get(2)?.as_str().parse().ok()?,
+            caps.get(3)?.as_str().parse().ok()?,
+        ))
+    })
+}
+
+/// Parse Bun test runner output from its `junit` reporter
+///
+/// Failures are placed at the first stack frame inside a target file, falling
+/// back to the line of the failing `<testcase>`.
+pub fn parse_bun_output(
+    contents: &str,
+    workspace_root: &Path,
+    file_paths: &[String],
+) -> Result<Diagnostics, LSError> {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for testcase in parse_testcases(contents)? {
+        let Some(failure) = &testcase.failure else {
+            continue;
+        };
+        let file = testcase.attribute("file");
+        if file.is_empty() {
+            continue;
+        }
+        let line = testcase.attribute("line").parse().unwrap_or(1);
+        let message = format!("{}\n{}", failure.message, failure.text.trim());
+        let (path, line, column) = find_stack_location(&message, workspace_root, file_paths)
+            .unwrap_or_else(|| {
+                let path = resolve_path(workspace_root, file);
+                (path.to_string_lossy().to_string(), line, 1)
+            });
+        if !contains_path(file_paths, &path) {
+            continue;
+        }
+        let diagnostic = Diagnostic {
+            range: Range {
+                start: Position {
+                    line: line.saturating_sub(1),
+                    character: column.saturating_sub(1),
+                },
+                end: Position {
+                    line: line.saturating_sub(1),
+                    character: MAX_CHAR_LENGTH,
+                },
+            },
+            message: clean_ansi(message.trim()),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("bun-test".to_string()),
+            code: Some(NumberOrString::String("bun-test-failed".to_string())),
+            ..Diagnostic::default()
+        };
+        result_map.entry(path).or_default().push(diagnostic);
+    }
+
+    Ok(Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    })
+}
+
 pub struct ResultFromXml {
     pub message: String,
     pub path: String,
@@ -275,7 +460,7 @@ fn parse_error_location(error_text: &str, target_file_paths: &[String]) -> Optio
     for line in error_text.lines() {
         if let Some(caps) = re.captures(line) {
             let file_path = caps.get(1)?.as_str();
-            if !target_file_paths.contains(&file_path.to_string()) {
+            if !contains_path(target_file_paths, file_path) {
                 continue;
             }
             return Some(ResultFromXml {
@@ -325,3 +510,537 @@ pub fn parse_node_test_xml(output: &str, target_file_paths: &[String]) -> Vec<Re
 
     results
 }
+
+/// Parse Node.js test runner TAP output, the default reporter of Node
+/// versions without a JUnit reporter.
+///
+/// Failures are placed at their first stack frame in a target file, falling
+/// back to the `location` of the test. Suites failing only because of their
+/// subtests are left out, as the subtests are reported themselves.
+#[must_use]
+pub fn parse_node_test_tap(output: &str, target_file_paths: &[String]) -> Vec<ResultFromXml> {
+    let results = tap::parse_tap(output);
+    results
+        .iter()
+        .flat_map(TapResult::failures)
+        .filter(|failure| failure.field("failureType") != Some("subtestsFailed"))
+        .filter_map(|failure| {
+            let (path, line, col) = failure
+                .locations()
+                .into_iter()
+                .find(|(path, _, _)| contains_path(target_file_paths, path))?;
+            Some(ResultFromXml {
+                message: failure
+                    .field("error")
+                    .unwrap_or(&failure.description)
+                    .to_string(),
+                path,
+                line,
+                col,
+            })
+        })
+        .collect()
+}
+
+/// Failure message of an AVA test: the assertion message followed by the
+/// diff of the compared values.
+fn ava_message(failure: &TapResult) -> String {
+    let lines: Vec<&str> = ["message", "values"]
+        .iter()
+        .filter_map(|key| failure.field(key))
+        .collect();
+    if lines.is_empty() {
+        "test failed".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// Parse AVA output from its `--tap` reporter
+///
+/// Failures are placed at the `at` stack frame when it points into a target
+/// file, falling back to the definition of the test with the failed title.
+#[must_use]
+pub fn parse_ava_tap(
+    contents: &str,
+    workspace_root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+    let results = tap::parse_tap(contents);
+    for failure in results.iter().flat_map(TapResult::failures) {
+        let location = failure.location().and_then(|(path, line, col)| {
+            find_stack_location(&format!("{path}:{line}:{col}"), workspace_root, file_paths)
+        });
+        let range = |line: u32, character: u32| Range {
+            start: Position { line, character },
+            end: Position {
+                line,
+                character: MAX_CHAR_LENGTH,
+            },
+        };
+        // Titles of tests in multiple files are prefixed with the file name
+        let test_name = failure.description.rsplit(" › ").next().unwrap_or_default();
+        let (path, range) = match location {
+            Some((path, line, col)) => (path, range(line.saturating_sub(1), col.saturating_sub(1))),
+            None => match test_items
+                .iter()
+                .find(|item| item.name == test_name && file_paths.contains(&item.path))
+            {
+                Some(item) => (item.path.clone(), item.start_position),
+                None => continue,
+            },
+        };
+        result_map.entry(path).or_default().push(Diagnostic {
+            range,
+            message: format!("[{}] {}", failure.description, ava_message(failure)),
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("ava".to_string()),
+            code: Some(NumberOrString::String("ava-failed".to_string())),
+            ..Diagnostic::default()
+        });
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaywrightReport {
+    config: PlaywrightConfig,
+    #[serde(default)]
+    suites: Vec<PlaywrightSuite>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaywrightConfig {
+    root_dir: String,
+}
+
+/// A test file or `test.describe` group, with the groups nested in it.
+#[derive(Debug, Deserialize)]
+struct PlaywrightSuite {
+    #[serde(default)]
+    specs: Vec<PlaywrightSpec>,
+    #[serde(default)]
+    suites: Vec<PlaywrightSuite>,
+}
+
+impl PlaywrightSuite {
+    /// Specs of this suite and of all suites nested in it.
+    fn flatten(&self) -> Vec<&PlaywrightSpec> {
+        self.specs
+            .iter()
+            .chain(self.suites.iter().flat_map(PlaywrightSuite::flatten))
+            .collect()
+    }
+}
+
+/// A single `test(...)`, run once per project.
+#[derive(Debug, Deserialize)]
+struct PlaywrightSpec {
+    title: String,
+    file: String,
+    line: u32,
+    column: u32,
+    #[serde(default)]
+    tests: Vec<PlaywrightTest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaywrightTest {
+    #[serde(default)]
+    project_name: String,
+    #[serde(default)]
+    results: Vec<PlaywrightResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaywrightResult {
+    status: String,
+    #[serde(default)]
+    errors: Vec<PlaywrightError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaywrightError {
+    message: Option<String>,
+    location: Option<PlaywrightLocation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaywrightLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+/// Parse the report of Playwright's `json` reporter
+///
+/// Failures are placed at the location of each error when it is in a target
+/// file, falling back to the `test(...)` call of the spec. Only the last
+/// attempt of a retried test counts.
+pub fn parse_playwright_json(
+    contents: &str,
+    workspace_root: &Path,
+    file_paths: &[String],
+) -> Result<Diagnostics, LSError> {
+    let report: PlaywrightReport = serde_json::from_str(contents)?;
+    // Spec files are relative to the test directory
+    let root_dir = resolve_path(workspace_root, &report.config.root_dir);
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for spec in report.suites.iter().flat_map(PlaywrightSuite::flatten) {
+        let spec_path = resolve_path(&root_dir, &spec.file)
+            .to_string_lossy()
+            .to_string();
+        for test in &spec.tests {
+            let Some(result) = test.results.last() else {
+                continue;
+            };
+            if !matches!(result.status.as_str(), "failed" | "timedOut") {
+                continue;
+            }
+            let title = if test.project_name.is_empty() {
+                spec.title.clone()
+            } else {
+                format!("[{}] {}", test.project_name, spec.title)
+            };
+            for error in &result.errors {
+                let (path, line, column) = error
+                    .location
+                    .as_ref()
+                    .map(|location| {
+                        let path = resolve_path(&root_dir, &location.file);
+                        (
+                            path.to_string_lossy().to_string(),
+                            location.line,
+                            location.column,
+                        )
+                    })
+                    .filter(|(path, _, _)| contains_path(file_paths, path))
+                    .unwrap_or_else(|| (spec_path.clone(), spec.line, spec.column));
+                if !contains_path(file_paths, &path) {
+                    continue;
+                }
+                let message = error.message.as_deref().map_or_else(
+                    || format!("{title} {}", result.status),
+                    |message| format!("{title}\n{}", clean_ansi(message)),
+                );
+                let line = line.saturating_sub(1);
+                result_map.entry(path).or_default().push(Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line,
+                            character: column.saturating_sub(1),
+                        },
+                        end: Position {
+                            line,
+                            character: MAX_CHAR_LENGTH,
+                        },
+                    },
+                    message,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("playwright".to_string()),
+                    code: Some(NumberOrString::String("playwright-failed".to_string())),
+                    ..Diagnostic::default()
+                });
+            }
+        }
+    }
+
+    Ok(Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jest_json_links_test_definition() {
+        let contents = std::fs::read_to_string("demo/jest/output.json").unwrap();
+        let file_path = "/absolute_path/demo/jest/index.spec.js".to_string();
+        let test_item = TestItem {
+            id: "index::fail".to_string(),
+            name: "index::fail".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(1, 2), Position::new(1, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(4, 0), Position::new(4, 4)),
+        };
+        let diagnostics = parse_jest_json(
+            &contents,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        // At the stack frame of the failed `expect`, not the test
+        assert_eq!(diagnostic.range.start, Position::new(3, 14));
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.range, test_item.start_position);
+    }
+
+    #[test]
+    fn test_parse_jest_json_soft_assertions() {
+        let contents = std::fs::read_to_string("demo/jest/soft-assertions-output.json").unwrap();
+        let file_path = "/absolute_path/demo/jest/soft-assertions.spec.js".to_string();
+        let diagnostics =
+            parse_jest_json(&contents, std::slice::from_ref(&file_path), &[]).unwrap();
+
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        let starts: Vec<Position> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.range.start)
+            .collect();
+        assert_eq!(starts, vec![Position::new(2, 23), Position::new(3, 23)]);
+        assert!(diagnostics[1].message.contains("Expected: 5\nReceived: 4"));
+
+        // Without a frame in the test file, a failure is placed at the test
+        let contents = contents.replace("soft-assertions.spec.js:3:24", "helpers.js:3:24");
+        let diagnostics =
+            parse_jest_json(&contents, std::slice::from_ref(&file_path), &[]).unwrap();
+        assert_eq!(
+            diagnostics.files[0].diagnostics[0].range.start,
+            Position::new(1, 2)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_jest_json_through_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real");
+        std::fs::create_dir(&real).unwrap();
+        std::fs::write(real.join("index.spec.js"), "").unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("link")).unwrap();
+
+        // Jest reports the real path of the file the editor opened via a link
+        let contents = std::fs::read_to_string("demo/jest/output.json")
+            .unwrap()
+            .replace("/absolute_path/demo/jest", &real.to_string_lossy());
+        let file_path = dir
+            .path()
+            .join("link/index.spec.js")
+            .to_string_lossy()
+            .to_string();
+        let diagnostics =
+            parse_jest_json(&contents, std::slice::from_ref(&file_path), &[]).unwrap();
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+
+        // Without such a file, the only file of the same name is taken
+        let file_path = "/elsewhere/Index.spec.js".to_string();
+        let diagnostics =
+            parse_jest_json(&contents, std::slice::from_ref(&file_path), &[]).unwrap();
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let file_paths = [file_path, "/other/index.spec.js".to_string()];
+        assert!(
+            parse_jest_json(&contents, &file_paths, &[])
+                .unwrap()
+                .files
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_jest_json_suite_failure() {
+        let contents = std::fs::read_to_string("demo/jest/import-error-output.json").unwrap();
+        let file_path = "/absolute_path/demo/jest/import-error.spec.js".to_string();
+        let diagnostics =
+            parse_jest_json(&contents, std::slice::from_ref(&file_path), &[]).unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 0));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("● Test suite failed to run")
+        );
+        assert!(
+            diagnostics[0]
+                .message
+                .contains("Error: configuration is missing")
+        );
+
+        // Results without the expected fields are skipped rather than panicking
+        let malformed = r#"{"testResults": [{"assertionResults": [{"status": "failed"}]}, {}]}"#;
+        assert!(
+            parse_jest_json(malformed, &[file_path], &[])
+                .unwrap()
+                .files
+                .is_empty()
+        );
+        assert!(parse_jest_json("{}", &[], &[]).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_parse_node_test_tap() {
+        let contents = std::fs::read_to_string("demo/node-test/output.tap").unwrap();
+        let target_file_path = "/home/test-user/projects/testing-language-server/demo/node-test/\
+                                index.test.js"
+            .to_string();
+        let results = parse_node_test_tap(&contents, &[target_file_path.clone()]);
+
+        let locations: Vec<(u32, u32)> = results
+            .iter()
+            .map(|result| (result.line, result.col))
+            .collect();
+        assert_eq!(locations, vec![(13, 10), (43, 1), (111, 5)]);
+        assert!(results.iter().all(|result| result.path == target_file_path));
+        assert_eq!(
+            results[0].message,
+            "Expected values to be strictly equal:\n\n1 !== 2"
+        );
+        assert_eq!(results[1].message, "callback failure");
+    }
+
+    #[test]
+    fn test_parse_node_test_tap_mixed_separators() {
+        let output = r"TAP version 13
+not ok 1 - fails
+  ---
+  location: 'C:\proj\index.test.js:3:1'
+  error: 'boom'
+  stack: |-
+    TestContext.<anonymous> (file:///C:/proj/index.test.js:4:9)
+  ...
+1..1
+";
+        let results = parse_node_test_tap(output, &[r"c:\proj\index.test.js".to_string()]);
+        assert_eq!(results.len(), 1);
+        assert_eq!((results[0].line, results[0].col), (4, 9));
+    }
+
+    #[test]
+    fn test_parse_bun_output() {
+        let contents = std::fs::read_to_string("demo/bun/output.xml").unwrap();
+        let workspace = PathBuf::from("/home/demo/bun");
+        let target_file_path = "/home/demo/bun/index.test.ts".to_string();
+        let diagnostics =
+            parse_bun_output(&contents, &workspace, &[target_file_path.clone()]).unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, target_file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start.line, 8);
+        assert_eq!(diagnostic.range.start.character, 18);
+        assert!(diagnostic.message.contains("Expected: 1"));
+        assert_eq!(diagnostic.source, Some("bun-test".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ava_tap() {
+        let contents = std::fs::read_to_string("demo/ava/output.tap").unwrap();
+        let workspace = PathBuf::from("/home/demo/ava");
+        let target_file_path = "/home/demo/ava/math.test.js".to_string();
+        let diagnostics = parse_ava_tap(&contents, &workspace, &[target_file_path.clone()], &[]);
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, target_file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start.line, 7);
+        assert_eq!(diagnostic.range.start.character, 4);
+        assert!(diagnostic.message.starts_with("[subtracts] "));
+        assert!(diagnostic.message.contains("+ 1"));
+        assert_eq!(diagnostic.source, Some("ava".to_string()));
+    }
+
+    #[test]
+    fn test_parse_playwright_json() {
+        let contents = std::fs::read_to_string("demo/playwright/output.json").unwrap();
+        let workspace = PathBuf::from("/home/demo/playwright");
+        let target_file_path = "/home/demo/playwright/tests/example.spec.ts".to_string();
+        let diagnostics =
+            parse_playwright_json(&contents, &workspace, &[target_file_path.clone()]).unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, target_file_path);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(10, 45));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("[chromium] shows heading\n")
+        );
+        assert!(
+            diagnostics[0]
+                .message
+                .contains("Received string: \"Welcome\"")
+        );
+    }
+
+    #[test]
+    fn test_parse_vitest_json_resolves_project_paths() {
+        let contents = r#"{"testResults": [
+            {"name": "src/sum.test.ts", "assertionResults": [
+                {"status": "failed", "location": {"line": 4, "column": 3}, "failureMessages": ["expected 3 to be 4"]}
+            ]},
+            {"name": "/repo/packages/b/src/sum.test.ts", "assertionResults": [
+                {"status": "passed", "location": {"line": 2, "column": 3}, "failureMessages": []}
+            ]}
+        ]}"#;
+        let file_paths = vec![
+            "/repo/packages/a/src/sum.test.ts".to_string(),
+            "/repo/packages/b/src/sum.test.ts".to_string(),
+        ];
+        let project_dirs = vec![
+            PathBuf::from("/repo/packages/a"),
+            PathBuf::from("/repo/packages/b"),
+        ];
+
+        let diagnostics =
+            parse_vitest_json(contents, Path::new("/repo"), &project_dirs, &file_paths).unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(
+            diagnostics.files[0].path,
+            "/repo/packages/a/src/sum.test.ts"
+        );
+        assert_eq!(diagnostics.files[0].diagnostics[0].range.start.line, 3);
+    }
+
+    #[test]
+    fn test_parse_vitest_json_suite_failure() {
+        let contents = std::fs::read_to_string("demo/vitest/import-error-output.json").unwrap();
+        let file_path = "/absolute_path/demo/vitest/import-error.test.ts".to_string();
+        let diagnostics = parse_vitest_json(
+            &contents,
+            Path::new("/absolute_path/demo/vitest"),
+            &[],
+            std::slice::from_ref(&file_path),
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 0));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("Failed to load url ./missing-config")
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use std::process::{Command, Output};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Command running every test of the package with `Pkg.test()`, which runs
+/// `test/runtests.jl`. Extra arguments are passed to `julia`, before the
+/// expression.
+pub fn julia_test_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut julia = test_command(context, command, &["julia", "--project"])?;
+    julia.args(extra_args).args(["-e", "using Pkg; Pkg.test()"]);
+    Ok(julia)
+}
+
+/// Run a `Pkg.test()` command.
+pub fn run_julia_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("julia_test.log", &output)?;
+    Ok(output)
+}
@@ -0,0 +1,236 @@
+//! Julia tests of the `Test` standard library run by `Pkg.test()`.
+//!
+//! Tests are the `@testset "name"` blocks of a file, with ids joined by `/`
+//! after the test sets around them, like `Test` nests them in its summary:
+//! `Demo/math`. No Julia grammar is bundled, so they are found by scanning
+//! the source, taking a block to end at the first `end` indented like its
+//! header.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+};
+
+/// Zero-based line of the `end` closing the block whose header is at
+/// `header`, or the header itself for a test set on one line.
+fn block_end(lines: &[&str], header: usize, indent: usize) -> usize {
+    let opens_block = Regex::new(r"\b(begin|for|let)\b\s*(#.*)?$").unwrap();
+    if !opens_block.is_match(lines[header]) {
+        return header;
+    }
+    lines
+        .iter()
+        .enumerate()
+        .skip(header + 1)
+        .find(|(_, line)| {
+            let trimmed = line.trim_start();
+            line.len() - trimmed.len() == indent
+                && (trimmed == "end" || trimmed.starts_with("end ") || trimmed.starts_with("end#"))
+        })
+        .map_or(header, |(i, _)| i)
+}
+
+/// Test sets of a file, with ids like `Demo` or `Demo/math`.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let testset_re = Regex::new(r#"^(\s*)@testset\s*\(?\s*"([^"]*)""#).unwrap();
+    let lines: Vec<&str> = source.lines().collect();
+
+    // Ids and last lines of the test sets around the current line
+    let mut parents: Vec<(String, usize)> = vec![];
+    let mut items = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = testset_re.captures(line) else {
+            continue;
+        };
+        let indent = m[1].len();
+        let end = block_end(&lines, i, indent);
+        parents.retain(|(_, parent_end)| i <= *parent_end);
+        let id = match parents.last() {
+            Some((parent, _)) => format!("{parent}/{}", &m[2]),
+            None => m[2].to_string(),
+        };
+        let line_number = i as u32;
+        let end_line = end as u32;
+        items.push(TestItem {
+            id: id.clone(),
+            name: id.clone(),
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line_number, indent as u32),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(
+                Position::new(end_line, 0),
+                Position::new(end_line, lines[end].len() as u32),
+            ),
+        });
+        parents.push((id, end));
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Directory of the Julia package a file belongs to: the nearest directory
+/// whose `Project.toml` names a package. A `test/Project.toml` declaring
+/// only the dependencies of the tests is passed over.
+fn julia_package_dir(file_path: &str) -> Option<&Path> {
+    Path::new(file_path).ancestors().skip(1).find(|dir| {
+        std::fs::read_to_string(dir.join("Project.toml"))
+            .ok()
+            .and_then(|project| project.parse::<toml::Table>().ok())
+            .is_some_and(|project| project.contains_key("name"))
+    })
+}
+
+fn diagnostics(output: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_julia_output(output),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct JuliaTestRunner;
+
+impl Runner for JuliaTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        _filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::julia_test_command(context, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_julia_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        // Test prints to stdout, Pkg and uncaught errors to stderr
+        let output = [output.stdout, output.stderr].concat();
+        Ok(diagnostics(
+            &String::from_utf8_lossy(&output),
+            context.workspace,
+            file_paths,
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(package) = julia_package_dir(file_path) {
+                map.entry(package.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/julia/test/runtests.jl").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["Demo", "Demo/adds", "Demo/math"]);
+        assert_eq!(items[0].end_position.end.line, 12);
+        assert_eq!(items[2].start_position.start, Position::new(8, 4));
+        assert_eq!(items[2].end_position.end, Position::new(11, 7));
+
+        let source =
+            "@testset \"a\" for x in 1:3\n    @test x > 0\nend\n@testset \"b\" @test true\n";
+        let ids: Vec<String> = discover_in_source("t.jl", source)
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_julia_package_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("test")).unwrap();
+        std::fs::write(dir.path().join("Project.toml"), "name = \"Demo\"\n").unwrap();
+        std::fs::write(
+            dir.path().join("test/Project.toml"),
+            "[deps]\nTest = \"8dfed614-e22c-5e08-85e1-65c5234f0b40\"\n",
+        )
+        .unwrap();
+        let file_path = dir.path().join("test/runtests.jl");
+        assert_eq!(
+            julia_package_dir(file_path.to_str().unwrap()),
+            Some(dir.path())
+        );
+    }
+}
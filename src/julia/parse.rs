@@ -0,0 +1,242 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    log::clean_ansi,
+    workspace::{resolve_path, same_path},
+};
+
+/// A `@test` the `Test` standard library reported as failed, or as erroring
+/// when it threw an exception.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JuliaTestFailure {
+    /// Name of the innermost test set around the test
+    pub testset: Option<String>,
+    pub error: bool,
+    pub path: String,
+    /// One-based line of the `@test`
+    pub line: u32,
+    /// Lines printed under the header, like the expression and what it
+    /// evaluated to, without the stack trace
+    pub message: String,
+}
+
+/// Parse the failed tests from the output of `Pkg.test()`, in which `Test`
+/// prints a block per failed test headed like `math: Test Failed at
+/// test/runtests.jl:10` or `math: Error During Test at ...`. Other output,
+/// like stack traces and the summary table, is skipped.
+#[must_use]
+pub fn parse_julia_output(contents: &str) -> Vec<JuliaTestFailure> {
+    let header_re =
+        Regex::new(r"^(?:(.*?): )?(Test Failed|Error During Test) at (\S+?):(\d+)\s*$").unwrap();
+    let mut failures: Vec<JuliaTestFailure> = vec![];
+    // Whether lines still belong to the message of the last failure
+    let mut in_message = false;
+
+    for line in clean_ansi(contents).lines() {
+        if let Some(m) = header_re.captures(line) {
+            failures.push(JuliaTestFailure {
+                testset: m.get(1).map(|testset| testset.as_str().to_string()),
+                error: &m[2] == "Error During Test",
+                path: m[3].to_string(),
+                line: m[4].parse().unwrap_or(1),
+                message: String::new(),
+            });
+            in_message = true;
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("Stacktrace:") || !line.starts_with(' ') {
+            in_message = false;
+        }
+        if in_message && let Some(failure) = failures.last_mut() {
+            if !failure.message.is_empty() {
+                failure.message.push('\n');
+            }
+            failure.message.push_str(trimmed);
+        }
+    }
+    failures
+}
+
+fn error_diagnostic(range: Range, message: String, error: bool) -> Diagnostic {
+    let code = if error {
+        "julia-test-error"
+    } else {
+        "julia-test-failed"
+    };
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("julia-test".to_string()),
+        code: Some(NumberOrString::String(code.to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Innermost discovered test set a failure belongs to: the one around its
+/// line, or else the one named like the test set `Test` reported.
+fn find_testset<'a>(
+    test_items: &'a [TestItem],
+    failure: &JuliaTestFailure,
+    path: Option<&str>,
+) -> Option<&'a TestItem> {
+    let line = failure.line.saturating_sub(1);
+    path.and_then(|path| {
+        test_items
+            .iter()
+            .filter(|item| {
+                same_path(&item.path, path)
+                    && item.start_position.start.line <= line
+                    && line <= item.end_position.end.line
+            })
+            .max_by_key(|item| item.start_position.start.line)
+    })
+    .or_else(|| {
+        let testset = failure.testset.as_deref()?;
+        test_items
+            .iter()
+            .find(|item| item.id == testset || item.id.ends_with(&format!("/{testset}")))
+    })
+}
+
+/// Diagnostics for failed tests.
+///
+/// Failures are placed at their `@test` when it is in one of `file_paths`,
+/// resolved against `root`, and linked to the definition of their test set.
+/// Other failures are placed at the definition of their test set.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[JuliaTestFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let resolved = resolve_path(root, &failure.path)
+            .to_string_lossy()
+            .to_string();
+        let path = file_paths.iter().find(|path| same_path(path, &resolved));
+        let test_item = find_testset(test_items, failure, path.map(String::as_str));
+
+        if let Some(path) = path {
+            let line_number = failure.line.saturating_sub(1);
+            let range = Range::new(
+                Position::new(line_number, 0),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, failure.message.clone(), failure.error)
+                });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!(
+                        "`{}` failed at {}:{}\n{}",
+                        test_item.id, failure.path, failure.line, failure.message
+                    ),
+                    failure.error,
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_julia_output() {
+        let contents = std::fs::read_to_string("demo/julia/test-output.txt").unwrap();
+        let failures = parse_julia_output(&contents);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].testset.as_deref(), Some("math"));
+        assert!(!failures[0].error);
+        assert_eq!(failures[0].line, 10);
+        assert_eq!(
+            failures[0].message,
+            "Expression: add(2, 2) == 5\nEvaluated: 4 == 5"
+        );
+        assert!(failures[1].error);
+        assert_eq!(
+            failures[1].message,
+            "Test threw exception\nExpression: [1, 2][3] == 1\nBoundsError: attempt to access \
+             2-element Vector{Int64} at index [3]"
+        );
+
+        let root = Path::new("/home/demo/julia");
+        let file_path = "/home/demo/julia/test/runtests.jl".to_string();
+        let test_item = |id: &str, start: u32, end: u32| TestItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(
+                Position::new(start, 4),
+                Position::new(start, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end, 0), Position::new(end, 7)),
+        };
+        let test_items = [test_item("Demo", 3, 12), test_item("Demo/math", 8, 11)];
+        let diagnostics = to_diagnostics(
+            &failures,
+            root,
+            std::slice::from_ref(&file_path),
+            &test_items,
+        );
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(9, 0));
+        assert_eq!(
+            diagnostics[0].related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            test_items[1].start_position
+        );
+        assert_eq!(
+            diagnostics[1].code,
+            Some(NumberOrString::String("julia-test-error".to_string()))
+        );
+
+        // A failure in another file is placed at its test set
+        let failures = [JuliaTestFailure {
+            path: "/home/demo/julia/test/helpers.jl".to_string(),
+            ..failures[0].clone()
+        }];
+        let diagnostics = to_diagnostics(&failures, root, &[file_path], &test_items);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(8, 4));
+        assert!(
+            diagnostic
+                .message
+                .starts_with("`Demo/math` failed at /home/demo/julia/test/helpers.jl:10\n")
+        );
+    }
+}
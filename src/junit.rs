@@ -0,0 +1,202 @@
+//! JUnit XML report parsing.
+//!
+//! Many test runners write their results as JUnit reports, each with its own
+//! attributes on `<testcase>`: Gradle and Maven give the class, pytest the
+//! file, Bun the file and line, and CTest a `status`. The reports are read
+//! into their test cases here, leaving what they mean to the runners.
+
+use std::{collections::HashMap, path::Path};
+
+use xml::{ParserConfig, reader::XmlEvent};
+
+use crate::error::LSError;
+
+/// The `<failure>` or `<error>` of a test case.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Failure {
+    /// The `message` attribute
+    pub message: String,
+    /// Text inside the element, usually a stack trace
+    pub text: String,
+}
+
+/// A `<testcase>` of a JUnit report.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TestCase {
+    pub attributes: HashMap<String, String>,
+    /// First `<failure>` or `<error>` of the test, which has none when it
+    /// passed or was skipped
+    pub failure: Option<Failure>,
+    /// All text inside the `<testcase>`, including its `<system-out>`
+    pub output: String,
+}
+
+impl TestCase {
+    /// Value of an attribute of the `<testcase>`, empty when it has none.
+    #[must_use]
+    pub fn attribute(&self, key: &str) -> &str {
+        self.attributes.get(key).map_or("", String::as_str)
+    }
+}
+
+/// Parse the test cases of a JUnit report, in their order in the report.
+pub fn parse_testcases(contents: &str) -> Result<Vec<TestCase>, LSError> {
+    let mut reader = ParserConfig::default().create_reader(contents.as_bytes());
+    let mut testcases = vec![];
+    let mut testcase: Option<TestCase> = None;
+    // Whether the text read is inside the first <failure> or <error>
+    let mut in_failure = false;
+
+    loop {
+        match reader.next() {
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                let attributes = attributes
+                    .into_iter()
+                    .map(|attribute| (attribute.name.local_name, attribute.value))
+                    .collect::<HashMap<_, _>>();
+                match name.local_name.as_str() {
+                    "testcase" => {
+                        testcase = Some(TestCase {
+                            attributes,
+                            ..TestCase::default()
+                        });
+                    }
+                    "failure" | "error" => {
+                        if let Some(testcase) = &mut testcase
+                            && testcase.failure.is_none()
+                        {
+                            testcase.failure = Some(Failure {
+                                message: attributes.get("message").cloned().unwrap_or_default(),
+                                text: String::new(),
+                            });
+                            in_failure = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(XmlEvent::Characters(data) | XmlEvent::CData(data)) => {
+                if let Some(testcase) = &mut testcase {
+                    testcase.output.push_str(&data);
+                    if in_failure && let Some(failure) = &mut testcase.failure {
+                        failure.text.push_str(&data);
+                    }
+                }
+            }
+            Ok(XmlEvent::EndElement { name }) => match name.local_name.as_str() {
+                "testcase" => testcases.extend(testcase.take()),
+                "failure" | "error" => in_failure = false,
+                _ => {}
+            },
+            Ok(XmlEvent::EndDocument) => break,
+            Err(e) => {
+                log::error!("XML parse error: {e}");
+                return Err(LSError::XmlParse);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(testcases)
+}
+
+/// A failed `<testcase>` of a JUnit report of a JVM test framework.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JUnitFailure {
+    /// Qualified test class, e.g. `com.example.MathTest`
+    pub class_name: String,
+    /// Test method, with the parameters of parameterized tests stripped
+    pub name: String,
+    pub message: String,
+    pub stack_trace: String,
+}
+
+impl JUnitFailure {
+    /// Id of the failed test, as given to discovered tests.
+    #[must_use]
+    pub fn test_id(&self) -> String {
+        format!("{}.{}", self.class_name, self.name)
+    }
+}
+
+/// Parse the failed tests from a JUnit report.
+pub fn parse_junit(contents: &str) -> Result<Vec<JUnitFailure>, LSError> {
+    Ok(parse_testcases(contents)?
+        .into_iter()
+        .filter_map(|testcase| {
+            let failure = testcase.failure.as_ref()?;
+            // JUnit 5 names end in the parameter list, and parameterized
+            // tests in an index
+            let name = testcase.attribute("name").split('(').next()?;
+            Some(JUnitFailure {
+                class_name: testcase.attribute("classname").to_string(),
+                name: name.to_string(),
+                message: failure.message.clone(),
+                stack_trace: failure.text.clone(),
+            })
+        })
+        .collect())
+}
+
+/// Parse the failed tests from a JUnit report, or from every report in a
+/// directory like `build/test-results/test`. A missing directory has none.
+pub fn parse_junit_reports(path: &Path) -> Result<Vec<JUnitFailure>, LSError> {
+    if path.is_file() {
+        return parse_junit(&std::fs::read_to_string(path)?);
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return Ok(vec![]);
+    };
+    let mut failures = vec![];
+    for entry in entries.flatten() {
+        let report = entry.path();
+        if report
+            .extension()
+            .is_some_and(|extension| extension == "xml")
+        {
+            failures.extend(parse_junit(&std::fs::read_to_string(report)?)?);
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_testcases() {
+        let contents = r#"<testsuite>
+  <testcase classname="a.B" name="passes"/>
+  <testcase classname="a.B" name="fails(int)[1]">
+    <system-out>before</system-out>
+    <failure message="expected 1"><![CDATA[at a.B.fails(B.kt:3)]]></failure>
+    <error message="teardown">ignored</error>
+  </testcase>
+</testsuite>"#;
+        let testcases = parse_testcases(contents).unwrap();
+        assert_eq!(testcases.len(), 2);
+        assert_eq!(testcases[0].attribute("name"), "passes");
+        assert!(testcases[0].failure.is_none());
+        assert_eq!(
+            testcases[1].failure,
+            Some(Failure {
+                message: "expected 1".to_string(),
+                text: "at a.B.fails(B.kt:3)".to_string(),
+            })
+        );
+        assert_eq!(testcases[1].output, "beforeat a.B.fails(B.kt:3)ignored");
+        assert_eq!(testcases[1].attribute("file"), "");
+
+        let failures = parse_junit(contents).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].test_id(), "a.B.fails");
+    }
+
+    #[test]
+    fn test_parse_invalid_report() {
+        assert!(parse_testcases("<testsuite><testcase>").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Directory Gradle writes the JUnit reports of a project's `test` task to.
+#[must_use]
+pub fn results_dir(module: &Path) -> PathBuf {
+    module.join("build/test-results/test")
+}
+
+/// Command running the given `test` tasks with the Gradle wrapper of the
+/// workspace, or `gradle` when there is none.
+pub fn gradle_test_command(
+    context: &RunContext,
+    tasks: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let gradle = if Path::new(context.workspace).join("gradlew").exists() {
+        "./gradlew"
+    } else {
+        "gradle"
+    };
+    let mut gradle = test_command(context, command, &[gradle])?;
+    gradle
+        .arg("--continue")
+        .arg("--console=plain")
+        .args(extra_args)
+        .args(tasks);
+    Ok(gradle)
+}
+
+/// Directory Maven Surefire writes the JUnit reports of a module to.
+#[must_use]
+pub fn surefire_reports_dir(module: &Path) -> PathBuf {
+    module.join("target/surefire-reports")
+}
+
+/// Command running `mvn -q test` with the Maven wrapper of the workspace, or
+/// `mvn` when there is none, in the given modules (every one when empty) and
+/// for the given Surefire `-Dtest` patterns (every test when empty).
+pub fn maven_test_command(
+    context: &RunContext,
+    modules: &[String],
+    tests: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mvn = if Path::new(context.workspace).join("mvnw").exists() {
+        "./mvnw"
+    } else {
+        "mvn"
+    };
+    let mut mvn = test_command(context, command, &[mvn, "-q", "test"])?;
+    if !modules.is_empty() {
+        mvn.arg("-pl").arg(modules.join(","));
+    }
+    if !tests.is_empty() {
+        // Modules without any of the tests mustn't fail the build
+        mvn.arg(format!("-Dtest={}", tests.join(",")))
+            .arg("-Dsurefire.failIfNoSpecifiedTests=false");
+    }
+    mvn.args(extra_args);
+    Ok(mvn)
+}
+
+/// Run a Maven test command.
+pub fn run_maven_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("maven_test.log", &output)?;
+    Ok(output)
+}
+
+/// Run a Gradle test command.
+pub fn run_gradle_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("gradle_test.log", &output)?;
+    Ok(output)
+}
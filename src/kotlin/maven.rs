@@ -0,0 +1,229 @@
+//! Java and Kotlin tests run by Maven Surefire.
+//!
+//! Tests are discovered like those of Gradle and their JUnit reports are the
+//! same, so only the command, the report location and the workspaces differ:
+//! a workspace is the root of a Maven reactor, and its modules are the
+//! directories with a `pom.xml`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tree_sitter::{Language, Tree};
+
+use super::{call, discover_all, discover_in_source, discover_tests, parse};
+use crate::{
+    Diagnostics, DiscoveredTests, TestItem, Workspaces,
+    error::LSError,
+    junit,
+    runner::{RunContext, Runner, discover_files},
+};
+
+/// Directory of the Maven module a file belongs to: the nearest directory
+/// with a `pom.xml`.
+fn module_dir(file_path: &str) -> Option<PathBuf> {
+    Path::new(file_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join("pom.xml").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Root of the Maven reactor containing a file: the outermost of the
+/// directories with a `pom.xml` directly above its module.
+fn reactor_root(file_path: &str) -> Option<PathBuf> {
+    let module = module_dir(file_path)?;
+    module
+        .ancestors()
+        .take_while(|dir| dir.join("pom.xml").exists())
+        .last()
+        .map(Path::to_path_buf)
+}
+
+/// Surefire `-Dtest` pattern of a test, like `com.example.MathTest#adds`.
+fn surefire_pattern(test_item: &TestItem) -> String {
+    match test_item.id.rsplit_once('.') {
+        Some((class, method)) => format!("{class}#{method}"),
+        None => test_item.id.clone(),
+    }
+}
+
+/// Modules of `file_paths`, relative to the reactor `root`, as `-pl` takes
+/// them. Empty when the tests are in the root module.
+fn module_args(root: &Path, file_paths: &[String]) -> Vec<String> {
+    let mut modules: Vec<String> = file_paths
+        .iter()
+        .filter_map(|path| module_dir(path))
+        .filter_map(|module| {
+            let relative = module.strip_prefix(root).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    modules.sort();
+    modules.dedup();
+    if modules.iter().any(String::is_empty) {
+        return vec![];
+    }
+    modules
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct MavenTestRunner;
+
+impl Runner for MavenTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let mut tests: Vec<String> = discover_all(file_paths)
+            .iter()
+            .filter(|item| filter.is_none_or(|filter| item.name.contains(filter)))
+            .map(surefire_pattern)
+            .collect();
+        if tests.is_empty()
+            && let Some(filter) = filter
+        {
+            tests.push(format!("*#*{filter}*"));
+        }
+        let modules = module_args(Path::new(context.workspace), file_paths);
+        call::maven_test_command(context, &modules, &tests, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_maven_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+
+        let mut reports_dirs: Vec<PathBuf> = file_paths
+            .iter()
+            .filter_map(|path| module_dir(path))
+            .map(|module| call::surefire_reports_dir(&module))
+            .collect();
+        reports_dirs.sort();
+        reports_dirs.dedup();
+        let failures = reports_dirs
+            .iter()
+            .map(|dir| junit::parse_junit_reports(dir))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        Ok(parse::to_diagnostics(
+            "maven-test",
+            &failures,
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = junit::parse_junit_reports(results_path)?;
+        Ok(parse::to_diagnostics(
+            "maven-test",
+            &failures,
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(root) = reactor_root(file_path) {
+                map.entry(root.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maven_command_and_reports() {
+        let root = std::env::current_dir().unwrap().join("demo/maven");
+        let file_path = root
+            .join("core/src/test/java/com/example/CalculatorTest.java")
+            .to_string_lossy()
+            .to_string();
+        let file_paths = [file_path.clone()];
+        assert_eq!(reactor_root(&file_path), Some(root.clone()));
+        assert_eq!(module_args(&root, &file_paths), vec!["core"]);
+
+        // Maven may not be installed, so a stand-in takes its place
+        let command = MavenTestRunner
+            .build_command(
+                &file_paths,
+                &RunContext::new(&root.to_string_lossy()),
+                &[],
+                Some(&["sh".to_string()]),
+                Some("divides"),
+            )
+            .unwrap();
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            args,
+            vec![
+                "-pl",
+                "core",
+                "-Dtest=com.example.CalculatorTest$Division#dividesEvenly",
+                "-Dsurefire.failIfNoSpecifiedTests=false"
+            ]
+        );
+
+        let diagnostics = MavenTestRunner
+            .parse_results(
+                &root.join("surefire-report.xml"),
+                &file_paths,
+                &root.to_string_lossy(),
+            )
+            .unwrap();
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.source.as_deref(), Some("maven-test"));
+        assert_eq!(diagnostic.range.start.line, 15);
+    }
+}
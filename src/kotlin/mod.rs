@@ -0,0 +1,372 @@
+//! Kotlin and Java tests run by Gradle with JUnit.
+//!
+//! Tests are the `@Test` (or `@ParameterizedTest`, `@RepeatedTest`) functions
+//! and methods of classes, including `@Nested` inner classes, whatever their
+//! `@Tag`s. No Kotlin or Java grammar is bundled, so they are found by
+//! scanning the source, balancing braces to find where classes and functions
+//! end.
+
+pub mod call;
+mod maven;
+pub mod parse;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use lsp_types::{Position, Range};
+pub use maven::MavenTestRunner;
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    junit,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+};
+
+const BUILD_FILES: &[&str] = &["build.gradle.kts", "build.gradle"];
+const SETTINGS_FILES: &[&str] = &["settings.gradle.kts", "settings.gradle"];
+
+fn has_any(dir: &Path, files: &[&str]) -> bool {
+    files.iter().any(|file| dir.join(file).exists())
+}
+
+/// Directory of the Gradle project a file belongs to: the nearest directory
+/// with a build script.
+fn module_dir(file_path: &str) -> Option<PathBuf> {
+    Path::new(file_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| has_any(dir, BUILD_FILES))
+        .map(Path::to_path_buf)
+}
+
+/// Root of the Gradle build containing a file: the nearest directory with a
+/// settings script, or else the project of the file.
+fn gradle_root(file_path: &str) -> Option<PathBuf> {
+    Path::new(file_path)
+        .ancestors()
+        .skip(1)
+        .find(|dir| has_any(dir, SETTINGS_FILES))
+        .map(Path::to_path_buf)
+        .or_else(|| module_dir(file_path))
+}
+
+/// Path of the `test` task of the project in `module`, like `:app:test`.
+fn test_task(root: &Path, module: &Path) -> String {
+    let project: Vec<String> = module
+        .strip_prefix(root)
+        .unwrap_or(Path::new(""))
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if project.is_empty() {
+        "test".to_string()
+    } else {
+        format!(":{}:test", project.join(":"))
+    }
+}
+
+/// Tests of a Kotlin or Java file, with ids like `com.example.MathTest.adds`
+/// as Gradle `--tests` matches them. Nested classes are joined with `$`, like
+/// the binary class names in JUnit reports.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let package_re = Regex::new(r"(?m)^\s*package\s+([\w.]+)").unwrap();
+    let class_re = Regex::new(r"\bclass\s+(\w+)").unwrap();
+    let test_re = Regex::new(
+        r"@(?:org\.junit(?:\.jupiter\.api)?\.)?(?:Test|ParameterizedTest|RepeatedTest)\b(?:\([^)]*\))?(?:\s+(?:@[\w.]+(?:\([^)]*\))?|public|protected|private|internal|open|override|suspend|final))*\s+(?:fun\s+(\w+|`[^`]+`)|void\s+(\w+))\s*\(",
+    )
+    .unwrap();
+
+    let package = package_re
+        .captures(source)
+        .map(|m| format!("{}.", &m[1]))
+        .unwrap_or_default();
+
+    // Name and body of each class with a body
+    let classes: Vec<(&str, usize, usize)> = class_re
+        .captures_iter(source)
+        .filter_map(|m| {
+            let header_start = m.get(0).unwrap().end();
+            let body_start = header_start + source[header_start..].find('{')?;
+            // A class without a body ends before the next declaration
+            let header = &source[header_start..body_start];
+            if class_re.is_match(header) || header.contains("fun ") {
+                return None;
+            }
+            let name = m.get(1).unwrap().as_str();
+            Some((name, body_start, block_end(source, body_start)))
+        })
+        .collect();
+
+    let mut items = vec![];
+    for m in test_re.captures_iter(source) {
+        let found = m.get(0).unwrap();
+        let enclosing: Vec<&str> = classes
+            .iter()
+            .filter(|(_, start, end)| (*start..*end).contains(&found.start()))
+            .map(|(name, _, _)| *name)
+            .collect();
+        if enclosing.is_empty() {
+            continue;
+        }
+        let method = m.get(1).or_else(|| m.get(2)).unwrap().as_str();
+        let name = format!("{}.{}", enclosing.join("$"), method.trim_matches('`'));
+        let (line, column) = position_at(source, found.start());
+        let end = position_at(source, block_end(source, found.end()));
+        items.push(TestItem {
+            id: format!("{package}{name}"),
+            name,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Task arguments running the given tests, each `test` task followed by the
+/// `--tests` filters of its project. Without discovered tests the projects of
+/// `file_paths` run the tests whose name contains `filter`.
+fn task_args(
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+    filter: Option<&str>,
+) -> Vec<String> {
+    let mut modules: Vec<PathBuf> = file_paths
+        .iter()
+        .filter_map(|path| module_dir(path))
+        .collect();
+    modules.sort();
+    modules.dedup();
+
+    let mut args = vec![];
+    for module in modules {
+        args.push(test_task(root, &module));
+        let module_items: Vec<&TestItem> = test_items
+            .iter()
+            .filter(|item| module_dir(&item.path).as_deref() == Some(module.as_path()))
+            .collect();
+        if module_items.is_empty() {
+            if let Some(filter) = filter {
+                args.extend(["--tests".to_string(), format!("*{filter}*")]);
+            }
+            continue;
+        }
+        for item in module_items {
+            args.extend(["--tests".to_string(), item.id.clone()]);
+        }
+    }
+    args
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct GradleTestRunner;
+
+impl Runner for GradleTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .filter(|item| filter.is_none_or(|filter| item.name.contains(filter)))
+            .collect();
+        let tasks = task_args(
+            Path::new(context.workspace),
+            file_paths,
+            &test_items,
+            filter,
+        );
+        call::gradle_test_command(context, &tasks, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_gradle_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+
+        let mut results_dirs: Vec<PathBuf> = file_paths
+            .iter()
+            .filter_map(|path| module_dir(path))
+            .map(|module| call::results_dir(&module))
+            .collect();
+        results_dirs.sort();
+        results_dirs.dedup();
+        let failures = results_dirs
+            .iter()
+            .map(|dir| junit::parse_junit_reports(dir))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        Ok(parse::to_diagnostics(
+            "gradle-test",
+            &failures,
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = junit::parse_junit_reports(results_path)?;
+        Ok(parse::to_diagnostics(
+            "gradle-test",
+            &failures,
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(root) = gradle_root(file_path) {
+                map.entry(root.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let file_path = "demo/gradle/app/src/test/kotlin/com/example/MathTest.kt";
+        let items = discover_tests(file_path).unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "com.example.MathTest.adds",
+                "com.example.MathTest.fails on purpose",
+                "com.example.MathTest$LargeNumbers.addsLargeNumbers"
+            ]
+        );
+        assert_eq!(items[1].name, "MathTest.fails on purpose");
+        assert_eq!(items[1].start_position.start, Position::new(14, 4));
+        assert_eq!(items[1].end_position.end, Position::new(17, 5));
+    }
+
+    #[test]
+    fn test_discover_java_nested_classes() {
+        let file_path = std::env::current_dir()
+            .unwrap()
+            .join("demo/gradle/app/src/test/java/com/example/CalculatorTest.java")
+            .to_string_lossy()
+            .to_string();
+        let items = discover_tests(&file_path).unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "com.example.CalculatorTest.adds",
+                "com.example.CalculatorTest.addsManyNumbers",
+                "com.example.CalculatorTest$Division.dividesEvenly",
+                "com.example.CalculatorTest$Division$Remainder.keepsRemainder"
+            ]
+        );
+        assert_eq!(items[3].start_position.start, Position::new(36, 12));
+        assert_eq!(items[3].end_position.end, Position::new(39, 13));
+
+        let failures =
+            junit::parse_junit_reports(Path::new("demo/gradle/test-results-nested.xml")).unwrap();
+        let file_paths = [file_path];
+        let diagnostics = parse::to_diagnostics("gradle-test", &failures, &file_paths, &items);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(38, 0));
+        assert_eq!(
+            diagnostic.related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            items[3].start_position
+        );
+    }
+
+    #[test]
+    fn test_task_args() {
+        let root = std::env::current_dir().unwrap().join("demo/gradle");
+        let file_path = root
+            .join("app/src/test/kotlin/com/example/MathTest.kt")
+            .to_string_lossy()
+            .to_string();
+        let file_paths = [file_path.clone()];
+        assert_eq!(gradle_root(&file_path), Some(root.clone()));
+
+        let items = discover_in_source(&file_path, &std::fs::read_to_string(&file_path).unwrap());
+        assert_eq!(
+            task_args(&root, &file_paths, &items[..1], None),
+            [":app:test", "--tests", "com.example.MathTest.adds"]
+        );
+        assert_eq!(
+            task_args(&root, &file_paths, &[], Some("large")),
+            [":app:test", "--tests", "*large*"]
+        );
+        assert_eq!(task_args(&root, &file_paths, &[], None), [":app:test"]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem, junit::JUnitFailure,
+    workspace::same_path,
+};
+
+fn error_diagnostic(test_kind: &str, range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some(test_kind.to_string()),
+        code: Some(NumberOrString::String(format!("{test_kind}-failed"))),
+        ..Diagnostic::default()
+    }
+}
+
+/// Target file of a stack frame in `file_name` of the class `class_name`:
+/// the one in the directory of the class package, or the only one with that
+/// name.
+fn frame_file<'a>(file_paths: &'a [String], class_name: &str, file_name: &str) -> Option<&'a str> {
+    let package_dir = class_name
+        .rsplit_once('.')
+        .map(|(package, _)| package.replace('.', "/"))
+        .unwrap_or_default();
+    let candidates: Vec<&String> = file_paths
+        .iter()
+        .filter(|path| {
+            Path::new(path)
+                .file_name()
+                .is_some_and(|name| name == file_name)
+        })
+        .collect();
+    candidates
+        .iter()
+        .find(|path| {
+            Path::new(path.as_str()).parent().is_some_and(|dir| {
+                dir.to_string_lossy()
+                    .replace('\\', "/")
+                    .ends_with(&package_dir)
+            })
+        })
+        .or_else(|| (candidates.len() == 1).then(|| &candidates[0]))
+        .map(|path| path.as_str())
+}
+
+/// Diagnostics for failed tests.
+///
+/// Failures are placed at the innermost stack frame, like
+/// `at app//com.example.MathTest.adds(MathTest.kt:12)` or
+/// `at app//com.example.MathTest$Nested.adds(MathTest.java:12)` (or a
+/// `.scala` file), in one of
+/// `file_paths`, and linked to the definition of the test. A failure without
+/// such a frame is placed at the definition. Diagnostics have `test_kind` as
+/// their source.
+#[must_use]
+pub fn to_diagnostics(
+    test_kind: &str,
+    failures: &[JUnitFailure],
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let frame_re =
+        Regex::new(r"^\s*at\s+(?:\S*//)?(.+)\.[^.(]+\(([^():]+\.(?:kts?|java|scala)):(\d+)\)")
+            .unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let test_id = failure.test_id();
+        let test_item = test_items.iter().find(|item| item.id == test_id);
+        let message = if failure.message.trim().is_empty() {
+            failure.stack_trace.lines().next().unwrap_or_default()
+        } else {
+            failure.message.trim()
+        };
+
+        let frame = failure.stack_trace.lines().find_map(|line| {
+            let m = frame_re.captures(line)?;
+            let path = frame_file(file_paths, &m[1], &m[2])?;
+            let line_number = m[3].parse::<u32>().ok()?.saturating_sub(1);
+            Some((path, line_number))
+        });
+        if let Some((path, line_number)) = frame {
+            let range = Range::new(
+                Position::new(line_number, 0),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path.to_string())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(test_kind, range, message.to_string())
+                });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_kind,
+                    test_item.start_position,
+                    format!("`{test_id}` failed\n{message}"),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::junit::parse_junit;
+
+    #[test]
+    fn test_parse_junit() {
+        let contents = std::fs::read_to_string("demo/gradle/test-results.xml").unwrap();
+        let failures = parse_junit(&contents).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].test_id(),
+            "com.example.MathTest.fails on purpose"
+        );
+
+        let file_path = "/home/demo/gradle/app/src/test/kotlin/com/example/MathTest.kt".to_string();
+        let test_item = TestItem {
+            id: "com.example.MathTest.fails on purpose".to_string(),
+            name: "MathTest.fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(14, 4), Position::new(14, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(17, 0), Position::new(17, 5)),
+        };
+        let diagnostics = to_diagnostics(
+            "gradle-test",
+            &failures,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(16, 0));
+        assert_eq!(
+            diagnostic.message,
+            "org.opentest4j.AssertionFailedError: expected: <4> but was: <3>"
+        );
+        assert_eq!(
+            diagnostic.related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            test_item.start_position
+        );
+
+        let other_file = "/home/demo/gradle/app/src/test/kotlin/com/other/MathTest.kt";
+        let diagnostics = to_diagnostics(
+            "gradle-test",
+            &failures,
+            &[other_file.to_string(), file_path.clone()],
+            &[],
+        );
+        assert_eq!(diagnostics.files[0].path, file_path);
+    }
+}
@@ -2,30 +2,92 @@
 
 use std::collections::HashMap;
 
-use lsp_types::{Diagnostic, Range, ShowMessageParams};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, Location, Range, ShowMessageParams, Url,
+};
 use serde::{Deserialize, Serialize};
 
+pub mod bats;
+pub mod catch2;
+pub mod cmake;
 pub mod config;
+pub mod coverage;
+pub mod discovery_cache;
+pub mod document;
+pub mod dotenv;
 pub mod error;
+pub mod hover;
+pub mod junit;
 pub mod log;
 pub mod protocol;
 pub mod runner;
 pub mod server;
+pub mod source;
+pub mod tap;
 pub mod workspace;
 
 // Language-specific modules
+pub mod ctest;
+pub mod dart;
 pub mod go;
+pub mod haskell;
 pub mod javascript;
+pub mod julia;
+pub mod kotlin;
+pub mod lua;
+pub mod nim;
+pub mod ocaml;
 pub mod php;
+pub mod pytest;
 pub mod rust;
+pub mod scala;
+pub mod swift;
+pub mod zig;
 
 // Re-export config types for convenience
 pub use config::{AdapterConfig, Config};
 
-/// If the character value is greater than the line length it defaults back to
-/// the line length.
+/// End column of ranges reaching the end of their line. Where the source is at
+/// hand it's replaced by the length of the line, see [`LineLengths`]; clients
+/// otherwise clamp it to the line length.
 pub const MAX_CHAR_LENGTH: u32 = 10000;
 
+/// Lengths of the lines of a source, in the UTF-16 code units LSP positions
+/// count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineLengths(Vec<u32>);
+
+impl LineLengths {
+    #[must_use]
+    pub fn new(source: &str) -> Self {
+        Self(
+            source
+                .lines()
+                .map(|line| u32::try_from(line.encode_utf16().count()).unwrap_or(MAX_CHAR_LENGTH))
+                .collect(),
+        )
+    }
+
+    /// Line lengths of the file at `path`, or `None` when it can't be read.
+    #[must_use]
+    pub fn of_file(path: &str) -> Option<Self> {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|source| Self::new(&source))
+    }
+
+    /// Clamp the columns of `range` past the end of their line, like
+    /// `MAX_CHAR_LENGTH`, to the line's length. Positions on lines the
+    /// source doesn't have are left alone.
+    pub fn clamp(&self, range: &mut Range) {
+        for position in [&mut range.start, &mut range.end] {
+            if let Some(&length) = self.0.get(position.line as usize) {
+                position.character = position.character.min(length);
+            }
+        }
+    }
+}
+
 // --- Core Types ---
 
 pub type FilePath = String;
@@ -42,11 +104,38 @@ pub struct TestItem {
     pub end_position: Range,
 }
 
+impl TestItem {
+    /// Related information pointing a failure back at the definition of the
+    /// test.
+    #[must_use]
+    pub fn definition_info(&self) -> Option<DiagnosticRelatedInformation> {
+        Some(DiagnosticRelatedInformation {
+            location: Location {
+                uri: Url::from_file_path(&self.path).ok()?,
+                range: self.start_position,
+            },
+            message: format!("test `{}` defined here", self.id),
+        })
+    }
+
+    /// Clamp the ranges of the test to the lengths of its lines.
+    pub fn clamp_line_ends(&mut self, lines: &LineLengths) {
+        lines.clamp(&mut self.start_position);
+        lines.clamp(&mut self.end_position);
+    }
+}
+
 /// Tests found in a single file.
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct FileTests {
     pub path: String,
     pub tests: Vec<TestItem>,
+    /// Test kind of the adapter that owns the file, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_kind: Option<String>,
+    /// Id of the adapter that owns the file, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adapter_id: Option<String>,
 }
 
 /// Collection of discovered tests across files.
@@ -70,23 +159,210 @@ pub struct Diagnostics {
     pub messages: Vec<ShowMessageParams>,
 }
 
+impl Diagnostics {
+    /// Add diagnostics of files, joining those of files already present.
+    pub fn extend_files(&mut self, files: impl IntoIterator<Item = FileDiagnostics>) {
+        for file in files {
+            match self.files.iter_mut().find(|known| known.path == file.path) {
+                Some(known) => known.diagnostics.extend(file.diagnostics),
+                None => self.files.push(file),
+            }
+        }
+    }
+}
+
 /// Map of workspace roots to their contained files.
 #[derive(Debug, Serialize, Clone, Deserialize, Default)]
 pub struct Workspaces {
     pub map: HashMap<WorkspacePath, Vec<FilePath>>,
 }
 
+/// Parameters of the `$/parseResults` request.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseResultsParams {
+    pub test_kind: String,
+    /// Results file produced by a previous run of the test command
+    pub path: String,
+    /// Workspace the results were produced in, by default the first detected
+    /// workspace of the test kind
+    pub workspace: Option<WorkspacePath>,
+    /// Files to report diagnostics for, by default the files of the
+    /// workspace
+    pub files: Option<Vec<FilePath>>,
+}
+
+/// Parameters of the `$/explainRun` request.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainRunParams {
+    /// File or workspace folder to explain, by default every detected
+    /// workspace
+    pub uri: Option<String>,
+    /// Name filter, as taken by `$/runFileTest`
+    pub filter: Option<String>,
+}
+
+/// Parameters of the `$/runTaggedTests` request.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTaggedTestsParams {
+    /// Tag expression, like a cargo nextest filterset or go build tags
+    pub tags: String,
+}
+
+/// Adapters that ran tests by tag, as returned by `$/runTaggedTests`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaggedTestsRun {
+    pub adapters: Vec<AdapterId>,
+    /// Adapters whose runner can't select tests by tag
+    pub unsupported: Vec<AdapterId>,
+    /// Says which adapters couldn't run, when some couldn't
+    pub message: Option<String>,
+}
+
+/// Parameters of the `$/testOutput` request.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TestOutputParams {
+    /// Id of the test, as discovered
+    pub test_id: String,
+}
+
+/// Result of a test in the last run and what it printed, as returned by
+/// `$/testOutput`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TestOutput {
+    pub test_id: String,
+    pub status: runner::TestStatus,
+    /// Failure message, as in the test's diagnostic
+    pub message: Option<String>,
+    /// Output captured while the test failed, for runners that capture it per
+    /// test (`cargo-test` and `go-test`)
+    pub output: Option<String>,
+}
+
+/// Parameters of the `$/debugTest` request.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugTestParams {
+    pub test_kind: String,
+    /// File defining the test
+    pub uri: String,
+    /// Id of the test, as discovered
+    pub test_id: String,
+}
+
+/// Command a runner would execute for the files of a workspace, as returned
+/// by `$/explainRun`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainedRun {
+    pub test_kind: String,
+    pub workspace: WorkspacePath,
+    pub files: Vec<FilePath>,
+    pub command: Option<runner::CommandLine>,
+    /// Why the command couldn't be assembled, e.g. a missing binary
+    pub error: Option<String>,
+}
+
+/// Readiness of a configured adapter, as returned by `$/health`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AdapterHealth {
+    pub adapter_id: AdapterId,
+    pub test_kind: String,
+    /// Whether the binary was found and workspaces with files were detected
+    pub ready: bool,
+    /// Full path of the test binary, when found on `PATH`
+    pub binary: Option<String>,
+    /// First line printed by the binary's `--version`
+    pub version: Option<String>,
+    pub workspaces: usize,
+    pub files: usize,
+    /// Why the adapter isn't ready, e.g. a missing binary
+    pub error: Option<String>,
+}
+
+/// Warnings about the configuration, as returned by `$/validateConfig`.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidation {
+    /// Warnings of every adapter, sorted by adapter id, then those about
+    /// adapters conflicting and the server options
+    pub warnings: Vec<String>,
+}
+
+/// Kind of a node of the test tree.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TestTreeKind {
+    Workspace,
+    File,
+    Test,
+}
+
+/// Node of the test tree returned by `$/testing/listTests`: workspaces
+/// contain files, which contain tests. Ids are unique among siblings.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTreeItem {
+    pub id: String,
+    pub label: String,
+    pub kind: TestTreeKind,
+    /// Test kind of the adapter running the tests of a workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_kind: Option<String>,
+    pub uri: Url,
+    /// Range of a test, from its start to the end of its body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+    pub children: Vec<TestTreeItem>,
+}
+
+/// Number of files and discovered tests of an adapter in a workspace, sent in
+/// a `$/discoveredTestCount` notification after workspaces are detected.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct DiscoveredTestCount {
+    pub adapter_id: AdapterId,
+    pub workspace: WorkspacePath,
+    /// Files of the workspace the adapter runs the tests of
+    pub file_count: usize,
+    /// Tests discovered in these files
+    pub test_count: usize,
+}
+
+/// A test run that failed to produce results, sent in a `$/testRunError`
+/// notification along with the message shown to the user.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct TestRunError {
+    pub workspace: WorkspacePath,
+    pub adapter_id: AdapterId,
+    /// Kind of the error, like `binary-missing`; see
+    /// [`crate::error::LSError::kind`]
+    pub kind: String,
+    pub error: String,
+}
+
 /// Analysis result for a workspace with its adapter configuration.
 #[derive(Debug, Serialize, Clone)]
 pub struct WorkspaceAnalysis {
+    pub adapter_id: AdapterId,
     pub adapter_config: AdapterConfig,
     pub workspaces: Workspaces,
 }
 
 impl WorkspaceAnalysis {
     #[must_use]
-    pub fn new(adapter_config: AdapterConfig, workspaces: Workspaces) -> Self {
+    pub fn new(
+        adapter_id: AdapterId,
+        adapter_config: AdapterConfig,
+        workspaces: Workspaces,
+    ) -> Self {
         Self {
+            adapter_id,
             adapter_config,
             workspaces,
         }
@@ -0,0 +1,57 @@
+use std::process::{Command, Output};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// `text` as a Lua pattern matching it literally, with `%` before the magic
+/// characters.
+fn lua_pattern_escape(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "^$()%.[]*+-?".contains(c) {
+            pattern.push('%');
+        }
+        pattern.push(c);
+    }
+    pattern
+}
+
+/// Command running the given spec files with `busted --output=json`, which
+/// prints the results as one JSON document. A filter is a Lua pattern
+/// matched against the full names of the tests.
+pub fn busted_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut busted = test_command(context, command, &["busted", "--output=json"])?;
+    if let Some(filter) = filter {
+        busted.arg("--filter").arg(lua_pattern_escape(filter));
+    }
+    busted.args(extra_args).args(file_paths);
+    Ok(busted)
+}
+
+/// Run a `busted` command.
+pub fn run_busted(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("busted.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lua_pattern_escape() {
+        assert_eq!(lua_pattern_escape("adds (1 + 2)"), "adds %(1 %+ 2%)");
+        assert_eq!(lua_pattern_escape("100%"), "100%%");
+    }
+}
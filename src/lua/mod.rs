@@ -0,0 +1,266 @@
+//! Lua tests run by busted.
+//!
+//! Tests are the `it(...)` calls of a spec file (or their aliases `spec` and
+//! `test`), named after the `describe(...)` (or `context`) calls around them.
+//! No Lua grammar is bundled, so they are found by scanning the source,
+//! balancing parentheses to find where calls end.
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::position_at,
+};
+
+/// Byte offset just past the call whose argument list opens after `start`,
+/// judged by balancing parentheses outside of strings and `--` comments.
+fn call_end(source: &str, start: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = source[start..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open || c == '\n' {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '-' if chars.peek().is_some_and(|(_, next)| *next == '-') => {
+                chars.find(|(_, c)| *c == '\n');
+            }
+            '(' => depth += 1,
+            ')' if depth == 1 => return start + i + 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Whether `offset` is in a `--` line comment.
+fn in_comment(source: &str, offset: usize) -> bool {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..offset].contains("--")
+}
+
+/// Tests of a file, with ids like `math fails on purpose` as busted names
+/// them: the names of the enclosing blocks and the test, joined by spaces.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let call_re = Regex::new(
+        r#"(?m)(?:^|[^\w.:])(describe|context|it|spec|test)\s*\(\s*(?:'([^'\n]*)'|"([^"\n]*)")"#,
+    )
+    .unwrap();
+
+    // Whether it's a block, name, start and end of each call
+    let calls: Vec<(bool, &str, usize, usize)> = call_re
+        .captures_iter(source)
+        .filter_map(|m| {
+            let keyword = m.get(1).unwrap();
+            if in_comment(source, keyword.start()) {
+                return None;
+            }
+            let name = m.get(2).or_else(|| m.get(3)).unwrap().as_str();
+            Some((
+                matches!(keyword.as_str(), "describe" | "context"),
+                name,
+                keyword.start(),
+                call_end(source, keyword.end()),
+            ))
+        })
+        .collect();
+
+    let mut items = vec![];
+    for &(is_block, name, start, end) in &calls {
+        if is_block {
+            continue;
+        }
+        let mut names: Vec<&str> = calls
+            .iter()
+            .filter(|(is_block, _, block_start, block_end)| {
+                *is_block && *block_start < start && end <= *block_end
+            })
+            .map(|(_, name, _, _)| *name)
+            .collect();
+        names.push(name);
+        let id = names.join(" ");
+        let (line, column) = position_at(source, start);
+        let end = position_at(source, end);
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+fn diagnostics(contents: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_busted_json(contents),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct BustedRunner;
+
+impl Runner for BustedRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::busted_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_busted(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        Ok(diagnostics(
+            &String::from_utf8_lossy(&output.stdout),
+            context.workspace,
+            file_paths,
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &[".busted"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/busted/spec/math_spec.lua").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "math adds",
+                "math fails on purpose",
+                "math large numbers adds large numbers",
+                "runs outside of a describe"
+            ]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(7, 2));
+        assert_eq!(items[1].end_position.end, Position::new(9, 6));
+        assert_eq!(items[3].start_position.start, Position::new(19, 0));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let contents = std::fs::read_to_string("demo/busted/results.json").unwrap();
+        let root = std::env::current_dir().unwrap().join("demo/busted");
+        let spec = root
+            .join("spec/math_spec.lua")
+            .to_string_lossy()
+            .to_string();
+        let diagnostics = diagnostics(
+            &contents,
+            &root.to_string_lossy(),
+            std::slice::from_ref(&spec),
+        );
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(8, 0));
+        assert_eq!(
+            diagnostics[0].related_information.as_ref().unwrap()[0]
+                .location
+                .range
+                .start,
+            Position::new(7, 2)
+        );
+        assert_eq!(
+            diagnostics[1].code,
+            Some(lsp_types::NumberOrString::String(
+                "busted-error".to_string()
+            ))
+        );
+        assert_eq!(diagnostics[1].range.start, Position::new(20, 0));
+    }
+}
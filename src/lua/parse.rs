@@ -0,0 +1,208 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    workspace::{resolve_path, same_path},
+};
+
+/// Debug info of a function, as busted reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Trace {
+    /// Chunk name, like `@spec/math_spec.lua` for a file
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub currentline: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Element {
+    trace: Option<Trace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestResult {
+    name: String,
+    #[serde(default)]
+    message: Option<String>,
+    trace: Option<Trace>,
+    element: Option<Element>,
+}
+
+/// Output of `busted --output=json`.
+#[derive(Debug, Deserialize)]
+struct Results {
+    #[serde(default)]
+    failures: Vec<TestResult>,
+    #[serde(default)]
+    errors: Vec<TestResult>,
+}
+
+/// A test that failed an assertion, or raised an error when `is_error`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BustedFailure {
+    /// Full name, the names of the enclosing `describe` blocks and the test
+    /// joined by spaces
+    pub name: String,
+    pub message: String,
+    pub is_error: bool,
+    /// Where the test failed
+    pub trace: Option<Trace>,
+    /// Where the test is defined
+    pub definition: Option<Trace>,
+}
+
+/// Parse the failures and errors from the output of `busted --output=json`.
+/// Output printed before the JSON document, e.g. by the tests, is skipped.
+#[must_use]
+pub fn parse_busted_json(contents: &str) -> Vec<BustedFailure> {
+    let Some(results) = contents
+        .match_indices('{')
+        .find_map(|(start, _)| serde_json::from_str::<Results>(contents[start..].trim_end()).ok())
+    else {
+        return vec![];
+    };
+    let location_re = Regex::new(r"^\S+:\d+: ").unwrap();
+    let failure = |result: TestResult, is_error: bool| BustedFailure {
+        name: result.name,
+        message: location_re
+            .replace(result.message.as_deref().unwrap_or_default().trim(), "")
+            .to_string(),
+        is_error,
+        trace: result.trace,
+        definition: result.element.and_then(|element| element.trace),
+    };
+    results
+        .failures
+        .into_iter()
+        .map(|result| failure(result, false))
+        .chain(
+            results
+                .errors
+                .into_iter()
+                .map(|result| failure(result, true)),
+        )
+        .collect()
+}
+
+fn error_diagnostic(range: Range, message: String, code: &str) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("busted".to_string()),
+        code: Some(NumberOrString::String(code.to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// File of `file_paths` and line a trace points at.
+fn trace_location<'a>(
+    trace: &Trace,
+    root: &Path,
+    file_paths: &'a [String],
+) -> Option<(&'a String, u32)> {
+    let source = trace.source.strip_prefix('@')?;
+    let resolved = resolve_path(root, source).to_string_lossy().to_string();
+    let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+    Some((path, trace.currentline?.saturating_sub(1)))
+}
+
+/// Diagnostics for failed tests.
+///
+/// Failures are placed at their trace in one of `file_paths` and linked to
+/// the discovered test of their name. A failure traced elsewhere, like in the
+/// code under test, is placed at the test.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[BustedFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let code = if failure.is_error {
+            "busted-error"
+        } else {
+            "busted-failed"
+        };
+        let test_item = test_items.iter().find(|item| item.id == failure.name);
+
+        if let Some((path, line)) = failure
+            .trace
+            .as_ref()
+            .and_then(|trace| trace_location(trace, root, file_paths))
+        {
+            let range = Range::new(Position::new(line, 0), Position::new(line, MAX_CHAR_LENGTH));
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, failure.message.clone(), code)
+                });
+            continue;
+        }
+
+        let message = format!("`{}` failed\n{}", failure.name, failure.message);
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(test_item.start_position, message, code));
+        } else if let Some((path, line)) = failure
+            .definition
+            .as_ref()
+            .and_then(|trace| trace_location(trace, root, file_paths))
+        {
+            let range = Range::new(Position::new(line, 0), Position::new(line, MAX_CHAR_LENGTH));
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(error_diagnostic(range, message, code));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_busted_json() {
+        let contents = std::fs::read_to_string("demo/busted/results.json").unwrap();
+        let failures = parse_busted_json(&format!("printed by a test\n{contents}"));
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].name, "math fails on purpose");
+        assert!(!failures[0].is_error);
+        assert_eq!(
+            failures[0].message,
+            "Expected objects to be the same.\nPassed in:\n(number) 3\nExpected:\n(number) 4"
+        );
+        assert_eq!(failures[0].trace.as_ref().unwrap().currentline, Some(9));
+        assert_eq!(failures[1].name, "runs outside of a describe");
+        assert!(failures[1].is_error);
+        assert_eq!(
+            failures[1].message,
+            "attempt to call a nil value (field 'sub')"
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use std::process::{Command, Output};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Command running every test of the package with `nimble test`, which
+/// compiles and runs the `tests/t*.nim` files.
+pub fn nimble_test_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut nimble = test_command(context, command, &["nimble", "test"])?;
+    nimble.args(extra_args);
+    Ok(nimble)
+}
+
+/// Run a `nimble test` command.
+pub fn run_nimble_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("nimble_test.log", &output)?;
+    Ok(output)
+}
@@ -0,0 +1,219 @@
+//! Nim tests of the `unittest` module run by `nimble test`.
+//!
+//! Tests are the `test "name":` blocks of a file, named after the
+//! `suite "name":` block around them like `unittest` filters them:
+//! `suite::test`. No Nim grammar is bundled, so they are found by scanning
+//! the source, taking a block to end before the next line indented no deeper
+//! than its header.
+
+pub mod call;
+pub mod parse;
+
+use std::{collections::HashMap, path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+};
+
+/// Zero-based line of the last line of the indented block whose header is
+/// at `header`, skipping blank lines and comments.
+fn block_end(lines: &[&str], header: usize, indent: usize) -> usize {
+    let mut end = header;
+    for (i, line) in lines.iter().enumerate().skip(header + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if line.len() - trimmed.len() <= indent {
+            break;
+        }
+        end = i;
+    }
+    end
+}
+
+/// Tests of a file, with ids like `adds`, or `math::fails on purpose` in a
+/// suite.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let block_re = Regex::new(r#"^(\s*)(suite|test)\s*\(?\s*"([^"]*)"\s*\)?\s*:"#).unwrap();
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut suite: Option<(&str, usize)> = None;
+    let mut items = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = block_re.captures(line) else {
+            continue;
+        };
+        let indent = m[1].len();
+        let end = block_end(&lines, i, indent);
+        let name = m.get(3).unwrap().as_str();
+        if &m[2] == "suite" {
+            suite = Some((name, end));
+            continue;
+        }
+        let id = match suite {
+            Some((suite, suite_end)) if i <= suite_end => format!("{suite}::{name}"),
+            _ => name.to_string(),
+        };
+        let line_number = i as u32;
+        let end_line = end as u32;
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line_number, indent as u32),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(
+                Position::new(end_line, 0),
+                Position::new(end_line, lines[end].len() as u32),
+            ),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Directory of the nimble package a file belongs to: the nearest directory
+/// with a `.nimble` file.
+fn nimble_package_dir(file_path: &str) -> Option<&Path> {
+    Path::new(file_path).ancestors().skip(1).find(|dir| {
+        std::fs::read_dir(dir).is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "nimble"))
+            })
+        })
+    })
+}
+
+fn diagnostics(output: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_nim_output(output),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct NimTestRunner;
+
+impl Runner for NimTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        _filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::nimble_test_command(context, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_nimble_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        // unittest prints to stdout, nimble and the compiler to stderr
+        let output = [output.stdout, output.stderr].concat();
+        Ok(diagnostics(
+            &String::from_utf8_lossy(&output),
+            context.workspace,
+            file_paths,
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for file_path in file_paths {
+            if let Some(package) = nimble_package_dir(file_path) {
+                map.entry(package.to_string_lossy().to_string())
+                    .or_default()
+                    .push(file_path.clone());
+            }
+        }
+        Workspaces { map }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/nimble/tests/test_math.nim").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["adds", "math::fails on purpose", "math::raises"]);
+        assert_eq!(items[1].start_position.start, Position::new(7, 2));
+        assert_eq!(items[1].end_position.end, Position::new(8, 24));
+        assert_eq!(items[2].end_position.end.line, 12);
+
+        let source = "suite \"a\":\n  test \"x\":\n    check true\ntest \"y\":\n  discard\n";
+        let ids: Vec<String> = discover_in_source("t.nim", source)
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(ids, vec!["a::x", "y"]);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    log::clean_ansi,
+    workspace::{resolve_path, same_path},
+};
+
+/// A test `unittest` reported as `[FAILED]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NimTestFailure {
+    /// Name of the test, after its suite and `::` if it has one
+    pub name: String,
+    /// Lines the test printed before its result, like failed checks and
+    /// unhandled exceptions with their stack trace
+    pub output: Vec<String>,
+}
+
+/// Parse the failed tests from the output of `nimble test`, in which
+/// `unittest` prints `[Suite] name` before the tests of a suite and
+/// `[OK] name` or `[FAILED] name` after each test. Compiler and nimble
+/// messages are skipped.
+#[must_use]
+pub fn parse_nim_output(contents: &str) -> Vec<NimTestFailure> {
+    let result_re = Regex::new(r"^\s*\[(Suite|OK|FAILED|SKIPPED)\]\s+(.*?)\s*$").unwrap();
+    let noise_re =
+        Regex::new(r"^\s*(?:Hint|Warning|CC|Success|Executing|Verifying|Compiling|Building)\b")
+            .unwrap();
+    let mut failures = vec![];
+    let mut suite: Option<String> = None;
+    let mut output: Vec<String> = vec![];
+
+    for line in clean_ansi(contents).lines() {
+        let Some(m) = result_re.captures(line) else {
+            if !line.trim().is_empty() && !noise_re.is_match(line) {
+                output.push(line.trim().to_string());
+            }
+            continue;
+        };
+        match &m[1] {
+            "Suite" => suite = Some(m[2].to_string()),
+            "FAILED" => failures.push(NimTestFailure {
+                name: suite
+                    .as_ref()
+                    .map_or_else(|| m[2].to_string(), |suite| format!("{suite}::{}", &m[2])),
+                output: std::mem::take(&mut output),
+            }),
+            _ => {}
+        }
+        output.clear();
+    }
+    failures
+}
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("nim-test".to_string()),
+        code: Some(NumberOrString::String("nim-test-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests.
+///
+/// Failures are placed at the first failed check, like
+/// `tests/test_math.nim(9, 18): Check failed: ...`, or else at the innermost
+/// frame of the stack trace of an unhandled exception, like
+/// `tests/test_math.nim(13) test_math`, in one of `file_paths`, and linked to
+/// the definition of the test. A failure without either is placed at the
+/// definition.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[NimTestFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let check_re = Regex::new(r"^(\S.*?\.nim)\((\d+), (\d+)\):\s*(.*)$").unwrap();
+    let frame_re = Regex::new(r"^(\S.*?\.nim)\((\d+)\)\s+\S+$").unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let test_item = test_items.iter().find(|item| item.id == failure.name);
+        let resolve = |path: &str| {
+            let resolved = resolve_path(root, path).to_string_lossy().to_string();
+            file_paths.iter().find(|path| same_path(path, &resolved))
+        };
+
+        let mut check = None;
+        let mut frame = None;
+        let mut message = vec![];
+        for line in &failure.output {
+            if let Some(m) = check_re.captures(line) {
+                if check.is_none()
+                    && let Some(path) = resolve(&m[1])
+                {
+                    let line_number = m[2].parse::<u32>().unwrap_or(1).saturating_sub(1);
+                    let column = m[3].parse::<u32>().unwrap_or(1).saturating_sub(1);
+                    check = Some((path, line_number, column));
+                }
+                message.push(m[4].to_string());
+            } else if let Some(m) = frame_re.captures(line) {
+                if let Some(path) = resolve(&m[1]) {
+                    let line_number = m[2].parse::<u32>().unwrap_or(1).saturating_sub(1);
+                    frame = Some((path, line_number, 0));
+                }
+            } else if !line.starts_with("Traceback") {
+                message.push(line.clone());
+            }
+        }
+        let message = message.join("\n");
+
+        if let Some((path, line_number, column)) = check.or(frame) {
+            let range = Range::new(
+                Position::new(line_number, column),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, message)
+                });
+            continue;
+        }
+
+        if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!("`{}` failed\n{message}", failure.name),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nim_output() {
+        let contents = std::fs::read_to_string("demo/nimble/test-output.txt").unwrap();
+        let failures = parse_nim_output(&contents);
+        let names: Vec<&str> = failures.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["math::fails on purpose", "math::raises"]);
+
+        let root = Path::new("/home/demo/nimble");
+        let file_path = "/home/demo/nimble/tests/test_math.nim".to_string();
+        let test_item = TestItem {
+            id: "math::fails on purpose".to_string(),
+            name: "math::fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(7, 2), Position::new(7, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(8, 0), Position::new(8, 24)),
+        };
+        let diagnostics = to_diagnostics(
+            &failures,
+            root,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        let mut diagnostics = diagnostics.files[0].diagnostics.clone();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(8, 17));
+        assert_eq!(
+            diagnostics[0].message,
+            "Check failed: add(2, 2) == 5\nadd(2, 2) was 4"
+        );
+        assert!(diagnostics[0].related_information.is_some());
+        assert_eq!(diagnostics[1].range.start, Position::new(12, 0));
+        assert_eq!(
+            diagnostics[1].message,
+            "Unhandled exception: index 3 not in 0 .. 1 [IndexDefect]"
+        );
+
+        // Without a location in the file, the failure is placed at the test
+        let failures = [NimTestFailure {
+            output: vec!["Check failed: false".to_string()],
+            ..failures[0].clone()
+        }];
+        let diagnostics = to_diagnostics(&failures, root, &[file_path], &[test_item]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(7, 2));
+        assert_eq!(
+            diagnostic.message,
+            "`math::fails on purpose` failed\nCheck failed: false"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use std::process::{Command, Output};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Command running `dune runtest`, forced so that tests dune cached as
+/// passed run again. dune runs every test of the project: neither inline
+/// tests nor Alcotest take a name filter from it.
+pub fn dune_runtest_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut dune = test_command(context, command, &["dune", "runtest", "--force"])?;
+    dune.args(extra_args);
+    Ok(dune)
+}
+
+/// Run a `dune runtest` command.
+pub fn run_dune_runtest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("dune_runtest.log", &output)?;
+    Ok(output)
+}
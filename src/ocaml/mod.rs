@@ -0,0 +1,214 @@
+//! OCaml tests run by `dune runtest`.
+//!
+//! Tests are the named inline tests `let%test "name"`, `let%test_unit` and
+//! `let%expect_test` of `ppx_inline_test` and `ppx_expect`, and the Alcotest
+//! `test_case "name"` calls. No OCaml grammar is bundled, so they are found by
+//! scanning the source; a test ends before the next line indented no deeper
+//! than it.
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::{indented_end, position_at},
+};
+
+/// Tests of a file, with ids like `adds` as the name written in the source.
+/// Unnamed inline tests can't be told apart, so they aren't listed.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let test_re = Regex::new(
+        r#"(?m)(?:^[ \t]*let%(?:test|test_unit|expect_test)|(?:\bAlcotest\.)?\btest_case)\s+"((?:[^"\\\n]|\\.)*)""#,
+    )
+    .unwrap();
+
+    test_re
+        .captures_iter(source)
+        .map(|m| {
+            let found = m.get(0).unwrap();
+            let name = m[1].to_string();
+            let start = found.start() + (found.len() - found.as_str().trim_start().len());
+            let (line, column) = position_at(source, start);
+            let end = indented_end(source, line, column);
+            TestItem {
+                id: name.clone(),
+                name,
+                path: file_path.to_string(),
+                start_position: Range::new(
+                    Position::new(line, column),
+                    Position::new(line, MAX_CHAR_LENGTH),
+                ),
+                end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+            }
+        })
+        .collect()
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+fn diagnostics(contents: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_dune_output(contents),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct DuneTestRunner;
+
+impl Runner for DuneTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        _file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        _filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::dune_runtest_command(context, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_dune_runtest(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        // dune reports errors on stderr, and test executables print on stdout
+        let contents = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(diagnostics(&contents, context.workspace, file_paths))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["dune-project"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/dune/src/math.ml").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["adds", "fails on purpose", "unit adds", "prints sum"]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(4, 0));
+        assert_eq!(items[3].start_position.start, Position::new(8, 0));
+        assert_eq!(items[3].end_position.end, Position::new(10, 19));
+
+        let items = discover_tests("demo/dune/test/test_math.ml").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["adds", "fails with alcotest"]);
+        assert_eq!(items[1].start_position.start, Position::new(10, 10));
+        assert_eq!(items[1].end_position.end.line, 10);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let contents = std::fs::read_to_string("demo/dune/runtest-output.txt").unwrap();
+        let root = std::env::current_dir().unwrap().join("demo/dune");
+        let file_paths: Vec<String> = ["src/math.ml", "test/test_math.ml"]
+            .iter()
+            .map(|path| root.join(path).to_string_lossy().to_string())
+            .collect();
+        let diagnostics = diagnostics(&contents, &root.to_string_lossy(), &file_paths);
+        assert_eq!(diagnostics.files.len(), 2);
+
+        let file = |path: &str| {
+            &diagnostics
+                .files
+                .iter()
+                .find(|file| file.path == *path)
+                .unwrap()
+                .diagnostics
+        };
+        let math = file(&file_paths[0]);
+        assert_eq!(math.len(), 2);
+        assert_eq!(math[0].range.start, Position::new(4, 0));
+        assert!(math[0].related_information.is_some());
+        assert_eq!(math[1].range.start, Position::new(10, 0));
+
+        let alcotest = file(&file_paths[1]);
+        assert_eq!(alcotest[0].range.start, Position::new(2, 20));
+        assert!(
+            alcotest[0]
+                .message
+                .starts_with("`fails with alcotest` failed\n")
+        );
+        assert_eq!(
+            alcotest[0].related_information.as_ref().unwrap()[0]
+                .location
+                .range
+                .start,
+            Position::new(10, 10)
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    workspace::{resolve_path, same_path},
+};
+
+/// A failed Alcotest test case, or, when `name` is `None`, an error dune
+/// reports at a location: a failed inline test, an expect test whose output
+/// differs or a compile error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuneFailure {
+    /// Name of the Alcotest test case
+    pub name: Option<String>,
+    pub message: String,
+    /// File, one-based line and zero-based column of the error, or of each
+    /// stack frame, innermost first
+    pub frames: Vec<(String, u32, u32)>,
+}
+
+/// Line of the first changed line of a diff hunk, like `@@ -9,3 +9,3 @@`
+/// followed by context lines, and the lines of the hunk.
+fn diff_hunk(lines: &[String]) -> Option<(u32, Vec<&str>)> {
+    let hunk_re = Regex::new(r"^@@ -(\d+)(?:,\d+)? \+\d+(?:,\d+)? @@").unwrap();
+    let start = lines.iter().position(|line| hunk_re.is_match(line))?;
+    let first_line: u32 = hunk_re.captures(&lines[start])?[1].parse().ok()?;
+    let hunk: Vec<&str> = lines[start + 1..]
+        .iter()
+        .map(String::as_str)
+        .take_while(|line| line.starts_with([' ', '-', '+']))
+        .collect();
+    let context = hunk
+        .iter()
+        .take_while(|line| !line.starts_with(['-', '+']))
+        .count();
+    Some((first_line + u32::try_from(context).ok()?, hunk))
+}
+
+/// A failure at a `File` marker, with the expect test diff that follows it
+/// placed at the first changed line.
+fn located_failure(path: String, line: u32, column: u32, lines: &[String]) -> DuneFailure {
+    if let Some((changed, hunk)) = diff_hunk(lines) {
+        return DuneFailure {
+            name: None,
+            message: format!("expect test output differs:\n{}", hunk.join("\n")),
+            frames: vec![(path, changed, 0)],
+        };
+    }
+    DuneFailure {
+        name: None,
+        message: lines.join("\n").trim().to_string(),
+        frames: vec![(path, line, column)],
+    }
+}
+
+/// Parse failures from the output of `dune runtest`.
+///
+/// Errors start at markers like `File "src/math.ml", line 5, characters
+/// 0-45: <message>`, with the message on the marker line (failed inline
+/// tests) or on the following lines (compile errors, and the diff of an
+/// expect test). Alcotest failures start at the boxed `[FAIL] <suite> <index>
+/// <name>.` line of the summary, and are located by the stack frames like
+/// `Called from ... in file "test/test_math.ml", line 3, characters 20-63`.
+#[must_use]
+pub fn parse_dune_output(contents: &str) -> Vec<DuneFailure> {
+    let marker_re =
+        Regex::new(r#"^File "([^"]+)", line (\d+), characters (\d+)-\d+:\s*(.*)$"#).unwrap();
+    let fail_re = Regex::new(r"\[FAIL\]\s+\S+\s+\d+\s+(.+?)\.?\s*│?$").unwrap();
+    let frame_re = Regex::new(r#"in file "([^"]+)", line (\d+), characters (\d+)-\d+"#).unwrap();
+
+    let mut failures: Vec<DuneFailure> = vec![];
+    // Location and message lines of the error being read
+    let mut located: Option<(String, u32, u32, Vec<String>)> = None;
+    let mut alcotest: Option<(DuneFailure, Vec<String>)> = None;
+
+    let finish = |failures: &mut Vec<DuneFailure>,
+                  located: &mut Option<(String, u32, u32, Vec<String>)>,
+                  alcotest: &mut Option<(DuneFailure, Vec<String>)>| {
+        if let Some((path, line, column, lines)) = located.take() {
+            failures.push(located_failure(path, line, column, &lines));
+        }
+        if let Some((mut failure, lines)) = alcotest.take() {
+            failure.message = lines.join("\n");
+            failures.push(failure);
+        }
+    };
+
+    for line in contents.lines() {
+        let line = line.trim_end();
+        if let Some(m) = marker_re.captures(line) {
+            finish(&mut failures, &mut located, &mut alcotest);
+            let (Ok(line_number), Ok(column)) = (m[2].parse(), m[3].parse()) else {
+                continue;
+            };
+            let message: Vec<String> = Some(m[4].to_string())
+                .filter(|message| !message.is_empty())
+                .into_iter()
+                .collect();
+            located = Some((m[1].to_string(), line_number, column, message));
+        } else if let Some(m) = fail_re.captures(line) {
+            finish(&mut failures, &mut located, &mut alcotest);
+            let name = m[1].to_string();
+            if line.trim_start().starts_with('│') {
+                // The summary repeats the failures listed while running
+                failures.retain(|failure| failure.name.as_ref() != Some(&name));
+                alcotest = Some((
+                    DuneFailure {
+                        name: Some(name),
+                        message: String::new(),
+                        frames: vec![],
+                    },
+                    vec![],
+                ));
+            } else if !failures.iter().any(|f| f.name.as_ref() == Some(&name)) {
+                failures.push(DuneFailure {
+                    name: Some(name),
+                    message: String::new(),
+                    frames: vec![],
+                });
+            }
+        } else if let Some((failure, lines)) = alcotest.as_mut() {
+            let trimmed = line.trim();
+            if let Some(m) = frame_re.captures(line) {
+                if let (Ok(line_number), Ok(column)) = (m[2].parse(), m[3].parse()) {
+                    failure.frames.push((m[1].to_string(), line_number, column));
+                }
+            } else if trimmed.starts_with('─') || trimmed.starts_with("Full test results") {
+                finish(&mut failures, &mut located, &mut alcotest);
+            } else if !trimmed.is_empty() && !trimmed.starts_with(['┌', '└']) {
+                lines.push(trimmed.to_string());
+            }
+        } else if let Some((_, _, _, lines)) = located.as_mut() {
+            if line.is_empty() {
+                finish(&mut failures, &mut located, &mut alcotest);
+            } else {
+                lines.push(line.to_string());
+            }
+        }
+    }
+    finish(&mut failures, &mut located, &mut alcotest);
+
+    failures
+}
+
+fn error_diagnostic(range: Range, message: String, code: &str) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("dune".to_string()),
+        code: Some(NumberOrString::String(code.to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failures.
+///
+/// Failures are placed at their location, or at the innermost stack frame,
+/// in one of `file_paths`, and linked to the definition of the test: the
+/// Alcotest test case of that name, or the inline test defined at the
+/// location. An Alcotest failure without such a frame is placed at the
+/// definition.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[DuneFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let frame = failure.frames.iter().find_map(|(path, line, column)| {
+            let resolved = resolve_path(root, path).to_string_lossy().to_string();
+            let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+            let line = line.saturating_sub(1);
+            Some((
+                path,
+                Range::new(
+                    Position::new(line, *column),
+                    Position::new(line, MAX_CHAR_LENGTH),
+                ),
+            ))
+        });
+
+        let Some(name) = &failure.name else {
+            let Some((path, range)) = frame else {
+                continue;
+            };
+            let test_item = test_items.iter().find(|item| {
+                same_path(&item.path, path) && item.start_position.start.line == range.start.line
+            });
+            let code = if test_item.is_some() || failure.message.starts_with("expect test") {
+                "dune-test-failed"
+            } else {
+                "dune-build-error"
+            };
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, failure.message.clone(), code)
+                });
+            continue;
+        };
+
+        let matching = |item: &&TestItem| &item.id == name;
+        let test_item = frame
+            .and_then(|(path, _)| {
+                test_items
+                    .iter()
+                    .filter(matching)
+                    .find(|item| same_path(&item.path, path))
+            })
+            .or_else(|| test_items.iter().find(matching));
+        let message = if failure.message.is_empty() {
+            format!("`{name}` failed")
+        } else {
+            format!("`{name}` failed\n{}", failure.message)
+        };
+
+        if let Some((path, range)) = frame {
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, message, "dune-test-failed")
+                });
+        } else if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    message,
+                    "dune-test-failed",
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dune_output() {
+        let contents = std::fs::read_to_string("demo/dune/runtest-output.txt").unwrap();
+        let failures = parse_dune_output(&contents);
+        assert_eq!(failures.len(), 4);
+        assert_eq!(failures[0].name, None);
+        assert_eq!(failures[0].message, "fails on purpose is false.");
+        assert_eq!(failures[0].frames, vec![("src/math.ml".to_string(), 5, 0)]);
+        assert_eq!(failures[1].frames, vec![("src/math.ml".to_string(), 11, 0)]);
+        assert!(
+            failures[1]
+                .message
+                .ends_with("-  [%expect {| 4 |}]\n+  [%expect {| 3 |}]")
+        );
+        assert_eq!(failures[2].name.as_deref(), Some("fails with alcotest"));
+        assert_eq!(
+            failures[2].message,
+            "ASSERT sum\nFAIL sum\nExpected: `4'\nReceived: `3'"
+        );
+        assert_eq!(
+            failures[2].frames[1],
+            ("test/test_math.ml".to_string(), 3, 20)
+        );
+        assert_eq!(failures[3].frames, vec![("test/dune".to_string(), 2, 7)]);
+    }
+}
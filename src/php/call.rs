@@ -3,27 +3,41 @@ use std::{
     process::{Command, Output, Stdio},
 };
 
-use crate::{config, error::LSError};
+use crate::{
+    config,
+    error::LSError,
+    runner::{RunContext, test_command},
+};
+
+/// JUnit report written by the PHPUnit command.
+#[must_use]
+pub fn results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("phpunit.xml")
+}
 
-pub fn run_phpunit(
-    workspace: &str,
+/// Command running PHPUnit on `file_paths`, writing JUnit results to the
+/// cache directory.
+pub fn phpunit_command(
+    context: &RunContext,
     file_paths: &[String],
     filter_pattern: &str,
-) -> Result<(Output, PathBuf), LSError> {
-    let log_path = config::CONFIG.cache_dir.join("phpunit.xml");
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut phpunit = test_command(context, command, &["phpunit"])?;
+    phpunit
+        .arg("--log-junit")
+        .arg(results_path())
+        .args(["--filter", filter_pattern])
+        .args(file_paths);
+    Ok(phpunit)
+}
 
-    let output = Command::new("phpunit")
-        .current_dir(workspace)
-        .args([
-            "--log-junit",
-            log_path.to_str().unwrap(),
-            "--filter",
-            filter_pattern,
-        ])
-        .args(file_paths)
+/// Run a PHPUnit command, discarding its console output.
+pub fn run_phpunit(command: &mut Command) -> Result<Output, LSError> {
+    let output = command
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .output()?;
 
-    Ok((output, log_path))
+    Ok(output)
 }
@@ -2,10 +2,31 @@
 ; From https://github.com/olimorris/neotest-phpunit/blob/bbd79d95e927ccd16f0e1d765060058d34838e2e/lua/neotest-phpunit/init.lua#L111
 ; License: https://github.com/olimorris/neotest-phpunit/blob/bbd79d95e927ccd16f0e1d765060058d34838e2e/LICENSE
 
+; Test methods are only taken from the classes and traits below
+
 ((class_declaration
   name: (name) @namespace.name (#match? @namespace.name "Test")
 )) @namespace.definition
 
+; Classes extending a test case, whatever their name
+((class_declaration
+  (base_clause) @namespace.base (#match? @namespace.base "TestCase")
+)) @namespace.definition
+
+; Classes with PHPUnit attributes, e.g. `#[CoversClass(Calculator::class)]`
+((class_declaration
+  attributes: (attribute_list
+    (attribute_group
+      (attribute) @namespace.attribute (#match? @namespace.attribute "^\\\\?(PHPUnit\\\\Framework\\\\Attributes\\\\)?(Covers|Uses|Group|TestDox|Small|Medium|Large)")
+    )
+  )
+)) @namespace.definition
+
+; Traits, whose test methods run in the classes using them
+(trait_declaration
+  name: (name) @namespace.name
+) @namespace.definition
+
 ((method_declaration
   (attribute_list
     (attribute_group
@@ -27,3 +48,23 @@
     (name) @test.name
   ) @test.definition
 ))
+
+; Data provider methods, e.g. `#[DataProvider('additionProvider')]`
+((method_declaration
+  (attribute_list
+    (attribute_group
+        (attribute) @data_provider_attribute (#match? @data_provider_attribute "DataProvider")
+    )
+  )
+  (
+    (visibility_modifier)
+    (name) @test.name
+  ) @test.definition
+ ))
+
+; Data provider methods, e.g. `/** @dataProvider additionProvider */`
+(((comment) @data_provider_comment (#match? @data_provider_comment "\\@dataProvider") .
+  (method_declaration
+    (name) @test.name
+  ) @test.definition
+))
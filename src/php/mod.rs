@@ -1,12 +1,15 @@
 pub mod call;
 pub mod parse;
 
+use std::{collections::HashSet, path::Path, process::Command};
+
 use lsp_types::{Position, Range};
-use tree_sitter::{Query, QueryCursor};
+use tree_sitter::{Language, Query, QueryCursor, Tree};
 
 use crate::{
-    Diagnostics, DiscoveredTests, FileTests, MAX_CHAR_LENGTH, TestItem, Workspaces, error::LSError,
-    runner::Runner,
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
 };
 
 const DISCOVER_QUERY: &str = include_str!("discover.scm");
@@ -14,25 +17,35 @@ const DISCOVER_QUERY: &str = include_str!("discover.scm");
 fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     let source_code = std::fs::read_to_string(file_path)?;
     let mut parser = tree_sitter::Parser::new();
-    let language = tree_sitter_php::language_php();
-    parser.set_language(&language)?;
+    parser.set_language(&tree_sitter_php::language_php())?;
 
     let tree = parser
         .parse(&source_code, None)
-        .ok_or_else(|| LSError::TreeSitterParse)?;
+        .ok_or(LSError::TreeSitterParse)?;
+    discover_tests_in_tree(file_path, &source_code, &tree)
+}
 
-    let query = Query::new(&language, DISCOVER_QUERY)?;
+fn discover_tests_in_tree(
+    file_path: &str,
+    source_code: &str,
+    tree: &Tree,
+) -> Result<Vec<TestItem>, LSError> {
+    let query = Query::new(&tree_sitter_php::language_php(), DISCOVER_QUERY)?;
     let mut cursor = QueryCursor::new();
     let matches = cursor.matches(&query, tree.root_node(), source_code.as_bytes());
 
     let mut tests = Vec::new();
+    let mut test_id_set = HashSet::new();
     let name_idx = query.capture_index_for_name("test.name");
     let def_idx = query.capture_index_for_name("test.definition");
+    let namespace_idx = query.capture_index_for_name("namespace.definition");
 
+    // Test classes and traits, and candidate test methods with their byte range
+    let mut namespaces = Vec::new();
+    let mut candidates = Vec::new();
     for m in matches {
         let mut name: Option<String> = None;
-        let mut start_point = None;
-        let mut end_point = None;
+        let mut definition = None;
 
         for capture in m.captures {
             if Some(capture.index) == name_idx {
@@ -40,38 +53,56 @@ fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
                 name = Some(text.to_string());
             }
             if Some(capture.index) == def_idx {
-                start_point = Some(capture.node.start_position());
-                end_point = Some(capture.node.end_position());
+                definition = Some(capture.node);
+            }
+            if Some(capture.index) == namespace_idx {
+                namespaces.push(capture.node.byte_range());
             }
         }
+        if let (Some(name), Some(definition)) = (name, definition) {
+            candidates.push((name, definition));
+        }
+    }
 
-        if let (Some(test_name), Some(start), Some(end)) = (name, start_point, end_point) {
-            tests.push(TestItem {
-                id: test_name.clone(),
-                name: test_name,
-                path: file_path.to_string(),
-                start_position: Range {
-                    start: Position {
-                        line: start.row as u32,
-                        character: start.column as u32,
-                    },
-                    end: Position {
-                        line: start.row as u32,
-                        character: MAX_CHAR_LENGTH,
-                    },
+    for (test_name, definition) in candidates {
+        // Methods of other classes, like a `testConnection` of a client, aren't tests
+        let range = definition.byte_range();
+        if !namespaces
+            .iter()
+            .any(|namespace| namespace.start <= range.start && range.end <= namespace.end)
+        {
+            continue;
+        }
+        // A method can match several patterns, e.g. `testAdd` with a data provider
+        if !test_id_set.insert(test_name.clone()) {
+            continue;
+        }
+        let (start, end) = (definition.start_position(), definition.end_position());
+        tests.push(TestItem {
+            id: test_name.clone(),
+            name: test_name,
+            path: file_path.to_string(),
+            start_position: Range {
+                start: Position {
+                    line: start.row as u32,
+                    character: start.column as u32,
                 },
-                end_position: Range {
-                    start: Position {
-                        line: end.row as u32,
-                        character: 0,
-                    },
-                    end: Position {
-                        line: end.row as u32,
-                        character: end.column as u32,
-                    },
+                end: Position {
+                    line: start.row as u32,
+                    character: MAX_CHAR_LENGTH,
                 },
-            });
-        }
+            },
+            end_position: Range {
+                start: Position {
+                    line: end.row as u32,
+                    character: 0,
+                },
+                end: Position {
+                    line: end.row as u32,
+                    character: end.column as u32,
+                },
+            },
+        });
     }
 
     Ok(tests)
@@ -82,29 +113,66 @@ pub struct PhpunitRunner;
 
 impl Runner for PhpunitRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
-        let mut files = Vec::new();
-        for file_path in file_paths {
-            let tests = discover_tests(file_path)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.to_string(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_php::language_php())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_tests_in_tree(file_path, source, tree)
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let filter = filter.map(regex::escape);
+        let filter_pattern = filter
+            .as_deref()
+            .or(extra_args.first().map(|s| s.as_str()))
+            .unwrap_or(".*");
+        call::phpunit_command(context, file_paths, filter_pattern, command)
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
     ) -> Result<Diagnostics, LSError> {
-        let filter_pattern = extra_args.first().map(|s| s.as_str()).unwrap_or(".*");
+        call::run_phpunit(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
 
-        let (_, log_path) = call::run_phpunit(workspace, file_paths, filter_pattern)?;
+        self.parse_results(&call::results_path(), file_paths, context.workspace)
+    }
 
-        let results = parse::parse_phpunit_xml(log_path.to_str().unwrap())?;
-        Ok(parse::to_diagnostics(results))
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let test_items: Vec<TestItem> = file_paths
+            .iter()
+            .filter_map(|path| discover_tests(path).ok())
+            .flatten()
+            .collect();
+        let results = parse::parse_phpunit_xml(&results_path.to_string_lossy())?;
+        Ok(parse::to_diagnostics(results, &test_items))
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
@@ -125,4 +193,42 @@ mod tests {
         let test_items = discover_tests(file_path).unwrap();
         assert!(!test_items.is_empty());
     }
+
+    #[test]
+    fn test_discover_data_providers() {
+        let file_path = "demo/phpunit/src/DataProviderTest.php";
+        let test_items = discover_tests(file_path).unwrap();
+        let ids: Vec<_> = test_items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["testAdd", "adds"]);
+    }
+
+    #[test]
+    fn test_discover_traits_and_attributes() {
+        let ids = |file_path: &str| -> Vec<String> {
+            discover_tests(file_path)
+                .unwrap()
+                .into_iter()
+                .map(|item| item.id)
+                .collect()
+        };
+        assert_eq!(
+            ids("demo/phpunit/src/AddsNumbers.php"),
+            vec!["addsZero", "testAddsOne"]
+        );
+        assert_eq!(
+            ids("demo/phpunit/src/CalculatorBehaviour.php"),
+            vec!["subtracts"]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("Slow.php");
+        std::fs::write(
+            &file_path,
+            "<?php\n#[Group('slow')]\nfinal class SlowCalculation extends IntegrationCase\n{\n    \
+             #[Test]\n    public function adds(): void {}\n}\n\nclass Client\n{\n    public \
+             function testConnection(): bool { return true; }\n}\n",
+        )
+        .unwrap();
+        assert_eq!(ids(file_path.to_str().unwrap()), vec!["adds"]);
+    }
 }
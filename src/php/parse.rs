@@ -1,11 +1,76 @@
 use std::{fs::File, io::BufReader};
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, NumberOrString, Position, Range,
+};
 use xml::reader::{ParserConfig, XmlEvent};
 
-use crate::{Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, error::LSError};
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem, error::LSError, workspace::same_path,
+};
+
+/// Kind of a non-passing `<testcase>` result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultKind {
+    /// A failed assertion, `<failure>`
+    Failure,
+    /// An uncaught exception, `<error>`
+    Error,
+    /// A skipped test, `<skipped>`
+    Skipped,
+    /// A test marked incomplete, `<skipped>` of type `IncompleteTestError`
+    Incomplete,
+    /// A risky test, like one without assertions, reported with type
+    /// `RiskyTestError`
+    Risky,
+}
+
+impl ResultKind {
+    /// Kind of a result element, refined by its `type` attribute.
+    fn from_element(name: &str, error_type: Option<&str>) -> Option<Self> {
+        let kind = match name {
+            "failure" => Self::Failure,
+            "error" => Self::Error,
+            "skipped" => Self::Skipped,
+            _ => return None,
+        };
+        match error_type {
+            Some(error_type) if error_type.ends_with("IncompleteTestError") => {
+                Some(Self::Incomplete)
+            }
+            Some(error_type) if error_type.ends_with("RiskyTestError") => Some(Self::Risky),
+            _ => Some(kind),
+        }
+    }
+
+    /// Whether the test failed, rather than not being run in full.
+    fn is_failure(self) -> bool {
+        matches!(self, Self::Failure | Self::Error)
+    }
+
+    fn severity(self) -> DiagnosticSeverity {
+        if self.is_failure() {
+            DiagnosticSeverity::ERROR
+        } else {
+            DiagnosticSeverity::INFORMATION
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Self::Failure => "phpunit-failed",
+            Self::Error => "phpunit-error",
+            Self::Skipped => "phpunit-skipped",
+            Self::Incomplete => "phpunit-incomplete",
+            Self::Risky => "phpunit-risky",
+        }
+    }
+}
 
 pub struct ResultFromXml {
+    /// Name of the failing `<testcase>`, e.g. `testAdd with data set #2`
+    pub name: Option<String>,
+    pub kind: ResultKind,
     pub message: String,
     pub path: String,
     pub line: u32,
@@ -28,30 +93,92 @@ impl From<ResultFromXml> for FileDiagnostics {
                     },
                 },
                 message: result.message,
-                severity: Some(DiagnosticSeverity::ERROR),
+                severity: Some(result.kind.severity()),
                 source: Some("phpunit".to_string()),
-                code: Some(NumberOrString::String("phpunit-failed".to_string())),
+                code: Some(NumberOrString::String(result.kind.code().to_string())),
                 ..Diagnostic::default()
             }],
         }
     }
 }
 
-fn parse_failure_characters(characters: &str) -> Option<ResultFromXml> {
+/// Parse the text of a `<failure>` or `<error>`: the message, a blank line,
+/// then the stack trace as `path:line` frames. The frame in the file of the
+/// testcase is preferred, falling back to the innermost frame.
+fn parse_failure_characters(
+    characters: &str,
+    kind: ResultKind,
+    testcase_file: Option<&str>,
+) -> Option<ResultFromXml> {
     let mut split = characters.split("\n\n");
     let message = split
         .next()?
         .trim_start_matches("Failed asserting that ")
         .trim_end_matches(".")
         .to_string();
-    let location = split.next()?;
-    let mut parts = location.split(':');
-    let path = parts.next()?.to_string();
-    let line = parts.next()?.parse().ok()?;
+    let frames: Vec<(&str, u32)> = split
+        .next()?
+        .lines()
+        .filter_map(|frame| {
+            let (path, line) = frame.trim().rsplit_once(':')?;
+            Some((path, line.parse().ok()?))
+        })
+        .collect();
+    let (path, line) = frames
+        .iter()
+        .find(|(path, _)| Some(*path) == testcase_file)
+        .or_else(|| frames.first())?;
+    Some(ResultFromXml {
+        name: None,
+        kind,
+        message,
+        path: (*path).to_string(),
+        line: *line,
+        col: 1,
+    })
+}
+
+/// A `<testcase>` being read, with its name and location.
+#[derive(Default)]
+struct Testcase {
+    name: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+/// Reason of an incomplete or risky test: the text before its stack trace,
+/// without the `Class::method` line PHPUnit starts it with.
+fn reason(text: &str) -> &str {
+    let reason = text.trim().split("\n\n").next().unwrap_or_default();
+    match reason.split_once('\n') {
+        Some((first, rest)) if first.contains("::") => rest.trim(),
+        _ if reason.contains("::") && !reason.contains(' ') => "",
+        _ => reason,
+    }
+}
+
+/// Result of a testcase whose text has no stack trace, placed at the
+/// testcase itself.
+fn testcase_result(testcase: &Testcase, kind: ResultKind, text: &str) -> Option<ResultFromXml> {
+    let message = match (text.trim(), kind) {
+        ("", ResultKind::Skipped) => "skipped".to_string(),
+        (_, ResultKind::Incomplete) => match reason(text) {
+            "" => "incomplete test".to_string(),
+            reason => format!("incomplete test: {reason}"),
+        },
+        (_, ResultKind::Risky) => match reason(text) {
+            "" => "risky test".to_string(),
+            reason => format!("risky test: {reason}"),
+        },
+        ("", _) => "failed".to_string(),
+        (text, _) => text.to_string(),
+    };
     Some(ResultFromXml {
+        name: None,
+        kind,
         message,
-        path,
-        line,
+        path: testcase.file.clone()?,
+        line: testcase.line.unwrap_or(1),
         col: 1,
     })
 }
@@ -62,19 +189,57 @@ pub fn parse_phpunit_xml(path: &str) -> Result<Vec<ResultFromXml>, LSError> {
         .ignore_root_level_whitespace(false)
         .create_reader(BufReader::new(file));
 
-    let mut in_failure = false;
+    let mut testcase = Testcase::default();
+    // Kind and text of the current <failure>, <error> or <skipped>
+    let mut current: Option<(ResultKind, String)> = None;
     let mut results = Vec::new();
 
     loop {
         match reader.next() {
-            Ok(XmlEvent::StartElement { name, .. }) if name.local_name.starts_with("failure") => {
-                in_failure = true;
+            Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            }) => {
+                let attribute = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|attribute| attribute.name.local_name == key)
+                        .map(|attribute| attribute.value.clone())
+                };
+                if name.local_name == "testcase" {
+                    testcase = Testcase {
+                        name: attribute("name"),
+                        file: attribute("file"),
+                        line: attribute("line").and_then(|line| line.parse().ok()),
+                    };
+                } else if let Some(kind) =
+                    ResultKind::from_element(&name.local_name, attribute("type").as_deref())
+                {
+                    current = Some((kind, String::new()));
+                }
             }
-            Ok(XmlEvent::EndElement { .. }) => {
-                in_failure = false;
+            Ok(XmlEvent::Characters(data) | XmlEvent::CData(data)) => {
+                if let Some((_, text)) = &mut current {
+                    text.push_str(&data);
+                }
             }
-            Ok(XmlEvent::Characters(data)) if in_failure => {
-                if let Some(result) = parse_failure_characters(&data) {
+            Ok(XmlEvent::EndElement { name })
+                if ResultKind::from_element(&name.local_name, None).is_some() =>
+            {
+                let Some((kind, text)) = current.take() else {
+                    continue;
+                };
+                let result = match kind {
+                    ResultKind::Failure | ResultKind::Error => {
+                        parse_failure_characters(&text, kind, testcase.file.as_deref())
+                            .or_else(|| testcase_result(&testcase, kind, &text))
+                    }
+                    // Placed at the test, not where PHPUnit noticed
+                    ResultKind::Skipped | ResultKind::Incomplete | ResultKind::Risky => {
+                        testcase_result(&testcase, kind, &text)
+                    }
+                };
+                if let Some(mut result) = result {
+                    result.name.clone_from(&testcase.name);
                     results.push(result);
                 }
             }
@@ -90,9 +255,64 @@ pub fn parse_phpunit_xml(path: &str) -> Result<Vec<ResultFromXml>, LSError> {
     Ok(results)
 }
 
-pub fn to_diagnostics(results: Vec<ResultFromXml>) -> Diagnostics {
+/// Attach a data set failure to the test method it was generated from.
+///
+/// Each data set case is named `<method> with data set <label>`, so the
+/// discovered method id is a prefix of the testcase name. The diagnostic moves
+/// to the method definition and the message is tagged with the data set label.
+fn attach_data_set(mut result: ResultFromXml, test_items: &[TestItem]) -> ResultFromXml {
+    let Some(name) = result.name.as_deref() else {
+        return result;
+    };
+    let Some((test_item, data_set)) = test_items.iter().find_map(|item| {
+        let data_set = name
+            .strip_prefix(item.id.as_str())?
+            .strip_prefix(" with ")?;
+        (item.path == result.path && data_set.starts_with("data set ")).then_some((item, data_set))
+    }) else {
+        return result;
+    };
+
+    result.message = format!("[{data_set}] {}", result.message);
+    result.line = test_item.start_position.start.line + 1;
+    result.col = test_item.start_position.start.character;
+    result
+}
+
+/// Related information pointing a failure or error back at the definition of
+/// its test method, including data set cases of the method.
+fn definition_info(
+    result: &ResultFromXml,
+    test_items: &[TestItem],
+) -> Option<DiagnosticRelatedInformation> {
+    if !result.kind.is_failure() {
+        return None;
+    }
+    let name = result.name.as_deref()?;
+    test_items
+        .iter()
+        .filter(|item| same_path(&item.path, &result.path))
+        .find(|item| {
+            name.strip_prefix(item.id.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(" with "))
+        })?
+        .definition_info()
+}
+
+#[must_use]
+pub fn to_diagnostics(results: Vec<ResultFromXml>, test_items: &[TestItem]) -> Diagnostics {
     Diagnostics {
-        files: results.into_iter().map(std::convert::Into::into).collect(),
+        files: results
+            .into_iter()
+            .map(|result| {
+                let result = attach_data_set(result, test_items);
+                let related_information =
+                    definition_info(&result, test_items).map(|info| vec![info]);
+                let mut file_diagnostics = FileDiagnostics::from(result);
+                file_diagnostics.diagnostics[0].related_information = related_information;
+                file_diagnostics
+            })
+            .collect(),
         messages: vec![],
     }
 }
@@ -107,5 +327,103 @@ mod tests {
         path.push("demo/phpunit/output.xml");
         let result = parse_phpunit_xml(path.to_str().unwrap()).unwrap();
         assert_eq!(result.len(), 1);
+        assert_eq!(result[0].kind, ResultKind::Failure);
+        assert_eq!(result[0].line, 28);
+    }
+
+    #[test]
+    fn test_parse_phpunit_errors_and_skipped() {
+        let mut path = std::env::current_dir().unwrap();
+        path.push("demo/phpunit/output_errors.xml");
+        let results = parse_phpunit_xml(path.to_str().unwrap()).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let diagnostics = to_diagnostics(results, &[]);
+        let error = &diagnostics.files[0];
+        assert!(error.path.ends_with("src/ErrorTest.php"));
+        assert_eq!(error.diagnostics[0].range.start.line, 12);
+        assert_eq!(
+            error.diagnostics[0].severity,
+            Some(DiagnosticSeverity::ERROR)
+        );
+        assert!(error.diagnostics[0].message.contains("undefined method"));
+
+        let skipped = &diagnostics.files[1];
+        assert_eq!(skipped.diagnostics[0].range.start.line, 15);
+        assert_eq!(
+            skipped.diagnostics[0].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+    }
+
+    #[test]
+    fn test_parse_incomplete_and_risky() {
+        let diagnostics = ["output_incomplete.xml", "output_risky.xml"].map(|file| {
+            let path = std::env::current_dir()
+                .unwrap()
+                .join("demo/phpunit")
+                .join(file);
+            let results = parse_phpunit_xml(path.to_str().unwrap()).unwrap();
+            assert_eq!(results.len(), 1);
+            to_diagnostics(results, &[]).files.remove(0)
+        });
+
+        let incomplete = &diagnostics[0].diagnostics[0];
+        assert!(diagnostics[0].path.ends_with("src/PendingTest.php"));
+        assert_eq!(incomplete.range.start.line, 8);
+        assert_eq!(incomplete.severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(
+            incomplete.message,
+            "incomplete test: Subtraction is not written yet"
+        );
+        assert_eq!(
+            incomplete.code,
+            Some(NumberOrString::String("phpunit-incomplete".to_string()))
+        );
+
+        let risky = &diagnostics[1].diagnostics[0];
+        assert_eq!(risky.range.start.line, 13);
+        assert_eq!(risky.severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(
+            risky.message,
+            "risky test: This test did not perform any assertions"
+        );
+        assert!(risky.related_information.is_none());
+    }
+
+    #[test]
+    fn test_data_set_failure_maps_to_method() {
+        let mut path = std::env::current_dir().unwrap();
+        path.push("demo/phpunit/output_data_provider.xml");
+        let results = parse_phpunit_xml(path.to_str().unwrap()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name.as_deref(), Some("testAdd with data set #2"));
+
+        let file_path = results[0].path.clone();
+        let test_items = vec![TestItem {
+            id: "testAdd".to_string(),
+            name: "testAdd".to_string(),
+            path: file_path.clone(),
+            start_position: Range {
+                start: Position {
+                    line: 19,
+                    character: 4,
+                },
+                end: Position {
+                    line: 19,
+                    character: MAX_CHAR_LENGTH,
+                },
+            },
+            end_position: Range::default(),
+        }];
+
+        let diagnostics = to_diagnostics(results, &test_items);
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, file_path);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start.line, 19);
+        assert!(diagnostic.message.starts_with("[data set #2] "));
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related[0].message, "test `testAdd` defined here");
     }
 }
@@ -0,0 +1,72 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use crate::{
+    config,
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// JUnit report written by the pytest command.
+#[must_use]
+pub fn results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("pytest.xml")
+}
+
+/// Arguments selecting the tests whose name contains `filter`: a `-k`
+/// expression requiring each `::` separated part of it, since `-k` matches
+/// the names of a test and its classes one by one.
+#[must_use]
+pub fn filter_args(filter: Option<&str>) -> Vec<String> {
+    let Some(filter) = filter else {
+        return vec![];
+    };
+    let expression: Vec<&str> = filter.split("::").filter(|part| !part.is_empty()).collect();
+    vec!["-k".to_string(), expression.join(" and ")]
+}
+
+/// Command running the tests of the given files whose name contains
+/// `filter`, writing a JUnit report with the file and line of each test to
+/// the cache directory.
+pub fn pytest_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    let mut pytest = test_command(context, command, &["pytest"])?;
+    pytest
+        .arg(format!("--junitxml={}", results_path().display()))
+        .args(["-o", "junit_family=xunit1"])
+        .args(filter_args(filter))
+        .args(extra_args)
+        .args(file_paths);
+    Ok(pytest)
+}
+
+/// Run a pytest command.
+pub fn run_pytest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("pytest.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_args() {
+        assert_eq!(
+            filter_args(Some("TestCalc::test_subtracts")),
+            vec!["-k", "TestCalc and test_subtracts"]
+        );
+        assert_eq!(filter_args(Some("test_adds")), vec!["-k", "test_adds"]);
+        assert!(filter_args(None).is_empty());
+    }
+}
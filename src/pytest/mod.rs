@@ -0,0 +1,203 @@
+//! Python tests run by pytest.
+//!
+//! Tests are the `test*` functions of a module and the `test*` methods of its
+//! `Test*` classes, with ids like pytest's node ids after the file:
+//! `test_adds` or `TestMath::test_subtracts`. No Python grammar is bundled,
+//! so they are found by scanning the source; a function ends before the next
+//! line indented no deeper than its `def`.
+//!
+//! `@pytest.mark.parametrize` makes a case of a test for every parameter set,
+//! with ids like `test_adds[2-3-5]`. Only the function is discovered, so the
+//! failure of a case is reported on it, see [`parse::to_diagnostics`].
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::indented_end,
+};
+
+/// Files naming the root directory of pytest, nearest first.
+const ROOT_MARKERS: &[&str] = &["pytest.ini", "pyproject.toml", "tox.ini", "setup.cfg"];
+
+/// Tests of a file, with ids like `test_adds` or `TestMath::test_subtracts`.
+/// Functions nested in functions and methods of classes pytest doesn't
+/// collect aren't tests.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let definition_re = Regex::new(r"^(\s*)(?:async\s+)?(def|class)\s+(\w+)").unwrap();
+
+    // Indentation and, for `Test*` classes, name of the definitions around
+    // the current line
+    let mut scopes: Vec<(usize, Option<&str>)> = vec![];
+    let mut items = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        scopes.retain(|(scope_indent, _)| *scope_indent < indent);
+        let Some(m) = definition_re.captures(line) else {
+            continue;
+        };
+        let name = m.get(3).unwrap().as_str();
+        let collected = scopes.iter().all(|(_, class)| class.is_some());
+        if &m[2] == "class" {
+            scopes.push((
+                indent,
+                (collected && name.starts_with("Test")).then_some(name),
+            ));
+            continue;
+        }
+        scopes.push((indent, None));
+        if !collected || !name.starts_with("test") {
+            continue;
+        }
+
+        let mut names: Vec<&str> = scopes.iter().filter_map(|(_, class)| *class).collect();
+        names.push(name);
+        let id = names.join("::");
+        let line_number = i as u32;
+        let (end_line, end_length) = indented_end(source, line_number, indent as u32);
+        items.push(TestItem {
+            id: id.clone(),
+            name: id,
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line_number, indent as u32),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(
+                Position::new(end_line, 0),
+                Position::new(end_line, end_length),
+            ),
+        });
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct PytestRunner;
+
+impl Runner for PytestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::pytest_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        // A report left by an earlier run would be read as this run's
+        let _ = std::fs::remove_file(call::results_path());
+        call::run_pytest(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        self.parse_results(&call::results_path(), file_paths, context.workspace)
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let failures = parse::parse_pytest_junit(&std::fs::read_to_string(results_path)?)?;
+        Ok(parse::to_diagnostics(
+            &failures,
+            Path::new(workspace),
+            file_paths,
+            &discover_all(file_paths),
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, ROOT_MARKERS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/pytest/tests/test_calc.py").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "test_adds",
+                "test_divides",
+                "TestCalc::test_subtracts",
+                "TestCalc::TestNegative::test_keeps_sign"
+            ]
+        );
+        assert_eq!(items[0].start_position.start, Position::new(6, 0));
+        assert_eq!(items[0].end_position.end.line, 7);
+        assert_eq!(items[2].start_position.start, Position::new(15, 4));
+
+        let source =
+            "def test_outer():\n    def test_inner():\n        pass\n\nclass Helper:\n    def \
+             test_not_collected(self):\n        pass\n\nasync def test_async():\n    pass\n";
+        let ids: Vec<String> = discover_in_source("test_a.py", source)
+            .into_iter()
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(ids, vec!["test_outer", "test_async"]);
+    }
+}
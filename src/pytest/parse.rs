@@ -0,0 +1,299 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    error::LSError,
+    junit::{Failure, parse_testcases},
+    workspace::{resolve_path, same_path},
+};
+
+/// A failed test, or a module that failed to be collected, of a pytest JUnit
+/// report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PytestFailure {
+    /// Node id, like `tests/test_calc.py::TestCalc::test_subtracts` or
+    /// `tests/test_calc.py::test_adds[2-3-6]`, or just the file of a module
+    /// that failed to be collected
+    pub node_id: String,
+    /// The `E` lines of the traceback, or else the message of the report
+    pub message: String,
+    /// Files and one-based lines of the traceback's frames, innermost last
+    pub locations: Vec<(String, u32)>,
+}
+
+/// Node id of a `<testcase>`. Its class name is the dotted module followed
+/// by the classes of the test, and is empty for a module that failed to be
+/// collected.
+fn node_id(file: &str, class_name: &str, name: &str) -> String {
+    if class_name.is_empty() {
+        return file.to_string();
+    }
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let parts: Vec<&str> = class_name.split('.').collect();
+    let classes = parts
+        .iter()
+        .rposition(|part| *part == stem)
+        .map_or(&[][..], |i| &parts[i + 1..]);
+    std::iter::once(file)
+        .chain(classes.iter().copied())
+        .chain(std::iter::once(name))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// The failure of a `<failure>` or `<error>` with `message` and the
+/// traceback pytest printed.
+fn failure(node_id: String, message: &str, traceback: &str) -> PytestFailure {
+    let location_re = Regex::new(r"^(\S[^:\n]*\.py):(\d+): ").unwrap();
+    let locations = traceback
+        .lines()
+        .filter_map(|line| {
+            let m = location_re.captures(line)?;
+            Some((m[1].to_string(), m[2].parse().ok()?))
+        })
+        .collect();
+
+    let error_lines: Vec<&str> = traceback
+        .lines()
+        .filter_map(|line| line.strip_prefix('E'))
+        .filter(|line| line.is_empty() || line.starts_with(' '))
+        .collect();
+    let indent = error_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let errors = error_lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default().trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = [errors.trim(), message.trim(), traceback.trim()]
+        .into_iter()
+        .find(|text| !text.is_empty())
+        .unwrap_or_default()
+        .to_string();
+
+    PytestFailure {
+        node_id,
+        message,
+        locations,
+    }
+}
+
+/// Parse the failed tests from a JUnit report of pytest in the `xunit1`
+/// family, which gives the file of each test. A test failing in its call
+/// and erroring in its teardown is reported once.
+pub fn parse_pytest_junit(contents: &str) -> Result<Vec<PytestFailure>, LSError> {
+    Ok(parse_testcases(contents)?
+        .into_iter()
+        .filter_map(|testcase| {
+            let Failure { message, text } = testcase.failure.as_ref()?;
+            let node_id = node_id(
+                testcase.attribute("file"),
+                testcase.attribute("classname"),
+                testcase.attribute("name"),
+            );
+            Some(failure(node_id, message, text))
+        })
+        .collect())
+}
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("pytest".to_string()),
+        code: Some(NumberOrString::String("pytest-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Discovered test of the test `id` of `path`, and the `[...]` id of the case
+/// when it's a case of a parametrized test: the test with that id, or else
+/// the one whose id with a case id appended is `id`.
+fn find_test<'a>(
+    test_items: &'a [TestItem],
+    path: &str,
+    id: &'a str,
+) -> Option<(&'a TestItem, Option<&'a str>)> {
+    let in_file = || {
+        test_items
+            .iter()
+            .filter(move |item| same_path(&item.path, path))
+    };
+    if let Some(item) = in_file().find(|item| item.id == id) {
+        return Some((item, None));
+    }
+    in_file().find_map(|item| {
+        let case = id.strip_prefix(item.id.as_str())?;
+        case.starts_with('[').then_some((item, Some(case)))
+    })
+}
+
+/// Diagnostics for failed tests.
+///
+/// A failure is placed at the innermost frame of its traceback in one of
+/// `file_paths`, and linked to the definition of its test. A failure
+/// without such a frame is placed at the definition, and a module that
+/// failed to be collected at its start. The failure of a case of a
+/// parametrized test starts with the case id, like `[2-3-6]`.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[PytestFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let find_path = |file: &str| {
+        let resolved = resolve_path(root, file).to_string_lossy().to_string();
+        file_paths.iter().find(|path| same_path(path, &resolved))
+    };
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let (file, id) = match failure.node_id.split_once("::") {
+            Some((file, id)) => (file, Some(id)),
+            None => (failure.node_id.as_str(), None),
+        };
+        let Some(path) = find_path(file) else {
+            continue;
+        };
+        let Some(id) = id else {
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(error_diagnostic(Range::default(), failure.message.clone()));
+            continue;
+        };
+        let found = find_test(test_items, path, id);
+        let message = match found {
+            Some((_, Some(case))) => format!("{case} {}", failure.message),
+            _ => failure.message.clone(),
+        };
+
+        let location = failure
+            .locations
+            .iter()
+            .rev()
+            .find_map(|(file, line)| Some((find_path(file)?, line.saturating_sub(1))));
+        if let Some((location_path, line)) = location {
+            let range = Range::new(Position::new(line, 0), Position::new(line, MAX_CHAR_LENGTH));
+            result_map
+                .entry(location_path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: found
+                        .and_then(|(item, _)| item.definition_info())
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(range, message)
+                });
+        } else if let Some((item, _)) = found {
+            result_map
+                .entry(item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    item.start_position,
+                    format!("`{id}` failed\n{message}"),
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pytest_junit() {
+        let contents = std::fs::read_to_string("demo/pytest/report.xml").unwrap();
+        let failures = parse_pytest_junit(&contents).unwrap();
+        let node_ids: Vec<&str> = failures
+            .iter()
+            .map(|failure| failure.node_id.as_str())
+            .collect();
+        assert_eq!(
+            node_ids,
+            vec![
+                "tests/test_broken.py",
+                "tests/test_calc.py::test_adds[2-3-6]",
+                "tests/test_calc.py::TestCalc::TestNegative::test_keeps_sign"
+            ]
+        );
+        assert_eq!(
+            failures[1].message,
+            "assert 5 == 6\n +  where 5 = add(2, 3)"
+        );
+        assert_eq!(
+            failures[1].locations,
+            vec![("tests/test_calc.py".to_string(), 8)]
+        );
+        assert_eq!(
+            failures[0].message,
+            "ModuleNotFoundError: No module named 'missing'"
+        );
+    }
+
+    #[test]
+    fn test_parametrized_case_on_base_test() {
+        let root = std::env::current_dir().unwrap().join("demo/pytest");
+        let file_path = root
+            .join("tests/test_calc.py")
+            .to_string_lossy()
+            .to_string();
+        let test_items = super::super::discover_tests(&file_path).unwrap();
+        let contents = std::fs::read_to_string("demo/pytest/report.xml").unwrap();
+        let failures = parse_pytest_junit(&contents).unwrap();
+        let diagnostics = to_diagnostics(
+            &failures,
+            &root,
+            std::slice::from_ref(&file_path),
+            &test_items,
+        );
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+
+        let case = &diagnostics[0];
+        assert_eq!(case.range.start, Position::new(7, 0));
+        assert!(case.message.starts_with("[2-3-6] assert 5 == 6"));
+        assert_eq!(
+            case.related_information.as_ref().unwrap()[0].location.range,
+            test_items[0].start_position
+        );
+
+        let nested = &diagnostics[1];
+        assert_eq!(
+            nested.related_information.as_ref().unwrap()[0]
+                .location
+                .range,
+            test_items[3].start_position
+        );
+
+        let items = [TestItem {
+            id: "test_add".to_string(),
+            ..test_items[0].clone()
+        }];
+        assert!(find_test(&items, &file_path, "test_adds[2-3-6]").is_none());
+        assert_eq!(
+            find_test(&items, &file_path, "test_add[1]").map(|(_, case)| case),
+            Some(Some("[1]"))
+        );
+    }
+}
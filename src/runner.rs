@@ -1,6 +1,59 @@
 //! Test runner trait and registry.
 
-use crate::{Diagnostics, DiscoveredTests, Workspaces, error::LSError, go, javascript, php, rust};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    time::Duration,
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+use tree_sitter::{Language, Parser, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, FileDiagnostics, FileTests, TestItem, Workspaces, bats, catch2,
+    ctest, dart, error::LSError, go, haskell, javascript, julia, kotlin, lua, nim, ocaml, php,
+    pytest, rust, scala, swift, zig,
+};
+
+/// Result status of a single test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// Result of a single discovered test, as reported by the test command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestOutcome {
+    pub id: String,
+    pub status: TestStatus,
+    pub duration: Option<Duration>,
+    /// Output the test printed, kept for failed tests by runners that
+    /// capture it per test
+    pub output: Option<String>,
+}
+
+/// Code of the warning diagnostics for what a test run printed to stderr,
+/// like compiler warnings. They're only published for adapters with
+/// `surface_warnings`.
+pub const STDERR_WARNING_CODE: &str = "stderr-warning";
+
+/// Event reported by a runner while tests are running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunEvent {
+    /// A discovered test reported its result.
+    TestCompleted(TestOutcome),
+    /// A discovered test finished; `completed` of `total` are done.
+    TestFinished { completed: usize, total: usize },
+    /// All discovered tests of a file finished, with that file's diagnostics.
+    FileFinished(FileDiagnostics),
+}
 
 /// Trait for test runners.
 ///
@@ -10,16 +63,448 @@ pub trait Runner: Send + Sync {
     /// Discover tests in the given files.
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError>;
 
-    /// Run tests and return diagnostics.
+    /// Tree-sitter grammar used to parse files for discovery, or `None` for
+    /// runners that discover tests without parsing the source.
+    fn language(&self) -> Option<Language>;
+
+    /// Discover tests in an already parsed syntax tree of `source`.
+    ///
+    /// Lets open documents be reparsed incrementally instead of re-reading and
+    /// fully parsing the file on every discovery.
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError>;
+
+    /// Discover tests in `source`, the possibly unsaved text of `file_path`.
+    ///
+    /// Runners with a grammar parse it in full; runners without one discover
+    /// tests in the saved file unless they override this.
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        let Some(language) = self.language() else {
+            let discovered = self.discover(&[file_path.to_string()])?;
+            return Ok(discovered
+                .files
+                .into_iter()
+                .flat_map(|file| file.tests)
+                .collect());
+        };
+        let mut parser = Parser::new();
+        parser.set_language(&language)?;
+        let tree = parser.parse(source, None).ok_or(LSError::TreeSitterParse)?;
+        self.discover_tree(file_path, source, &tree)
+    }
+
+    /// Run the tests of a workspace in `context` and return diagnostics. With
+    /// a `filter`, only tests whose name contains it run.
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError>;
+
+    /// Command `run_tests` executes for the same arguments, assembled without
+    /// running it.
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError>;
+
+    /// Run tests, reporting progress and per-file diagnostics through
+    /// `on_event` as tests complete. Returns the diagnostics of the whole run.
+    ///
+    /// Runners that can't stream results report nothing until the run ends.
+    fn run_tests_streaming(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+        _on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Diagnostics, LSError> {
+        self.run_tests(file_paths, context, extra_args, command, filter)
+    }
+
+    /// Parse an already produced results file, in the format this runner
+    /// reads from its test command, into diagnostics for `file_paths`.
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
     ) -> Result<Diagnostics, LSError>;
 
     /// Detect workspaces containing the given files.
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces;
+
+    /// Whether the tests discovered in a file stay valid until the file is
+    /// modified, so they can be cached across restarts.
+    fn caches_discovery(&self) -> bool {
+        true
+    }
+
+    /// Arguments selecting the tests matching the tag expression `tags`, in
+    /// the runner's own syntax, or `None` when it can't select tests by tag.
+    fn tag_args(&self, _tags: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Whether the runner runs exactly the tests of
+    /// [`RunContext::test_ids`] when it is given, instead of every test of
+    /// the files.
+    fn selects_ids(&self) -> bool {
+        false
+    }
+
+    /// Build the executable running the test `test_id` of `file_path` and
+    /// return how a debug adapter launches just that test.
+    fn debug_launch(
+        &self,
+        _test_id: &str,
+        _file_path: &str,
+        _context: &RunContext,
+        _extra_args: &[String],
+        _command: Option<&[String]>,
+    ) -> Result<DebugLaunch, LSError> {
+        Err(LSError::DebugUnsupported)
+    }
+}
+
+/// Launch configuration for a debug adapter (DAP) running a single test, as
+/// returned by `$/debugTest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebugLaunch {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: String,
+    /// Variables set on top of the debugger's environment
+    pub env: HashMap<String, String>,
+}
+
+/// Tracks which discovered tests are still running, to tell when all tests of
+/// a file have finished.
+///
+/// Progress is judged against the number of discovered tests, or against the
+/// number of tests the runner announced once it starts announcing them, which
+/// also counts tests that weren't discovered, like macro-expanded cases.
+pub struct CompletionTracker {
+    pending: HashMap<String, HashSet<String>>,
+    completed: usize,
+    total: usize,
+    /// Tests of the suites the runner started so far
+    announced: usize,
+}
+
+impl CompletionTracker {
+    #[must_use]
+    pub fn new<'a>(test_items: impl IntoIterator<Item = &'a TestItem>) -> Self {
+        let mut pending: HashMap<String, HashSet<String>> = HashMap::new();
+        for item in test_items {
+            pending
+                .entry(item.path.clone())
+                .or_default()
+                .insert(item.id.clone());
+        }
+        let total = pending.values().map(HashSet::len).sum();
+        Self {
+            pending,
+            completed: 0,
+            total,
+            announced: 0,
+        }
+    }
+
+    /// Count the tests of a suite the runner started, like a libtest binary.
+    pub fn start_suite(&mut self, test_count: usize) {
+        self.announced += test_count;
+    }
+
+    /// Mark a test as finished and report progress, followed by the files
+    /// whose tests have now all finished. Unknown test ids are ignored until
+    /// the runner announced a suite.
+    pub fn finish(&mut self, test_id: &str) -> (Option<RunEvent>, Vec<String>) {
+        let mut finished_files = vec![];
+        let mut known = false;
+        self.pending.retain(|path, ids| {
+            known |= ids.remove(test_id);
+            if ids.is_empty() {
+                finished_files.push(path.clone());
+                return false;
+            }
+            true
+        });
+        if !known && self.announced == 0 {
+            return (None, finished_files);
+        }
+        self.completed += 1;
+        let total = if self.announced == 0 {
+            self.total
+        } else {
+            self.announced
+        };
+        let progress = RunEvent::TestFinished {
+            completed: self.completed,
+            total: total.max(self.completed),
+        };
+        (Some(progress), finished_files)
+    }
+}
+
+/// Find an adapter's test binary on `PATH`, or relative to `cwd` if it is a
+/// path, before running it.
+pub fn require_binary(binary: &str, cwd: &Path) -> Result<PathBuf, LSError> {
+    which::which_in(binary, std::env::var_os("PATH"), cwd)
+        .map_err(|_| LSError::AdapterBinaryMissing(binary.to_string()))
+}
+
+/// First line `program --version` prints, on stdout or else stderr, if it
+/// succeeds.
+#[must_use]
+pub fn binary_version(program: &OsStr, cwd: &Path) -> Option<String> {
+    let mut command = Command::new(program);
+    command.arg("--version");
+    if cwd.is_dir() {
+        command.current_dir(cwd);
+    }
+    let output = command
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    [output.stdout, output.stderr].iter().find_map(|stream| {
+        String::from_utf8_lossy(stream)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// Arguments selecting the tests whose name contains `filter`, for runners
+/// taking a name pattern as a regex after `flag`.
+#[must_use]
+pub fn name_filter_args(flag: &str, filter: Option<&str>) -> Vec<String> {
+    filter.map_or_else(Vec::new, |filter| {
+        vec![flag.to_string(), regex::escape(filter)]
+    })
+}
+
+/// Arguments selecting the tests of a run, for runners taking a name pattern
+/// as a regex after `flag`: the tests of [`RunContext::test_ids`], by the
+/// name `name` gives each id and an anchored pattern like `^(adds|divides)$`,
+/// or else those whose name contains `filter`.
+#[must_use]
+pub fn name_selection_args(
+    flag: &str,
+    context: &RunContext,
+    filter: Option<&str>,
+    name: impl Fn(&str) -> String,
+) -> Vec<String> {
+    let Some(test_ids) = &context.test_ids else {
+        return name_filter_args(flag, filter);
+    };
+    let mut names: Vec<String> = test_ids.iter().map(|id| regex::escape(&name(id))).collect();
+    names.sort();
+    names.dedup();
+    vec![flag.to_string(), format!("^({})$", names.join("|"))]
+}
+
+/// Where the test commands of a run start and what they start with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunContext<'a> {
+    /// Workspace whose tests run
+    pub workspace: &'a str,
+    /// Directory the commands start in instead of the workspace, like the
+    /// resolved `run_dir_template` of the adapter being run
+    pub run_dir: Option<PathBuf>,
+    /// Variables set on the commands, like the `env` of the adapter
+    pub env: HashMap<String, String>,
+    /// Ids of the only tests to run, like the tests that failed in the last
+    /// run, for runners that [select tests by id](Runner::selects_ids)
+    pub test_ids: Option<Vec<String>>,
+}
+
+impl<'a> RunContext<'a> {
+    /// Context starting commands in `workspace` with no extra variables.
+    #[must_use]
+    pub fn new(workspace: &'a str) -> Self {
+        Self {
+            workspace,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the test `id` is one the run selects by id, or the run
+    /// doesn't select tests by id.
+    #[must_use]
+    pub fn selects(&self, id: &str) -> bool {
+        self.test_ids
+            .as_ref()
+            .is_none_or(|test_ids| test_ids.iter().any(|test_id| test_id == id))
+    }
+}
+
+/// Command running an adapter's tests in the workspace of `context`, or in
+/// its run directory, which must exist. The configured `command` replaces
+/// the `default` program and leading arguments, and the environment of
+/// `context` is set.
+pub fn test_command(
+    context: &RunContext,
+    command: Option<&[String]>,
+    default: &[&str],
+) -> Result<Command, LSError> {
+    let parts: Vec<&str> = command.map_or_else(
+        || default.to_vec(),
+        |command| command.iter().map(String::as_str).collect(),
+    );
+    let Some((program, args)) = parts.split_first() else {
+        return Err(LSError::CommandSpawn("empty adapter command".to_string()));
+    };
+    let mut command = Command::new(require_binary(program, Path::new(context.workspace))?);
+    match &context.run_dir {
+        Some(run_dir) if !run_dir.is_dir() => {
+            return Err(LSError::RunDirNotFound(run_dir.clone()));
+        }
+        Some(run_dir) => command.current_dir(run_dir),
+        None => command.current_dir(context.workspace),
+    };
+    command.args(args).envs(&context.env);
+    Ok(command)
+}
+
+/// An assembled command line, as reported by `$/explainRun`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandLine {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    /// Variables set on top of the server's environment
+    pub env: HashMap<String, String>,
+    /// The command as a line to paste into a POSIX shell
+    pub shell: String,
+}
+
+impl From<&Command> for CommandLine {
+    fn from(command: &Command) -> Self {
+        let lossy = |s: &std::ffi::OsStr| s.to_string_lossy().to_string();
+        let program = lossy(command.get_program());
+        let args: Vec<String> = command.get_args().map(lossy).collect();
+        let cwd = command
+            .get_current_dir()
+            .map(|dir| dir.to_string_lossy().to_string());
+        let mut env: Vec<(String, String)> = command
+            .get_envs()
+            .filter_map(|(key, value)| Some((lossy(key), lossy(value?))))
+            .collect();
+        env.sort();
+
+        let mut words: Vec<String> = env
+            .iter()
+            .map(|(key, value)| format!("{key}={}", shell_quote(value)))
+            .collect();
+        words.extend(
+            std::iter::once(&program)
+                .chain(&args)
+                .map(|s| shell_quote(s)),
+        );
+        let mut shell = words.join(" ");
+        if let Some(cwd) = &cwd {
+            shell = format!("cd {} && {shell}", shell_quote(cwd));
+        }
+
+        Self {
+            program,
+            args,
+            cwd,
+            env: env.into_iter().collect(),
+            shell,
+        }
+    }
+}
+
+/// Quote a word for a POSIX shell, leaving plain words as they are.
+fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+/// Run a command, passing each line of its stdout to `on_line` as soon as it
+/// is printed. Returns the complete output once the process exits.
+pub fn stream_output(
+    command: &mut Command,
+    mut on_line: impl FnMut(&str),
+) -> Result<Output, LSError> {
+    // Like `Command::output`, keep the child away from the server's stdin
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain stderr concurrently so a chatty build can't block the process
+    let mut child_stderr = child.stderr.take().ok_or(LSError::AdapterNoOutput)?;
+    let stderr_handle = std::thread::spawn(move || {
+        let mut stderr = Vec::new();
+        let _ = child_stderr.read_to_end(&mut stderr);
+        stderr
+    });
+
+    let mut stdout = Vec::new();
+    let mut reader = BufReader::new(child.stdout.take().ok_or(LSError::AdapterNoOutput)?);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        on_line(String::from_utf8_lossy(&line).trim_end());
+        stdout.append(&mut line);
+    }
+
+    let status = child.wait()?;
+    let stderr = stderr_handle.join().unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Discover tests file by file with the given discovery function, on a
+/// thread pool. Files are returned in the order of `file_paths`.
+///
+/// A file that fails to read or parse is logged and reported with no tests,
+/// so one bad file doesn't abort discovery for the rest. Tree-sitter parsers
+/// can't be shared between threads, so `discover` creates its own.
+pub fn discover_files<F>(file_paths: &[String], discover: F) -> DiscoveredTests
+where
+    F: Fn(&str) -> Result<Vec<TestItem>, LSError> + Sync,
+{
+    let files = file_paths
+        .par_iter()
+        .map(|file_path| {
+            let tests = discover(file_path).unwrap_or_else(|e| {
+                log::warn!("Failed to discover tests in {file_path}: {e}");
+                vec![]
+            });
+            FileTests {
+                path: file_path.clone(),
+                tests,
+                test_kind: None,
+                adapter_id: None,
+            }
+        })
+        .collect();
+    DiscoveredTests { files }
 }
 
 /// Get a runner by test kind identifier.
@@ -27,12 +512,249 @@ pub fn get(test_kind: &str) -> Result<Box<dyn Runner>, LSError> {
     match test_kind {
         "cargo-test" => Ok(Box::new(rust::CargoTestRunner)),
         "cargo-nextest" => Ok(Box::new(rust::CargoNextestRunner)),
+        "cargo-criterion" => Ok(Box::new(rust::CargoCriterionRunner)),
         "go-test" => Ok(Box::new(go::GoTestRunner)),
         "phpunit" => Ok(Box::new(php::PhpunitRunner)),
         "jest" => Ok(Box::new(javascript::JestRunner)),
         "vitest" => Ok(Box::new(javascript::VitestRunner)),
+        "bun-test" => Ok(Box::new(javascript::BunTestRunner)),
+        "ava" => Ok(Box::new(javascript::AvaRunner)),
         "deno" => Ok(Box::new(javascript::DenoRunner)),
         "node-test" => Ok(Box::new(javascript::NodeTestRunner)),
+        "playwright" => Ok(Box::new(javascript::PlaywrightRunner)),
+        "ctest" => Ok(Box::new(ctest::CTestRunner)),
+        "catch2" => Ok(Box::new(catch2::Catch2Runner)),
+        "swift-test" => Ok(Box::new(swift::XCTestRunner)),
+        "gradle-test" => Ok(Box::new(kotlin::GradleTestRunner)),
+        "maven-test" => Ok(Box::new(kotlin::MavenTestRunner)),
+        "dart-test" => Ok(Box::new(dart::DartTestRunner)),
+        "zig-test" => Ok(Box::new(zig::ZigTestRunner)),
+        "dune-test" => Ok(Box::new(ocaml::DuneTestRunner)),
+        "busted" => Ok(Box::new(lua::BustedRunner)),
+        "stack-test" => Ok(Box::new(haskell::HaskellTestRunner::Stack)),
+        "cabal-test" => Ok(Box::new(haskell::HaskellTestRunner::Cabal)),
+        "sbt-test" => Ok(Box::new(scala::SbtTestRunner)),
+        "nim-test" => Ok(Box::new(nim::NimTestRunner)),
+        "julia-test" => Ok(Box::new(julia::JuliaTestRunner)),
+        "bats" => Ok(Box::new(bats::BatsRunner)),
+        "pytest" => Ok(Box::new(pytest::PytestRunner)),
         _ => Err(LSError::UnknownTestKind(test_kind.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn test_item(id: &str, path: &str) -> TestItem {
+        TestItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: path.to_string(),
+            start_position: Range::default(),
+            end_position: Range::default(),
+        }
+    }
+
+    #[test]
+    fn test_completion_tracker_reports_finished_files() {
+        let items = [
+            test_item("a::one", "a.rs"),
+            test_item("a::two", "a.rs"),
+            test_item("b::one", "b.rs"),
+        ];
+        let mut tracker = CompletionTracker::new(&items);
+
+        assert_eq!(tracker.finish("unknown"), (None, vec![]));
+        assert_eq!(
+            tracker.finish("a::one"),
+            (
+                Some(RunEvent::TestFinished {
+                    completed: 1,
+                    total: 3
+                }),
+                vec![]
+            )
+        );
+        assert_eq!(tracker.finish("b::one").1, vec!["b.rs".to_string()]);
+        assert_eq!(tracker.finish("a::two").1, vec!["a.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_completion_tracker_counts_announced_tests() {
+        let items = [test_item("a::one", "a.rs"), test_item("a::two", "a.rs")];
+        let mut tracker = CompletionTracker::new(&items);
+        tracker.start_suite(4);
+
+        let progress = |completed, total| Some(RunEvent::TestFinished { completed, total });
+        assert_eq!(tracker.finish("a::one").0, progress(1, 4));
+        // Tests that weren't discovered count once the suite announced them
+        assert_eq!(tracker.finish("a::cases::case_1").0, progress(2, 4));
+        tracker.start_suite(1);
+        assert_eq!(tracker.finish("a::two").0, progress(3, 5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_output_yields_lines_in_order() {
+        let mut lines = vec![];
+        let output = stream_output(
+            Command::new("sh").args(["-c", "echo one; echo two >&2; echo three"]),
+            |line| lines.push(line.to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(lines, vec!["one", "three"]);
+        assert_eq!(output.stdout, b"one\nthree\n");
+        assert_eq!(output.stderr, b"two\n");
+    }
+
+    #[test]
+    fn test_name_filter_args_escapes_filter() {
+        assert!(name_filter_args("-t", None).is_empty());
+        assert_eq!(
+            name_filter_args("-run", Some("Add(1+2)")),
+            vec!["-run".to_string(), r"Add\(1\+2\)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_name_selection_args_anchors_ids() {
+        let mut context = RunContext::new("/project");
+        assert_eq!(
+            name_selection_args("-t", &context, Some("adds"), str::to_string),
+            vec!["-t", "adds"]
+        );
+        context.test_ids = Some(vec![
+            "math::adds".to_string(),
+            "math::adds(1+2)".to_string(),
+            "math::adds".to_string(),
+        ]);
+        assert_eq!(
+            name_selection_args("-t", &context, Some("adds"), |id| id.replace("::", " ")),
+            vec!["-t", r"^(math adds|math adds\(1\+2\))$"]
+        );
+        assert!(context.selects("math::adds"));
+        assert!(!context.selects("math::adds_more"));
+    }
+
+    #[test]
+    fn test_require_binary() {
+        let cwd = std::env::current_dir().unwrap();
+        assert!(require_binary("sh", &cwd).is_ok());
+        let err = require_binary("assert-lsp-missing-binary", &cwd).unwrap_err();
+        assert!(matches!(err, LSError::AdapterBinaryMissing(_)));
+        assert_eq!(
+            err.to_string(),
+            "assert-lsp-missing-binary not found on PATH; install it or set a path"
+        );
+    }
+
+    #[test]
+    fn test_command_override() {
+        let workspace = std::env::current_dir().unwrap();
+        let workspace = workspace.to_str().unwrap();
+        let command = [
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo custom".to_string(),
+        ];
+
+        let context = RunContext::new(workspace);
+        let output = test_command(&context, Some(&command), &["cargo", "test"])
+            .unwrap()
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "custom\n");
+        assert!(test_command(&context, Some(&[]), &["cargo"]).is_err());
+
+        let env = [("GREETING".to_string(), "hi".to_string())];
+        let command = [
+            "sh".to_string(),
+            "-c".to_string(),
+            "echo $GREETING".to_string(),
+        ];
+        let with_env = RunContext {
+            env: HashMap::from(env),
+            ..RunContext::new(workspace)
+        };
+        // The environment reaches commands built on other threads as well
+        let output = std::thread::scope(|scope| {
+            scope
+                .spawn(|| test_command(&with_env, Some(&command), &[]))
+                .join()
+                .unwrap()
+        })
+        .unwrap()
+        .output()
+        .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+        let command = test_command(&context, Some(&command), &[]).unwrap();
+        assert_eq!(command.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn test_command_in_run_dir() {
+        let workspace = std::env::current_dir().unwrap();
+        let run_dir = workspace.join("demo/rust");
+        let in_run_dir = |run_dir: PathBuf| RunContext {
+            run_dir: Some(run_dir),
+            ..RunContext::new(workspace.to_str().unwrap())
+        };
+        let command = test_command(&in_run_dir(run_dir.clone()), None, &["cargo", "test"]).unwrap();
+        assert_eq!(command.get_current_dir(), Some(run_dir.as_path()));
+
+        let missing = workspace.join("demo/missing");
+        assert!(matches!(
+            test_command(&in_run_dir(missing), None, &["cargo", "test"]),
+            Err(LSError::RunDirNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_command_line_quotes_for_shell() {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir("/home/demo/my project")
+            .env("CARGO_TERM_COLOR", "never")
+            .args(["test", "--", "it's fine", ""]);
+
+        let line = CommandLine::from(&command);
+        assert_eq!(line.args, ["test", "--", "it's fine", ""]);
+        assert_eq!(line.env["CARGO_TERM_COLOR"], "never");
+        assert_eq!(
+            line.shell,
+            r"cd '/home/demo/my project' && CARGO_TERM_COLOR=never cargo test -- 'it'\''s fine' ''"
+        );
+    }
+
+    #[test]
+    fn test_parallel_discovery_matches_sequential() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_paths: Vec<String> = (0..16)
+            .map(|i| {
+                let tests: String = (0..=i)
+                    .map(|j| format!("    #[test]\n    fn case_{j}() {{}}\n"))
+                    .collect();
+                let path = dir.path().join(format!("file_{i}.rs"));
+                std::fs::write(&path, format!("#[cfg(test)]\nmod tests {{\n{tests}}}\n")).unwrap();
+                path.to_string_lossy().to_string()
+            })
+            .collect();
+        let runner = get("cargo-test").unwrap();
+
+        let sequential: Vec<FileTests> = file_paths
+            .iter()
+            .flat_map(|path| runner.discover(std::slice::from_ref(path)).unwrap().files)
+            .collect();
+        let parallel = runner.discover(&file_paths).unwrap().files;
+
+        assert_eq!(parallel, sequential);
+        assert_eq!(
+            parallel.iter().map(|file| file.tests.len()).sum::<usize>(),
+            (1..=16).sum::<usize>()
+        );
+    }
+}
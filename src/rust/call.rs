@@ -1,24 +1,43 @@
 use std::process::{Command, Output};
 
-use crate::{error::LSError, log::write_result_log};
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, stream_output, test_command},
+};
 
-/// Run cargo test with JSON output format.
-pub fn run_cargo_test(
-    workspace: &str,
+/// Command running cargo test with JSON output format.
+pub fn cargo_test_command(
+    context: &RunContext,
     extra_args: &[String],
     test_ids: &[String],
-) -> Result<Output, LSError> {
-    let output = Command::new("cargo")
-        .current_dir(workspace)
-        .arg("test")
-        .args(extra_args)
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    // Arguments after a `--` in `extra_args` are meant for the test harness
+    let separator = extra_args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(extra_args.len());
+    let mut cargo = test_command(context, command, &["cargo", "test"])?;
+    cargo
+        .env("CARGO_TERM_COLOR", "never")
+        .arg("--color=never")
+        .args(&extra_args[..separator])
         .arg("--")
         .arg("-Z")
         .arg("unstable-options")
         .arg("--format")
         .arg("json")
-        .args(test_ids)
-        .output()?;
+        .arg("--report-time")
+        .args(extra_args.iter().skip(separator + 1))
+        .args(test_ids);
+    Ok(cargo)
+}
+
+/// Run a cargo test command, passing each line of output to `on_line` as it
+/// is printed.
+pub fn run_cargo_test(command: &mut Command, on_line: impl FnMut(&str)) -> Result<Output, LSError> {
+    let output = stream_output(command, on_line)?;
 
     write_result_log("cargo_test.log", &output)?;
 
@@ -32,22 +51,111 @@ pub fn run_cargo_test(
     Ok(output)
 }
 
-/// Run cargo nextest with text output format.
-pub fn run_cargo_nextest(
-    workspace: &str,
+/// Command running `cargo criterion` with JSON messages, for the given bench
+/// targets (every one when empty). A `filter` selects the benchmarks whose id
+/// contains it.
+pub fn cargo_criterion_command(
+    context: &RunContext,
+    extra_args: &[String],
+    bench_targets: &[String],
+    filter: Option<&str>,
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut criterion = test_command(context, command, &["cargo", "criterion"])?;
+    criterion
+        .env("CARGO_TERM_COLOR", "never")
+        .arg("--message-format=json");
+    for target in bench_targets {
+        criterion.arg("--bench").arg(target);
+    }
+    criterion.args(extra_args);
+    if let Some(filter) = filter {
+        // The filter is an argument of the benchmark harness
+        if !extra_args.iter().any(|arg| arg == "--") {
+            criterion.arg("--");
+        }
+        criterion.arg(regex::escape(filter));
+    }
+    Ok(criterion)
+}
+
+/// Run a cargo criterion command.
+pub fn run_cargo_criterion(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("cargo_criterion.log", &output)?;
+
+    Ok(output)
+}
+
+/// Command building the test executables without running them, printing the
+/// artifacts as JSON messages. Arguments after a `--` in `extra_args` are
+/// meant for the test harness and left out.
+pub fn cargo_build_tests_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let cargo_args = extra_args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .collect::<Vec<_>>();
+    let mut cargo = test_command(context, command, &["cargo", "test"])?;
+    cargo
+        .env("CARGO_TERM_COLOR", "never")
+        .arg("--no-run")
+        .arg("--message-format=json")
+        .args(cargo_args);
+    Ok(cargo)
+}
+
+/// Run a cargo test build, failing when it doesn't compile.
+pub fn run_cargo_build_tests(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("cargo_build_tests.log", &output)?;
+
+    if !output.status.success() {
+        return Err(LSError::AdapterError);
+    }
+    Ok(output)
+}
+
+/// Command running cargo nextest with text output format.
+pub fn cargo_nextest_command(
+    context: &RunContext,
     extra_args: &[String],
     test_ids: &[String],
-) -> Result<Output, LSError> {
-    let output = Command::new("cargo")
-        .current_dir(workspace)
-        .arg("nextest")
-        .arg("run")
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut nextest = test_command(context, command, &["cargo", "nextest", "run"])?;
+    nextest
+        .env("CARGO_TERM_COLOR", "never")
+        .arg("--color=never")
         .arg("--workspace")
         .arg("--no-fail-fast")
         .args(extra_args)
         .arg("--")
-        .args(test_ids)
-        .output()?;
+        .args(test_ids);
+    Ok(nextest)
+}
+
+/// Arguments of cargo nextest selecting the tests matching a filterset, like
+/// `test(slow) & !test(flaky)`. A bare word selects the tests whose name
+/// contains it.
+#[must_use]
+pub fn nextest_filterset_args(expression: &str) -> Vec<String> {
+    let expression = if expression.contains('(') {
+        expression.to_string()
+    } else {
+        format!("test({expression})")
+    };
+    vec!["-E".to_string(), expression]
+}
+
+/// Run a cargo nextest command.
+pub fn run_cargo_nextest(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
 
     write_result_log("cargo_nextest.log", &output)?;
 
@@ -0,0 +1,372 @@
+//! Criterion benchmarks run by `cargo criterion`, reported when they regress.
+//!
+//! Benchmarks are the `bench_function` and `bench_with_input` calls in the
+//! functions named by `criterion_group!` (or named `bench_*`). Macro bodies
+//! aren't parsed by tree-sitter, so they are found by scanning the source.
+//! `cargo criterion` compares every run with the previous one and judges
+//! whether a benchmark regressed beyond its noise threshold.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    process::Command,
+};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+use serde::Deserialize;
+use tree_sitter::{Language, Tree};
+
+use super::call;
+use crate::{
+    Diagnostics, DiscoveredTests, FileDiagnostics, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+    workspace::same_path,
+};
+
+fn bench_item(file_path: &str, id: String, (line, column): (u32, u32)) -> TestItem {
+    TestItem {
+        id: id.clone(),
+        name: id,
+        path: file_path.to_string(),
+        start_position: Range::new(
+            Position::new(line, column),
+            Position::new(line, MAX_CHAR_LENGTH),
+        ),
+        end_position: Range::new(
+            Position::new(line, column),
+            Position::new(line, MAX_CHAR_LENGTH),
+        ),
+    }
+}
+
+/// Benchmarks of a file, with ids like `fib 20`, or `sizes/fib` in a
+/// benchmark group. Benchmarks whose name isn't a literal are represented by
+/// their function.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let group_re =
+        Regex::new(r"criterion_group!\s*(?:\(\s*\w+\s*,([^)]*)\)|\{[^}]*\btargets\s*=([^;}]*))")
+            .unwrap();
+    let fn_re = Regex::new(r"\bfn\s+(\w+)\s*(?:<[^>]*>)?\s*\(").unwrap();
+    let benchmark_group_re = Regex::new(r#"\.benchmark_group\(\s*"([^"]*)""#).unwrap();
+    let bench_re = Regex::new(
+        r#"\.(bench_function|bench_with_input)\(\s*(?:"([^"]*)"|BenchmarkId::(?:new|from_parameter)\(\s*"([^"]*)")?"#,
+    )
+    .unwrap();
+
+    let targets: HashSet<&str> = group_re
+        .captures_iter(source)
+        .filter_map(|m| m.get(1).or_else(|| m.get(2)))
+        .flat_map(|targets| targets.as_str().split(','))
+        .map(str::trim)
+        .filter(|target| !target.is_empty())
+        .collect();
+
+    let mut items = vec![];
+    for m in fn_re.captures_iter(source) {
+        let name = m.get(1).unwrap().as_str();
+        if !targets.contains(name) && !name.starts_with("bench_") {
+            continue;
+        }
+        let found = m.get(0).unwrap();
+        let end = block_end(source, found.end());
+        let body = &source[found.end()..end];
+
+        let mut group: Option<&str> = None;
+        let mut calls: Vec<(usize, Option<&str>, Option<&str>)> = benchmark_group_re
+            .captures_iter(body)
+            .map(|m| {
+                (
+                    m.get(0).unwrap().start(),
+                    m.get(1).map(|g| g.as_str()),
+                    None,
+                )
+            })
+            .collect();
+        calls.extend(bench_re.captures_iter(body).map(|m| {
+            let bench = m.get(2).or_else(|| m.get(3)).map(|b| b.as_str());
+            (m.get(1).unwrap().start(), None, Some(bench.unwrap_or("")))
+        }));
+        calls.sort_by_key(|(offset, _, _)| *offset);
+
+        let mut found_bench = false;
+        for (offset, group_name, bench) in calls {
+            if let Some(group_name) = group_name {
+                group = Some(group_name);
+                continue;
+            }
+            let Some(bench) = bench.filter(|bench| !bench.is_empty()) else {
+                continue;
+            };
+            let id = group.map_or_else(|| bench.to_string(), |group| format!("{group}/{bench}"));
+            let position = position_at(source, found.end() + offset);
+            items.push(bench_item(file_path, id, position));
+            found_bench = true;
+        }
+        if !found_bench {
+            items.push(bench_item(
+                file_path,
+                name.to_string(),
+                position_at(source, found.start()),
+            ));
+        }
+    }
+    items
+}
+
+fn discover_benches(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+#[derive(Debug, Deserialize)]
+struct Estimate {
+    estimate: f64,
+    unit: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Change {
+    mean: Estimate,
+    change: String,
+}
+
+/// Message of `cargo criterion --message-format=json`.
+#[derive(Debug, Deserialize)]
+struct Message {
+    reason: String,
+    id: Option<String>,
+    typical: Option<Estimate>,
+    change: Option<Change>,
+}
+
+/// A benchmark `cargo criterion` judged slower than in the previous run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub id: String,
+    /// Relative change of the mean time, e.g. `0.07` for 7% slower
+    pub change: f64,
+    /// Typical time and its unit
+    pub typical: Option<(f64, String)>,
+}
+
+/// Parse the regressed benchmarks from the messages of `cargo criterion
+/// --message-format=json`.
+#[must_use]
+pub fn parse_criterion_json(contents: &str) -> Vec<Regression> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Message>(line).ok())
+        .filter(|message| message.reason == "benchmark-complete")
+        .filter_map(|message| {
+            let change = message
+                .change
+                .filter(|change| change.change == "Regressed")?;
+            Some(Regression {
+                id: message.id?,
+                change: change.mean.estimate,
+                typical: message
+                    .typical
+                    .map(|typical| (typical.estimate, typical.unit)),
+            })
+        })
+        .collect()
+}
+
+/// Diagnostics for regressed benchmarks, placed at the discovered benchmark
+/// whose id is the benchmark id or, for parameterized benchmarks, a prefix
+/// of it.
+#[must_use]
+pub fn to_diagnostics(regressions: &[Regression], test_items: &[TestItem]) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+    for regression in regressions {
+        let Some(test_item) = test_items
+            .iter()
+            .filter(|item| {
+                regression.id == item.id || regression.id.starts_with(&format!("{}/", item.id))
+            })
+            .max_by_key(|item| item.id.len())
+        else {
+            continue;
+        };
+        let typical = regression
+            .typical
+            .as_ref()
+            .map(|(estimate, unit)| format!(", typically {estimate:.2} {unit}"))
+            .unwrap_or_default();
+        result_map
+            .entry(test_item.path.clone())
+            .or_default()
+            .push(Diagnostic {
+                range: test_item.start_position,
+                message: format!(
+                    "`{}` regressed: mean time {:+.2}%{typical}",
+                    regression.id,
+                    regression.change * 100.0
+                ),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("cargo-criterion".to_string()),
+                code: Some(NumberOrString::String("criterion-regressed".to_string())),
+                ..Diagnostic::default()
+            });
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+/// Bench targets of files in a `benches` directory.
+fn bench_targets(file_paths: &[String]) -> Vec<String> {
+    file_paths
+        .iter()
+        .map(Path::new)
+        .filter(|path| {
+            path.parent()
+                .and_then(Path::file_name)
+                .is_some_and(|dir| dir == "benches")
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect()
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_benches(path).ok())
+        .flatten()
+        .collect()
+}
+
+fn diagnostics(contents: &str, file_paths: &[String]) -> Diagnostics {
+    let test_items: Vec<TestItem> = discover_all(file_paths)
+        .into_iter()
+        .filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        .collect();
+    to_diagnostics(&parse_criterion_json(contents), &test_items)
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct CargoCriterionRunner;
+
+impl Runner for CargoCriterionRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_benches))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::cargo_criterion_command(
+            context,
+            extra_args,
+            &bench_targets(file_paths),
+            filter,
+            command,
+        )
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_cargo_criterion(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        Ok(diagnostics(
+            &String::from_utf8_lossy(&output.stdout),
+            file_paths,
+        ))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["Cargo.toml"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_benches() {
+        let items = discover_benches("demo/criterion/benches/fib.rs").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["fib 20", "sizes/fib", "bench_other"]);
+        assert_eq!(items[0].start_position.start, Position::new(12, 6));
+        assert_eq!(items[1].start_position.start, Position::new(18, 14));
+        assert_eq!(items[2].start_position.start, Position::new(25, 0));
+
+        assert_eq!(
+            bench_targets(&[
+                "/project/benches/fib.rs".to_string(),
+                "/project/src/lib.rs".to_string()
+            ]),
+            vec!["fib"]
+        );
+    }
+
+    #[test]
+    fn test_parse_criterion_json() {
+        let contents = std::fs::read_to_string("demo/criterion/messages.json").unwrap();
+        let regressions = parse_criterion_json(&contents);
+        let ids: Vec<&str> = regressions.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["fib 20", "sizes/fib/10"]);
+
+        let items = discover_benches("demo/criterion/benches/fib.rs").unwrap();
+        let diagnostics = to_diagnostics(&regressions, &items);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start, Position::new(12, 6));
+        assert_eq!(
+            diagnostics[0].message,
+            "`fib 20` regressed: mean time +7.31%, typically 21480.52 ns"
+        );
+        assert_eq!(diagnostics[1].range.start, Position::new(18, 14));
+    }
+}
@@ -15,6 +15,6 @@
   [(attribute_item (attribute (identifier))) (line_comment)]*
   .
   (function_item name: (identifier) @test.name) @test.definition
-  (#any-of? @macro_name "test" "rstest" "case")
+  (#any-of? @macro_name "test" "rstest" "case" "test_case")
 )
 (mod_item name: (identifier) @namespace.name)? @namespace.definition
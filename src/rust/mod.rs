@@ -1,17 +1,21 @@
 mod call;
+mod criterion;
 mod parse;
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    process::Command,
 };
 
+pub use criterion::CargoCriterionRunner;
 use lsp_types::{Position, Range};
-use tree_sitter::{Point, Query, QueryCursor};
+use tree_sitter::{Language, Point, Query, QueryCursor, Tree};
 
 use crate::{
-    Diagnostics, DiscoveredTests, FileTests, MAX_CHAR_LENGTH, TestItem, Workspaces, error::LSError,
-    runner::Runner,
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{CompletionTracker, DebugLaunch, RunContext, RunEvent, Runner, discover_files},
 };
 
 const DISCOVER_QUERY: &str = include_str!("discover.scm");
@@ -20,17 +24,27 @@ const DISCOVER_QUERY: &str = include_str!("discover.scm");
 /// e.g., "src/rules/side_effects/mod.rs" -> "rules::side_effects"
 /// e.g., "src/rules/side_effects/detect_bad.rs" ->
 /// "rules::side_effects::detect_bad"
+///
+/// Outside of `src`, files of an integration test target are relative to its
+/// crate root, `tests/<name>.rs` or `tests/<name>/main.rs`.
+/// e.g., "tests/integration.rs" -> ""
+/// e.g., "tests/integration/helpers.rs" -> "helpers"
 fn file_path_to_module_path(file_path: &str) -> String {
     let path = Path::new(file_path);
     let components: Vec<_> = path.components().collect();
 
-    let src_idx = components
-        .iter()
-        .position(|c| matches!(c, std::path::Component::Normal(s) if s.to_str() == Some("src")));
+    let position = |dir: &str| {
+        components
+            .iter()
+            .position(|c| matches!(c, std::path::Component::Normal(s) if s.to_str() == Some(dir)))
+    };
 
-    let relevant = match src_idx {
-        Some(idx) => &components[idx + 1..],
-        None => &components[..],
+    let relevant = match (position("src"), position("tests")) {
+        (Some(idx), _) => &components[idx + 1..],
+        // The name of the test target's directory isn't a module
+        (None, Some(idx)) if components.len() > idx + 2 => &components[idx + 2..],
+        (None, Some(_)) => &[],
+        (None, None) => &components[..],
     };
 
     relevant
@@ -54,14 +68,22 @@ fn file_path_to_module_path(file_path: &str) -> String {
 /// Discover Rust tests in a file using tree-sitter.
 fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     let mut parser = tree_sitter::Parser::new();
-    parser
-        .set_language(&tree_sitter_rust::language())
-        .expect("Error loading Rust grammar");
+    parser.set_language(&tree_sitter_rust::language())?;
 
     let source_code = std::fs::read_to_string(file_path)?;
-    let tree = parser.parse(&source_code, None).unwrap();
-    let query =
-        Query::new(&tree_sitter_rust::language(), DISCOVER_QUERY).expect("Error creating query");
+    let tree = parser
+        .parse(&source_code, None)
+        .ok_or(LSError::TreeSitterParse)?;
+    discover_tests_in_tree(file_path, &source_code, &tree)
+}
+
+/// Discover Rust tests in an already parsed syntax tree.
+fn discover_tests_in_tree(
+    file_path: &str,
+    source_code: &str,
+    tree: &Tree,
+) -> Result<Vec<TestItem>, LSError> {
+    let query = Query::new(&tree_sitter_rust::language(), DISCOVER_QUERY)?;
 
     let mut cursor = QueryCursor::new();
     cursor.set_byte_range(tree.root_node().byte_range());
@@ -160,45 +182,276 @@ fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
     Ok(test_items)
 }
 
+/// Tests of the files the run of `context` selects, whose name contains
+/// `filter`, if any.
+fn discover_matching(
+    file_paths: &[String],
+    context: &RunContext,
+    filter: Option<&str>,
+) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .filter(|item| filter.is_none_or(|filter| item.name.contains(filter)))
+        .filter(|item| context.selects(&item.id))
+        .collect()
+}
+
+/// Most test name filters passed on one command line. Longer lists of tests
+/// are selected by their modules instead, staying well below the limit of
+/// the OS on the length of argument lists.
+const MAX_TEST_FILTERS: usize = 200;
+
+/// Leave out the filters a module filter among them already selects.
+fn drop_covered(filters: &mut Vec<String>) {
+    let modules: Vec<String> = filters
+        .iter()
+        .filter(|filter| filter.ends_with("::"))
+        .cloned()
+        .collect();
+    filters.retain(|filter| {
+        !modules
+            .iter()
+            .any(|module| module != filter && filter.starts_with(module.as_str()))
+    });
+}
+
+/// Filters selecting at least the tests of `filters` in at most
+/// [`MAX_TEST_FILTERS`] arguments: the modules of the tests, or else the
+/// user's `filter` alone, or no filter, running every test.
+fn bounded_filters(filters: Vec<String>, filter: Option<&str>) -> Vec<String> {
+    if filters.len() <= MAX_TEST_FILTERS {
+        return filters;
+    }
+    let mut modules: Vec<String> = vec![];
+    for test in &filters {
+        let module = match test.rsplit_once("::") {
+            _ if test.ends_with("::") => test.clone(),
+            Some((module, _)) => format!("{module}::"),
+            None => {
+                modules.clear();
+                break;
+            }
+        };
+        if !modules.contains(&module) {
+            modules.push(module);
+        }
+    }
+    drop_covered(&mut modules);
+    if !modules.is_empty() && modules.len() <= MAX_TEST_FILTERS {
+        return modules;
+    }
+    filter
+        .map(|filter| vec![filter.to_string()])
+        .unwrap_or_default()
+}
+
+/// Test name filters passed to the test binary, which matches them as
+/// substrings.
+///
+/// Without a filter, the tests of a file below the crate root are selected by
+/// its module path, like `rules::parse::`, so that files with many tests don't
+/// make for a command line too long; tests of a crate root are selected by
+/// id. With a filter, the ids of the matching tests, or the filter itself when
+/// none were discovered. Long lists are bounded by [`bounded_filters`].
+fn test_filters(test_items: &[TestItem], filter: Option<&str>) -> Vec<String> {
+    let filters = match filter {
+        Some(filter) if test_items.is_empty() => vec![filter.to_string()],
+        Some(_) => test_items.iter().map(|t| t.id.clone()).collect(),
+        None => {
+            let mut filters: Vec<String> = vec![];
+            for item in test_items {
+                let module = file_path_to_module_path(&item.path);
+                let filter = if module.is_empty() {
+                    item.id.clone()
+                } else {
+                    format!("{module}::")
+                };
+                if !filters.contains(&filter) {
+                    filters.push(filter);
+                }
+            }
+            // A module's filter also selects the tests of its submodules
+            drop_covered(&mut filters);
+            filters
+        }
+    };
+    bounded_filters(filters, filter)
+}
+
+/// Test name filters of a run: exactly the tests of
+/// [`RunContext::test_ids`], matched in full with `--exact`, or else the
+/// [`test_filters`] of `test_items`.
+fn run_filters(context: &RunContext, test_items: &[TestItem], filter: Option<&str>) -> Vec<String> {
+    match &context.test_ids {
+        Some(test_ids) => [vec!["--exact".to_string()], test_ids.clone()].concat(),
+        None => test_filters(test_items, filter),
+    }
+}
+
+/// Build the test executables with `cargo test --no-run` and launch the one
+/// of the crate of `file_path` with just the test `test_id`.
+fn debug_launch(
+    test_id: &str,
+    file_path: &str,
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<DebugLaunch, LSError> {
+    let output = call::run_cargo_build_tests(&mut call::cargo_build_tests_command(
+        context, extra_args, command,
+    )?)?;
+    let program = parse::test_executable(&String::from_utf8_lossy(&output.stdout), file_path)
+        .ok_or_else(|| LSError::TestExecutableNotFound(file_path.to_string()))?;
+    Ok(DebugLaunch {
+        program,
+        args: vec![
+            test_id.to_string(),
+            "--exact".to_string(),
+            "--nocapture".to_string(),
+        ],
+        cwd: context.workspace.to_string(),
+        env: HashMap::new(),
+    })
+}
+
 #[derive(Eq, PartialEq, Hash, Debug)]
 pub struct CargoTestRunner;
 
 impl Runner for CargoTestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
-        let mut files = Vec::new();
-        for file_path in file_paths {
-            let tests = discover_tests(file_path)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.to_string(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_rust::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_tests_in_tree(file_path, source, tree)
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
     ) -> Result<Diagnostics, LSError> {
-        let discovered_tests: Vec<TestItem> = file_paths
-            .iter()
-            .filter_map(|path| discover_tests(path).ok())
-            .flatten()
-            .collect();
+        self.run_tests_streaming(
+            file_paths,
+            context,
+            extra_args,
+            command,
+            filter,
+            &mut |_| {},
+        )
+    }
 
-        let test_ids: Vec<String> = discovered_tests.iter().map(|t| t.id.clone()).collect();
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let test_ids = run_filters(
+            context,
+            &discover_matching(file_paths, context, filter),
+            filter,
+        );
+        call::cargo_test_command(context, extra_args, &test_ids, command)
+    }
 
-        let output = call::run_cargo_test(workspace, extra_args, &test_ids)?;
-        let json_output = String::from_utf8(output.stdout)?;
+    fn run_tests_streaming(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+        on_event: &mut dyn FnMut(RunEvent),
+    ) -> Result<Diagnostics, LSError> {
+        let discovered_tests = discover_matching(file_paths, context, filter);
+        let test_ids = run_filters(context, &discovered_tests, filter);
 
-        Ok(parse::parse_libtest_json(
-            &json_output,
-            PathBuf::from(workspace),
+        let mut parser = parse::LibtestParser::new(
+            PathBuf::from(context.workspace),
             file_paths,
             &discovered_tests,
-        ))
+        );
+        let mut tracker = CompletionTracker::new(&discovered_tests);
+        let mut command = call::cargo_test_command(context, extra_args, &test_ids, command)?;
+        let output = call::run_cargo_test(&mut command, |line| {
+            let outcome = parser.parse_line(line);
+            if let Some(test_count) = parser.take_started_suite() {
+                tracker.start_suite(test_count);
+            }
+            let Some(outcome) = outcome else {
+                return;
+            };
+            let (progress, finished_files) = tracker.finish(&outcome.id);
+            on_event(RunEvent::TestCompleted(outcome));
+            if let Some(progress) = progress {
+                on_event(progress);
+            }
+            for path in finished_files {
+                on_event(RunEvent::FileFinished(parser.file_diagnostics(&path)));
+            }
+        })?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        parser.parse_stderr(&stderr);
+
+        let mut diagnostics = parser.finish();
+        diagnostics.extend_files(parse::compiler_warnings(
+            &stderr,
+            Path::new(context.workspace),
+            file_paths,
+        ));
+        Ok(diagnostics)
+    }
+
+    fn debug_launch(
+        &self,
+        test_id: &str,
+        file_path: &str,
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+    ) -> Result<DebugLaunch, LSError> {
+        debug_launch(test_id, file_path, context, extra_args, command)
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let discovered_tests: Vec<TestItem> = file_paths
+            .iter()
+            .filter_map(|path| discover_tests(path).ok())
+            .flatten()
+            .collect();
+        let contents = std::fs::read_to_string(results_path)?;
+        let mut parser =
+            parse::LibtestParser::new(PathBuf::from(workspace), file_paths, &discovered_tests);
+        for line in contents.lines() {
+            parser.parse_line(line);
+        }
+        Ok(parser.finish())
     }
 
     fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
@@ -212,32 +465,52 @@ pub struct CargoNextestRunner;
 impl Runner for CargoNextestRunner {
     fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
         // Nextest uses the same test discovery as cargo test
-        let mut files = Vec::new();
-        for file_path in file_paths {
-            let tests = discover_tests(file_path)?;
-            files.push(FileTests {
-                tests,
-                path: file_path.to_string(),
-            });
-        }
-        Ok(DiscoveredTests { files })
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        Some(tree_sitter_rust::language())
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        discover_tests_in_tree(file_path, source, tree)
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let test_ids = run_filters(
+            context,
+            &discover_matching(file_paths, context, filter),
+            filter,
+        );
+        call::cargo_nextest_command(context, extra_args, &test_ids, command)
     }
 
     fn run_tests(
         &self,
         file_paths: &[String],
-        workspace: &str,
+        context: &RunContext,
         extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
     ) -> Result<Diagnostics, LSError> {
-        let discovered_tests: Vec<TestItem> = file_paths
-            .iter()
-            .filter_map(|path| discover_tests(path).ok())
-            .flatten()
-            .collect();
-
-        let test_ids: Vec<String> = discovered_tests.iter().map(|t| t.id.clone()).collect();
+        let discovered_tests = discover_matching(file_paths, context, filter);
+        let test_ids = run_filters(context, &discovered_tests, filter);
 
-        let output = call::run_cargo_nextest(workspace, extra_args, &test_ids)?;
+        let output = call::run_cargo_nextest(&mut call::cargo_nextest_command(
+            context, extra_args, &test_ids, command,
+        )?)?;
 
         // Nextest outputs to stderr, and status code 100 means tests failed (not an
         // error)
@@ -251,8 +524,54 @@ impl Runner for CargoNextestRunner {
             return Err(LSError::AdapterError);
         }
 
-        Ok(parse::parse_nextest_output(
+        let mut diagnostics = parse::parse_nextest_output(
+            &stderr_output,
+            PathBuf::from(context.workspace),
+            file_paths,
+            &discovered_tests,
+        );
+        diagnostics.extend_files(parse::compiler_warnings(
             &stderr_output,
+            Path::new(context.workspace),
+            file_paths,
+        ));
+        Ok(diagnostics)
+    }
+
+    fn debug_launch(
+        &self,
+        test_id: &str,
+        file_path: &str,
+        context: &RunContext,
+        extra_args: &[String],
+        _command: Option<&[String]>,
+    ) -> Result<DebugLaunch, LSError> {
+        // Nextest runs the executables `cargo test` builds, but has no
+        // `--no-run` of its own
+        debug_launch(test_id, file_path, context, extra_args, None)
+    }
+
+    fn tag_args(&self, tags: &str) -> Option<Vec<String>> {
+        Some(call::nextest_filterset_args(tags))
+    }
+
+    fn selects_ids(&self) -> bool {
+        true
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        let discovered_tests: Vec<TestItem> = file_paths
+            .iter()
+            .filter_map(|path| discover_tests(path).ok())
+            .flatten()
+            .collect();
+        Ok(parse::parse_nextest_output(
+            &std::fs::read_to_string(results_path)?,
             PathBuf::from(workspace),
             file_paths,
             &discovered_tests,
@@ -276,5 +595,137 @@ mod tests {
         );
         assert_eq!(file_path_to_module_path("src/lib.rs"), "");
         assert_eq!(file_path_to_module_path("src/rules/mod.rs"), "rules");
+        assert_eq!(
+            file_path_to_module_path("/project/tests/integration.rs"),
+            ""
+        );
+        assert_eq!(
+            file_path_to_module_path("/project/tests/integration/main.rs"),
+            ""
+        );
+        assert_eq!(
+            file_path_to_module_path("/project/tests/integration/helpers.rs"),
+            "helpers"
+        );
+    }
+
+    #[test]
+    fn test_nextest_tag_args() {
+        assert_eq!(
+            CargoNextestRunner.tag_args("slow"),
+            Some(vec!["-E".to_string(), "test(slow)".to_string()])
+        );
+        assert_eq!(
+            CargoNextestRunner.tag_args("test(slow) & !test(flaky)"),
+            Some(vec![
+                "-E".to_string(),
+                "test(slow) & !test(flaky)".to_string()
+            ])
+        );
+        assert_eq!(CargoTestRunner.tag_args("slow"), None);
+    }
+
+    #[test]
+    fn test_module_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/rules")).unwrap();
+        let tests = "#[cfg(test)]\nmod tests {\n    #[test]\n    fn a() {}\n    #[test]\n    fn \
+                     b() {}\n}\n";
+        for file in ["src/lib.rs", "src/rules/mod.rs", "src/rules/parse.rs"] {
+            std::fs::write(dir.path().join(file), tests).unwrap();
+        }
+        let path = |file: &str| dir.path().join(file).to_string_lossy().to_string();
+        let workspace = dir.path().to_str().unwrap();
+        let args = |file_paths: &[String], filter: Option<&str>| -> Vec<String> {
+            let command = CargoTestRunner
+                .build_command(file_paths, &RunContext::new(workspace), &[], None, filter)
+                .unwrap();
+            let args: Vec<String> = command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect();
+            args[args.iter().position(|arg| arg == "--report-time").unwrap() + 1..].to_vec()
+        };
+
+        assert_eq!(
+            args(&[path("src/rules/parse.rs")], None),
+            vec!["rules::parse::"]
+        );
+        assert_eq!(
+            args(
+                &[path("src/rules/parse.rs"), path("src/rules/mod.rs")],
+                None
+            ),
+            vec!["rules::"]
+        );
+        // Tests of the crate root aren't in a module of the file
+        assert_eq!(
+            args(&[path("src/lib.rs")], None),
+            vec!["tests::a", "tests::b"]
+        );
+        assert_eq!(
+            args(&[path("src/rules/parse.rs")], Some("b")),
+            vec!["rules::parse::tests::b"]
+        );
+
+        // Tests selected by id run alone, not with the rest of their module
+        let context = RunContext {
+            test_ids: Some(vec!["tests::a".to_string()]),
+            ..RunContext::new(workspace)
+        };
+        let command = CargoTestRunner
+            .build_command(&[path("src/lib.rs")], &context, &[], None, None)
+            .unwrap();
+        let args: Vec<String> = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        assert!(args.ends_with(&["--exact".to_string(), "tests::a".to_string()]));
+    }
+
+    #[test]
+    fn test_many_tests_selected_by_module() {
+        let test_items = |id: &dyn Fn(usize) -> String| -> Vec<TestItem> {
+            (0..5000)
+                .map(|i| TestItem {
+                    id: id(i),
+                    name: id(i),
+                    path: "/project/src/lib.rs".to_string(),
+                    start_position: Range::default(),
+                    end_position: Range::default(),
+                })
+                .collect()
+        };
+
+        let items = test_items(&|i| format!("tests::case_{i}"));
+        assert_eq!(test_filters(&items, None), vec!["tests::"]);
+        assert_eq!(test_filters(&items, Some("case")), vec!["tests::"]);
+
+        let items = test_items(&|i| format!("group_{}::case_{i}", i % 300));
+        assert_eq!(test_filters(&items, Some("case")), vec!["case"]);
+        assert!(test_filters(&items, None).is_empty());
+
+        let items = test_items(&|i| format!("case_{i}"));
+        assert!(test_filters(&items, None).is_empty());
+        assert_eq!(
+            test_filters(&items[..MAX_TEST_FILTERS], None).len(),
+            MAX_TEST_FILTERS
+        );
+    }
+
+    #[test]
+    fn test_discover_unparsable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("broken.rs");
+        std::fs::write(&file_path, [0xff, 0xfe, 0xfd]).unwrap();
+        let file_path = file_path.to_string_lossy().to_string();
+
+        assert!(discover_tests(&file_path).is_err());
+
+        let discovered = CargoTestRunner
+            .discover(std::slice::from_ref(&file_path))
+            .unwrap();
+        assert_eq!(discovered.files.len(), 1);
+        assert!(discovered.files[0].tests.is_empty());
     }
 }
@@ -1,22 +1,38 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
 use regex::Regex;
 use serde::Deserialize;
 
-use crate::{Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem};
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    log::clean_ansi,
+    runner::{STDERR_WARNING_CODE, TestOutcome, TestStatus},
+    workspace::{contains_path, normalize_path, resolve_path, same_path},
+};
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 enum LibtestEvent {
-    Suite(()),
+    Suite(SuiteEvent),
     Test(TestEvent),
     Bench(()),
 }
 
+#[derive(Debug, Deserialize)]
+struct SuiteEvent {
+    event: String,
+    #[serde(default)]
+    test_count: Option<usize>,
+}
+
 #[derive(Debug, Deserialize)]
 struct TestEvent {
     event: String,
@@ -25,6 +41,27 @@ struct TestEvent {
     stdout: Option<String>,
     #[serde(default)]
     message: Option<String>,
+    /// Seconds, reported with `--report-time`
+    #[serde(default)]
+    exec_time: Option<f64>,
+}
+
+/// Block of `output` reporting the panic of the thread named `thread`: the
+/// `panicked at` line and the message after it, up to the next thread's
+/// report or note.
+fn thread_panic<'o>(output: &'o str, thread: &str) -> Option<&'o str> {
+    let start = output.find(&format!("thread '{thread}' panicked at "))?;
+    let rest = &output[start..];
+    let first_line_end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+    let end = rest[first_line_end..]
+        .match_indices('\n')
+        .map(|(i, _)| first_line_end + i + 1)
+        .find(|&i| {
+            let next = &rest[i..];
+            next.starts_with("thread '") || next.starts_with("note: ") || next.starts_with('\n')
+        })
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
 }
 
 /// Extract panic location and message from test stdout.
@@ -45,15 +82,27 @@ fn extract_panic_location(
             .and_then(|m| m.as_str().parse().ok())
             .unwrap_or(1);
 
-        let absolute_path = workspace_root.join(relative_path);
+        // Absolute paths, like of sources generated in a target directory
+        // outside of the workspace, are kept
+        let absolute_path = resolve_path(workspace_root, relative_path);
         let file_path = absolute_path
             .exists()
             .then(|| absolute_path.to_string_lossy().to_string());
 
-        let message = stdout
+        let mut message = stdout
             .find(":\n")
             .map(|pos| stdout[pos + 2..].trim().to_string())
             .unwrap_or_default();
+        // Without a file to point at, the diagnostic stays at the test, so
+        // say where the panic was
+        if file_path.is_none() {
+            message = format!(
+                "{message}\n(panicked at {relative_path}:{line}:{col}, which isn't a file that \
+                 can be opened)"
+            )
+            .trim_start()
+            .to_string();
+        }
 
         (file_path, line, col, message)
     } else {
@@ -61,18 +110,335 @@ fn extract_panic_location(
     }
 }
 
+/// A failed `insta` snapshot assertion, from the summary insta prints before
+/// it panics.
+struct InstaSnapshot {
+    name: String,
+    /// The `.snap` file, or the pending `.snap.new` when only that exists
+    snap_file: Option<PathBuf>,
+    /// File of the assertion, when insta names it
+    source: Option<PathBuf>,
+    /// One-based line of the assertion
+    line: u32,
+}
+
+impl InstaSnapshot {
+    /// File, line and column of the assertion, in `test_file` unless insta
+    /// names another.
+    fn location(&self, test_file: &str) -> (String, u32, u32) {
+        let file = self.source.as_ref().map_or_else(
+            || test_file.to_string(),
+            |source| source.to_string_lossy().to_string(),
+        );
+        (file, self.line, 1)
+    }
+
+    /// Link to the snapshot file.
+    fn snap_file_info(&self) -> Option<DiagnosticRelatedInformation> {
+        Some(DiagnosticRelatedInformation {
+            location: Location {
+                uri: Url::from_file_path(self.snap_file.as_ref()?).ok()?,
+                range: Range::default(),
+            },
+            message: "snapshot file".to_string(),
+        })
+    }
+}
+
+/// The failed `insta` snapshot assertion a test's output reports, if any:
+/// a panic like `snapshot assertion for 'adds' failed in line 12`, after the
+/// `Snapshot file:` and `Source:` of the snapshot summary.
+fn insta_snapshot(output: &str, workspace_root: &Path) -> Option<InstaSnapshot> {
+    let assertion_re =
+        Regex::new(r"snapshot assertion for '([^']*)' failed in line (\d+)").unwrap();
+    let m = assertion_re.captures(output)?;
+    let field = |name: &str| {
+        output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(name))
+            .map(str::trim)
+    };
+    let snap_file = field("Snapshot file:").map(|path| {
+        let snap = resolve_path(workspace_root, path);
+        let pending = PathBuf::from(format!("{}.new", snap.display()));
+        if !snap.exists() && pending.exists() {
+            pending
+        } else {
+            snap
+        }
+    });
+    let source = field("Source:")
+        .and_then(|source| source.rsplit_once(':'))
+        .map(|(path, _)| resolve_path(workspace_root, path));
+    Some(InstaSnapshot {
+        name: m[1].to_string(),
+        snap_file,
+        source,
+        line: m[2].parse().ok()?,
+    })
+}
+
+/// A failed `proptest!` property, from the panic proptest raises once it has
+/// shrunk the failing input.
+struct ProptestFailure {
+    /// Why the property failed, without the location and input
+    reason: String,
+    /// The minimal failing input, like `a = 1, b = 2`
+    input: String,
+    /// File, line and column of the failed `prop_assert`, when reported
+    location: Option<(String, u32, u32)>,
+    /// The file proptest persists the failure in, to replay it
+    regressions_file: Option<PathBuf>,
+}
+
+impl ProptestFailure {
+    /// Link to the regressions file.
+    fn regressions_file_info(&self) -> Option<DiagnosticRelatedInformation> {
+        Some(DiagnosticRelatedInformation {
+            location: Location {
+                uri: Url::from_file_path(self.regressions_file.as_ref()?).ok()?,
+                range: Range::default(),
+            },
+            message: "failing input persisted here".to_string(),
+        })
+    }
+}
+
+/// The failed property a test's output reports, if any: a panic like
+/// `Test failed: ... at src/lib.rs:23:13; minimal failing input: a = 1`,
+/// with the `Saving this and future failures in ...` note proptest prints
+/// before it.
+fn proptest_failure(output: &str, workspace_root: &Path) -> Option<ProptestFailure> {
+    let failure_re = Regex::new(
+        r"(?s)Test failed: (.*?)(?: at ([^\s:]+\.rs):(\d+):(\d+))?; minimal failing input: ([^\n]*)",
+    )
+    .unwrap();
+    let saving_re = Regex::new(r"proptest: Saving this and future failures in (\S+)").unwrap();
+    let m = failure_re.captures(output)?;
+    let location = m.get(2).and_then(|file| {
+        Some((
+            resolve_path(workspace_root, file.as_str())
+                .to_string_lossy()
+                .to_string(),
+            m[3].parse().ok()?,
+            m[4].parse().ok()?,
+        ))
+    });
+    Some(ProptestFailure {
+        reason: m[1].trim().to_string(),
+        input: m[5].trim().to_string(),
+        location,
+        regressions_file: saving_re
+            .captures(output)
+            .map(|m| resolve_path(workspace_root, &m[1])),
+    })
+}
+
+/// Failure of a `#[should_panic]` test, from the message libtest reported:
+/// a panic without the expected substring, or no panic at all.
+fn should_panic_failure(message: &str) -> Option<String> {
+    if message.starts_with("test did not panic as expected") {
+        return Some("expected a panic, but the test didn't panic".to_string());
+    }
+    // Quoted with `{:?}`, and in older releases between backticks too
+    let quoted = |label: &str| {
+        let re = Regex::new(&format!(r#"{label}: `?("(?:[^"\\]|\\.)*")`?"#)).unwrap();
+        let quoted = re.captures(message)?.get(1)?.as_str();
+        Some(
+            serde_json::from_str::<String>(quoted)
+                .unwrap_or_else(|_| quoted[1..quoted.len() - 1].to_string()),
+        )
+    };
+    if message.starts_with("panic did not contain expected string") {
+        return Some(format!(
+            "expected panic containing '{}', got '{}'",
+            quoted("expected substring")?,
+            quoted("panic message")?
+        ));
+    }
+    None
+}
+
+/// Code of a failed test's diagnostic, based on the test's path
+/// (integration vs unit test) and name.
+fn test_kind_code(test_item: &TestItem, test_name: &str) -> &'static str {
+    if test_item.path.contains("/tests/") {
+        "integration-test-failed"
+    } else if test_name.starts_with("doc") || test_name.contains("::doc::") {
+        "doctest-failed"
+    } else {
+        "unit-test-failed"
+    }
+}
+
+/// Code of a failed test's diagnostic: the kind of assertion that failed, or
+/// else the kind of test.
+fn failure_code(
+    test_item: &TestItem,
+    test_name: &str,
+    snapshot: Option<&InstaSnapshot>,
+    proptest: Option<&ProptestFailure>,
+    message: &str,
+) -> &'static str {
+    if snapshot.is_some() {
+        "insta-snapshot-failed"
+    } else if proptest.is_some() {
+        "proptest-failed"
+    } else if should_panic_failure(message).is_some() {
+        "should-panic-failed"
+    } else {
+        test_kind_code(test_item, test_name)
+    }
+}
+
+/// What a failed test's diagnostic says, after the test's name: the failed
+/// snapshot, property or expected panic, the panic, or the message libtest
+/// reported.
+fn failure_message(
+    snapshot: Option<&InstaSnapshot>,
+    proptest: Option<&ProptestFailure>,
+    panic_message: String,
+    message: String,
+) -> String {
+    if let Some(snapshot) = snapshot {
+        format!(
+            "snapshot '{}' doesn't match, review the new snapshot with `cargo insta review`",
+            snapshot.name
+        )
+    } else if let Some(proptest) = proptest {
+        format!(
+            "{}\nminimal failing input: {}",
+            proptest.reason, proptest.input
+        )
+    } else if let Some(should_panic) = should_panic_failure(&message) {
+        should_panic
+    } else if !panic_message.is_empty() {
+        panic_message
+    } else if !message.is_empty() {
+        message
+    } else {
+        "test failed".to_string()
+    }
+}
+
+/// Match a test name reported by a runner to a discovered test.
+///
+/// Either may have leading module segments the other lacks, so they match when
+/// the `::` segments of one end with all segments of the other. The test
+/// sharing the most segments wins, so `foo::bar` doesn't take the results of
+/// `baz::foo::bar`.
+fn find_test_item<'a>(test_items: &'a [TestItem], name: &str) -> Option<&'a TestItem> {
+    let name_segments: Vec<&str> = name.split("::").collect();
+    test_items
+        .iter()
+        .filter_map(|item| {
+            let item_segments: Vec<&str> = item.id.split("::").collect();
+            let matches =
+                name_segments.ends_with(&item_segments) || item_segments.ends_with(&name_segments);
+            matches.then_some((item_segments.len().min(name_segments.len()), item))
+        })
+        .min_by_key(|(shared, _)| std::cmp::Reverse(*shared))
+        .map(|(_, item)| item)
+}
+
+/// Discovered test a macro-expanded test belongs to, for names that don't
+/// appear in the source. `#[test_case]` expands a function into a module of
+/// cases, so `tests::adds::two_plus_two` is a case of `tests::adds`. The
+/// test with the longest id wins.
+fn expanded_test_item<'a>(test_items: &'a [TestItem], name: &str) -> Option<&'a TestItem> {
+    test_items
+        .iter()
+        .filter(|item| {
+            name.strip_prefix(&item.id)
+                .is_some_and(|rest| rest.starts_with("::"))
+        })
+        .max_by_key(|item| item.id.len())
+}
+
+/// Diagnostic at a test's definition for a nextest status line without a
+/// panic location, such as a timeout or a leak.
+fn nextest_status_diagnostic(test_item: &TestItem, status: &str, time: &str) -> Diagnostic {
+    let (message, severity, code) = match status {
+        "TIMEOUT" => (
+            format!("`{}` timed out after {time}", test_item.name),
+            DiagnosticSeverity::ERROR,
+            "nextest-timeout",
+        ),
+        "LEAK" => (
+            format!(
+                "`{}` passed but leaked subprocesses or handles",
+                test_item.name
+            ),
+            DiagnosticSeverity::WARNING,
+            "nextest-leak",
+        ),
+        "LEAK-FAIL" => (
+            format!(
+                "`{}` failed because it leaked subprocesses or handles",
+                test_item.name
+            ),
+            DiagnosticSeverity::ERROR,
+            "nextest-leak",
+        ),
+        _ => (
+            format!("`{}` failed ({status}) after {time}", test_item.name),
+            DiagnosticSeverity::ERROR,
+            "nextest-failed",
+        ),
+    };
+    Diagnostic {
+        range: test_item.start_position,
+        message,
+        severity: Some(severity),
+        source: Some("cargo-nextest".to_string()),
+        code: Some(NumberOrString::String(code.to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Report failures from nextest status lines for tests not reported yet.
+fn report_nextest_statuses<'a>(
+    lines: &[&str],
+    test_items: &'a [TestItem],
+    reported: &mut HashSet<&'a str>,
+    result_map: &mut HashMap<String, Vec<Diagnostic>>,
+) {
+    let status_re = Regex::new(
+        r"^\s*(FAIL|TIMEOUT|LEAK|LEAK-FAIL|SIG[A-Z]+|ABORT)\s+\[\s*([\d.]+s)\]\s+(?:\S+\s+)?(\S+)$",
+    )
+    .unwrap();
+    // Status lines are repeated in the final summary, so report each test once
+    for m in lines.iter().filter_map(|line| status_re.captures(line)) {
+        let Some(test_item) = find_test_item(test_items, &m[3]) else {
+            continue;
+        };
+        if !reported.insert(&test_item.id) {
+            continue;
+        }
+        result_map
+            .entry(test_item.path.clone())
+            .or_default()
+            .push(nextest_status_diagnostic(test_item, &m[1], &m[2]));
+    }
+}
+
 /// Parse cargo nextest text output (from stderr)
+///
+/// Panics are reported at their location and at the test definition. Status
+/// lines of failures without a panic, like `TIMEOUT` or `LEAK`, are reported
+/// at the test definition only.
 pub fn parse_nextest_output(
     contents: &str,
     workspace_root: PathBuf,
     file_paths: &[String],
     test_items: &[TestItem],
 ) -> Diagnostics {
-    let contents = contents.replace("\r\n", "\n");
+    let contents = clean_ansi(&contents.replace("\r\n", "\n"));
     let lines: Vec<&str> = contents.lines().collect();
     let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
 
     let panic_re = Regex::new(r"thread '([^']+)' panicked at ([^:]+):(\d+):(\d+):").unwrap();
+    let mut reported: HashSet<&str> = HashSet::new();
 
     for (i, line) in lines.iter().enumerate() {
         if let Some(m) = panic_re.captures(line) {
@@ -103,15 +469,13 @@ pub fn parse_nextest_output(
             }
 
             let absolute_path = workspace_root.join(&relative_file_path);
-            let file_path = file_paths
-                .iter()
-                .find(|p| p.contains(absolute_path.to_str().unwrap_or("")));
+            let file_path = file_paths.iter().find(|p| {
+                normalize_path(p).contains(&normalize_path(&absolute_path.to_string_lossy()))
+            });
 
             if let Some(file_path) = file_path {
                 // Find matching test item
-                let matched_test_item = test_items.iter().find(|item| {
-                    id_with_file.ends_with(&item.id) || item.id.ends_with(&id_with_file)
-                });
+                let matched_test_item = find_test_item(test_items, &id_with_file);
 
                 let diagnostic = Diagnostic {
                     range: Range {
@@ -139,6 +503,7 @@ pub fn parse_nextest_output(
 
                 // Also add diagnostic at test definition if found
                 if let Some(test_item) = matched_test_item {
+                    reported.insert(&test_item.id);
                     let test_diagnostic = Diagnostic {
                         range: test_item.start_position,
                         message: format!(
@@ -163,6 +528,8 @@ pub fn parse_nextest_output(
         }
     }
 
+    report_nextest_statuses(&lines, test_items, &mut reported, &mut result_map);
+
     Diagnostics {
         files: result_map
             .into_iter()
@@ -172,132 +539,406 @@ pub fn parse_nextest_output(
     }
 }
 
-/// Parse libtest JSON format output from `cargo test -- -Z unstable-options
-/// --format json`
-pub fn parse_libtest_json(
-    json_output: &str,
-    workspace_root: PathBuf,
+/// Compiler warnings cargo printed to stderr, like `warning: unused variable`
+/// followed by `  --> src/lib.rs:10:9`, as diagnostics of the files in
+/// `file_paths`. Warnings cargo repeats for several targets are reported
+/// once.
+#[must_use]
+pub fn compiler_warnings(
+    stderr: &str,
+    workspace_root: &Path,
     file_paths: &[String],
-    test_items: &[TestItem],
-) -> Diagnostics {
+) -> Vec<FileDiagnostics> {
+    let location_re = Regex::new(r"^\s*--> (.+):(\d+):(\d+)$").unwrap();
     let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+    let mut message: Option<&str> = None;
+    for line in stderr.lines() {
+        if let Some(warning) = line.strip_prefix("warning: ") {
+            message = Some(warning);
+            continue;
+        }
+        let (Some(warning), Some(location)) = (message.take(), location_re.captures(line)) else {
+            continue;
+        };
+        let path = resolve_path(workspace_root, &location[1])
+            .to_string_lossy()
+            .to_string();
+        let (Ok(line_number), Ok(column)) =
+            (location[2].parse::<u32>(), location[3].parse::<u32>())
+        else {
+            continue;
+        };
+        let Some(target_file) = file_paths.iter().find(|p| same_path(p, &path)) else {
+            continue;
+        };
+        let line_number = line_number.saturating_sub(1);
+        let diagnostic = Diagnostic {
+            range: Range::new(
+                Position::new(line_number, column.saturating_sub(1)),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            ),
+            message: warning.to_string(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            source: Some("cargo".to_string()),
+            code: Some(NumberOrString::String(STDERR_WARNING_CODE.to_string())),
+            ..Diagnostic::default()
+        };
+        let diagnostics = result_map.entry(target_file.clone()).or_default();
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    result_map
+        .into_iter()
+        .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+        .collect()
+}
 
-    for line in json_output.lines() {
+#[derive(Debug, Deserialize)]
+struct ArtifactTarget {
+    src_path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactProfile {
+    test: bool,
+}
+
+/// `compiler-artifact` message of `cargo test --no-run --message-format=json`.
+#[derive(Debug, Deserialize)]
+struct Artifact {
+    reason: String,
+    target: Option<ArtifactTarget>,
+    profile: Option<ArtifactProfile>,
+    executable: Option<String>,
+}
+
+/// Test executable built for the crate `file_path` belongs to: the target
+/// whose root is the file, like `tests/integration.rs`, or else the one with
+/// the deepest root directory containing it, like `src/lib.rs` for
+/// `src/parse.rs`.
+#[must_use]
+pub fn test_executable(messages: &str, file_path: &str) -> Option<String> {
+    let executables: Vec<(PathBuf, String)> = messages
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Artifact>(line).ok())
+        .filter(|artifact| {
+            artifact.reason == "compiler-artifact"
+                && artifact
+                    .profile
+                    .as_ref()
+                    .is_some_and(|profile| profile.test)
+        })
+        .filter_map(|artifact| Some((artifact.target?.src_path, artifact.executable?)))
+        .collect();
+
+    if let Some((_, executable)) = executables
+        .iter()
+        .find(|(src_path, _)| same_path(&src_path.to_string_lossy(), file_path))
+    {
+        return Some(executable.clone());
+    }
+    let file_path = PathBuf::from(normalize_path(file_path));
+    executables
+        .into_iter()
+        .filter_map(|(src_path, executable)| {
+            let root = PathBuf::from(normalize_path(&src_path.parent()?.to_string_lossy()));
+            file_path
+                .starts_with(&root)
+                .then(|| (root.components().count(), executable))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, executable)| executable)
+}
+
+/// Incremental parser for libtest JSON lines, collecting diagnostics for
+/// failed tests as they are reported.
+pub struct LibtestParser<'a> {
+    workspace_root: PathBuf,
+    file_paths: &'a [String],
+    test_items: &'a [TestItem],
+    result_map: HashMap<String, Vec<Diagnostic>>,
+    /// Failures whose captured output has no panic location, which may still
+    /// be found in the stderr of the test process
+    unlocated: Vec<(&'a TestItem, TestEvent)>,
+    /// Number of tests of the suite that started on the last line
+    started_suite: Option<usize>,
+}
+
+impl<'a> LibtestParser<'a> {
+    #[must_use]
+    pub fn new(
+        workspace_root: PathBuf,
+        file_paths: &'a [String],
+        test_items: &'a [TestItem],
+    ) -> Self {
+        Self {
+            workspace_root,
+            file_paths,
+            test_items,
+            result_map: HashMap::new(),
+            unlocated: vec![],
+            started_suite: None,
+        }
+    }
+
+    /// Number of tests of a suite (one per test binary) that started on the
+    /// line parsed last.
+    pub fn take_started_suite(&mut self) -> Option<usize> {
+        self.started_suite.take()
+    }
+
+    /// Parse one line of output. Returns the outcome of the discovered test
+    /// that finished on this line, if any.
+    pub fn parse_line(&mut self, line: &str) -> Option<TestOutcome> {
         let line = line.trim();
         if line.is_empty() {
-            continue;
+            return None;
         }
 
         let event: LibtestEvent = match serde_json::from_str(line) {
             Ok(e) => e,
             Err(e) => {
                 log::debug!("Failed to parse libtest JSON: {line}, error: {e}");
-                continue;
+                return None;
             }
         };
 
-        if let LibtestEvent::Test(test_event) = event {
-            if test_event.event != "failed" {
-                continue;
+        let test_event = match event {
+            LibtestEvent::Test(test_event) => test_event,
+            LibtestEvent::Suite(SuiteEvent {
+                event,
+                test_count: Some(test_count),
+            }) if event == "started" => {
+                self.started_suite = Some(test_count);
+                return None;
             }
+            LibtestEvent::Suite(_) | LibtestEvent::Bench(()) => return None,
+        };
+        let status = match test_event.event.as_str() {
+            "ok" => TestStatus::Passed,
+            "failed" => TestStatus::Failed,
+            "ignored" => TestStatus::Ignored,
+            _ => return None,
+        };
 
-            let test_name = &test_event.name;
-            let stdout = test_event.stdout.unwrap_or_default();
-            let message = test_event.message.unwrap_or_default();
-
-            let Some(test_item) = test_items
-                .iter()
-                .find(|item| item.id == *test_name || item.name == *test_name)
-            else {
+        let test_name = &test_event.name;
+        let test_items = self.test_items;
+        let exact_item = test_items
+            .iter()
+            .find(|item| item.id == *test_name || item.name == *test_name);
+        let Some(test_item) = exact_item.or_else(|| expanded_test_item(test_items, test_name))
+        else {
+            if test_event.event == "failed" {
                 log::warn!("Could not find test item for failed test: {}", test_name);
-                continue;
-            };
-
-            let (panic_file, panic_line, panic_col, panic_message) =
-                extract_panic_location(&stdout, &workspace_root);
+            }
+            return None;
+        };
 
-            // Build diagnostic message with short test name
-            let base_message = if !panic_message.is_empty() {
-                panic_message.clone()
-            } else if !message.is_empty() {
-                message
+        // The cases of a macro-expanded test keep their own names, so one
+        // failing case doesn't take the outcome of the others
+        let outcome = TestOutcome {
+            id: if exact_item.is_some() {
+                test_item.id.clone()
             } else {
-                "test failed".to_string()
-            };
-            let short_name = test_name.rsplit("::").next().unwrap_or(test_name);
-            let diagnostic_message = format!("[{}] {}", short_name, base_message);
-
-            // Related information pointing to test definition
-            let related_info = lsp_types::DiagnosticRelatedInformation {
-                location: lsp_types::Location {
-                    uri: lsp_types::Url::from_file_path(&test_item.path)
-                        .unwrap_or_else(|_| lsp_types::Url::parse("file:///unknown").unwrap()),
-                    range: test_item.start_position,
+                test_name.clone()
+            },
+            status,
+            duration: test_event
+                .exec_time
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+            output: test_event
+                .stdout
+                .as_deref()
+                .filter(|_| status == TestStatus::Failed)
+                .map(clean_ansi),
+        };
+        if status == TestStatus::Failed {
+            let stdout = test_event.stdout.as_deref().unwrap_or_default();
+            if thread_panic(&clean_ansi(stdout), test_name).is_some() {
+                self.add_failure(test_item, &test_event, None);
+            } else {
+                self.unlocated.push((test_item, test_event));
+            }
+        }
+        Some(outcome)
+    }
+
+    /// Look up the panics of failed tests whose captured output had none in
+    /// the stderr of the test process, where panics of tests run without
+    /// capturing output end up.
+    pub fn parse_stderr(&mut self, stderr: &str) {
+        let stderr = clean_ansi(stderr);
+        for (test_item, test_event) in std::mem::take(&mut self.unlocated) {
+            let panic = thread_panic(&stderr, &test_event.name);
+            self.add_failure(test_item, &test_event, panic);
+        }
+    }
+
+    fn add_failure(&mut self, test_item: &TestItem, test_event: &TestEvent, stderr: Option<&str>) {
+        let (target_file, diagnostic) = self.failure_diagnostic(test_item, test_event, stderr);
+        let diagnostics = self.result_map.entry(target_file).or_default();
+        if !diagnostics
+            .iter()
+            .any(|d| d.range == diagnostic.range && d.message == diagnostic.message)
+        {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Diagnostic for a failed test and the target file it belongs to. The
+    /// panic is taken from `stderr` when given, otherwise from the captured
+    /// output.
+    fn failure_diagnostic(
+        &self,
+        test_item: &TestItem,
+        test_event: &TestEvent,
+        stderr: Option<&str>,
+    ) -> (String, Diagnostic) {
+        let test_name = &test_event.name;
+        let stdout = stderr.map_or_else(
+            || clean_ansi(test_event.stdout.as_deref().unwrap_or_default()),
+            str::to_string,
+        );
+        let message = clean_ansi(test_event.message.as_deref().unwrap_or_default());
+
+        let (panic_file, panic_line, panic_col, panic_message) =
+            extract_panic_location(&stdout, &self.workspace_root);
+        let snapshot = insta_snapshot(&stdout, &self.workspace_root);
+        let proptest = proptest_failure(&stdout, &self.workspace_root);
+        let code = failure_code(
+            test_item,
+            test_name,
+            snapshot.as_ref(),
+            proptest.as_ref(),
+            &message,
+        );
+
+        // Build diagnostic message with short test name
+        let base_message =
+            failure_message(snapshot.as_ref(), proptest.as_ref(), panic_message, message);
+        let short_name = test_name.rsplit("::").next().unwrap_or(test_name);
+        // Name the case of a macro-expanded test along with its function
+        let short_name = match test_name.strip_prefix(&format!("{}::", test_item.id)) {
+            Some(case) => format!(
+                "{}::{case}",
+                test_item.id.rsplit("::").next().unwrap_or(&test_item.id)
+            ),
+            None => short_name.to_string(),
+        };
+        let diagnostic_message = format!("[{}] {}", short_name, base_message);
+
+        // A snapshot assertion panics inside insta, and a property in the
+        // proptest runner, so they're located at the assertion instead
+        let panic_location = match (&snapshot, &proptest) {
+            (Some(snapshot), _) => Some(snapshot.location(&test_item.path)),
+            (
+                None,
+                Some(ProptestFailure {
+                    location: Some(location),
+                    ..
+                }),
+            ) => Some(location.clone()),
+            _ => panic_file.map(|file| (file, panic_line, panic_col)),
+        }
+        .map(|(file, line, col)| {
+            let range = Range {
+                start: Position {
+                    line: line.saturating_sub(1),
+                    character: col.saturating_sub(1),
+                },
+                end: Position {
+                    line: line.saturating_sub(1),
+                    character: MAX_CHAR_LENGTH,
                 },
-                message: format!("test `{}` defined here", test_name),
             };
+            (file, range)
+        });
 
-            // Determine primary diagnostic location
-            let (primary_file, primary_range) = if let Some(ref pf) = panic_file {
-                (
-                    pf.clone(),
-                    Range {
-                        start: Position {
-                            line: panic_line.saturating_sub(1),
-                            character: panic_col.saturating_sub(1),
-                        },
-                        end: Position {
-                            line: panic_line.saturating_sub(1),
-                            character: MAX_CHAR_LENGTH,
-                        },
-                    },
-                )
-            } else {
-                (test_item.path.clone(), test_item.start_position)
-            };
+        // A panic outside the tested files, e.g. in a shared helper, is
+        // reported at the test and linked from there
+        let (primary_file, primary_range) = match &panic_location {
+            Some((file, range)) if contains_path(self.file_paths, file) => (file.clone(), *range),
+            _ => (test_item.path.clone(), test_item.start_position),
+        };
 
-            // Determine code based on test path (integration vs unit test)
-            let code = if test_item.path.contains("/tests/") {
-                "integration-test-failed"
-            } else if test_name.starts_with("doc") || test_name.contains("::doc::") {
-                "doctest-failed"
-            } else {
-                "unit-test-failed"
-            };
+        let mut related_information: Vec<DiagnosticRelatedInformation> =
+            test_item.definition_info().into_iter().collect();
+        if let Some((file, range)) = panic_location
+            && !(same_path(&file, &primary_file) && range == primary_range)
+            && let Ok(uri) = Url::from_file_path(&file)
+        {
+            related_information.push(DiagnosticRelatedInformation {
+                location: Location { uri, range },
+                message: if snapshot.is_some() {
+                    "snapshot asserted here"
+                } else if proptest.is_some() {
+                    "property failed here"
+                } else {
+                    "panicked here"
+                }
+                .to_string(),
+            });
+        }
+        related_information.extend(snapshot.as_ref().and_then(InstaSnapshot::snap_file_info));
+        related_information.extend(
+            proptest
+                .as_ref()
+                .and_then(ProptestFailure::regressions_file_info),
+        );
 
-            let diagnostic = Diagnostic {
-                range: primary_range,
-                message: diagnostic_message,
-                severity: Some(DiagnosticSeverity::ERROR),
-                source: Some("cargo-test".to_string()),
-                code: Some(NumberOrString::String(code.to_string())),
-                related_information: Some(vec![related_info]),
-                ..Diagnostic::default()
-            };
+        let diagnostic = Diagnostic {
+            range: primary_range,
+            message: diagnostic_message,
+            severity: Some(DiagnosticSeverity::ERROR),
+            source: Some("cargo-test".to_string()),
+            code: Some(NumberOrString::String(code.to_string())),
+            related_information: Some(related_information),
+            ..Diagnostic::default()
+        };
+
+        let target_file = self
+            .file_paths
+            .iter()
+            .find(|p| {
+                let (p, primary_file) = (normalize_path(p), normalize_path(&primary_file));
+                p.contains(&primary_file) || primary_file.contains(&p)
+            })
+            .cloned()
+            .unwrap_or_else(|| test_item.path.clone());
+        (target_file, diagnostic)
+    }
 
-            // Find target file and deduplicate
-            let target_file = file_paths
-                .iter()
-                .find(|p| p.contains(&primary_file) || primary_file.contains(*p))
-                .cloned()
-                .unwrap_or_else(|| test_item.path.clone());
-
-            let diagnostics = result_map.entry(target_file).or_default();
-            if !diagnostics
-                .iter()
-                .any(|d| d.range == diagnostic.range && d.message == diagnostic.message)
-            {
+    /// Diagnostics collected so far for a file. Failures still waiting for
+    /// stderr are reported with what their captured output tells.
+    #[must_use]
+    pub fn file_diagnostics(&self, path: &str) -> FileDiagnostics {
+        let mut diagnostics = self.result_map.get(path).cloned().unwrap_or_default();
+        for (test_item, test_event) in &self.unlocated {
+            let (target_file, diagnostic) = self.failure_diagnostic(test_item, test_event, None);
+            if target_file == path {
                 diagnostics.push(diagnostic);
             }
         }
+        FileDiagnostics {
+            path: path.to_string(),
+            diagnostics,
+        }
     }
 
-    Diagnostics {
-        files: result_map
-            .into_iter()
-            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
-            .collect(),
-        messages: vec![],
+    #[must_use]
+    pub fn finish(mut self) -> Diagnostics {
+        for (test_item, test_event) in std::mem::take(&mut self.unlocated) {
+            self.add_failure(test_item, &test_event, None);
+        }
+        Diagnostics {
+            files: self
+                .result_map
+                .into_iter()
+                .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+                .collect(),
+            messages: vec![],
+        }
     }
 }
 
@@ -307,6 +948,19 @@ mod tests {
 
     use super::*;
 
+    fn parse_libtest_json(
+        json_output: &str,
+        workspace_root: PathBuf,
+        file_paths: &[String],
+        test_items: &[TestItem],
+    ) -> Diagnostics {
+        let mut parser = LibtestParser::new(workspace_root, file_paths, test_items);
+        for line in json_output.lines() {
+            parser.parse_line(line);
+        }
+        parser.finish()
+    }
+
     #[test]
     fn test_parse_libtest_json() {
         let fixture = r#"{"type":"suite","event":"started","test_count":1}
@@ -356,4 +1010,584 @@ mod tests {
             Some("cargo-test".to_string())
         );
     }
+
+    #[test]
+    fn test_macro_expanded_test_cases() {
+        let root = std::env::current_dir().unwrap().join("demo/test-case");
+        let lib_rs = root.join("src/lib.rs").to_string_lossy().to_string();
+        let test_items = super::super::discover_tests(&lib_rs).unwrap();
+        let ids: Vec<&str> = test_items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["tests::adds", "tests::adds_zero"]);
+
+        let contents = std::fs::read_to_string(root.join("test-results.json")).unwrap();
+        let file_paths = [lib_rs.clone()];
+        let mut parser = LibtestParser::new(root, &file_paths, &test_items);
+        let outcomes: Vec<(String, TestStatus)> = contents
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .map(|outcome| (outcome.id, outcome.status))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("tests::adds::one_plus_one".to_string(), TestStatus::Passed),
+                ("tests::adds::two_plus_two".to_string(), TestStatus::Failed),
+                ("tests::adds_zero".to_string(), TestStatus::Passed),
+            ]
+        );
+
+        let diagnostics = parser.finish();
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(10, 4));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("[adds::two_plus_two] assertion `left == right` failed")
+        );
+        let related = diagnostics[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].message, "test `tests::adds` defined here");
+    }
+
+    #[test]
+    fn test_panic_in_helper_links_test_and_panic_site() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        std::fs::write(root.path().join("src/helpers.rs"), "").unwrap();
+        let librs = root.path().join("src/lib.rs").to_string_lossy().to_string();
+        let fixture = r#"{"type":"test","name":"tests::adds","event":"failed","stdout":"thread 'tests::adds' panicked at src/helpers.rs:3:5:\nassertion failed\n"}"#;
+        let test_item = TestItem {
+            id: "tests::adds".to_string(),
+            name: "tests::adds".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(10, 4), Position::new(10, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(12, 0), Position::new(12, 5)),
+        };
+
+        let diagnostics = parse_libtest_json(
+            fixture,
+            root.path().to_path_buf(),
+            std::slice::from_ref(&librs),
+            std::slice::from_ref(&test_item),
+        );
+
+        assert_eq!(diagnostics.files[0].path, librs);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range, test_item.start_position);
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[0].message, "test `tests::adds` defined here");
+        assert!(related[1].location.uri.path().ends_with("src/helpers.rs"));
+        assert_eq!(related[1].location.range.start, Position::new(2, 4));
+    }
+
+    #[test]
+    fn test_panic_in_custom_target_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let out_dir = target_dir.path().join("debug/build/demo-1a2b/out");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let generated = out_dir.join("generated.rs");
+        std::fs::write(&generated, "").unwrap();
+        let librs = root.path().join("src/lib.rs").to_string_lossy().to_string();
+        let fixture = serde_json::json!({
+            "type": "test",
+            "name": "tests::generated",
+            "event": "failed",
+            "stdout": format!(
+                "thread 'tests::generated' panicked at {}:3:5:\nassertion failed\n",
+                generated.display()
+            ),
+        })
+        .to_string();
+        let test_item = TestItem {
+            id: "tests::generated".to_string(),
+            name: "tests::generated".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(4, 4), Position::new(4, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(6, 0), Position::new(6, 5)),
+        };
+
+        let diagnostics = parse_libtest_json(
+            &fixture,
+            root.path().to_path_buf(),
+            std::slice::from_ref(&librs),
+            std::slice::from_ref(&test_item),
+        );
+
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range, test_item.start_position);
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(
+            related[1].location.uri,
+            Url::from_file_path(&generated).unwrap()
+        );
+        assert_eq!(related[1].location.range.start, Position::new(2, 4));
+    }
+
+    #[test]
+    fn test_insta_snapshot_failure() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("src/snapshots")).unwrap();
+        let snap = root.path().join("src/snapshots/demo__tests__adds.snap");
+        std::fs::write(&snap, "---\nsource: src/lib.rs\n---\n4\n").unwrap();
+        let librs = root.path().join("src/lib.rs").to_string_lossy().to_string();
+        let test_item = TestItem {
+            id: "tests::adds".to_string(),
+            name: "tests::adds".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(9, 4), Position::new(9, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(12, 0), Position::new(12, 5)),
+        };
+
+        let diagnostics = parse_libtest_json(
+            &std::fs::read_to_string("tests/insta-snapshot.json").unwrap(),
+            root.path().to_path_buf(),
+            std::slice::from_ref(&librs),
+            std::slice::from_ref(&test_item),
+        );
+
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(11, 0));
+        assert_eq!(
+            diagnostic.message,
+            "[adds] snapshot 'adds' doesn't match, review the new snapshot with `cargo insta \
+             review`"
+        );
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("insta-snapshot-failed".to_string()))
+        );
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[1].message, "snapshot file");
+        assert_eq!(related[1].location.uri, Url::from_file_path(&snap).unwrap());
+    }
+
+    #[test]
+    fn test_unresolvable_panic_path_stays_at_test() {
+        let root = std::env::current_dir().unwrap().join("demo/rust");
+        let librs = root.join("src/lib.rs").to_string_lossy().to_string();
+        let test_item = TestItem {
+            id: "tests::fail".to_string(),
+            name: "tests::fail".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(14, 4), Position::new(14, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(16, 0), Position::new(16, 5)),
+        };
+        let contents = r#"{"type":"test","name":"tests::fail","event":"failed","stdout":"thread 'tests::fail' panicked at src/generated/missing.rs:3:5:\nboom\n"}"#;
+
+        let diagnostics = parse_libtest_json(
+            contents,
+            root,
+            std::slice::from_ref(&librs),
+            std::slice::from_ref(&test_item),
+        );
+
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostics.files[0].path, librs);
+        assert_eq!(diagnostic.range, test_item.start_position);
+        assert_eq!(
+            diagnostic.message,
+            "[fail] boom\n(panicked at src/generated/missing.rs:3:5, which isn't a file that can \
+             be opened)"
+        );
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(
+            related[0].location.uri,
+            Url::from_file_path(&librs).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_proptest_failure() {
+        let root = tempfile::tempdir().unwrap();
+        let librs = root.path().join("src/lib.rs").to_string_lossy().to_string();
+        let test_item = TestItem {
+            id: "tests::adds_commute".to_string(),
+            name: "tests::adds_commute".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(20, 8), Position::new(20, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(23, 0), Position::new(23, 9)),
+        };
+
+        let diagnostics = parse_libtest_json(
+            &std::fs::read_to_string("tests/proptest-failure.json").unwrap(),
+            root.path().to_path_buf(),
+            std::slice::from_ref(&librs),
+            std::slice::from_ref(&test_item),
+        );
+
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(22, 12));
+        assert_eq!(
+            diagnostic.message,
+            "[adds_commute] assertion failed: `(left == right)`\n  left: `3`,\n right: \
+             `4`\nminimal failing input: a = 1, b = 2"
+        );
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("proptest-failed".to_string()))
+        );
+        let related = diagnostic.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[1].message, "failing input persisted here");
+        assert_eq!(
+            related[1].location.uri.as_str(),
+            "file:///home/user/demo/proptest-regressions/lib.txt"
+        );
+    }
+
+    #[test]
+    fn test_should_panic_failures() {
+        let root = tempfile::tempdir().unwrap();
+        let librs = root.path().join("src/lib.rs");
+        std::fs::create_dir_all(librs.parent().unwrap()).unwrap();
+        std::fs::write(&librs, "").unwrap();
+        let librs = librs.to_string_lossy().to_string();
+        let test_item = |name: &str, line: u32| TestItem {
+            id: format!("tests::{name}"),
+            name: format!("tests::{name}"),
+            path: librs.clone(),
+            start_position: Range::new(
+                Position::new(line, 4),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(line + 3, 0), Position::new(line + 3, 5)),
+        };
+
+        let diagnostics = parse_libtest_json(
+            &std::fs::read_to_string("tests/should-panic.json").unwrap(),
+            root.path().to_path_buf(),
+            std::slice::from_ref(&librs),
+            &[
+                test_item("divides_by_zero", 6),
+                test_item("rejects_empty", 12),
+            ],
+        );
+        let mut diagnostics = diagnostics.files[0].diagnostics.clone();
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+        assert_eq!(diagnostics.len(), 2);
+
+        // A panic with another message is placed where it panicked
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 8));
+        assert_eq!(
+            diagnostics[0].message,
+            "[divides_by_zero] expected panic containing 'division by zero', got 'attempt to \
+             divide by zero'"
+        );
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("should-panic-failed".to_string()))
+        );
+
+        assert_eq!(diagnostics[1].range.start, Position::new(12, 4));
+        assert_eq!(
+            diagnostics[1].message,
+            "[rejects_empty] expected a panic, but the test didn't panic"
+        );
+        assert_eq!(diagnostics[1].code, diagnostics[0].code);
+
+        // Older releases quoted the strings in backticks too
+        assert_eq!(
+            should_panic_failure(
+                "panic did not contain expected string\n      panic message: `\"it's \
+                 \\\"odd\\\"\"`,\n expected substring: `\"even\"`"
+            )
+            .unwrap(),
+            "expected panic containing 'even', got 'it's \"odd\"'"
+        );
+    }
+
+    #[test]
+    fn test_panic_location_from_stderr() {
+        let root = std::env::current_dir().unwrap().join("demo/rust");
+        let librs = root.join("src/lib.rs").to_string_lossy().to_string();
+        let test_item = TestItem {
+            id: "tests::fail".to_string(),
+            name: "tests::fail".to_string(),
+            path: librs.clone(),
+            start_position: Range::new(Position::new(14, 4), Position::new(14, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(16, 0), Position::new(16, 5)),
+        };
+        let file_paths = [librs.clone()];
+        let test_items = [test_item];
+        let mut parser = LibtestParser::new(root, &file_paths, &test_items);
+        for line in std::fs::read_to_string("tests/cargo-test-stderr.json")
+            .unwrap()
+            .lines()
+        {
+            parser.parse_line(line);
+        }
+        // Until stderr is read, the failure is placed at the test
+        assert_eq!(
+            parser.file_diagnostics(&librs).diagnostics[0].range,
+            test_items[0].start_position
+        );
+
+        parser.parse_stderr(&std::fs::read_to_string("tests/cargo-test-stderr.txt").unwrap());
+        let diagnostics = parser.finish();
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(15, 8));
+        assert!(diagnostics[0].message.contains("assertion failed: false"));
+        assert!(!diagnostics[0].message.contains("RUST_BACKTRACE"));
+    }
+
+    #[test]
+    fn test_libtest_parser_reports_finished_tests() {
+        let test_items = vec![
+            TestItem {
+                id: "tests::passes".to_string(),
+                name: "passes".to_string(),
+                path: "/project/src/lib.rs".to_string(),
+                start_position: Range::default(),
+                end_position: Range::default(),
+            },
+            TestItem {
+                id: "tests::fails".to_string(),
+                name: "fails".to_string(),
+                path: "/project/src/lib.rs".to_string(),
+                start_position: Range::default(),
+                end_position: Range::default(),
+            },
+        ];
+        let file_paths = vec!["/project/src/lib.rs".to_string()];
+        let mut parser = LibtestParser::new(PathBuf::from("/project"), &file_paths, &test_items);
+
+        assert_eq!(
+            parser.parse_line(r#"{"type":"test","event":"started","name":"tests::passes"}"#),
+            None
+        );
+        assert_eq!(
+            parser.parse_line(
+                r#"{"type":"test","event":"ok","name":"tests::passes","exec_time":0.5}"#
+            ),
+            Some(TestOutcome {
+                id: "tests::passes".to_string(),
+                status: TestStatus::Passed,
+                duration: Some(Duration::from_millis(500)),
+                output: None,
+            })
+        );
+        assert!(
+            parser
+                .file_diagnostics("/project/src/lib.rs")
+                .diagnostics
+                .is_empty()
+        );
+        let failed = parser
+            .parse_line(
+                r#"{"type":"test","event":"failed","name":"tests::fails","message":"boom","stdout":"boom\n"}"#,
+            )
+            .unwrap();
+        assert_eq!(failed.status, TestStatus::Failed);
+        assert_eq!(failed.output.as_deref(), Some("boom\n"));
+        assert_eq!(
+            parser
+                .file_diagnostics("/project/src/lib.rs")
+                .diagnostics
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_nextest_output_with_colors() {
+        let contents = std::fs::read_to_string("tests/nextest-color.txt").unwrap();
+        let file_paths = vec!["/home/demo/rust/src/lib.rs".to_string()];
+        let test_items = vec![TestItem {
+            id: "tests::fail".to_string(),
+            name: "fail".to_string(),
+            path: "/home/demo/rust/src/lib.rs".to_string(),
+            start_position: Range::new(Position::new(14, 4), Position::new(14, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(16, 0), Position::new(16, 5)),
+        }];
+
+        let diagnostics = parse_nextest_output(
+            &contents,
+            PathBuf::from("/home/demo/rust"),
+            &file_paths,
+            &test_items,
+        );
+
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position::new(15, 8));
+        assert!(
+            diagnostics[0]
+                .message
+                .starts_with("assertion failed: false")
+        );
+        assert!(!diagnostics[1].message.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_find_test_item_matches_whole_segments() {
+        let test_item = |id: &str| TestItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: "/home/demo/rust/src/lib.rs".to_string(),
+            start_position: Range::default(),
+            end_position: Range::default(),
+        };
+        let test_items = vec![
+            test_item("foo::bar"),
+            test_item("baz::foo::bar"),
+            test_item("tests::fail"),
+        ];
+
+        let find = |name| find_test_item(&test_items, name).map(|item| item.id.as_str());
+        assert_eq!(find("baz::foo::bar"), Some("baz::foo::bar"));
+        assert_eq!(find("foo::bar"), Some("foo::bar"));
+        assert_eq!(find("fail"), Some("tests::fail"));
+        assert_eq!(find("ail"), None);
+        assert_eq!(find("other::bar"), None);
+
+        let contents = r"
+    Starting 2 tests across 1 binary
+        PASS [   0.002s] demo foo::bar
+        FAIL [   0.003s] demo baz::foo::bar
+------------
+     Summary [   0.004s] 2 tests run: 1 passed, 1 failed, 0 skipped
+        FAIL [   0.003s] demo baz::foo::bar
+";
+        let test_items = vec![
+            TestItem {
+                start_position: Range::new(Position::new(3, 4), Position::new(3, 20)),
+                ..test_item("foo::bar")
+            },
+            TestItem {
+                start_position: Range::new(Position::new(9, 4), Position::new(9, 20)),
+                ..test_item("baz::foo::bar")
+            },
+        ];
+        let diagnostics = parse_nextest_output(
+            contents,
+            PathBuf::from("/home/demo/rust"),
+            &["/home/demo/rust/src/lib.rs".to_string()],
+            &test_items,
+        );
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 9);
+    }
+
+    fn nextest_test_items() -> Vec<TestItem> {
+        ["slow", "leaky"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| TestItem {
+                id: format!("tests::{name}"),
+                name: (*name).to_string(),
+                path: "/home/demo/rust/src/lib.rs".to_string(),
+                start_position: Range::new(
+                    Position::new(10 * i as u32, 4),
+                    Position::new(10 * i as u32, MAX_CHAR_LENGTH),
+                ),
+                end_position: Range::new(
+                    Position::new(10 * i as u32 + 3, 0),
+                    Position::new(10 * i as u32 + 3, 5),
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_nextest_timeout() {
+        let contents = std::fs::read_to_string("tests/nextest-timeout.txt").unwrap();
+        let test_items = nextest_test_items();
+        let diagnostics = parse_nextest_output(
+            &contents,
+            PathBuf::from("/home/demo/rust"),
+            &["/home/demo/rust/src/lib.rs".to_string()],
+            &test_items,
+        );
+
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, test_items[0].start_position);
+        assert_eq!(diagnostics[0].message, "`slow` timed out after 1.002s");
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("nextest-timeout".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_nextest_leak() {
+        let contents = std::fs::read_to_string("tests/nextest-leak.txt").unwrap();
+        let test_items = nextest_test_items();
+        let diagnostics = parse_nextest_output(
+            &contents,
+            PathBuf::from("/home/demo/rust"),
+            &["/home/demo/rust/src/lib.rs".to_string()],
+            &test_items,
+        );
+
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostics = &diagnostics.files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, test_items[1].start_position);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("nextest-leak".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_started_suite() {
+        let mut parser = LibtestParser::new(PathBuf::from("/project"), &[], &[]);
+        parser.parse_line(r#"{"type":"suite","event":"started","test_count":3}"#);
+        assert_eq!(parser.take_started_suite(), Some(3));
+        assert_eq!(parser.take_started_suite(), None);
+        parser.parse_line(
+            r#"{"type":"suite","event":"ok","passed":3,"failed":0,"ignored":0,"measured":0,"filtered_out":0}"#,
+        );
+        assert_eq!(parser.take_started_suite(), None);
+    }
+
+    #[test]
+    fn test_test_executable() {
+        let messages = std::fs::read_to_string("tests/cargo-no-run.json").unwrap();
+        assert_eq!(
+            test_executable(&messages, "/home/demo/rust/src/parse.rs").as_deref(),
+            Some("/home/demo/rust/target/debug/deps/demo-0123456789abcdef")
+        );
+        assert_eq!(
+            test_executable(&messages, "/home/demo/rust/tests/integration.rs").as_deref(),
+            Some("/home/demo/rust/target/debug/deps/integration-fedcba9876543210")
+        );
+        assert_eq!(
+            test_executable(&messages, "/home/demo/other/src/lib.rs"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compiler_warnings() {
+        let stderr = std::fs::read_to_string("tests/cargo-warnings.txt").unwrap();
+        let file_path = "/home/demo/rust/src/lib.rs".to_string();
+        let files = compiler_warnings(
+            &stderr,
+            Path::new("/home/demo/rust"),
+            std::slice::from_ref(&file_path),
+        );
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, file_path);
+        let diagnostics = &files[0].diagnostics;
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unused variable: `x`");
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 8));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String(STDERR_WARNING_CODE.to_string()))
+        );
+    }
 }
@@ -0,0 +1,85 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Directory sbt writes the JUnit reports of a project's tests to.
+#[must_use]
+pub fn reports_dir(project: &Path) -> PathBuf {
+    project.join("target/test-reports")
+}
+
+/// `arg` quoted for the sbt command line when it has spaces or quotes.
+fn sbt_quote(arg: &str) -> String {
+    if arg.contains([' ', '"', '\\']) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Command running `sbt testOnly` for the given suites, or `sbt test` for
+/// every suite when there are none. A filter is passed to `ScalaTest` as `-z`,
+/// running the tests whose name contains it.
+pub fn sbt_test_command(
+    context: &RunContext,
+    suites: &[&str],
+    filter: Option<&str>,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut task = if suites.is_empty() && filter.is_none() {
+        "test".to_string()
+    } else {
+        ["testOnly"]
+            .into_iter()
+            .chain(suites.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if let Some(filter) = filter {
+        task.push_str(" -- -z ");
+        task.push_str(&sbt_quote(filter));
+    }
+    let mut sbt = test_command(context, command, &["sbt", "-batch"])?;
+    sbt.args(extra_args).arg(task);
+    Ok(sbt)
+}
+
+/// Run an sbt test command.
+pub fn run_sbt_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("sbt_test.log", &output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sbt_test_command() {
+        let sh = ["sh".to_string()];
+        let args = |suites: &[&str], filter| {
+            let command =
+                sbt_test_command(&RunContext::new("/"), suites, filter, &[], Some(&sh)).unwrap();
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(args(&[], None), vec!["test"]);
+        assert_eq!(
+            args(&["com.example.MathSpec"], Some("fail on purpose")),
+            vec![r#"testOnly com.example.MathSpec -- -z "fail on purpose""#]
+        );
+        assert_eq!(args(&[], Some("adds")), vec!["testOnly -- -z adds"]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Scala tests run by sbt with `ScalaTest`.
+//!
+//! Tests are the `test("name")` calls of `AnyFunSuite`s and the `"name" in`
+//! clauses of the other `ScalaTest` styles, named after the clauses around
+//! them like `ScalaTest` does: `"Math" should { "add" in ... }` and `"Math"
+//! should "add" in ...` are both `Math should add`, and `"A" - { "b" in ...
+//! }` is `A b`. No Scala grammar is bundled, so they are found by scanning
+//! the source, balancing braces to find where classes and clauses end.
+//! Results are read from the JUnit reports sbt writes, like Gradle's.
+
+pub mod call;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    junit,
+    kotlin::parse,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+};
+
+/// Byte offset just past the block opening right after `start`, past a
+/// closing parenthesis, or `None` when no block follows.
+fn body_end(source: &str, start: usize) -> Option<usize> {
+    let rest = source[start..].trim_start_matches(|c: char| c == ')' || c.is_whitespace());
+    rest.starts_with('{').then(|| block_end(source, start))
+}
+
+/// Tests of a Scala file, with ids like `com.example.MathSpec.add should
+/// fail on purpose`: the qualified suite class and the test name, as in the
+/// JUnit reports of sbt.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let package_re = Regex::new(r"(?m)^\s*package\s+([\w.]+)").unwrap();
+    let class_re = Regex::new(r"\b(?:class|object)\s+(\w+)[^{]*\{").unwrap();
+    let clause_re = Regex::new(
+        r#"(?x)
+        \btest\s*\(\s*"(?P<fun>[^"\n]*)"
+        | "(?P<subject>[^"\n]*)"\s+(?P<flat_verb>should|must|can)\s+"(?P<flat>[^"\n]*)"\s+in\b
+        | \b(?:it|they)\s+(?P<it_verb>should|must|can)\s+"(?P<it>[^"\n]*)"\s+in\b
+        | "(?P<word>[^"\n]*)"\s+(?P<word_verb>should|must|can|when|which)\s*\{
+        | "(?P<free>[^"\n]*)"\s+-\s*\{
+        | "(?P<in>[^"\n]*)"\s+in\b
+        "#,
+    )
+    .unwrap();
+
+    let package = package_re
+        .captures_iter(source)
+        .map(|m| m[1].to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+    // Name, start and end of each class body
+    let suites: Vec<(&str, usize, usize)> = class_re
+        .captures_iter(source)
+        .map(|m| {
+            let found = m.get(0).unwrap();
+            let end = block_end(source, found.end() - 1);
+            (m.get(1).unwrap().as_str(), found.end(), end)
+        })
+        .collect();
+
+    // Prefix, start and end of each `should` or `-` clause
+    let mut clauses: Vec<(String, usize, usize)> = vec![];
+    let mut subject: Option<&str> = None;
+    let mut items = vec![];
+    for m in clause_re.captures_iter(source) {
+        let found = m.get(0).unwrap();
+        let enclosing = |start: usize| -> Vec<&str> {
+            clauses
+                .iter()
+                .filter(|(_, clause_start, clause_end)| {
+                    *clause_start < start && start < *clause_end
+                })
+                .map(|(prefix, _, _)| prefix.as_str())
+                .collect()
+        };
+        let name = if let Some(word) = m.name("word") {
+            let prefix = format!("{} {}", word.as_str(), &m["word_verb"]);
+            clauses.push((prefix, found.start(), block_end(source, found.end() - 1)));
+            continue;
+        } else if let Some(free) = m.name("free") {
+            let prefix = free.as_str().to_string();
+            clauses.push((prefix, found.start(), block_end(source, found.end() - 1)));
+            continue;
+        } else if let Some(fun) = m.name("fun") {
+            fun.as_str().to_string()
+        } else if let Some(flat) = m.name("flat") {
+            let flat_subject = m.name("subject").unwrap().as_str();
+            subject = Some(flat_subject);
+            format!("{flat_subject} {} {}", &m["flat_verb"], flat.as_str())
+        } else if let Some(it) = m.name("it") {
+            let Some(subject) = subject else {
+                continue;
+            };
+            format!("{subject} {} {}", &m["it_verb"], it.as_str())
+        } else {
+            let mut names = enclosing(found.start());
+            names.push(&m["in"]);
+            names.join(" ")
+        };
+
+        let Some((class, _, _)) = suites
+            .iter()
+            .filter(|(_, start, end)| *start <= found.start() && found.start() < *end)
+            .max_by_key(|(_, start, _)| *start)
+        else {
+            continue;
+        };
+        let qualified_class = if package.is_empty() {
+            (*class).to_string()
+        } else {
+            format!("{package}.{class}")
+        };
+        let (line, column) = position_at(source, found.start());
+        let end = body_end(source, found.end())
+            .map_or((line, MAX_CHAR_LENGTH), |end| position_at(source, end));
+        items.push(TestItem {
+            id: format!("{qualified_class}.{name}"),
+            name: format!("{class}.{name}"),
+            path: file_path.to_string(),
+            start_position: Range::new(
+                Position::new(line, column),
+                Position::new(line, MAX_CHAR_LENGTH),
+            ),
+            end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+        });
+    }
+    items
+}
+
+/// Qualified suite class of a discovered test, like `com.example.MathSpec`.
+fn suite(test_item: &TestItem) -> Option<&str> {
+    let (_, test_name) = test_item.name.split_once('.')?;
+    test_item.id.strip_suffix(test_name)?.strip_suffix('.')
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+/// Directories sbt writes the JUnit reports of the projects of `file_paths`
+/// to: the existing `target/test-reports` directories between each file and
+/// the workspace.
+fn reports_dirs(workspace: &Path, file_paths: &[String]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = file_paths
+        .iter()
+        .flat_map(|path| {
+            Path::new(path)
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(workspace))
+                .map(call::reports_dir)
+                .filter(|dir| dir.is_dir())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+fn diagnostics(reports: &[PathBuf], file_paths: &[String]) -> Result<Diagnostics, LSError> {
+    let failures = reports
+        .iter()
+        .map(|dir| junit::parse_junit_reports(dir))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    Ok(parse::to_diagnostics(
+        "sbt-test",
+        &failures,
+        file_paths,
+        &discover_all(file_paths),
+    ))
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct SbtTestRunner;
+
+impl Runner for SbtTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let test_items = discover_all(file_paths);
+        let mut suites: Vec<&str> = test_items.iter().filter_map(suite).collect();
+        suites.sort_unstable();
+        suites.dedup();
+        call::sbt_test_command(context, &suites, filter, extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        call::run_sbt_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        diagnostics(
+            &reports_dirs(Path::new(context.workspace), file_paths),
+            file_paths,
+        )
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        diagnostics(&[results_path.to_path_buf()], file_paths)
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["build.sbt"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/sbt/src/test/scala/com/example/MathSpec.scala").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "com.example.MathSpec.add should add two numbers",
+                "com.example.MathSpec.add should fail on purpose",
+                "com.example.MathFlatSpec.add should be commutative",
+                "com.example.MathFlatSpec.add should keep zero neutral",
+                "com.example.MathSuite.adds",
+            ]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(11, 4));
+        assert_eq!(items[1].end_position.end, Position::new(13, 5));
+        assert_eq!(suite(&items[4]), Some("com.example.MathSuite"));
+
+        let source = "class S extends AnyFreeSpec {\n  \"A\" - {\n    \"b\" - {\n      \"c\" in \
+                      {}\n    }\n  }\n}\n";
+        let items = discover_in_source("S.scala", source);
+        assert_eq!(items[0].id, "S.A b c");
+    }
+
+    #[test]
+    fn test_parse_sbt_reports() {
+        let root = std::env::current_dir().unwrap().join("demo/sbt");
+        let file_path = root
+            .join("src/test/scala/com/example/MathSpec.scala")
+            .to_string_lossy()
+            .to_string();
+        let diagnostics = SbtTestRunner
+            .parse_results(
+                &root.join("test-reports"),
+                std::slice::from_ref(&file_path),
+                &root.to_string_lossy(),
+            )
+            .unwrap();
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start.line, 12);
+        assert_eq!(diagnostic.message, "3 did not equal 4");
+        assert_eq!(diagnostic.source.as_deref(), Some("sbt-test"));
+        assert_eq!(
+            diagnostic.related_information.as_ref().unwrap()[0].message,
+            "test `com.example.MathSpec.add should fail on purpose` defined here"
+        );
+    }
+}
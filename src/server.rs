@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
     env::current_dir,
     path::{Path, PathBuf},
 };
@@ -7,31 +8,54 @@ use std::{
 use crossbeam_channel::Sender;
 use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities, InitializeParams, MessageType,
-    NumberOrString, ProgressParams, ProgressParamsValue, PublishDiagnosticsParams,
-    ServerCapabilities, ShowMessageParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
-    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams, WorkDoneProgressEnd,
-    WorkDoneProgressOptions, WorkspaceFolder,
+    Diagnostic, DiagnosticOptions, DiagnosticServerCapabilities, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, FileSystemWatcher, GlobPattern, Hover,
+    HoverParams, HoverProviderCapability, InitializeParams, MessageType, NumberOrString,
+    ProgressParams, ProgressParamsValue, PublishDiagnosticsParams, Range, Registration,
+    RegistrationParams, ServerCapabilities, ShowMessageParams, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressOptions,
+    WorkDoneProgressReport, WorkspaceFolder,
 };
 use serde::de::Error as _;
 use serde_json::Value;
 
 use crate::{
-    AdapterConfig, AdapterId, Config, DiscoveredTests, FileDiagnostics, WorkspaceAnalysis,
-    Workspaces, error::LSError, runner, workspace,
+    AdapterConfig, AdapterHealth, AdapterId, Config, ConfigValidation, DebugTestParams,
+    Diagnostics, DiscoveredTestCount, DiscoveredTests, ExplainRunParams, ExplainedRun,
+    FileDiagnostics, FilePath, FileTests, LineLengths, ParseResultsParams, RunTaggedTestsParams,
+    TaggedTestsRun, TestItem, TestOutput, TestOutputParams, TestRunError, TestTreeItem,
+    TestTreeKind, WorkspaceAnalysis, WorkspacePath, Workspaces, config,
+    coverage::{self, CoverageParams},
+    discovery_cache::DiscoveryCache,
+    document::Document,
+    error::LSError,
+    hover::{self, LastResult},
+    runner::{self, DebugLaunch, RunContext, RunEvent, TestOutcome, TestStatus},
+    workspace,
 };
 
 const TOML_FILE_NAME: &str = ".assert-lsp.toml";
 
 pub struct TestingLS {
     pub workspace_folders: Option<Vec<WorkspaceFolder>>,
+    /// Options the client sent on initialization, the configuration when the
+    /// project has no config file
+    pub initialization_options: Option<Value>,
     pub config: Config,
     pub workspaces_cache: Vec<WorkspaceAnalysis>,
+    documents: HashMap<FilePath, Document>,
+    last_results: HashMap<String, LastResult>,
+    /// Tests that failed in the last run, keyed by adapter id and workspace
+    last_failures: HashMap<(String, WorkspacePath), Vec<TestItem>>,
+    /// Tests discovered in unmodified files, kept across restarts
+    discovery_cache: RefCell<DiscoveryCache>,
     sender: Sender<Message>,
 }
 
 fn uri_to_path(uri: &str) -> String {
-    uri.replace("file://", "")
+    workspace::normalize_path(&uri.replace("file://", ""))
 }
 
 fn extract_textdocument_uri(params: &Value) -> Result<String, serde_json::Error> {
@@ -48,6 +72,68 @@ fn extract_uri(params: &Value) -> Result<String, serde_json::Error> {
     Ok(uri_to_path(uri))
 }
 
+/// Clamp the ranges of the tests of a file that reach the end of a line to
+/// the length of the line.
+fn clamp_test_ranges(tests: &mut [TestItem], lines: &LineLengths) {
+    for test in tests {
+        test.clamp_line_ends(lines);
+    }
+}
+
+/// Clamp the ranges of the diagnostics of a file that reach the end of a line
+/// to the length of the line.
+fn clamp_diagnostic_ranges(diagnostics: &mut [Diagnostic], lines: &LineLengths) {
+    for diagnostic in diagnostics {
+        lines.clamp(&mut diagnostic.range);
+    }
+}
+
+/// Cut a diagnostic message off after `max_len` characters, saying where to
+/// find the rest.
+fn truncate_message(message: &mut String, max_len: usize) {
+    if let Some((end, _)) = message.char_indices().nth(max_len) {
+        message.truncate(end);
+        message.push_str("…\n(truncated, the full message is in `$/testOutput`)");
+    }
+}
+
+/// Whether a run of the tests whose name contains `filter` reached any test:
+/// one of the discovered tests, or one the runner reported.
+fn filter_reached(
+    filter: &str,
+    results: &[(TestItem, LastResult)],
+    outcomes: &HashMap<String, TestOutcome>,
+) -> bool {
+    !results.is_empty() || outcomes.keys().any(|id| id.contains(filter))
+}
+
+/// Tests of the files a run selects.
+#[derive(Debug, Clone, Copy)]
+enum Selection<'a> {
+    /// Every test, or only those whose name contains the filter
+    Filter(Option<&'a str>),
+    /// Exactly the tests with these ids, for runners that select tests by id
+    Ids(&'a [String]),
+}
+
+impl<'a> Selection<'a> {
+    fn filter(self) -> Option<&'a str> {
+        match self {
+            Selection::Filter(filter) => filter,
+            Selection::Ids(_) => None,
+        }
+    }
+
+    fn includes(self, test_item: &TestItem) -> bool {
+        match self {
+            Selection::Filter(filter) => {
+                filter.is_none_or(|filter| test_item.name.contains(filter))
+            }
+            Selection::Ids(test_ids) => test_ids.contains(&test_item.id),
+        }
+    }
+}
+
 /// Runs the LSP server main loop.
 ///
 /// This function creates a stdio connection and processes incoming LSP messages
@@ -58,26 +144,36 @@ fn extract_uri(params: &Value) -> Result<String, serde_json::Error> {
 /// Returns an error if:
 /// - The connection fails to initialize
 /// - Message handling encounters an unrecoverable error
+#[allow(clippy::too_many_lines)]
 pub fn run() -> Result<(), LSError> {
     let (connection, io_threads) = Connection::stdio();
     let mut server = TestingLS::new(connection.sender.clone());
-    let mut is_workspace_checked = false;
 
     // Handle initialization using lsp-server's built-in method
     let (id, params) = connection.initialize_start()?;
     let init_params: InitializeParams = serde_json::from_value(params)?;
     server.workspace_folders = init_params.workspace_folders;
-    server.config = server.load_config(init_params.initialization_options.as_ref())?;
+    server.initialization_options = init_params.initialization_options;
+    server.config = server.load_config(server.initialization_options.as_ref())?;
+    server.load_discovery_cache()?;
+    let watches_files = init_params
+        .capabilities
+        .workspace
+        .and_then(|workspace| workspace.did_change_watched_files)
+        .and_then(|watched_files| watched_files.dynamic_registration)
+        .unwrap_or(false);
 
     let initialize_data = serde_json::json!({
         "capabilities": server.build_capabilities(),
     });
     connection.initialize_finish(id, initialize_data)?;
     log::info!("Server initialized");
+    if watches_files {
+        server.register_config_watcher()?;
+    }
 
-    // Run initial workspace diagnostics immediately after initialization
-    log::info!("Running initial workspace diagnostics");
-    server.diagnose_workspace()?;
+    // `initialize_finish` already received the `initialized` notification
+    let mut is_workspace_checked = server.run_on_startup()?;
 
     for msg in &connection.receiver {
         log::debug!("Received message: {:?}", msg);
@@ -101,6 +197,133 @@ pub fn run() -> Result<(), LSError> {
                             .send(Message::Response(response))
                             .map_err(|e| LSError::ChannelSend(e.to_string()))?;
                     }
+                    "$/discoverWorkspaceTests" => {
+                        let result = server.discover_workspace()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/testing/listTests" => {
+                        let result = server.list_tests()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/refreshTests" => {
+                        let result = server.refresh_tests()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/runFailedTests" => {
+                        let result = server.run_failed_tests()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/runTaggedTests" => {
+                        let params: RunTaggedTestsParams = serde_json::from_value(req.params)?;
+                        let result = server.run_tagged_tests(&params)?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/runChangedTests" => {
+                        let result = server.run_changed_tests()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/parseResults" => {
+                        let params: ParseResultsParams = serde_json::from_value(req.params)?;
+                        let response = match server.parse_results(&params) {
+                            Ok(result) => Response::new_ok(req_id, result),
+                            Err(err) => Response::new_err(
+                                req_id,
+                                lsp_server::ErrorCode::InvalidParams as i32,
+                                err.to_string(),
+                            ),
+                        };
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/debugTest" => {
+                        let params: DebugTestParams = serde_json::from_value(req.params)?;
+                        let response = match server.debug_test(&params) {
+                            Ok(result) => Response::new_ok(req_id, result),
+                            Err(err) => Response::new_err(
+                                req_id,
+                                lsp_server::ErrorCode::InvalidParams as i32,
+                                err.to_string(),
+                            ),
+                        };
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/explainRun" => {
+                        let params: ExplainRunParams = serde_json::from_value(req.params)?;
+                        let result = server.explain_run(&params)?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/testOutput" => {
+                        let params: TestOutputParams = serde_json::from_value(req.params)?;
+                        let response = Response::new_ok(req_id, server.test_output(&params));
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/validateConfig" => {
+                        let result = server.validate_config()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "$/health" => {
+                        let result = server.health()?;
+                        let response = Response::new_ok(req_id, result);
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
+                    "textDocument/hover" => {
+                        let params: HoverParams = serde_json::from_value(req.params)?;
+                        let response = match server.hover(&params) {
+                            Ok(result) => Response::new_ok(req_id, result),
+                            Err(err) => Response::new_err(
+                                req_id,
+                                lsp_server::ErrorCode::RequestFailed as i32,
+                                err.to_string(),
+                            ),
+                        };
+                        connection
+                            .sender
+                            .send(Message::Response(response))
+                            .map_err(|e| LSError::ChannelSend(e.to_string()))?;
+                    }
                     _ => {
                         let response = Response::new_err(
                             req_id,
@@ -120,17 +343,31 @@ pub fn run() -> Result<(), LSError> {
                     break;
                 }
                 "$/cancelRequest" => {}
-                "initialized" | "workspace/diagnostic" | "$/runWorkspaceTest" => {
+                "workspace/diagnostic" | "$/runWorkspaceTest" => {
                     log::info!("Received notification: {}", not.method);
-                    is_workspace_checked = true;
-                    server.diagnose_workspace()?;
+                    let event = if not.method == "workspace/diagnostic" {
+                        "diagnostic"
+                    } else {
+                        "manual"
+                    };
+                    if event == "manual" || server.config.runs_on(event) {
+                        is_workspace_checked = true;
+                        server.diagnose_workspace()?;
+                    }
                 }
                 "textDocument/diagnostic" | "textDocument/didSave" => {
-                    let uri = extract_textdocument_uri(&not.params)?;
-                    server.check_file(&uri, false)?;
+                    let event = if not.method == "textDocument/didSave" {
+                        "save"
+                    } else {
+                        "diagnostic"
+                    };
+                    if server.config.runs_on(event) {
+                        let uri = extract_textdocument_uri(&not.params)?;
+                        server.check_file(&uri, false, None)?;
+                    }
                 }
                 "textDocument/didOpen" => {
-                    if !is_workspace_checked {
+                    if !is_workspace_checked && server.config.runs_on("open") {
                         is_workspace_checked = true;
                         server.diagnose_workspace()?;
                     }
@@ -138,10 +375,34 @@ pub fn run() -> Result<(), LSError> {
                     if server.refreshing_needed(&uri) {
                         server.refresh_workspaces_cache()?;
                     }
+                    let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
+                    server
+                        .documents
+                        .insert(uri, Document::new(params.text_document.text));
+                }
+                "textDocument/didChange" => {
+                    let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)?;
+                    let uri = uri_to_path(params.text_document.uri.as_str());
+                    if let Some(document) = server.documents.get_mut(&uri) {
+                        for change in &params.content_changes {
+                            document.apply_change(change);
+                        }
+                    }
+                }
+                "workspace/didChangeWatchedFiles" => {
+                    let params: DidChangeWatchedFilesParams = serde_json::from_value(not.params)?;
+                    server.did_change_watched_files(&params)?;
+                }
+                "textDocument/didClose" => {
+                    let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)?;
+                    server
+                        .documents
+                        .remove(&uri_to_path(params.text_document.uri.as_str()));
                 }
                 "$/runFileTest" => {
                     let uri = extract_uri(&not.params)?;
-                    server.check_file(&uri, false)?;
+                    let filter = not.params["filter"].as_str();
+                    server.check_file(&uri, false, filter)?;
                 }
                 _ => {
                     log::warn!("unhandled notification: {}", not.method);
@@ -164,8 +425,13 @@ impl TestingLS {
     pub fn new(sender: Sender<Message>) -> Self {
         Self {
             workspace_folders: None,
+            initialization_options: None,
             config: Config::default(),
             workspaces_cache: Vec::new(),
+            documents: HashMap::new(),
+            last_results: HashMap::new(),
+            last_failures: HashMap::new(),
+            discovery_cache: RefCell::default(),
             sender,
         }
     }
@@ -197,6 +463,22 @@ impl TestingLS {
         Ok(())
     }
 
+    /// Send a `$/progress` notification, unless progress is turned off.
+    fn send_progress(
+        &self,
+        token: &NumberOrString,
+        progress: WorkDoneProgress,
+    ) -> Result<(), LSError> {
+        if !self.config.show_progress {
+            return Ok(());
+        }
+        let params = ProgressParams {
+            token: token.clone(),
+            value: ProgressParamsValue::WorkDone(progress),
+        };
+        self.send_notification("$/progress", params)
+    }
+
     fn project_dir(&self) -> Result<PathBuf, LSError> {
         // Prioritize workspace folders sent by the LSP client
         if let Some(first_folder) = self.workspace_folders.as_ref().and_then(|f| f.first()) {
@@ -210,8 +492,13 @@ impl TestingLS {
         self.config.adapter_command.clone()
     }
 
-    fn project_files(base_dir: &Path, extensions: &[&str]) -> Vec<String> {
-        workspace::walk_files(base_dir, extensions)
+    fn project_files(&self, base_dir: &Path, extensions: &[&str]) -> Vec<String> {
+        workspace::walk_files(
+            base_dir,
+            extensions,
+            self.config.max_walk_depth,
+            self.config.follow_symlinks,
+        )
     }
 
     fn build_capabilities(&self) -> ServerCapabilities {
@@ -222,7 +509,13 @@ impl TestingLS {
                 workspace_diagnostics: true,
                 work_done_progress_options: WorkDoneProgressOptions::default(),
             })),
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::NONE)),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            experimental: Some(serde_json::json!({
+                "testing": { "listTestsProvider": true },
+            })),
             ..ServerCapabilities::default()
         }
     }
@@ -245,6 +538,16 @@ impl TestingLS {
         let detected = workspace::detect_projects(&project_dir);
         if detected.is_empty() {
             log::info!("No project detected, using empty configuration");
+            let params = ShowMessageParams {
+                typ: MessageType::INFO,
+                message: format!(
+                    "No test project detected in {}; add an {TOML_FILE_NAME} or ensure one of \
+                     these is present: {}",
+                    project_dir.display(),
+                    workspace::PROJECT_MARKERS.join(", ")
+                ),
+            };
+            let _ = self.send_notification("window/showMessage", params);
             return Ok(Config::default());
         }
 
@@ -261,22 +564,30 @@ impl TestingLS {
         })
     }
 
+    /// Loads the tests discovered in the project before the last restart.
+    pub fn load_discovery_cache(&mut self) -> Result<(), LSError> {
+        let root = self.project_dir()?;
+        self.discovery_cache = RefCell::new(DiscoveryCache::load(
+            &self.config.cache_dir,
+            &root.to_string_lossy(),
+        ));
+        Ok(())
+    }
+
     pub fn refresh_workspaces_cache(&mut self) -> Result<(), LSError> {
         let adapter_commands = self.adapter_commands();
         let project_dir = self.project_dir()?;
         self.workspaces_cache = vec![];
+        let mut counts: Vec<DiscoveredTestCount> = vec![];
 
         // Validate adapter configurations and warn about issues
-        for (adapter_id, adapter) in &adapter_commands {
-            let warnings = adapter.validate(adapter_id);
-            for warning in warnings {
-                log::warn!("{}", warning);
-                let params = ShowMessageParams {
-                    typ: MessageType::WARNING,
-                    message: warning,
-                };
-                let _ = self.send_notification("window/showMessage", params);
-            }
+        for warning in self.validate_config()?.warnings {
+            log::warn!("{}", warning);
+            let params = ShowMessageParams {
+                typ: MessageType::WARNING,
+                message: warning,
+            };
+            let _ = self.send_notification("window/showMessage", params);
         }
 
         // Nested and multiple loops, but each count is small
@@ -287,7 +598,7 @@ impl TestingLS {
 
             // Get extensions for this test kind and walk files
             let extensions = workspace::extensions_for_test_kind(test_kind);
-            let file_paths = Self::project_files(&project_dir, &extensions);
+            let file_paths = self.project_files(&project_dir, &extensions);
             if file_paths.is_empty() {
                 continue;
             }
@@ -301,8 +612,12 @@ impl TestingLS {
                 }
             };
 
-            // Call detect_workspaces directly
-            let workspaces = test_runner.detect_workspaces(&file_paths);
+            // Call detect_workspaces directly, leaving out the files of
+            // projects of other kinds nested in the workspaces
+            let workspaces = workspace::scope_to_test_kind(
+                test_runner.detect_workspaces(&file_paths),
+                test_kind,
+            );
 
             let workspace_map = if let Some(workspace_dir) = workspace_dir {
                 let workspace_dir = workspace::resolve_path(&project_dir, workspace_dir)
@@ -318,16 +633,124 @@ impl TestingLS {
             } else {
                 workspaces.map
             };
+            for (workspace, paths) in &workspace_map {
+                counts.push(self.count_tests(&adapter_id, &adapter, workspace, paths));
+            }
             self.workspaces_cache.push(WorkspaceAnalysis::new(
+                adapter_id,
                 adapter,
                 Workspaces { map: workspace_map },
             ));
         }
         log::info!("workspaces_cache={:#?}", self.workspaces_cache);
         self.send_notification("$/detectedWorkspace", &self.workspaces_cache)?;
+        counts.sort_by(|a, b| (&a.adapter_id, &a.workspace).cmp(&(&b.adapter_id, &b.workspace)));
+        self.send_notification("$/discoveredTestCount", counts)?;
+        Ok(())
+    }
+
+    /// Counts the tests an adapter discovers in the files of a workspace, so
+    /// users can tell whether its globs and discovery match anything.
+    fn count_tests(
+        &self,
+        adapter_id: &str,
+        adapter: &AdapterConfig,
+        workspace: &str,
+        paths: &[String],
+    ) -> DiscoveredTestCount {
+        let test_count = match self.discover(adapter, paths) {
+            Ok(discovered) => discovered.files.iter().map(|file| file.tests.len()).sum(),
+            Err(e) => {
+                log::warn!("Failed to discover the tests of {adapter_id} in {workspace}: {e}");
+                0
+            }
+        };
+        log::info!(
+            "Adapter {adapter_id} found {test_count} tests in {} files of {workspace}",
+            paths.len()
+        );
+        DiscoveredTestCount {
+            adapter_id: adapter_id.to_string(),
+            workspace: workspace.to_string(),
+            file_count: paths.len(),
+            test_count,
+        }
+    }
+
+    /// Reloads the configuration, forgets the discovered tests and detects the
+    /// workspaces again, e.g. after the config file changed or projects were
+    /// added.
+    pub fn refresh_tests(&mut self) -> Result<Vec<WorkspaceAnalysis>, LSError> {
+        self.reload_config()?;
+        Ok(self.workspaces_cache.clone())
+    }
+
+    /// Reloads the configuration and detects the workspaces again. An invalid
+    /// config file is reported to the user and the old configuration is kept.
+    fn reload_config(&mut self) -> Result<(), LSError> {
+        match self.load_config(self.initialization_options.as_ref()) {
+            Ok(config) => self.config = config,
+            Err(error @ (LSError::Toml(_) | LSError::Json(_))) => {
+                let params = ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: format!("Invalid configuration, keeping the previous one: {error}"),
+                };
+                return self.send_notification("window/showMessage", params);
+            }
+            Err(error) => return Err(error),
+        }
+        self.discovery_cache = RefCell::default();
+        self.refresh_workspaces_cache()
+    }
+
+    /// Asks the client to notify the server of changes to the config file.
+    pub fn register_config_watcher(&self) -> Result<(), LSError> {
+        let register_options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String(format!("**/{TOML_FILE_NAME}")),
+                kind: None,
+            }],
+        };
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: "watch-config".to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: Some(serde_json::to_value(register_options)?),
+            }],
+        };
+        self.send_request(2, "client/registerCapability", params)
+    }
+
+    /// Reloads the configuration when the config file of the project was
+    /// created, changed or deleted.
+    pub fn did_change_watched_files(
+        &mut self,
+        params: &DidChangeWatchedFilesParams,
+    ) -> Result<(), LSError> {
+        let config_path = self.project_dir()?.join(TOML_FILE_NAME);
+        let config_changed = params.changes.iter().any(|change| {
+            workspace::same_path(
+                &uri_to_path(change.uri.as_str()),
+                &config_path.to_string_lossy(),
+            )
+        });
+        if config_changed {
+            self.reload_config()?;
+        }
         Ok(())
     }
 
+    /// Diagnoses the workspace after initialization when `run_on` has
+    /// "startup". Returns whether it ran.
+    pub fn run_on_startup(&mut self) -> Result<bool, LSError> {
+        if !self.config.runs_on("startup") {
+            return Ok(false);
+        }
+        log::info!("Running initial workspace diagnostics");
+        self.diagnose_workspace()?;
+        Ok(true)
+    }
+
     /// Diagnoses the entire workspace for test failures.
     /// Refreshes the workspace cache and runs tests for all detected
     /// workspaces, publishing diagnostics for any failures found.
@@ -339,15 +762,35 @@ impl TestingLS {
             "diagnose_workspace: processing {} workspace caches",
             self.workspaces_cache.len()
         );
+        let mut runs = vec![];
+        let mut accumulated = HashMap::new();
         for WorkspaceAnalysis {
+            adapter_id,
             adapter_config: adapter,
             workspaces,
         } in &self.workspaces_cache
         {
             for (workspace, paths) in &workspaces.map {
-                let _ = self.diagnose(adapter, workspace, paths);
+                if let Ok(results) = self.diagnose(
+                    adapter_id,
+                    adapter,
+                    workspace,
+                    paths,
+                    Selection::Filter(None),
+                    &mut accumulated,
+                ) {
+                    runs.push((
+                        adapter_id.clone(),
+                        workspace.clone(),
+                        paths.clone(),
+                        results,
+                    ));
+                }
             }
         }
+        for (adapter_id, workspace, paths, results) in runs {
+            self.record_results(adapter_id, workspace, &paths, None, results);
+        }
         Ok(())
     }
 
@@ -361,14 +804,14 @@ impl TestingLS {
                     .map
                     .iter()
                     .any(|(_, workspace): (&String, &Vec<String>)| {
-                        workspace.contains(&path.to_string())
+                        workspace::contains_path(workspace, path)
                     })
                 {
                     return false;
                 }
 
                 let extensions = workspace::extensions_for_test_kind(test_kind);
-                Self::project_files(&base_dir, &extensions).contains(&path.to_owned())
+                workspace::contains_path(&self.project_files(&base_dir, &extensions), path)
             }),
             Err(e) => {
                 log::error!("Error: {:?}", e);
@@ -377,35 +820,503 @@ impl TestingLS {
         }
     }
 
-    /// Checks a specific file for diagnostics, optionally refreshing the
-    /// workspace cache. This function will trigger the publication of
+    /// Checks a specific file for diagnostics by running its tests, only
+    /// those whose name contains `filter` if given, optionally refreshing
+    /// the workspace cache. This function will trigger the publication of
     /// diagnostics for the specified file through the Language Server
     /// Protocol.
-    pub fn check_file(&mut self, path: &str, refresh_needed: bool) -> Result<(), LSError> {
+    pub fn check_file(
+        &mut self,
+        path: &str,
+        refresh_needed: bool,
+        filter: Option<&str>,
+    ) -> Result<(), LSError> {
         if refresh_needed || self.workspaces_cache.is_empty() {
             self.refresh_workspaces_cache()?;
         }
+        let mut runs = vec![];
+        let mut accumulated = HashMap::new();
         self.workspaces_cache.iter().for_each(
             |WorkspaceAnalysis {
+                 adapter_id,
                  adapter_config: adapter,
                  workspaces,
              }| {
                 for (workspace, paths) in &workspaces.map {
-                    if !paths.contains(&path.to_string()) {
+                    if !workspace::contains_path(paths, path) {
                         continue;
                     }
-                    let _ = self.diagnose(adapter, workspace, &[path.to_string()]);
+                    if let Ok(results) = self.diagnose(
+                        adapter_id,
+                        adapter,
+                        workspace,
+                        &[path.to_string()],
+                        Selection::Filter(filter),
+                        &mut accumulated,
+                    ) {
+                        runs.push((adapter_id.clone(), workspace.clone(), results));
+                    }
                 }
             },
         );
+        for (adapter_id, workspace, results) in runs {
+            self.record_results(adapter_id, workspace, &[path.to_string()], filter, results);
+        }
         Ok(())
     }
 
+    /// Runs the test files changed since the last git commit, staged or not,
+    /// and returns them. Outside of a git repository nothing runs and the
+    /// user is told so.
+    pub fn run_changed_tests(&mut self) -> Result<Vec<FilePath>, LSError> {
+        let project_dir = self.project_dir()?;
+        let Some(changed) = workspace::changed_files(&project_dir) else {
+            let params = ShowMessageParams {
+                typ: MessageType::INFO,
+                message: format!(
+                    "{} isn't in a git repository, so changed tests can't be found",
+                    project_dir.display()
+                ),
+            };
+            let _ = self.send_notification("window/showMessage", params);
+            return Ok(vec![]);
+        };
+        if self.workspaces_cache.is_empty() {
+            self.refresh_workspaces_cache()?;
+        }
+        let mut test_files: Vec<FilePath> = self
+            .workspaces_cache
+            .iter()
+            .flat_map(|analysis| analysis.workspaces.map.values().flatten())
+            .filter(|path| {
+                changed
+                    .iter()
+                    .any(|changed| workspace::same_path(path, &changed.to_string_lossy()))
+            })
+            .cloned()
+            .collect();
+        test_files.sort();
+        test_files.dedup();
+        for path in &test_files {
+            self.check_file(path, false, None)?;
+        }
+        Ok(test_files)
+    }
+
+    /// Runs the tests matching the tag expression `tags` with every adapter
+    /// whose runner selects tests by tag, like cargo nextest with a filterset
+    /// or go test with build tags, in all of its workspaces.
+    pub fn run_tagged_tests(
+        &mut self,
+        params: &RunTaggedTestsParams,
+    ) -> Result<TaggedTestsRun, LSError> {
+        if self.workspaces_cache.is_empty() {
+            self.refresh_workspaces_cache()?;
+        }
+        let (mut adapters, mut unsupported) = (vec![], vec![]);
+        let mut runs = vec![];
+        let mut accumulated = HashMap::new();
+        for analysis in &self.workspaces_cache {
+            let adapter_id = &analysis.adapter_id;
+            let Some(tag_args) = runner::get(&analysis.adapter_config.test_kind)
+                .ok()
+                .and_then(|runner| runner.tag_args(&params.tags))
+            else {
+                unsupported.push(adapter_id.clone());
+                continue;
+            };
+            let adapter = AdapterConfig {
+                extra_arg: [analysis.adapter_config.extra_arg.clone(), tag_args].concat(),
+                ..analysis.adapter_config.clone()
+            };
+            adapters.push(adapter_id.clone());
+            for (workspace, paths) in &analysis.workspaces.map {
+                if let Ok(results) = self.diagnose(
+                    adapter_id,
+                    &adapter,
+                    workspace,
+                    paths,
+                    Selection::Filter(None),
+                    &mut accumulated,
+                ) {
+                    runs.push((
+                        adapter_id.clone(),
+                        workspace.clone(),
+                        paths.clone(),
+                        results,
+                    ));
+                }
+            }
+        }
+        for (adapter_id, workspace, paths, results) in runs {
+            self.record_results(adapter_id, workspace, &paths, None, results);
+        }
+        unsupported.sort();
+        let message = (!unsupported.is_empty()).then(|| {
+            format!(
+                "running tests by tag isn't supported by the adapters {}; it is by cargo-nextest \
+                 and go-test",
+                unsupported.join(", ")
+            )
+        });
+        adapters.sort();
+        Ok(TaggedTestsRun {
+            adapters,
+            unsupported,
+            message,
+        })
+    }
+
+    /// Reruns the tests that failed in the last run and returns their ids.
+    /// Each adapter runs once in each workspace: exactly the failed tests
+    /// when its runner selects tests by id, or else every test of their
+    /// files.
+    pub fn run_failed_tests(&mut self) -> Result<Vec<String>, LSError> {
+        let mut failed_ids = vec![];
+        let mut runs = vec![];
+        let mut accumulated = HashMap::new();
+        for WorkspaceAnalysis {
+            adapter_id,
+            adapter_config: adapter,
+            workspaces,
+        } in &self.workspaces_cache
+        {
+            let selects_ids =
+                runner::get(&adapter.test_kind).is_ok_and(|runner| runner.selects_ids());
+            for workspace in workspaces.map.keys() {
+                let key = (adapter_id.clone(), workspace.clone());
+                let Some(failures) = self
+                    .last_failures
+                    .get(&key)
+                    .filter(|failures| !failures.is_empty())
+                else {
+                    continue;
+                };
+                let test_ids: Vec<String> = failures.iter().map(|test| test.id.clone()).collect();
+                let mut paths: Vec<String> =
+                    failures.iter().map(|test| test.path.clone()).collect();
+                paths.sort();
+                paths.dedup();
+                let selection = if selects_ids {
+                    Selection::Ids(&test_ids)
+                } else {
+                    Selection::Filter(None)
+                };
+                if let Ok(results) = self.diagnose(
+                    adapter_id,
+                    adapter,
+                    workspace,
+                    &paths,
+                    selection,
+                    &mut accumulated,
+                ) {
+                    runs.push((key, paths, results));
+                }
+                failed_ids.extend(test_ids);
+            }
+        }
+        for ((adapter_id, workspace), paths, results) in runs {
+            self.record_results(adapter_id, workspace, &paths, None, results);
+        }
+        Ok(failed_ids)
+    }
+
+    /// Parses an already produced results file of a test kind into
+    /// diagnostics, without running any test command.
+    pub fn parse_results(&self, params: &ParseResultsParams) -> Result<Diagnostics, LSError> {
+        let runner = runner::get(&params.test_kind)?;
+        let detected: Vec<(&WorkspacePath, &Vec<FilePath>)> = self
+            .workspaces_cache
+            .iter()
+            .filter(|analysis| analysis.adapter_config.test_kind == params.test_kind)
+            .flat_map(|analysis| &analysis.workspaces.map)
+            .filter(|(workspace, _)| {
+                params
+                    .workspace
+                    .as_ref()
+                    .is_none_or(|requested| requested == *workspace)
+            })
+            .collect();
+
+        let workspace = match (&params.workspace, detected.first()) {
+            (Some(workspace), _) => workspace.clone(),
+            (None, Some((workspace, _))) => (*workspace).clone(),
+            (None, None) => self.project_dir()?.to_string_lossy().to_string(),
+        };
+        let file_paths = params.files.clone().unwrap_or_else(|| {
+            detected
+                .iter()
+                .filter(|(detected_workspace, _)| **detected_workspace == workspace)
+                .flat_map(|(_, paths)| paths.iter().cloned())
+                .collect()
+        });
+
+        let mut diagnostics =
+            runner.parse_results(Path::new(&params.path), &file_paths, &workspace)?;
+        for file in &mut diagnostics.files {
+            if let Some(lines) = self.line_lengths(&file.path) {
+                clamp_diagnostic_ranges(&mut file.diagnostics, &lines);
+            }
+        }
+        Ok(diagnostics)
+    }
+
+    /// Launch configuration for debugging a single test, after building what
+    /// it runs in. The test's file must be in a detected workspace of the
+    /// test kind.
+    pub fn debug_test(&self, params: &DebugTestParams) -> Result<DebugLaunch, LSError> {
+        let runner = runner::get(&params.test_kind)?;
+        let file_path = uri_to_path(&params.uri);
+        let (adapter, workspace) = self
+            .workspaces_cache
+            .iter()
+            .filter(|analysis| analysis.adapter_config.test_kind == params.test_kind)
+            .find_map(|analysis| {
+                analysis
+                    .workspaces
+                    .map
+                    .iter()
+                    .find(|(_, paths)| workspace::contains_path(paths, &file_path))
+                    .map(|(workspace, _)| (&analysis.adapter_config, workspace))
+            })
+            .ok_or_else(|| LSError::NotInWorkspace(file_path.clone()))?;
+
+        let context = adapter.run_context(
+            workspace,
+            std::slice::from_ref(&file_path),
+            &self.project_dir()?,
+        );
+        let mut launch = runner.debug_launch(
+            &params.test_id,
+            &file_path,
+            &context,
+            &adapter.test_args(),
+            adapter.command.as_deref(),
+        )?;
+        launch.env.extend(context.env);
+        if let Some(run_dir) = context.run_dir {
+            launch.cwd = run_dir.to_string_lossy().to_string();
+        }
+        Ok(launch)
+    }
+
+    /// Commands the runners would execute to test a file or workspace folder,
+    /// or every detected workspace, assembled without running anything.
+    pub fn explain_run(&self, params: &ExplainRunParams) -> Result<Vec<ExplainedRun>, LSError> {
+        let target = params.uri.as_deref().map(uri_to_path);
+        let project_dir = self.project_dir()?;
+        let mut runs = vec![];
+        for WorkspaceAnalysis {
+            adapter_config: adapter,
+            workspaces,
+            ..
+        } in &self.workspaces_cache
+        {
+            let runner = runner::get(&adapter.test_kind)?;
+            for (workspace, paths) in &workspaces.map {
+                let files: Vec<String> = match &target {
+                    Some(target) if !workspace::same_path(workspace, target) => paths
+                        .iter()
+                        .filter(|path| workspace::same_path(path, target))
+                        .cloned()
+                        .collect(),
+                    _ => paths.clone(),
+                };
+                if files.is_empty() {
+                    continue;
+                }
+                let command = runner.build_command(
+                    &files,
+                    &adapter.run_context(workspace, &files, &project_dir),
+                    &adapter.test_args(),
+                    adapter.command.as_deref(),
+                    params.filter.as_deref(),
+                );
+                runs.push(ExplainedRun {
+                    test_kind: adapter.test_kind.clone(),
+                    workspace: workspace.clone(),
+                    files,
+                    command: command.as_ref().ok().map(runner::CommandLine::from),
+                    error: command.err().map(|e| e.to_string()),
+                });
+            }
+        }
+        runs.sort_by(|a, b| (&a.test_kind, &a.workspace).cmp(&(&b.test_kind, &b.workspace)));
+        Ok(runs)
+    }
+
+    /// Warnings about the configuration of every adapter and the server
+    /// options, as shown when the workspaces are refreshed.
+    pub fn validate_config(&self) -> Result<ConfigValidation, LSError> {
+        let mut warnings =
+            config::validate_adapters(&self.config.adapter_command, &self.project_dir()?);
+        warnings.extend(self.config.run_on_warnings());
+        Ok(ConfigValidation { warnings })
+    }
+
+    /// Readiness of every configured adapter: whether its binary is found and
+    /// its version, and how many workspaces and files were detected.
+    pub fn health(&self) -> Result<Vec<AdapterHealth>, LSError> {
+        let project_dir = self.project_dir()?;
+        let mut adapters: Vec<(&AdapterId, &AdapterConfig)> =
+            self.config.adapter_command.iter().collect();
+        adapters.sort_by_key(|(adapter_id, _)| *adapter_id);
+
+        let mut health = vec![];
+        for (adapter_id, adapter) in adapters {
+            let workspaces: Vec<(&WorkspacePath, &Vec<FilePath>)> = self
+                .workspaces_cache
+                .iter()
+                .filter(|analysis| &analysis.adapter_id == adapter_id)
+                .flat_map(|analysis| &analysis.workspaces.map)
+                .collect();
+            let files = workspaces.iter().map(|(_, paths)| paths.len()).sum();
+            // The command for the first workspace tells where the binary is
+            let (workspace, paths) = workspaces.first().map_or_else(
+                || (project_dir.to_string_lossy().to_string(), vec![]),
+                |(workspace, paths)| ((*workspace).clone(), (*paths).clone()),
+            );
+            let command = runner::get(&adapter.test_kind).and_then(|runner| {
+                runner.build_command(
+                    &paths,
+                    &RunContext::new(&workspace),
+                    &adapter.test_args(),
+                    adapter.command.as_deref(),
+                    None,
+                )
+            });
+            let (binary, version, error) = match command {
+                Ok(command) => (
+                    Some(command.get_program().to_string_lossy().to_string()),
+                    runner::binary_version(command.get_program(), Path::new(&workspace)),
+                    (files == 0).then(|| "no workspaces or files detected".to_string()),
+                ),
+                Err(err) => (None, None, Some(err.to_string())),
+            };
+            health.push(AdapterHealth {
+                adapter_id: adapter_id.clone(),
+                test_kind: adapter.test_kind.clone(),
+                ready: error.is_none(),
+                binary,
+                version,
+                workspaces: workspaces.len(),
+                files,
+                error,
+            });
+        }
+        Ok(health)
+    }
+
+    /// Tree of the tests in every file of the cached workspaces, grouped by
+    /// workspace and file. Files without tests are left out.
+    pub fn list_tests(&mut self) -> Result<Vec<TestTreeItem>, LSError> {
+        let discovered = self.discover_workspace()?;
+        let mut workspaces: BTreeMap<(WorkspacePath, String), Vec<TestTreeItem>> = BTreeMap::new();
+        for file in discovered.files {
+            let Some(test_kind) = file.test_kind.clone() else {
+                continue;
+            };
+            let Some(workspace) = self
+                .workspaces_cache
+                .iter()
+                .filter(|analysis| analysis.adapter_config.test_kind == test_kind)
+                .flat_map(|analysis| &analysis.workspaces.map)
+                .find(|(_, paths)| workspace::contains_path(paths, &file.path))
+                .map(|(workspace, _)| workspace.clone())
+            else {
+                continue;
+            };
+            let (Ok(uri), false) = (Url::from_file_path(&file.path), file.tests.is_empty()) else {
+                continue;
+            };
+            let children = file
+                .tests
+                .into_iter()
+                .map(|test| TestTreeItem {
+                    id: test.id,
+                    label: test.name,
+                    kind: TestTreeKind::Test,
+                    test_kind: None,
+                    uri: uri.clone(),
+                    range: Some(Range::new(test.start_position.start, test.end_position.end)),
+                    children: vec![],
+                })
+                .collect();
+            let label = Path::new(&file.path)
+                .strip_prefix(&workspace)
+                .unwrap_or(Path::new(&file.path))
+                .to_string_lossy()
+                .to_string();
+            workspaces
+                .entry((workspace, test_kind))
+                .or_default()
+                .push(TestTreeItem {
+                    id: file.path,
+                    label,
+                    kind: TestTreeKind::File,
+                    test_kind: None,
+                    uri,
+                    range: None,
+                    children,
+                });
+        }
+
+        Ok(workspaces
+            .into_iter()
+            .filter_map(|((workspace, test_kind), children)| {
+                Some(TestTreeItem {
+                    id: format!("{test_kind}:{workspace}"),
+                    label: Path::new(&workspace).file_name().map_or_else(
+                        || workspace.clone(),
+                        |name| name.to_string_lossy().to_string(),
+                    ),
+                    kind: TestTreeKind::Workspace,
+                    test_kind: Some(test_kind),
+                    uri: Url::from_directory_path(&workspace).ok()?,
+                    range: None,
+                    children,
+                })
+            })
+            .collect())
+    }
+
+    /// Remembers the results of a run of `paths`, replacing the failures
+    /// previously recorded for the tests of those files that ran.
+    fn record_results(
+        &mut self,
+        adapter_id: String,
+        workspace: WorkspacePath,
+        paths: &[String],
+        filter: Option<&str>,
+        results: Vec<(TestItem, LastResult)>,
+    ) {
+        // No results means the runner failed, so nothing is known
+        if results.is_empty() {
+            return;
+        }
+        let failures = self
+            .last_failures
+            .entry((adapter_id, workspace))
+            .or_default();
+        failures.retain(|test| {
+            !paths.contains(&test.path) || filter.is_some_and(|filter| !test.name.contains(filter))
+        });
+        for (test_item, last_result) in results {
+            if last_result.status == TestStatus::Failed {
+                failures.push(test_item.clone());
+            }
+            self.last_results.insert(test_item.id, last_result);
+        }
+    }
+
     fn get_diagnostics(
         &self,
+        adapter_id: &str,
         adapter: &AdapterConfig,
         workspace: &str,
         paths: &[String],
+        selection: Selection,
+        on_event: &mut dyn FnMut(RunEvent),
     ) -> Result<Vec<(String, Vec<Diagnostic>)>, LSError> {
         let mut diagnostics: Vec<(String, Vec<Diagnostic>)> = vec![];
 
@@ -419,10 +1330,42 @@ impl TestingLS {
         // Get the runner for this test kind
         let test_runner = runner::get(&adapter.test_kind)?;
 
-        // Call run_tests directly
+        let lcov_path = coverage::lcov_path(&self.config.cache_dir, workspace);
+        let coverage_command = adapter.coverage_command(&lcov_path);
+        if coverage_command.is_some() {
+            // A report left by an earlier run mustn't pass for this one's
+            let _ = std::fs::remove_file(&lcov_path);
+            let _ = std::fs::remove_dir_all(coverage::raw_dir(&lcov_path));
+            if let Some(dir) = lcov_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+
         log::info!("Running tests with runner: {}", adapter.test_kind);
-        match test_runner.run_tests(paths, workspace, &adapter.extra_arg) {
+        let mut context = adapter.run_context(workspace, paths, &self.project_dir()?);
+        if let Selection::Ids(test_ids) = selection {
+            context.test_ids = Some(test_ids.to_vec());
+        }
+        match test_runner.run_tests_streaming(
+            paths,
+            &context,
+            &adapter.test_args(),
+            coverage_command.as_deref().or(adapter.command.as_deref()),
+            selection.filter(),
+            on_event,
+        ) {
             Ok(res) => {
+                if coverage_command.is_some() {
+                    let converted = adapter
+                        .coverage_report_command(&lcov_path)
+                        .map_or(Ok(()), |command| {
+                            coverage::convert_report(workspace, &command)
+                        });
+                    match converted {
+                        Ok(()) => self.send_coverage(workspace, &lcov_path),
+                        Err(e) => log::warn!("Coverage report not written: {e}"),
+                    }
+                }
                 log::info!("Test runner returned {} file results", res.files.len());
                 for file_result in &res.files {
                     log::debug!(
@@ -432,106 +1375,415 @@ impl TestingLS {
                     );
                 }
                 for target_file in paths {
-                    let diagnostics_for_file: Vec<Diagnostic> = res
-                        .files
-                        .clone()
-                        .into_iter()
-                        .filter(|FileDiagnostics { path, .. }| *path == *target_file)
-                        .flat_map(|FileDiagnostics { diagnostics, .. }| diagnostics)
-                        .collect();
+                    let mut diagnostics_for_file = adapter.filter_warnings(
+                        res.files
+                            .clone()
+                            .into_iter()
+                            .filter(|FileDiagnostics { path, .. }| {
+                                workspace::same_path(path, target_file)
+                            })
+                            .flat_map(|FileDiagnostics { diagnostics, .. }| diagnostics)
+                            .collect(),
+                    );
+                    if let Some(lines) = self.line_lengths(target_file) {
+                        clamp_diagnostic_ranges(&mut diagnostics_for_file, &lines);
+                    }
                     log::info!(
                         "Diagnostics for {}: {} items",
                         target_file,
                         diagnostics_for_file.len()
                     );
-                    let uri = Url::from_file_path(target_file.replace("file://", "")).unwrap();
+                    let Ok(uri) = Url::from_file_path(target_file.replace("file://", "")) else {
+                        log::warn!("Not an absolute file path: {target_file}");
+                        continue;
+                    };
                     diagnostics.push((uri.to_string(), diagnostics_for_file));
                 }
             }
             Err(err) => {
-                let message = format!("Test runner failed: {err:?}");
+                let message = match err {
+                    LSError::AdapterBinaryMissing(_) => err.to_string(),
+                    _ => format!("Test runner failed: {err:?}"),
+                };
                 log::error!("{}", message);
                 let params = ShowMessageParams {
                     typ: MessageType::ERROR,
                     message,
                 };
                 let _ = self.send_notification("window/showMessage", params);
+                let params = TestRunError {
+                    workspace: workspace.to_string(),
+                    adapter_id: adapter_id.to_string(),
+                    kind: err.kind().to_string(),
+                    error: err.to_string(),
+                };
+                let _ = self.send_notification("$/testRunError", params);
             }
         }
         Ok(diagnostics)
     }
 
+    /// Sends the coverage of the LCOV report at `lcov_path` in a `$/coverage`
+    /// notification.
+    fn send_coverage(&self, workspace: &str, lcov_path: &Path) {
+        let contents = match std::fs::read_to_string(lcov_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::warn!("No coverage report at {}: {e}", lcov_path.display());
+                return;
+            }
+        };
+        let params = CoverageParams {
+            workspace: workspace.to_string(),
+            files: coverage::parse_lcov(&contents, Path::new(workspace)),
+        };
+        let _ = self.send_notification("$/coverage", params);
+    }
+
+    /// Result of each test of `paths` that ran, the ones of `selection`.
+    fn last_results(
+        &self,
+        adapter: &AdapterConfig,
+        paths: &[String],
+        selection: Selection,
+        diagnostics: &[(String, Vec<Diagnostic>)],
+        outcomes: &HashMap<String, TestOutcome>,
+    ) -> Result<Vec<(TestItem, LastResult)>, LSError> {
+        // An empty result means the runner failed, so nothing is known
+        if diagnostics.is_empty() {
+            return Ok(vec![]);
+        }
+        let discovered = self.discover(adapter, paths)?;
+        Ok(discovered
+            .files
+            .iter()
+            .flat_map(|file| &file.tests)
+            .filter(|test_item| selection.includes(test_item))
+            .map(|test_item| {
+                let file_diagnostics = Url::from_file_path(&test_item.path)
+                    .ok()
+                    .and_then(|uri| diagnostics.iter().find(|(path, _)| *path == uri.as_str()))
+                    .map_or(&[][..], |(_, diagnostics)| diagnostics);
+                let last_result =
+                    LastResult::new(test_item, outcomes.get(&test_item.id), file_diagnostics);
+                (test_item.clone(), last_result)
+            })
+            .collect())
+    }
+
+    /// Runs the tests of the given files, publishing their diagnostics.
+    /// Returns the result of each test of the files that `selection`
+    /// selects.
+    ///
+    /// `accumulated` holds the diagnostics other adapters published for each
+    /// file earlier in the same pass. They're published along with this run's,
+    /// so a file owned by several adapters keeps the results of all of them.
     fn diagnose(
         &self,
+        adapter_id: &str,
         adapter: &AdapterConfig,
         workspace: &str,
         paths: &[String],
-    ) -> Result<(), LSError> {
+        selection: Selection,
+        accumulated: &mut HashMap<String, Vec<Diagnostic>>,
+    ) -> Result<Vec<(TestItem, LastResult)>, LSError> {
         let token = NumberOrString::String("assert-lsp/start_testing".to_string());
-        let progress_token = WorkDoneProgressCreateParams {
-            token: token.clone(),
-        };
-        self.send_request(1, "window/workDoneProgress/create", progress_token)?;
+        if self.config.show_progress {
+            let progress_token = WorkDoneProgressCreateParams {
+                token: token.clone(),
+            };
+            self.send_request(1, "window/workDoneProgress/create", progress_token)?;
+        }
         let progress_begin = WorkDoneProgressBegin {
             title: "Testing".to_string(),
             cancellable: Some(false),
             message: Some(format!("testing {} files ...", paths.len())),
             percentage: Some(0),
         };
-        let params = ProgressParams {
-            token: token.clone(),
-            value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(progress_begin)),
-        };
-        self.send_notification("$/progress", params)?;
-        let diagnostics = self.get_diagnostics(adapter, workspace, paths)?;
+        self.send_progress(&token, WorkDoneProgress::Begin(progress_begin))?;
+
+        // Publish each file as soon as its tests finish; the final results
+        // only need to be sent for files that changed since.
+        let mut published: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+        let mut outcomes: HashMap<String, TestOutcome> = HashMap::new();
+        let mut last_percentage = 0;
+        let diagnostics = self.get_diagnostics(
+            adapter_id,
+            adapter,
+            workspace,
+            paths,
+            selection,
+            &mut |event| match event {
+                RunEvent::TestCompleted(outcome) => {
+                    outcomes.insert(outcome.id.clone(), outcome);
+                }
+                RunEvent::TestFinished { completed, total } => {
+                    let percentage = u32::try_from(completed * 100 / total.max(1)).unwrap_or(100);
+                    if percentage == last_percentage {
+                        return;
+                    }
+                    last_percentage = percentage;
+                    let progress_report = WorkDoneProgressReport {
+                        cancellable: Some(false),
+                        message: Some(format!("{completed}/{total} tests")),
+                        percentage: Some(percentage),
+                    };
+                    let _ = self.send_progress(&token, WorkDoneProgress::Report(progress_report));
+                }
+                RunEvent::FileFinished(FileDiagnostics { path, diagnostics }) => {
+                    if !workspace::contains_path(paths, &path) {
+                        return;
+                    }
+                    let Ok(uri) = Url::from_file_path(&path) else {
+                        return;
+                    };
+                    let mut diagnostics = adapter.apply_overrides(diagnostics);
+                    if let Some(lines) = self.line_lengths(&path) {
+                        clamp_diagnostic_ranges(&mut diagnostics, &lines);
+                    }
+                    let earlier = accumulated.get(uri.as_str()).map_or(&[][..], Vec::as_slice);
+                    if self
+                        .send_diagnostics(uri.clone(), [earlier, &diagnostics].concat())
+                        .is_ok()
+                    {
+                        published.insert(uri.to_string(), diagnostics);
+                    }
+                }
+            },
+        )?;
+
+        let last_results = self.last_results(adapter, paths, selection, &diagnostics, &outcomes)?;
+        // A test requested by a stale name, e.g. from a code lens of a test
+        // since renamed, would otherwise go without any result
+        if let Some(filter) = selection.filter()
+            && !diagnostics.is_empty()
+            && !filter_reached(filter, &last_results, &outcomes)
+        {
+            let params = ShowMessageParams {
+                typ: MessageType::WARNING,
+                message: format!(
+                    "test '{filter}' not found; it may have been renamed or filtered out"
+                ),
+            };
+            let _ = self.send_notification("window/showMessage", params);
+        }
+
         for (path, diagnostics) in diagnostics {
-            self.send_diagnostics(
-                Url::from_file_path(path.replace("file://", "")).unwrap(),
-                diagnostics,
-            )?;
+            let diagnostics = adapter.apply_overrides(diagnostics);
+            if published.get(&path) != Some(&diagnostics) {
+                let earlier = accumulated.get(&path).map_or(&[][..], Vec::as_slice);
+                self.send_diagnostics(
+                    Url::from_file_path(path.replace("file://", "")).unwrap(),
+                    [earlier, &diagnostics].concat(),
+                )?;
+            }
+            accumulated.entry(path).or_default().extend(diagnostics);
         }
         let progress_end = WorkDoneProgressEnd {
             message: Some(format!("tested {} files", paths.len())),
         };
-        let params = ProgressParams {
-            token,
-            value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(progress_end)),
-        };
-        self.send_notification("$/progress", params)?;
-        Ok(())
+        self.send_progress(&token, WorkDoneProgress::End(progress_end))?;
+        Ok(last_results)
+    }
+
+    /// Hover showing the last result of the test under the cursor.
+    pub fn hover(&mut self, params: &HoverParams) -> Result<Option<Hover>, LSError> {
+        let position = &params.text_document_position_params;
+        let path = uri_to_path(position.text_document.uri.as_str());
+        let discovered = self.discover_file(&path)?;
+        let tests: Vec<TestItem> = discovered
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .collect();
+        Ok(hover::test_at(&tests, position.position)
+            .map(|test_item| hover::hover(test_item, self.last_results.get(&test_item.id))))
+    }
+
+    /// Result and captured output of a test in its last run, or `None` if it
+    /// hasn't run since the server started.
+    #[must_use]
+    pub fn test_output(&self, params: &TestOutputParams) -> Option<TestOutput> {
+        let last_result = self.last_results.get(&params.test_id)?;
+        Some(TestOutput {
+            test_id: params.test_id.clone(),
+            status: last_result.status,
+            message: last_result.message.clone(),
+            output: last_result.output.clone(),
+        })
     }
 
+    /// Line lengths of the file at `path`, measured in the text of its open
+    /// document so unsaved edits count, or else read from disk.
+    fn line_lengths(&self, path: &str) -> Option<LineLengths> {
+        match self.documents.get(path) {
+            Some(document) => Some(LineLengths::new(document.text())),
+            None => LineLengths::of_file(path),
+        }
+    }
+
+    /// Discovers tests in a single file. Open documents are discovered from
+    /// their in-memory text, reparsed incrementally since the last discovery.
     #[allow(clippy::for_kv_map)]
-    pub fn discover_file(&self, path: &str) -> Result<DiscoveredTests, LSError> {
+    pub fn discover_file(&mut self, path: &str) -> Result<DiscoveredTests, LSError> {
         let target_paths = vec![path.to_string()];
         let mut result: DiscoveredTests = DiscoveredTests { files: vec![] };
         for WorkspaceAnalysis {
+            adapter_id,
             adapter_config: adapter,
             workspaces,
+            ..
         } in &self.workspaces_cache
         {
             for (_, paths) in &workspaces.map {
-                if !paths.contains(&path.to_string()) {
+                if !workspace::contains_path(paths, path) {
+                    continue;
+                }
+                if let Some(document) = self.documents.get_mut(path) {
+                    let test_runner = runner::get(&adapter.test_kind)?;
+                    result.files.push(FileTests {
+                        path: path.to_string(),
+                        tests: document.discover(path, test_runner.as_ref())?,
+                        test_kind: Some(adapter.test_kind.clone()),
+                        adapter_id: Some(adapter_id.clone()),
+                    });
+                } else {
+                    result.files.extend(
+                        self.discover(adapter, &target_paths)?
+                            .files
+                            .into_iter()
+                            .map(|file| FileTests {
+                                adapter_id: Some(adapter_id.clone()),
+                                ..file
+                            }),
+                    );
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Discovers tests in every file of the cached workspaces. A file claimed
+    /// by several adapters is reported once, owned by the adapter whose id
+    /// sorts first, so the owner doesn't depend on the order of the config.
+    pub fn discover_workspace(&mut self) -> Result<DiscoveredTests, LSError> {
+        if self.workspaces_cache.is_empty() {
+            self.refresh_workspaces_cache()?;
+        }
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut result = DiscoveredTests::default();
+        let mut analyses: Vec<&WorkspaceAnalysis> = self.workspaces_cache.iter().collect();
+        analyses.sort_by(|a, b| a.adapter_id.cmp(&b.adapter_id));
+        for WorkspaceAnalysis {
+            adapter_id,
+            adapter_config: adapter,
+            workspaces,
+            ..
+        } in analyses
+        {
+            let mut closed_paths = vec![];
+            for path in workspaces.map.values().flatten() {
+                if !seen.insert(path.clone()) {
                     continue;
                 }
-                result
-                    .files
-                    .extend(self.discover(adapter, &target_paths)?.files);
+                let Some(document) = self.documents.get_mut(path) else {
+                    closed_paths.push(path.clone());
+                    continue;
+                };
+                let test_runner = runner::get(&adapter.test_kind)?;
+                result.files.push(FileTests {
+                    path: path.clone(),
+                    tests: document.discover(path, test_runner.as_ref())?,
+                    test_kind: Some(adapter.test_kind.clone()),
+                    adapter_id: Some(adapter_id.clone()),
+                });
+            }
+            if !closed_paths.is_empty() {
+                result.files.extend(
+                    self.discover(adapter, &closed_paths)?
+                        .files
+                        .into_iter()
+                        .map(|file| FileTests {
+                            adapter_id: Some(adapter_id.clone()),
+                            ..file
+                        }),
+                );
             }
         }
+        result.files.sort_by(|a, b| a.path.cmp(&b.path));
         Ok(result)
     }
 
+    /// Discovers tests in files, reusing the cached tests of files that
+    /// weren't modified since.
     fn discover(
         &self,
         adapter: &AdapterConfig,
         paths: &[String],
     ) -> Result<DiscoveredTests, LSError> {
-        let test_runner = runner::get(&adapter.test_kind)?;
-        test_runner.discover(paths)
+        let test_kind = &adapter.test_kind;
+        let test_runner = runner::get(test_kind)?;
+        if !test_runner.caches_discovery() {
+            let mut discovered = test_runner.discover(paths)?;
+            for file in &mut discovered.files {
+                if let Some(lines) = LineLengths::of_file(&file.path) {
+                    clamp_test_ranges(&mut file.tests, &lines);
+                }
+                file.test_kind = Some(test_kind.clone());
+            }
+            return Ok(discovered);
+        }
+
+        let mut cache = self.discovery_cache.borrow_mut();
+        let mut discovered = DiscoveredTests::default();
+        let mut stale = vec![];
+        for path in paths {
+            match cache.get(test_kind, path) {
+                Some(tests) => discovered.files.push(FileTests {
+                    path: path.clone(),
+                    tests: tests.to_vec(),
+                    test_kind: Some(test_kind.clone()),
+                    adapter_id: None,
+                }),
+                None => stale.push(path.clone()),
+            }
+        }
+        if stale.is_empty() {
+            return Ok(discovered);
+        }
+
+        // Closed files are read once, for both discovery and their line
+        // lengths
+        let fresh = runner::discover_files(&stale, |path| {
+            let source = std::fs::read_to_string(path)?;
+            let mut tests = test_runner.discover_source(path, &source)?;
+            clamp_test_ranges(&mut tests, &LineLengths::new(&source));
+            Ok(tests)
+        });
+        for mut file in fresh.files {
+            cache.insert(test_kind, &file.path, file.tests.clone());
+            file.test_kind = Some(test_kind.clone());
+            discovered.files.push(file);
+        }
+        let root = self.project_dir()?;
+        if let Err(e) = cache.save(&self.config.cache_dir, &root.to_string_lossy()) {
+            log::warn!("Failed to save the discovery cache: {e}");
+        }
+        Ok(discovered)
     }
 
-    pub fn send_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<(), LSError> {
+    /// Publishes the diagnostics of a file, with their messages cut off at
+    /// `max_message_len`.
+    pub fn send_diagnostics(
+        &self,
+        uri: Url,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> Result<(), LSError> {
+        if let Some(max_len) = self.config.max_message_len {
+            for diagnostic in &mut diagnostics {
+                truncate_message(&mut diagnostic.message, max_len);
+            }
+        }
         let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
         self.send_notification("textDocument/publishDiagnostics", params)
     }
@@ -554,21 +1806,956 @@ mod tests {
                 uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
                 name: "demo".to_string(),
             }]),
+            initialization_options: None,
             config: Config {
                 adapter_command: HashMap::new(),
                 ..Config::default()
             },
             workspaces_cache: Vec::new(),
+            documents: HashMap::new(),
+            last_results: HashMap::new(),
+            last_failures: HashMap::new(),
+            discovery_cache: RefCell::default(),
             sender,
         };
         let librs = abs_path_of_demo.join("src/lib.rs");
-        server.check_file(librs.to_str().unwrap(), true).unwrap();
+        server
+            .check_file(librs.to_str().unwrap(), true, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_check_file_accumulates_adapters() {
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        let librs_uri = Url::from_file_path(&librs).unwrap();
+        let check_with = |adapter_ids: &[&str]| {
+            let (sender, receiver) = crossbeam_channel::unbounded();
+            let mut server = TestingLS::new(sender);
+            server.workspace_folders = Some(vec![WorkspaceFolder {
+                uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+                name: "demo".to_string(),
+            }]);
+            server.config.adapter_command = adapter_ids
+                .iter()
+                .map(|id| {
+                    let adapter = AdapterConfig {
+                        test_kind: "cargo-test".to_string(),
+                        include: vec!["**/*.rs".to_string()],
+                        ..AdapterConfig::default()
+                    };
+                    ((*id).to_string(), adapter)
+                })
+                .collect();
+            server
+                .check_file(librs.to_str().unwrap(), true, None)
+                .unwrap();
+            receiver
+                .try_iter()
+                .filter_map(|message| match message {
+                    Message::Notification(not)
+                        if not.method == "textDocument/publishDiagnostics" =>
+                    {
+                        serde_json::from_value::<PublishDiagnosticsParams>(not.params).ok()
+                    }
+                    _ => None,
+                })
+                .filter(|params| params.uri == librs_uri)
+                .last()
+                .unwrap()
+                .diagnostics
+        };
+
+        let single = check_with(&["cargo"]);
+        assert!(!single.is_empty());
+        let both = check_with(&["cargo", "cargo-again"]);
+        assert_eq!(both.len(), single.len() * 2);
+    }
+
+    #[test]
+    fn test_stale_filter_reported() {
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        let check_with = |filter: &str| {
+            let (sender, receiver) = crossbeam_channel::unbounded();
+            let mut server = TestingLS::new(sender);
+            server.workspace_folders = Some(vec![WorkspaceFolder {
+                uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+                name: "demo".to_string(),
+            }]);
+            server.config.adapter_command = HashMap::from([(
+                "cargo-test".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    ..AdapterConfig::default()
+                },
+            )]);
+            server
+                .check_file(librs.to_str().unwrap(), true, Some(filter))
+                .unwrap();
+            receiver
+                .try_iter()
+                .filter_map(|message| match message {
+                    Message::Notification(not) if not.method == "window/showMessage" => {
+                        serde_json::from_value::<ShowMessageParams>(not.params).ok()
+                    }
+                    _ => None,
+                })
+                .map(|params| params.message)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            check_with("renamed_away"),
+            vec!["test 'renamed_away' not found; it may have been renamed or filtered out"]
+        );
+        assert!(check_with("fail").is_empty());
+    }
+
+    #[test]
+    fn test_progress_turned_off() {
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        let progress_sent = |show_progress: bool| {
+            let (sender, receiver) = crossbeam_channel::unbounded();
+            let mut server = TestingLS::new(sender);
+            server.workspace_folders = Some(vec![WorkspaceFolder {
+                uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+                name: "demo".to_string(),
+            }]);
+            server.config.show_progress = show_progress;
+            server.config.adapter_command = HashMap::from([(
+                "cargo-test".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    ..AdapterConfig::default()
+                },
+            )]);
+            server
+                .check_file(librs.to_str().unwrap(), true, None)
+                .unwrap();
+            receiver
+                .try_iter()
+                .filter(|message| match message {
+                    Message::Notification(not) => not.method == "$/progress",
+                    Message::Request(req) => req.method == "window/workDoneProgress/create",
+                    Message::Response(_) => false,
+                })
+                .count()
+        };
+
+        assert!(progress_sent(true) >= 3);
+        assert_eq!(progress_sent(false), 0);
+    }
+
+    #[test]
+    fn test_manual_run_on_skips_startup() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+            name: "demo".to_string(),
+        }]);
+        server.config.run_on = vec!["manual".to_string()];
+        server.config.adapter_command = HashMap::from([(
+            "cargo-test".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                ..AdapterConfig::default()
+            },
+        )]);
+
+        assert!(!server.run_on_startup().unwrap());
+        assert!(server.last_results.is_empty());
+        assert_eq!(receiver.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_run_error_notified() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+            name: "demo".to_string(),
+        }]);
+        server.config.adapter_command = HashMap::from([(
+            "rust".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                run_dir_template: Some("{workspace}/no-such-dir".to_string()),
+                ..AdapterConfig::default()
+            },
+        )]);
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        server
+            .check_file(librs.to_str().unwrap(), true, None)
+            .unwrap();
+
+        let methods: Vec<(String, Value)> = receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                Message::Notification(not) => Some((not.method, not.params)),
+                _ => None,
+            })
+            .filter(|(method, _)| method == "window/showMessage" || method == "$/testRunError")
+            .collect();
+        assert_eq!(methods.len(), 2);
+        assert_eq!(methods[0].0, "window/showMessage");
+        let (method, params) = &methods[1];
+        assert_eq!(method, "$/testRunError");
+        assert_eq!(params["adapter_id"], "rust");
+        assert_eq!(params["workspace"], abs_path_of_demo.to_str().unwrap());
+        assert_eq!(params["kind"], "run-dir-not-found");
+        assert!(
+            params["error"]
+                .as_str()
+                .unwrap()
+                .ends_with("no-such-dir is not an existing directory")
+        );
+    }
+
+    #[test]
+    fn test_run_failed_tests() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let mut server = TestingLS::new(sender);
+        assert!(server.run_failed_tests().unwrap().is_empty());
+
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+            name: "demo".to_string(),
+        }]);
+        server.config.adapter_command = HashMap::from([(
+            "rust".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                ..AdapterConfig::default()
+            },
+        )]);
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        server
+            .check_file(librs.to_str().unwrap(), true, None)
+            .unwrap();
+        assert!(
+            server
+                .last_failures
+                .keys()
+                .all(|(adapter_id, _)| adapter_id == "rust")
+        );
+        let failures: Vec<String> = server
+            .last_failures
+            .values()
+            .flatten()
+            .map(|test| test.id.clone())
+            .collect();
+        assert!(!failures.is_empty());
+
+        assert!(server.last_results.len() > failures.len());
+
+        // Only the failed tests run again, not the passing ones of their file
+        server.last_results.clear();
+        let rerun = server.run_failed_tests().unwrap();
+        assert_eq!(rerun, failures);
+        let mut rerun_results: Vec<&String> = server.last_results.keys().collect();
+        rerun_results.sort();
+        let mut expected: Vec<&String> = failures.iter().collect();
+        expected.sort();
+        assert_eq!(rerun_results, expected);
+    }
+
+    #[test]
+    fn test_run_changed_tests() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let root = tempfile::tempdir().unwrap();
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(root.path()).unwrap(),
+            name: "demo".to_string(),
+        }]);
+        assert!(server.run_changed_tests().unwrap().is_empty());
+        assert!(receiver.try_iter().any(|message| matches!(
+            message,
+            Message::Notification(not) if not.method == "window/showMessage"
+        )));
+
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .current_dir(root.path())
+                .args(["-c", "user.name=demo", "-c", "user.email=demo@example.com"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        let file = |name: &str| root.path().join(name).to_string_lossy().to_string();
+        std::fs::write(file("a_test.rs"), "").unwrap();
+        std::fs::write(file("b_test.rs"), "").unwrap();
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "init"]);
+        std::fs::write(file("b_test.rs"), "// changed\n").unwrap();
+
+        let adapter = AdapterConfig {
+            test_kind: "cargo-test".to_string(),
+            command: Some(vec!["true".to_string()]),
+            ..AdapterConfig::default()
+        };
+        server.config.adapter_command = HashMap::from([("rust".to_string(), adapter.clone())]);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "rust".to_string(),
+            adapter,
+            Workspaces {
+                map: HashMap::from([(
+                    root.path().to_string_lossy().to_string(),
+                    vec![file("a_test.rs"), file("b_test.rs")],
+                )]),
+            },
+        )];
+        assert_eq!(server.run_changed_tests().unwrap(), vec![file("b_test.rs")]);
+    }
+
+    #[test]
+    fn test_parse_results() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let server = TestingLS::new(sender);
+        let target_file_path = "/home/demo/test/go/src/test/cases_test.go".to_string();
+        let params = ParseResultsParams {
+            test_kind: "go-test".to_string(),
+            path: "tests/go-test.txt".to_string(),
+            workspace: Some("/home/demo/test/go/src/test".to_string()),
+            files: Some(vec![target_file_path.clone()]),
+        };
+        let diagnostics = server.parse_results(&params).unwrap();
+        assert_eq!(diagnostics.files.len(), 1);
+        assert_eq!(diagnostics.files[0].path, target_file_path);
+        assert_eq!(diagnostics.files[0].diagnostics[0].range.start.line, 30);
+
+        let unknown = ParseResultsParams {
+            test_kind: "unknown".to_string(),
+            ..params
+        };
+        assert!(server.parse_results(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_line_lengths_of_open_document() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        let librs = std::env::current_dir()
+            .unwrap()
+            .join("demo/rust/src/lib.rs");
+        let path = librs.to_string_lossy().to_string();
+        assert_eq!(server.line_lengths(&path), LineLengths::of_file(&path));
+
+        server.documents.insert(
+            path.clone(),
+            Document::new("fn a() {}\n// unsaved\n".to_string()),
+        );
+        assert_eq!(
+            server.line_lengths(&path),
+            Some(LineLengths::new("fn a() {}\n// unsaved\n"))
+        );
+    }
+
+    #[test]
+    fn test_explain_run() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let demo = std::env::current_dir().unwrap().join("demo/rust");
+        let workspace = demo.to_string_lossy().to_string();
+        let librs = demo.join("src/lib.rs").to_string_lossy().to_string();
+        let mut server = TestingLS::new(sender);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "cargo-test".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                extra_arg: vec!["--workspace".to_string()],
+                ..AdapterConfig::default()
+            },
+            Workspaces {
+                map: HashMap::from([(workspace.clone(), vec![librs.clone()])]),
+            },
+        )];
+
+        let runs = server
+            .explain_run(&ExplainRunParams {
+                uri: Some(format!("file://{librs}")),
+                filter: None,
+            })
+            .unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].files, vec![librs]);
+        let command = runs[0].command.as_ref().unwrap();
+        assert!(command.program.ends_with("cargo"));
+        assert_eq!(command.args[..3], ["test", "--color=never", "--workspace"]);
+        assert_eq!(command.cwd.as_deref(), Some(workspace.as_str()));
+        assert_eq!(command.env["CARGO_TERM_COLOR"], "never");
+
+        let elsewhere = ExplainRunParams {
+            uri: Some("file:///elsewhere/lib.rs".to_string()),
+            filter: None,
+        };
+        assert!(server.explain_run(&elsewhere).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_long_messages_truncated() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.config.max_message_len = Some(10);
+        let diagnostic = |message: &str| Diagnostic {
+            message: message.to_string(),
+            ..Diagnostic::default()
+        };
+        server
+            .send_diagnostics(
+                Url::parse("file:///project/lib.rs").unwrap(),
+                vec![
+                    diagnostic("assertion `left == right` failed"),
+                    diagnostic("short"),
+                ],
+            )
+            .unwrap();
+
+        let Ok(Message::Notification(notification)) = receiver.try_recv() else {
+            panic!("no diagnostics published");
+        };
+        let params: PublishDiagnosticsParams = serde_json::from_value(notification.params).unwrap();
+        assert_eq!(
+            params.diagnostics[0].message,
+            "assertion …\n(truncated, the full message is in `$/testOutput`)"
+        );
+        assert_eq!(params.diagnostics[1].message, "short");
+
+        let mut message = "ééé".to_string();
+        truncate_message(&mut message, 3);
+        assert_eq!(message, "ééé");
+    }
+
+    #[test]
+    fn test_health() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        let workspace = std::env::current_dir()
+            .unwrap()
+            .join("demo/rust")
+            .to_string_lossy()
+            .to_string();
+        let librs = format!("{workspace}/src/lib.rs");
+        server.config.adapter_command = HashMap::from([
+            (
+                "rust".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    ..AdapterConfig::default()
+                },
+            ),
+            (
+                "missing".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    command: Some(vec!["no-such-test-binary".to_string()]),
+                    ..AdapterConfig::default()
+                },
+            ),
+        ]);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "rust".to_string(),
+            server.config.adapter_command["rust"].clone(),
+            Workspaces {
+                map: HashMap::from([(workspace, vec![librs])]),
+            },
+        )];
+
+        let health = server.health().unwrap();
+        assert_eq!(health.len(), 2);
+        let missing = &health[0];
+        assert_eq!(missing.adapter_id, "missing");
+        assert!(!missing.ready);
+        assert_eq!(missing.binary, None);
+        assert!(
+            missing
+                .error
+                .as_ref()
+                .unwrap()
+                .contains("no-such-test-binary")
+        );
+
+        let rust = &health[1];
+        assert!(rust.ready, "{rust:?}");
+        assert!(rust.binary.as_ref().unwrap().ends_with("cargo"));
+        assert!(rust.version.as_ref().unwrap().starts_with("cargo "));
+        assert_eq!((rust.workspaces, rust.files), (1, 1));
+    }
+
+    #[test]
+    fn test_run_tagged_tests_unsupported() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "js".to_string(),
+            AdapterConfig {
+                test_kind: "jest".to_string(),
+                ..AdapterConfig::default()
+            },
+            Workspaces {
+                map: HashMap::from([("/demo".to_string(), vec!["/demo/a.test.js".to_string()])]),
+            },
+        )];
+
+        let params = RunTaggedTestsParams {
+            tags: "slow".to_string(),
+        };
+        let run = server.run_tagged_tests(&params).unwrap();
+        assert!(run.adapters.is_empty());
+        assert_eq!(run.unsupported, vec!["js"]);
+        assert!(
+            run.message
+                .unwrap()
+                .contains("isn't supported by the adapters js")
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.config.adapter_command = HashMap::from([
+            (
+                "rust".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    ..AdapterConfig::default()
+                },
+            ),
+            (
+                "php".to_string(),
+                AdapterConfig {
+                    test_kind: "phpunit".to_string(),
+                    jobs: Some(2),
+                    ..AdapterConfig::default()
+                },
+            ),
+        ]);
+        server.config.run_on = vec!["edit".to_string()];
+
+        let warnings = server.validate_config().unwrap().warnings;
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings[0].starts_with("Adapter 'php': jobs only applies to"));
+        assert!(warnings[1].contains("unknown run_on event 'edit'"));
+        // Validating doesn't refresh the workspaces
+        assert!(server.workspaces_cache.is_empty());
+    }
+
+    #[test]
+    fn test_debug_test() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().to_string_lossy().to_string();
+        let librs = dir.path().join("src/lib.rs").to_string_lossy().to_string();
+        let executable = dir.path().join("target/debug/deps/demo-0123");
+        let artifact = serde_json::json!({
+            "reason": "compiler-artifact",
+            "target": { "kind": ["lib"], "src_path": librs },
+            "profile": { "test": true },
+            "executable": executable,
+        });
+        std::fs::write(dir.path().join("messages.json"), artifact.to_string()).unwrap();
+
+        let mut server = TestingLS::new(sender);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "cargo-test".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                // Stands in for `cargo test --no-run`
+                command: Some(vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    "cat messages.json".to_string(),
+                ]),
+                env: HashMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+                ..AdapterConfig::default()
+            },
+            Workspaces {
+                map: HashMap::from([(workspace.clone(), vec![librs.clone()])]),
+            },
+        )];
+
+        let params = |test_kind: &str, path: &str| DebugTestParams {
+            test_kind: test_kind.to_string(),
+            uri: format!("file://{path}"),
+            test_id: "tests::adds".to_string(),
+        };
+        let launch = server.debug_test(&params("cargo-test", &librs)).unwrap();
+        assert_eq!(launch.program, executable.to_string_lossy());
+        assert_eq!(launch.args, ["tests::adds", "--exact", "--nocapture"]);
+        assert_eq!(launch.cwd, workspace);
+        assert_eq!(launch.env["RUST_LOG"], "debug");
+
+        assert!(matches!(
+            server.debug_test(&params("cargo-test", "/elsewhere/lib.rs")),
+            Err(LSError::NotInWorkspace(_))
+        ));
+        server.workspaces_cache[0].adapter_config.test_kind = "jest".to_string();
+        assert!(matches!(
+            server.debug_test(&params("jest", &librs)),
+            Err(LSError::DebugUnsupported)
+        ));
+    }
+
+    #[test]
+    fn test_list_tests() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let demo = std::env::current_dir().unwrap().join("demo/rust");
+        let workspace = demo.to_string_lossy().to_string();
+        let librs = demo.join("src/lib.rs").to_string_lossy().to_string();
+        let mut server = TestingLS::new(sender);
+        server.workspaces_cache = vec![WorkspaceAnalysis::new(
+            "cargo-test".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                ..AdapterConfig::default()
+            },
+            Workspaces {
+                map: HashMap::from([(workspace.clone(), vec![librs.clone()])]),
+            },
+        )];
+
+        let tree = server.list_tests().unwrap();
+        assert_eq!(tree.len(), 1);
+        let file = &tree[0].children[0];
+        assert_eq!(file.label, "src/lib.rs");
+        let fail = file
+            .children
+            .iter()
+            .find(|test| test.id == "tests::fail")
+            .unwrap();
+
+        let lib_uri = Url::from_file_path(&librs).unwrap();
+        assert_eq!(
+            serde_json::to_value(fail).unwrap(),
+            serde_json::json!({
+                "id": "tests::fail",
+                "label": "tests::fail",
+                "kind": "test",
+                "uri": lib_uri,
+                "range": {
+                    "start": { "line": 14, "character": 4 },
+                    "end": { "line": 16, "character": 5 }
+                },
+                "children": []
+            })
+        );
+        let root = serde_json::to_value(&tree[0]).unwrap();
+        assert_eq!(root["id"], format!("cargo-test:{workspace}"));
+        assert_eq!(root["label"], "rust");
+        assert_eq!(root["kind"], "workspace");
+        assert_eq!(root["testKind"], "cargo-test");
+        assert_eq!(root["children"][0]["kind"], "file");
+        assert!(root.get("range").is_none());
+    }
+
+    #[test]
+    fn test_discover_workspace_dedupes_files() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let abs_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
+        let adapter = |test_kind: &str| AdapterConfig {
+            test_kind: test_kind.to_string(),
+            ..AdapterConfig::default()
+        };
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(&abs_path_of_demo).unwrap(),
+            name: "demo".to_string(),
+        }]);
+        server.config.adapter_command = HashMap::from([
+            ("unit".to_string(), adapter("cargo-test")),
+            ("nextest".to_string(), adapter("cargo-nextest")),
+        ]);
+
+        let discovered = server.discover_workspace().unwrap();
+        let librs = abs_path_of_demo.join("src/lib.rs");
+        let lib_files: Vec<_> = discovered
+            .files
+            .iter()
+            .filter(|file| file.path == librs.to_str().unwrap())
+            .collect();
+        assert_eq!(lib_files.len(), 1);
+        assert!(!lib_files[0].tests.is_empty());
+        assert_eq!(lib_files[0].test_kind.as_deref(), Some("cargo-nextest"));
+        assert_eq!(lib_files[0].adapter_id.as_deref(), Some("nextest"));
+
+        server.workspaces_cache.reverse();
+        let discovered = server.discover_workspace().unwrap();
+        let lib_file = discovered
+            .files
+            .iter()
+            .find(|file| file.path == librs.to_str().unwrap())
+            .unwrap();
+        assert_eq!(lib_file.test_kind.as_deref(), Some("cargo-nextest"));
+    }
+
+    #[test]
+    fn test_refresh_tests_reloads_config() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"refreshed\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        let librs = root.path().join("src/lib.rs");
+        std::fs::write(&librs, "#[test]\nfn adds() {}\n").unwrap();
+        let modified = std::fs::metadata(&librs).unwrap().modified().unwrap();
+        let write_config = |test_kind: &str| {
+            std::fs::write(
+                root.path().join(TOML_FILE_NAME),
+                format!(
+                    "cache_dir = {:?}\n[adapter_command.rust]\ntest_kind = \"{test_kind}\"\n",
+                    root.path().join("cache")
+                ),
+            )
+            .unwrap();
+        };
+        write_config("cargo-test");
+
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(root.path()).unwrap(),
+            name: "root".to_string(),
+        }]);
+        let workspaces = server.refresh_tests().unwrap();
+        assert_eq!(workspaces.len(), 1);
+        assert_eq!(workspaces[0].adapter_config.test_kind, "cargo-test");
+        let test_names = |server: &mut TestingLS| -> Vec<String> {
+            let discovered = server.discover_workspace().unwrap();
+            discovered
+                .files
+                .into_iter()
+                .flat_map(|file| file.tests)
+                .map(|test| test.name)
+                .collect()
+        };
+        assert_eq!(test_names(&mut server), vec!["adds"]);
+
+        // The discovered tests are forgotten, even of files whose
+        // modification time didn't change
+        std::fs::write(&librs, "#[test]\nfn subtracts() {}\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&librs).unwrap();
+        file.set_modified(modified).unwrap();
+        write_config("cargo-nextest");
+        let workspaces = server.refresh_tests().unwrap();
+        assert_eq!(workspaces[0].adapter_config.test_kind, "cargo-nextest");
+        assert_eq!(test_names(&mut server), vec!["subtracts"]);
+    }
+
+    #[test]
+    fn test_discovered_test_count() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"counted\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        std::fs::write(
+            root.path().join("src/lib.rs"),
+            "#[test]\nfn adds() {}\n#[test]\nfn subtracts() {}\n",
+        )
+        .unwrap();
+        std::fs::write(root.path().join("src/util.rs"), "fn helper() {}\n").unwrap();
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(root.path()).unwrap(),
+            name: "root".to_string(),
+        }]);
+        server.config.cache_dir = root.path().join("cache");
+        server.config.adapter_command = HashMap::from([(
+            "rust".to_string(),
+            AdapterConfig {
+                test_kind: "cargo-test".to_string(),
+                ..AdapterConfig::default()
+            },
+        )]);
+        server.refresh_workspaces_cache().unwrap();
+
+        let counts = receiver
+            .try_iter()
+            .find_map(|message| match message {
+                Message::Notification(notification)
+                    if notification.method == "$/discoveredTestCount" =>
+                {
+                    Some(notification.params)
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            counts,
+            serde_json::json!([{
+                "adapter_id": "rust",
+                "workspace": root.path().to_string_lossy(),
+                "file_count": 2,
+                "test_count": 2,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_config_file_watched() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"watched\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        std::fs::write(root.path().join("src/lib.rs"), "#[test]\nfn adds() {}\n").unwrap();
+        let config_path = root.path().join(TOML_FILE_NAME);
+        let write_config = |test_kind: &str| {
+            std::fs::write(
+                &config_path,
+                format!("[adapter_command.rust]\ntest_kind = \"{test_kind}\"\n"),
+            )
+            .unwrap();
+        };
+        write_config("cargo-test");
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut server = TestingLS::new(sender);
+        server.workspace_folders = Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(root.path()).unwrap(),
+            name: "root".to_string(),
+        }]);
+        server.refresh_tests().unwrap();
+
+        server.register_config_watcher().unwrap();
+        let registration = receiver
+            .try_iter()
+            .find_map(|message| match message {
+                Message::Request(request) if request.method == "client/registerCapability" => {
+                    Some(request.params)
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(
+            registration["registrations"][0]["registerOptions"]["watchers"][0]["globPattern"],
+            "**/.assert-lsp.toml"
+        );
+
+        let changed = |path: &Path| DidChangeWatchedFilesParams {
+            changes: vec![lsp_types::FileEvent {
+                uri: Url::from_file_path(path).unwrap(),
+                typ: lsp_types::FileChangeType::CHANGED,
+            }],
+        };
+        let test_kind =
+            |server: &TestingLS| server.config.adapter_command["rust"].test_kind.clone();
+
+        // Changes to other files are ignored
+        write_config("cargo-nextest");
+        server
+            .did_change_watched_files(&changed(&root.path().join("Cargo.toml")))
+            .unwrap();
+        assert_eq!(test_kind(&server), "cargo-test");
+
+        server
+            .did_change_watched_files(&changed(&config_path))
+            .unwrap();
+        assert_eq!(test_kind(&server), "cargo-nextest");
+        assert_eq!(
+            server.workspaces_cache[0].adapter_config.test_kind,
+            "cargo-nextest"
+        );
+
+        // An invalid config is reported and the previous one kept
+        receiver.try_iter().for_each(drop);
+        std::fs::write(&config_path, "[adapter_command.rust\n").unwrap();
+        server
+            .did_change_watched_files(&changed(&config_path))
+            .unwrap();
+        assert_eq!(test_kind(&server), "cargo-nextest");
+        let error = receiver
+            .try_iter()
+            .find_map(|message| match message {
+                Message::Notification(notification)
+                    if notification.method == "window/showMessage" =>
+                {
+                    serde_json::from_value::<ShowMessageParams>(notification.params).ok()
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(error.typ, MessageType::ERROR);
+    }
+
+    #[test]
+    fn test_discovery_cache_survives_restart() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(
+            root.path().join("Cargo.toml"),
+            "[package]\nname = \"cached\"\n",
+        )
+        .unwrap();
+        std::fs::create_dir(root.path().join("src")).unwrap();
+        let librs = root.path().join("src/lib.rs");
+        std::fs::write(&librs, "#[test]\nfn adds() {}\n").unwrap();
+        let modified = std::fs::metadata(&librs).unwrap().modified().unwrap();
+        let cache_dir = root.path().join("cache");
+
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let start_server = || {
+            let mut server = TestingLS::new(sender.clone());
+            server.workspace_folders = Some(vec![WorkspaceFolder {
+                uri: Url::from_file_path(root.path()).unwrap(),
+                name: "root".to_string(),
+            }]);
+            server.config.cache_dir.clone_from(&cache_dir);
+            server.config.adapter_command = HashMap::from([(
+                "cargo-test".to_string(),
+                AdapterConfig {
+                    test_kind: "cargo-test".to_string(),
+                    ..AdapterConfig::default()
+                },
+            )]);
+            server.load_discovery_cache().unwrap();
+            server
+        };
+        let test_names = |server: &mut TestingLS| -> Vec<String> {
+            let discovered = server.discover_workspace().unwrap();
+            discovered
+                .files
+                .into_iter()
+                .flat_map(|file| file.tests)
+                .map(|test| test.name)
+                .collect()
+        };
+
+        assert_eq!(test_names(&mut start_server()), vec!["adds"]);
+
+        // An edit keeping the modification time isn't noticed after a restart
+        std::fs::write(&librs, "#[test]\nfn subtracts() {}\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&librs).unwrap();
+        file.set_modified(modified).unwrap();
+        let mut server = start_server();
+        assert_eq!(test_names(&mut server), vec!["adds"]);
+
+        file.set_modified(modified + std::time::Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(test_names(&mut server), vec!["subtracts"]);
     }
 
     #[test]
     fn project_files_finds_rust_files() {
+        let (sender, _receiver) = crossbeam_channel::unbounded();
+        let server = TestingLS::new(sender);
         let absolute_path_of_demo = std::env::current_dir().unwrap().join("demo/rust");
-        let files = TestingLS::project_files(&absolute_path_of_demo, &["rs"]);
+        let files = server.project_files(&absolute_path_of_demo, &["rs"]);
         assert!(!files.is_empty(), "Should find Rust files");
         assert!(
             files.iter().all(|f| f.ends_with(".rs")),
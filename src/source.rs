@@ -0,0 +1,67 @@
+//! Byte offsets in source text: their line and column, and where the
+//! brace-delimited or indented block after them ends, for runners that find
+//! tests by scanning the source instead of parsing it with a grammar.
+
+use crate::MAX_CHAR_LENGTH;
+
+/// Line and column of a byte offset.
+#[must_use]
+pub fn position_at(source: &str, byte: usize) -> (u32, u32) {
+    let before = &source[..byte];
+    let line = before.matches('\n').count() as u32;
+    let column = before.rfind('\n').map_or(byte, |i| byte - i - 1) as u32;
+    (line, column)
+}
+
+/// Byte offset just past the block that opens after `start`, judged by
+/// balancing braces.
+#[must_use]
+pub fn block_end(source: &str, start: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => return start + i + 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    source.len()
+}
+
+/// Zero-based line and length of the last line of a block starting on
+/// `line` at `column`: the last non-blank line before the next one indented
+/// no deeper than `column`.
+#[must_use]
+pub fn indented_end(source: &str, line: u32, column: u32) -> (u32, u32) {
+    let mut end = (line, 0);
+    for (number, text) in source.lines().enumerate().skip(line as usize) {
+        let number = u32::try_from(number).unwrap_or(u32::MAX);
+        let indent = text.len() - text.trim_start().len();
+        if number > line && !text.trim().is_empty() && indent <= column as usize {
+            break;
+        }
+        if !text.trim().is_empty() {
+            end = (number, u32::try_from(text.len()).unwrap_or(MAX_CHAR_LENGTH));
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_and_block_end() {
+        let source = "fn a() {\n    if x { y }\n}\nrest";
+        assert_eq!(position_at(source, 0), (0, 0));
+        assert_eq!(position_at(source, 13), (1, 4));
+        let end = block_end(source, 0);
+        assert_eq!(&source[..end], "fn a() {\n    if x { y }\n}");
+        assert_eq!(block_end("{ unclosed", 0), 10);
+
+        let source = "test a =\n  x\n\n  y\n\nrest";
+        assert_eq!(indented_end(source, 0, 0), (3, 3));
+    }
+}
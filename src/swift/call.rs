@@ -0,0 +1,45 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+use crate::{
+    config,
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// xUnit report written by the `swift test` command.
+#[must_use]
+pub fn results_path() -> PathBuf {
+    config::CONFIG.cache_dir.join("swift-xunit.xml")
+}
+
+/// Command running `swift test`, writing xUnit results to the cache
+/// directory.
+pub fn swift_test_command(
+    context: &RunContext,
+    extra_args: &[String],
+    command: Option<&[String]>,
+) -> Result<Command, LSError> {
+    let mut swift = test_command(context, command, &["swift", "test"])?;
+    swift
+        .arg("--parallel")
+        .arg("--xunit-output")
+        .arg(results_path())
+        .args(extra_args);
+    Ok(swift)
+}
+
+/// Run a `swift test` command.
+pub fn run_swift_test(command: &mut Command) -> Result<Output, LSError> {
+    // A failed build leaves no report, which must not be mistaken for the
+    // report of an earlier run
+    let _ = std::fs::remove_file(results_path());
+
+    let output = command.output()?;
+
+    write_result_log("swift_test.log", &output)?;
+    Ok(output)
+}
@@ -0,0 +1,201 @@
+//! Swift tests run by `swift test` with XCTest.
+//!
+//! Tests are the `func test...()` methods of classes inheriting from
+//! `XCTestCase`. No Swift grammar is bundled, so they are found by scanning
+//! the source, balancing braces to find where classes and methods end.
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+};
+
+/// Tests of a file, with ids like `MathTests/testAdds` as `swift test
+/// --filter` matches them.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let class_re = Regex::new(r"\bclass\s+(\w+)\s*:[^{]*\bXCTestCase\b[^{]*").unwrap();
+    let method_re = Regex::new(r"\bfunc\s+(test\w*)\s*\(\s*\)").unwrap();
+
+    let mut items = vec![];
+    for class in class_re.captures_iter(source) {
+        let body_start = class.get(0).unwrap().end();
+        let body = &source[body_start..block_end(source, body_start)];
+        for method in method_re.captures_iter(body) {
+            let found = method.get(0).unwrap();
+            let id = format!("{}/{}", &class[1], &method[1]);
+            let (line, column) = position_at(source, body_start + found.start());
+            let end = position_at(source, block_end(source, body_start + found.end()));
+            items.push(TestItem {
+                id: id.clone(),
+                name: id,
+                path: file_path.to_string(),
+                start_position: Range::new(
+                    Position::new(line, column),
+                    Position::new(line, MAX_CHAR_LENGTH),
+                ),
+                end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+            });
+        }
+    }
+    items
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+/// Value of `swift test --filter` selecting the given tests, or the tests
+/// whose name contains `filter` when none were discovered.
+fn filter_pattern(test_items: &[TestItem], filter: Option<&str>) -> Option<String> {
+    match filter {
+        Some(filter) if test_items.is_empty() => Some(regex::escape(filter)),
+        _ if test_items.is_empty() => None,
+        _ => {
+            let ids: Vec<String> = test_items
+                .iter()
+                .map(|item| regex::escape(&item.id))
+                .collect();
+            Some(format!("\\.(?:{})$", ids.join("|")))
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct XCTestRunner;
+
+impl XCTestRunner {
+    fn diagnostics(
+        results_path: &Path,
+        output: &str,
+        file_paths: &[String],
+    ) -> Result<Diagnostics, LSError> {
+        let failures = parse::parse_xunit(&std::fs::read_to_string(results_path)?)?;
+        let test_items: Vec<TestItem> = file_paths
+            .iter()
+            .filter_map(|path| discover_tests(path).ok())
+            .flatten()
+            .collect();
+        Ok(parse::to_diagnostics(
+            &failures,
+            output,
+            file_paths,
+            &test_items,
+        ))
+    }
+}
+
+impl Runner for XCTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        let test_items: Vec<TestItem> = self
+            .discover(file_paths)?
+            .files
+            .into_iter()
+            .flat_map(|file| file.tests)
+            .filter(|item| filter.is_none_or(|filter| item.name.contains(filter)))
+            .collect();
+        let filter_args = filter_pattern(&test_items, filter)
+            .map(|pattern| vec!["--filter".to_string(), pattern])
+            .unwrap_or_default();
+        let extra_args = [filter_args, extra_args.to_vec()].concat();
+        call::swift_test_command(context, &extra_args, command)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_swift_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        Self::diagnostics(
+            &call::results_path(),
+            &String::from_utf8_lossy(&output.stdout),
+            file_paths,
+        )
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        _workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Self::diagnostics(results_path, "", file_paths)
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["Package.swift"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let file_path = "demo/swift/Tests/MathTests/MathTests.swift";
+        let items = discover_tests(file_path).unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "MathTests/testAdds",
+                "MathTests/testFails",
+                "SlowMathTests/testLargeNumbers"
+            ]
+        );
+        assert_eq!(items[1].start_position.start, Position::new(8, 4));
+        assert_eq!(items[1].end_position.end, Position::new(10, 5));
+
+        assert_eq!(
+            filter_pattern(&items[..2], None).unwrap(),
+            r"\.(?:MathTests/testAdds|MathTests/testFails)$"
+        );
+        assert_eq!(filter_pattern(&[], Some("test.x")).unwrap(), r"test\.x");
+        assert_eq!(filter_pattern(&[], None), None);
+    }
+}
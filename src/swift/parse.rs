@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem, error::LSError,
+    junit::parse_testcases, workspace::contains_path,
+};
+
+/// A failed `<testcase>` of an xUnit report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XUnitFailure {
+    /// Test case class, qualified by its module, e.g. `MathTests.MathTests`
+    pub class_name: String,
+    pub name: String,
+    pub message: String,
+}
+
+impl XUnitFailure {
+    /// Id of the failed test, as given to discovered tests.
+    #[must_use]
+    pub fn test_id(&self) -> String {
+        let class = self.class_name.rsplit('.').next().unwrap_or_default();
+        format!("{class}/{}", self.name)
+    }
+}
+
+/// Parse the failed tests from the report of `swift test --xunit-output`.
+pub fn parse_xunit(contents: &str) -> Result<Vec<XUnitFailure>, LSError> {
+    Ok(parse_testcases(contents)?
+        .into_iter()
+        .filter_map(|testcase| {
+            let failure = testcase.failure.as_ref()?;
+            let mut message = failure.message.clone();
+            if !failure.text.is_empty() {
+                message.push('\n');
+                message.push_str(&failure.text);
+            }
+            Some(XUnitFailure {
+                class_name: testcase.attribute("classname").to_string(),
+                name: testcase.attribute("name").to_string(),
+                message,
+            })
+        })
+        .collect())
+}
+
+fn error_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("swift-test".to_string()),
+        code: Some(NumberOrString::String("swift-test-failed".to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests.
+///
+/// SwiftPM writes little more than `failed` into its xUnit report, so the
+/// assertion failures are taken from the test `output`, where XCTest prints
+/// them as `file:line: error: Class.testName : message` (or
+/// `-[Module.Class testName]` on macOS). A failure without such a line in one
+/// of `file_paths` is placed at the definition of the test.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[XUnitFailure],
+    output: &str,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let assertion_re =
+        Regex::new(r"^(.+\.swift):(\d+): error: (?:-\[)?([\w.]+)[ .](\w+)\]? : (.*)$").unwrap();
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let class = failure.class_name.rsplit('.').next().unwrap_or_default();
+        let mut located = false;
+        for line in output.lines().chain(failure.message.lines()) {
+            let Some(m) = assertion_re.captures(line.trim()) else {
+                continue;
+            };
+            let path = m[1].to_string();
+            if !contains_path(file_paths, &path)
+                || m[3].rsplit('.').next() != Some(class)
+                || m[4] != failure.name
+            {
+                continue;
+            }
+            let line_number = m[2].parse::<u32>().unwrap_or(1).saturating_sub(1);
+            let range = Range::new(
+                Position::new(line_number, 0),
+                Position::new(line_number, MAX_CHAR_LENGTH),
+            );
+            result_map
+                .entry(path)
+                .or_default()
+                .push(error_diagnostic(range, m[5].to_string()));
+            located = true;
+        }
+        if located {
+            continue;
+        }
+
+        let test_id = failure.test_id();
+        for test_item in test_items.iter().filter(|item| item.id == test_id) {
+            let message = format!("`{test_id}` failed\n{}", failure.message.trim());
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(test_item.start_position, message));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xunit() {
+        let contents = std::fs::read_to_string("demo/swift/xunit.xml").unwrap();
+        let failures = parse_xunit(&contents).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].test_id(), "MathTests/testFails");
+
+        let file_path = "/home/demo/swift/Tests/MathTests/MathTests.swift".to_string();
+        let output = std::fs::read_to_string("demo/swift/output.txt").unwrap();
+        let diagnostics = to_diagnostics(&failures, &output, std::slice::from_ref(&file_path), &[]);
+        assert_eq!(diagnostics.files.len(), 1);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(9, 0));
+        assert_eq!(
+            diagnostic.message,
+            r#"XCTAssertEqual failed: ("3") is not equal to ("4")"#
+        );
+
+        let test_item = TestItem {
+            id: "MathTests/testFails".to_string(),
+            name: "MathTests/testFails".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(8, 4), Position::new(8, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(10, 0), Position::new(10, 5)),
+        };
+        let diagnostics = to_diagnostics(&failures, "", &[file_path], &[test_item]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(8, 4));
+        assert_eq!(diagnostic.message, "`MathTests/testFails` failed\nfailed");
+    }
+}
@@ -0,0 +1,326 @@
+//! Test Anything Protocol (TAP) output parsing.
+//!
+//! Understands test points (`ok`/`not ok N - description # directive`), the
+//! YAML diagnostic block between `---` and `...` that follows a test point,
+//! comment lines following a test point, and subtests indented below their
+//! parent's test point.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+/// A single test point with its diagnostics and subtests.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TapResult {
+    pub ok: bool,
+    pub number: Option<usize>,
+    pub description: String,
+    /// `SKIP` or `TODO` directive with its reason
+    pub directive: Option<String>,
+    /// Top-level keys of the YAML diagnostic block. Block scalars and nested
+    /// maps are kept as dedented text.
+    pub diagnostic: HashMap<String, String>,
+    /// Comment lines following the test point, without their `# `, like the
+    /// failure details bats prints
+    pub comments: Vec<String>,
+    pub subtests: Vec<TapResult>,
+}
+
+impl TapResult {
+    #[must_use]
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.diagnostic.get(key).map(String::as_str)
+    }
+
+    /// First `path:line:column` in the `at` field, or else in the `stack`
+    /// or the `location`, with any `file://` scheme removed.
+    #[must_use]
+    pub fn location(&self) -> Option<(String, u32, u32)> {
+        self.locations().into_iter().next()
+    }
+
+    /// Every `path:line:column` in the `at`, `stack` and `location` fields,
+    /// in that order.
+    #[must_use]
+    pub fn locations(&self) -> Vec<(String, u32, u32)> {
+        let re = Regex::new(r"(?:file://)?([^\s()']+):(\d+):(\d+)").unwrap();
+        ["at", "stack", "location"]
+            .iter()
+            .filter_map(|key| self.field(key))
+            .flat_map(|text| re.captures_iter(text))
+            .filter_map(|captures| {
+                Some((
+                    captures[1].to_string(),
+                    captures[2].parse().ok()?,
+                    captures[3].parse().ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Failed test points in this result and its subtests, innermost first.
+    #[must_use]
+    pub fn failures(&self) -> Vec<&TapResult> {
+        let mut failures: Vec<&TapResult> =
+            self.subtests.iter().flat_map(TapResult::failures).collect();
+        if !self.ok && self.directive.is_none() {
+            failures.push(self);
+        }
+        failures
+    }
+}
+
+/// Parse TAP output into its top-level test points.
+#[must_use]
+pub fn parse_tap(input: &str) -> Vec<TapResult> {
+    let input = input.replace("\r\n", "\n");
+    let lines: Vec<&str> = input.lines().collect();
+    let mut index = 0;
+    parse_level(&lines, &mut index, 0)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Parse test points at `indent`, collecting more indented test points as
+/// subtests of the test point that follows them.
+fn parse_level(lines: &[&str], index: &mut usize, indent: usize) -> Vec<TapResult> {
+    let test_point_re =
+        Regex::new(r"^(not ok|ok)\b\s*(\d+)?\s*(?:-\s*)?([^#]*?)\s*(?:#\s*(.*))?$").unwrap();
+    let mut results: Vec<TapResult> = vec![];
+    let mut subtests = vec![];
+
+    while let Some(line) = lines.get(*index) {
+        let line_indent = indent_of(line);
+        if line.trim().is_empty() {
+            *index += 1;
+            continue;
+        }
+        if line_indent < indent {
+            break;
+        }
+        if line_indent > indent {
+            subtests.extend(parse_level(lines, index, line_indent));
+            continue;
+        }
+
+        *index += 1;
+        let Some(captures) = test_point_re.captures(line.trim()) else {
+            if let Some(comment) = line.trim().strip_prefix('#')
+                && !comment.trim_start().starts_with("Subtest:")
+                && subtests.is_empty()
+                && let Some(result) = results.last_mut()
+            {
+                let comment = comment.strip_prefix(' ').unwrap_or(comment);
+                result.comments.push(comment.trim_end().to_string());
+            }
+            // Plans, `# Subtest:` headers and the version line
+            continue;
+        };
+        let directive = captures
+            .get(4)
+            .map(|m| m.as_str().trim().to_string())
+            .filter(|directive| {
+                let directive = directive.to_ascii_uppercase();
+                directive.starts_with("SKIP") || directive.starts_with("TODO")
+            });
+        results.push(TapResult {
+            ok: &captures[1] == "ok",
+            number: captures.get(2).and_then(|m| m.as_str().parse().ok()),
+            description: captures[3].to_string(),
+            directive,
+            diagnostic: parse_yaml_block(lines, index, indent),
+            comments: vec![],
+            subtests: std::mem::take(&mut subtests),
+        });
+    }
+
+    results
+}
+
+/// Parse the YAML block following a test point, if there is one.
+fn parse_yaml_block(lines: &[&str], index: &mut usize, indent: usize) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let starts_block = lines
+        .get(*index)
+        .is_some_and(|line| indent_of(line) > indent && line.trim() == "---");
+    if !starts_block {
+        return fields;
+    }
+    *index += 1;
+
+    let mut block = vec![];
+    while let Some(line) = lines.get(*index) {
+        *index += 1;
+        if line.trim() == "..." {
+            break;
+        }
+        block.push(*line);
+    }
+
+    let Some(key_indent) = block
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_of(line))
+        .min()
+    else {
+        return fields;
+    };
+
+    let mut i = 0;
+    while i < block.len() {
+        let line = block[i];
+        i += 1;
+        if indent_of(line) != key_indent {
+            continue;
+        }
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        // Block scalars and nested maps continue on more indented lines
+        let nested_start = i;
+        while i < block.len() && (block[i].trim().is_empty() || indent_of(block[i]) > key_indent) {
+            i += 1;
+        }
+        let nested = &block[nested_start..i];
+
+        let value = if value.is_empty() || matches!(value, "|" | "|-" | ">" | ">-") {
+            let nested_indent = nested
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| indent_of(line))
+                .min()
+                .unwrap_or(0);
+            let text: Vec<&str> = nested
+                .iter()
+                .map(|line| line.get(nested_indent..).unwrap_or("").trim_end())
+                .collect();
+            text.join("\n").trim_end().to_string()
+        } else {
+            value.trim_matches(|c| c == '\'' || c == '"').to_string()
+        };
+        fields.insert(key.trim().to_string(), value);
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml_block() {
+        let output = r"TAP version 13
+ok 1 - adds
+not ok 2 - subtracts
+  ---
+  name: AssertionError
+  expected: 1
+  actual: 0
+  values:
+    'Difference (- actual, + expected):': |-
+      - 0
+      + 1
+  at: 'file:///home/demo/math.test.js:8:5'
+  ...
+ok 3 - later # SKIP not ready
+1..3
+";
+        let results = parse_tap(output);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].ok);
+        assert!(results[0].diagnostic.is_empty());
+
+        let failed = &results[1];
+        assert!(!failed.ok);
+        assert_eq!(failed.number, Some(2));
+        assert_eq!(failed.description, "subtracts");
+        assert_eq!(failed.field("expected"), Some("1"));
+        assert_eq!(failed.field("actual"), Some("0"));
+        assert_eq!(
+            failed.field("values"),
+            Some("'Difference (- actual, + expected):': |-\n  - 0\n  + 1")
+        );
+        assert_eq!(
+            failed.location(),
+            Some(("/home/demo/math.test.js".to_string(), 8, 5))
+        );
+
+        assert_eq!(results[2].directive.as_deref(), Some("SKIP not ready"));
+    }
+
+    #[test]
+    fn test_parse_nested_subtests() {
+        let output = r"TAP version 13
+# Subtest: math
+    # Subtest: adds
+    ok 1 - adds
+      ---
+      duration_ms: 0.5
+      ...
+    # Subtest: subtracts
+    not ok 2 - subtracts
+      ---
+      error: |-
+        Expected values to be strictly equal:
+
+        0 !== 1
+      expected: 1
+      actual: 0
+      stack: |-
+        TestContext.<anonymous> (file:///home/demo/index.test.js:9:12)
+        Test.runInAsyncScope (node:async_hooks:206:9)
+      ...
+    1..2
+not ok 1 - math
+  ---
+  error: '1 subtest failed'
+  ...
+ok 2 - standalone
+1..2
+";
+        let results = parse_tap(output);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].description, "math");
+        assert_eq!(results[0].subtests.len(), 2);
+        assert!(results[1].subtests.is_empty());
+
+        let subtracts = &results[0].subtests[1];
+        assert_eq!(
+            subtracts.field("error"),
+            Some("Expected values to be strictly equal:\n\n0 !== 1")
+        );
+        assert_eq!(
+            subtracts.location(),
+            Some(("/home/demo/index.test.js".to_string(), 9, 12))
+        );
+
+        let failures: Vec<&str> = results
+            .iter()
+            .flat_map(TapResult::failures)
+            .map(|failure| failure.description.as_str())
+            .collect();
+        assert_eq!(failures, vec!["subtracts", "math"]);
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let output = "1..2\nnot ok 1 fails\n# (in test file test/math.bats, line 9)\n#   `false' \
+                      failed\n#\nok 2 passes\n";
+        let results = parse_tap(output);
+        assert_eq!(results[0].description, "fails");
+        assert_eq!(
+            results[0].comments,
+            vec![
+                "(in test file test/math.bats, line 9)",
+                "  `false' failed",
+                ""
+            ]
+        );
+        assert!(results[1].comments.is_empty());
+    }
+}
@@ -3,13 +3,18 @@
 use std::{
     collections::HashMap,
     path::{Component, Path, PathBuf},
-    str::FromStr,
+    process::Command,
     string::String,
 };
 
-use ignore::WalkBuilder;
+use ignore::{WalkBuilder, overrides::OverrideBuilder};
+use serde_json::Value;
 
-use crate::{AdapterConfig, Workspaces};
+use crate::{AdapterConfig, WorkspacePath, Workspaces};
+
+/// Optional file with `.gitignore`-style patterns of files to leave out of
+/// testing.
+pub const IGNORE_FILE_NAME: &str = ".assert-lsp-ignore";
 
 /// Detected project type with its configuration.
 #[derive(Debug, Clone)]
@@ -18,9 +23,39 @@ pub struct DetectedProject {
     pub root: PathBuf,
 }
 
+/// Marker files `detect_projects` looks for.
+pub const PROJECT_MARKERS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "playwright.config.ts",
+    "deno.json",
+    "go.mod",
+    "composer.json",
+    "CMakeLists.txt",
+    "Package.swift",
+    "settings.gradle.kts",
+    "settings.gradle",
+    "build.gradle.kts",
+    "build.gradle",
+    "pom.xml",
+    "pubspec.yaml",
+    "build.zig",
+    "dune-project",
+    ".busted",
+    "stack.yaml",
+    "*.cabal",
+    "build.sbt",
+    "*.nimble",
+    "Project.toml",
+    "*.bats",
+    "pytest.ini",
+    "conftest.py",
+];
+
 /// Detect project types in a directory by looking for marker files.
 /// Returns a list of detected projects with their configurations.
 #[must_use]
+#[allow(clippy::too_many_lines)]
 pub fn detect_projects(base_dir: &Path) -> Vec<DetectedProject> {
     let mut projects = Vec::new();
 
@@ -46,10 +81,34 @@ pub fn detect_projects(base_dir: &Path) -> Vec<DetectedProject> {
                     test_kind: "jest".to_string(),
                     root: base_dir.to_path_buf(),
                 });
+            } else if content.contains("\"ava\"") {
+                projects.push(DetectedProject {
+                    test_kind: "ava".to_string(),
+                    root: base_dir.to_path_buf(),
+                });
+            } else if ["bunfig.toml", "bun.lockb", "bun.lock"]
+                .iter()
+                .any(|marker| base_dir.join(marker).exists())
+            {
+                projects.push(DetectedProject {
+                    test_kind: "bun-test".to_string(),
+                    root: base_dir.to_path_buf(),
+                });
             }
         }
     }
 
+    // Check for a Playwright config (end-to-end JavaScript/TypeScript)
+    if crate::javascript::PLAYWRIGHT_CONFIG_FILES
+        .iter()
+        .any(|config| base_dir.join(config).exists())
+    {
+        projects.push(DetectedProject {
+            test_kind: "playwright".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
     // Check for deno.json (Deno)
     if base_dir.join("deno.json").exists() || base_dir.join("deno.jsonc").exists() {
         projects.push(DetectedProject {
@@ -78,6 +137,163 @@ pub fn detect_projects(base_dir: &Path) -> Vec<DetectedProject> {
         }
     }
 
+    // Check for a CMake project with tests (C/C++), whose test binaries are
+    // run directly when they use Catch2 or doctest
+    if let Ok(content) = std::fs::read_to_string(base_dir.join("CMakeLists.txt")) {
+        if content.contains("Catch2") || content.contains("doctest") {
+            projects.push(DetectedProject {
+                test_kind: "catch2".to_string(),
+                root: base_dir.to_path_buf(),
+            });
+        } else if content.contains("enable_testing") || content.contains("include(CTest)") {
+            projects.push(DetectedProject {
+                test_kind: "ctest".to_string(),
+                root: base_dir.to_path_buf(),
+            });
+        }
+    }
+
+    // Check for Package.swift (Swift)
+    if base_dir.join("Package.swift").exists() {
+        projects.push(DetectedProject {
+            test_kind: "swift-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for a Gradle build (Kotlin)
+    if [
+        "settings.gradle.kts",
+        "settings.gradle",
+        "build.gradle.kts",
+        "build.gradle",
+    ]
+    .iter()
+    .any(|file| base_dir.join(file).exists())
+    {
+        projects.push(DetectedProject {
+            test_kind: "gradle-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for pom.xml (Maven)
+    if base_dir.join("pom.xml").exists() {
+        projects.push(DetectedProject {
+            test_kind: "maven-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for pubspec.yaml (Dart, Flutter)
+    if base_dir.join("pubspec.yaml").exists() {
+        projects.push(DetectedProject {
+            test_kind: "dart-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for build.zig (Zig)
+    if base_dir.join("build.zig").exists() {
+        projects.push(DetectedProject {
+            test_kind: "zig-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for dune-project (OCaml)
+    if base_dir.join("dune-project").exists() {
+        projects.push(DetectedProject {
+            test_kind: "dune-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for .busted (Lua)
+    if base_dir.join(".busted").exists() {
+        projects.push(DetectedProject {
+            test_kind: "busted".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for stack.yaml, or else a .cabal package (Haskell)
+    if base_dir.join("stack.yaml").exists() {
+        projects.push(DetectedProject {
+            test_kind: "stack-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    } else if std::fs::read_dir(base_dir).is_ok_and(|mut entries| {
+        entries.any(|entry| {
+            entry.is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "cabal"))
+        })
+    }) {
+        projects.push(DetectedProject {
+            test_kind: "cabal-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for build.sbt (Scala)
+    if base_dir.join("build.sbt").exists() {
+        projects.push(DetectedProject {
+            test_kind: "sbt-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for a .nimble package (Nim)
+    if std::fs::read_dir(base_dir).is_ok_and(|mut entries| {
+        entries.any(|entry| {
+            entry.is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "nimble"))
+        })
+    }) {
+        projects.push(DetectedProject {
+            test_kind: "nim-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for Project.toml (Julia)
+    if base_dir.join("Project.toml").exists() {
+        projects.push(DetectedProject {
+            test_kind: "julia-test".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for .bats files, in the project or its test directory (Bash)
+    let has_bats = |dir: PathBuf| {
+        std::fs::read_dir(dir).is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "bats"))
+            })
+        })
+    };
+    if ["", "test", "tests"]
+        .into_iter()
+        .any(|dir| has_bats(base_dir.join(dir)))
+    {
+        projects.push(DetectedProject {
+            test_kind: "bats".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
+    // Check for pytest.ini, a conftest.py or pytest settings in
+    // pyproject.toml (Python)
+    if ["pytest.ini", "conftest.py"]
+        .iter()
+        .any(|file| base_dir.join(file).exists())
+        || std::fs::read_to_string(base_dir.join("pyproject.toml"))
+            .is_ok_and(|pyproject| pyproject.contains("[tool.pytest"))
+    {
+        projects.push(DetectedProject {
+            test_kind: "pytest".to_string(),
+            root: base_dir.to_path_buf(),
+        });
+    }
+
     projects
 }
 
@@ -89,13 +305,20 @@ pub fn config_from_detected(project: &DetectedProject) -> AdapterConfig {
             vec!["**/*.rs".to_string()],
             vec!["**/target/**".to_string()],
         ),
-        "jest" | "vitest" => (
+        "jest" | "vitest" | "bun-test" => (
             vec![
                 "**/*.test.{js,ts,jsx,tsx}".to_string(),
                 "**/*.spec.{js,ts,jsx,tsx}".to_string(),
             ],
             vec!["**/node_modules/**".to_string()],
         ),
+        "playwright" => (
+            vec![
+                "**/*.spec.{js,ts,mjs,mts}".to_string(),
+                "**/*.test.{js,ts,mjs,mts}".to_string(),
+            ],
+            vec!["**/node_modules/**".to_string()],
+        ),
         "deno" => (
             vec!["**/*_test.ts".to_string(), "**/*.test.ts".to_string()],
             vec![],
@@ -109,22 +332,147 @@ pub fn config_from_detected(project: &DetectedProject) -> AdapterConfig {
             vec!["**/*.test.{js,mjs}".to_string()],
             vec!["**/node_modules/**".to_string()],
         ),
+        "ava" => (
+            vec![
+                "**/*.test.{js,mjs,cjs}".to_string(),
+                "**/*.spec.{js,mjs,cjs}".to_string(),
+                "**/test/**/*.{js,mjs,cjs}".to_string(),
+                "**/tests/**/*.{js,mjs,cjs}".to_string(),
+            ],
+            vec!["**/node_modules/**".to_string()],
+        ),
+        "ctest" => (
+            vec![
+                "**/*.{cpp,cc,cxx}".to_string(),
+                "**/CMakeLists.txt".to_string(),
+            ],
+            vec!["**/build/**".to_string(), "**/cmake-build-*/**".to_string()],
+        ),
+        "catch2" => (
+            vec!["**/*.{cpp,cc,cxx,hpp}".to_string()],
+            vec!["**/build/**".to_string(), "**/cmake-build-*/**".to_string()],
+        ),
+        "swift-test" => (
+            vec!["**/Tests/**/*.swift".to_string()],
+            vec!["**/.build/**".to_string()],
+        ),
+        "gradle-test" => (
+            vec![
+                "**/src/test/**/*.kt".to_string(),
+                "**/src/test/**/*.java".to_string(),
+            ],
+            vec!["**/build/**".to_string(), "**/.gradle/**".to_string()],
+        ),
+        "maven-test" => (
+            vec![
+                "**/src/test/**/*.java".to_string(),
+                "**/src/test/**/*.kt".to_string(),
+            ],
+            vec!["**/target/**".to_string()],
+        ),
+        "dart-test" => (
+            vec!["**/test/**/*_test.dart".to_string()],
+            vec!["**/.dart_tool/**".to_string(), "**/build/**".to_string()],
+        ),
+        "zig-test" => (
+            vec!["**/*.zig".to_string()],
+            vec![
+                "**/.zig-cache/**".to_string(),
+                "**/zig-cache/**".to_string(),
+                "**/zig-out/**".to_string(),
+            ],
+        ),
+        "dune-test" => (
+            vec!["**/*.ml".to_string()],
+            vec!["**/_build/**".to_string(), "**/_opam/**".to_string()],
+        ),
+        "busted" => (
+            vec!["**/*_spec.lua".to_string()],
+            vec![
+                "**/lua_modules/**".to_string(),
+                "**/.luarocks/**".to_string(),
+            ],
+        ),
+        "stack-test" | "cabal-test" => (
+            vec!["**/test/**/*.hs".to_string()],
+            vec![
+                "**/.stack-work/**".to_string(),
+                "**/dist-newstyle/**".to_string(),
+            ],
+        ),
+        "sbt-test" => (
+            vec!["**/src/test/**/*.scala".to_string()],
+            vec!["**/target/**".to_string(), "**/.bsp/**".to_string()],
+        ),
+        "nim-test" => (
+            vec!["**/tests/**/*.nim".to_string()],
+            vec!["**/nimcache/**".to_string(), "**/nimbledeps/**".to_string()],
+        ),
+        "julia-test" => (vec!["**/test/**/*.jl".to_string()], vec![]),
+        // bats-core and its helper libraries are often vendored as submodules
+        "bats" => (
+            vec!["**/*.bats".to_string()],
+            vec![
+                "**/test/bats/**".to_string(),
+                "**/test/test_helper/**".to_string(),
+            ],
+        ),
+        "pytest" => (
+            vec!["**/test_*.py".to_string(), "**/*_test.py".to_string()],
+            vec![
+                "**/.venv/**".to_string(),
+                "**/venv/**".to_string(),
+                "**/.tox/**".to_string(),
+                "**/site-packages/**".to_string(),
+            ],
+        ),
         _ => (vec![], vec![]),
     };
 
+    // Monorepo roots run each member package separately, so leave the
+    // workspace directory to `detect_workspaces`.
+    let is_monorepo = matches!(
+        project.test_kind.as_str(),
+        "jest" | "vitest" | "bun-test" | "ava"
+    ) && !package_json_workspaces(&project.root).is_empty();
+
     AdapterConfig {
         test_kind: project.test_kind.clone(),
+        command: None,
         extra_arg: vec![],
+        features: vec![],
+        no_default_features: false,
+        profile: None,
+        cargo_target: None,
+        target_dir: None,
+        regression_threshold: None,
+        jobs: None,
+        timeout_secs: None,
+        coverage: false,
+        surface_warnings: false,
         env: HashMap::new(),
+        env_file: None,
         include,
         exclude,
-        workspace_dir: Some(project.root.to_string_lossy().to_string()),
+        workspace_dir: (!is_monorepo).then(|| project.root.to_string_lossy().to_string()),
+        run_dir_template: None,
+        severity_overrides: HashMap::new(),
+        source_label: None,
     }
 }
 
-/// Walk directory respecting .gitignore and return matching files.
+/// Walk directory respecting .gitignore and `.assert-lsp-ignore`, and return
+/// matching files.
+///
+/// `max_depth` limits how deep below `base_dir` the walk descends, and
+/// `follow_symlinks` makes it descend into symlinked directories.
 #[must_use]
-pub fn walk_files(base_dir: &Path, extensions: &[&str]) -> Vec<String> {
+pub fn walk_files(
+    base_dir: &Path,
+    extensions: &[&str],
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+) -> Vec<String> {
     let mut files = Vec::new();
 
     let walker = WalkBuilder::new(base_dir)
@@ -132,6 +480,9 @@ pub fn walk_files(base_dir: &Path, extensions: &[&str]) -> Vec<String> {
         .git_ignore(true)
         .git_global(true)
         .git_exclude(true)
+        .add_custom_ignore_filename(IGNORE_FILE_NAME)
+        .max_depth(max_depth)
+        .follow_links(follow_symlinks)
         .build();
 
     for entry in walker.flatten() {
@@ -139,12 +490,64 @@ pub fn walk_files(base_dir: &Path, extensions: &[&str]) -> Vec<String> {
         if path.is_file() {
             if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
                 if extensions.contains(&ext) {
-                    files.push(path.to_string_lossy().to_string());
+                    files.push(normalize_path(&path.to_string_lossy()));
                 }
             }
         }
     }
 
+    dedup_linked_files(files)
+}
+
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+/// Identity of the file a path leads to, the same for every symlink and
+/// hardlink to it: its device and inode on Unix, its canonical path elsewhere.
+fn file_id(path: &Path) -> Option<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path).ok()?;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::canonicalize(path).ok()
+    }
+}
+
+/// Keep one path of each file reached through several symlinks or
+/// hardlinks, so it's discovered and diagnosed once. The path without
+/// symlinks is kept, or else the first in lexical order; diagnostics are
+/// published under its URI. Paths that can't be read are kept as they are.
+#[must_use]
+pub fn dedup_linked_files(file_paths: Vec<String>) -> Vec<String> {
+    // Symlinked paths sort after real ones, then by path
+    let preference = |path: &str| {
+        let real = std::fs::canonicalize(path)
+            .is_ok_and(|canonical| same_path(&canonical.to_string_lossy(), path));
+        (!real, path.to_string())
+    };
+
+    let mut kept: HashMap<FileId, usize> = HashMap::new();
+    let mut files: Vec<String> = Vec::with_capacity(file_paths.len());
+    for path in file_paths {
+        let Some(id) = file_id(Path::new(&path)) else {
+            files.push(path);
+            continue;
+        };
+        if let Some(&index) = kept.get(&id) {
+            if preference(&path) < preference(&files[index]) {
+                files[index] = path;
+            }
+        } else {
+            kept.insert(id, files.len());
+            files.push(path);
+        }
+    }
     files
 }
 
@@ -152,68 +555,329 @@ pub fn walk_files(base_dir: &Path, extensions: &[&str]) -> Vec<String> {
 #[must_use]
 pub fn extensions_for_test_kind(test_kind: &str) -> Vec<&'static str> {
     match test_kind {
-        "cargo-test" | "cargo-nextest" => vec!["rs"],
-        "jest" | "vitest" | "node-test" => vec!["js", "ts", "jsx", "tsx", "mjs"],
+        "cargo-test" | "cargo-nextest" | "cargo-criterion" => vec!["rs"],
+        "jest" | "vitest" | "node-test" | "bun-test" => vec!["js", "ts", "jsx", "tsx", "mjs"],
+        "playwright" => vec!["js", "ts", "mjs", "mts"],
+        "ava" => vec!["js", "mjs", "cjs"],
         "deno" => vec!["ts"],
         "go-test" => vec!["go"],
         "phpunit" => vec!["php"],
+        "swift-test" => vec!["swift"],
+        "gradle-test" => vec!["kt", "java"],
+        "maven-test" => vec!["java", "kt"],
+        "dart-test" => vec!["dart"],
+        "zig-test" => vec!["zig"],
+        "dune-test" => vec!["ml"],
+        "busted" => vec!["lua"],
+        "stack-test" | "cabal-test" => vec!["hs"],
+        "sbt-test" => vec!["scala"],
+        "nim-test" => vec!["nim"],
+        "julia-test" => vec!["jl"],
+        "bats" => vec!["bats"],
+        "catch2" => vec!["cpp", "cc", "cxx", "hpp"],
+        "pytest" => vec!["py"],
         _ => vec![],
     }
 }
 
-/// Determine if a particular file is the root of workspace based on marker
-/// files.
-fn detect_workspace_from_file(file_path: PathBuf, marker_files: &[&str]) -> Option<String> {
-    let parent = file_path.parent()?;
-    if marker_files
-        .iter()
-        .any(|file_name| parent.join(file_name).exists())
-    {
-        Some(parent.to_string_lossy().to_string())
-    } else {
-        detect_workspace_from_file(parent.to_path_buf(), marker_files)
-    }
+/// Directories above `path` holding one of `marker_files`, nearest first.
+/// Whether a directory holds one is kept in `has_marker`, for paths sharing
+/// directories.
+fn marker_dirs<'a>(
+    path: &'a Path,
+    marker_files: &'a [&str],
+    has_marker: &'a mut HashMap<PathBuf, bool>,
+) -> impl Iterator<Item = &'a Path> {
+    path.ancestors().skip(1).filter(move |dir| {
+        *has_marker.entry(dir.to_path_buf()).or_insert_with(|| {
+            marker_files
+                .iter()
+                .any(|file_name| dir.join(file_name).exists())
+        })
+    })
 }
 
 /// Detect workspaces from a list of file paths using marker files.
 ///
-/// Walks up the directory tree from each file looking for marker files
-/// (e.g., Cargo.toml, package.json) to determine workspace roots.
+/// Each file belongs to the nearest directory above it holding one of the
+/// marker files (e.g., Cargo.toml, package.json), so the files of a crate
+/// nested in a Cargo workspace are run in that crate. See
+/// [`enclosing_roots`] for the workspace around it.
 #[must_use]
 pub fn detect_from_files(file_paths: &[String], marker_files: &[&str]) -> Workspaces {
     let mut result_map: HashMap<String, Vec<String>> = HashMap::new();
-    let mut sorted_paths = file_paths.to_vec();
-    sorted_paths.sort_by_key(String::len);
+    let mut has_marker: HashMap<PathBuf, bool> = HashMap::new();
 
-    for file_path in sorted_paths {
-        let existing_workspace = result_map
-            .iter()
-            .find(|(workspace_root, _)| file_path.contains(workspace_root.as_str()));
+    for file_path in dedup_linked_files(file_paths.to_vec()) {
+        let Some(workspace) =
+            marker_dirs(Path::new(&file_path), marker_files, &mut has_marker).next()
+        else {
+            continue;
+        };
+        let files = result_map
+            .entry(workspace.to_string_lossy().to_string())
+            .or_default();
+        if !files.contains(&file_path) {
+            files.push(file_path);
+        }
+    }
 
-        if let Some((workspace_root, _)) = existing_workspace {
-            result_map
-                .entry(workspace_root.clone())
-                .or_default()
-                .push(file_path.clone());
+    Workspaces { map: result_map }
+}
+
+/// Outermost directory above each workspace that holds one of
+/// `marker_files` too, like the Cargo workspace a member crate belongs to,
+/// by workspace. Workspaces not nested in another are left out.
+#[must_use]
+pub fn enclosing_roots(
+    workspaces: &Workspaces,
+    marker_files: &[&str],
+) -> HashMap<WorkspacePath, WorkspacePath> {
+    let mut has_marker: HashMap<PathBuf, bool> = HashMap::new();
+    workspaces
+        .map
+        .keys()
+        .filter_map(|workspace| {
+            let root = marker_dirs(Path::new(workspace), marker_files, &mut has_marker)
+                .last()?
+                .to_string_lossy()
+                .to_string();
+            Some((workspace.clone(), root))
+        })
+        .collect()
+}
+
+/// Leave out the files of each workspace that belong to a project of another
+/// kind nested in it: files with a directory between them and the workspace
+/// root holding the markers of projects, none of them of `test_kind`. This
+/// keeps a Deno module inside a Jest package, or a Go module inside a Rust
+/// crate, from being tested by the outer project's adapter.
+#[must_use]
+pub fn scope_to_test_kind(workspaces: Workspaces, test_kind: &str) -> Workspaces {
+    let mut foreign_dirs: HashMap<PathBuf, bool> = HashMap::new();
+    let mut is_foreign = |dir: &Path| {
+        *foreign_dirs.entry(dir.to_path_buf()).or_insert_with(|| {
+            let projects = detect_projects(dir);
+            !projects.is_empty()
+                && projects
+                    .iter()
+                    .all(|project| project.test_kind != test_kind)
+        })
+    };
+    let map = workspaces
+        .map
+        .into_iter()
+        .map(|(workspace, paths)| {
+            let root = PathBuf::from(&workspace);
+            let paths = paths
+                .into_iter()
+                .filter(|path| {
+                    let path = Path::new(path);
+                    !path.starts_with(&root)
+                        || !path
+                            .ancestors()
+                            .skip(1)
+                            .take_while(|dir| *dir != root)
+                            .any(&mut is_foreign)
+                })
+                .collect();
+            (workspace, paths)
+        })
+        .filter(|(_, paths): &(String, Vec<String>)| !paths.is_empty())
+        .collect();
+    Workspaces { map }
+}
+
+/// Files under `dir` changed since the last commit, staged or not, as
+/// `git diff --name-only` and `git diff --name-only --cached` list them, or
+/// `None` when `dir` isn't in a git repository.
+#[must_use]
+pub fn changed_files(dir: &Path) -> Option<Vec<PathBuf>> {
+    let mut files = vec![];
+    for cached in [false, true] {
+        let mut git = Command::new("git");
+        git.current_dir(dir)
+            .args(["diff", "--name-only", "--relative"]);
+        if cached {
+            git.arg("--cached");
         }
+        let output = git.output().ok().filter(|output| output.status.success())?;
+        files.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| dir.join(line)),
+        );
+    }
+    files.sort();
+    files.dedup();
+    Some(files)
+}
+
+/// Read the `workspaces` globs declared in the `package.json` of a directory.
+///
+/// Supports both the array form and Yarn's `{ "packages": [...] }` form.
+fn package_json_workspaces(dir: &Path) -> Vec<String> {
+    let Ok(content) = std::fs::read_to_string(dir.join("package.json")) else {
+        return vec![];
+    };
+    let Ok(json) = serde_json::from_str::<Value>(&content) else {
+        return vec![];
+    };
+    let globs = match &json["workspaces"] {
+        Value::Array(globs) => globs,
+        Value::Object(workspaces) => match workspaces.get("packages") {
+            Some(Value::Array(globs)) => globs,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+    globs
+        .iter()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Resolve the member package directories of a monorepo root from the
+/// `workspaces` globs in its `package.json`.
+#[must_use]
+pub fn resolve_package_workspaces(root: &Path) -> Vec<PathBuf> {
+    let globs = package_json_workspaces(root);
+    if globs.is_empty() {
+        return vec![];
+    }
 
-        let workspace =
-            detect_workspace_from_file(PathBuf::from_str(&file_path).unwrap(), marker_files);
-        if let Some(workspace) = workspace
-            && result_map
-                .get(&workspace)
-                .is_none_or(|v| !v.contains(&file_path))
-        {
-            result_map
-                .entry(workspace)
-                .or_default()
-                .push(file_path.clone());
+    let mut builder = OverrideBuilder::new(root);
+    for glob in &globs {
+        let glob = glob.trim_start_matches("./").trim_end_matches('/');
+        if let Err(e) = builder.add(glob) {
+            log::warn!("Invalid workspaces glob '{glob}': {e}");
         }
     }
+    let matcher = match builder.build() {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            log::warn!("Failed to build workspaces matcher: {e}");
+            return vec![];
+        }
+    };
+
+    WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .filter_entry(|entry| entry.file_name() != "node_modules")
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+        .map(ignore::DirEntry::into_path)
+        .filter(|dir| {
+            dir != root
+                && dir.join("package.json").exists()
+                && matcher.matched(dir, true).is_whitelist()
+        })
+        .collect()
+}
+
+/// Detect JavaScript workspaces, honoring monorepo `workspaces` declarations.
+///
+/// Files below a `package.json` declaring `workspaces` are grouped under the
+/// deepest member package containing them, or under the monorepo root when no
+/// member does. All other files fall back to [`detect_from_files`].
+#[must_use]
+pub fn detect_package_workspaces(file_paths: &[String], marker_files: &[&str]) -> Workspaces {
+    // Monorepo root -> member packages, `None` for directories that aren't roots
+    let mut monorepo_cache: HashMap<PathBuf, Option<Vec<PathBuf>>> = HashMap::new();
+    let mut result_map: HashMap<String, Vec<String>> = HashMap::new();
+    let mut standalone_paths = Vec::new();
+
+    for file_path in file_paths {
+        let path = Path::new(file_path);
+        let monorepo = path.ancestors().skip(1).find_map(|dir| {
+            monorepo_cache
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| {
+                    (!package_json_workspaces(dir).is_empty())
+                        .then(|| resolve_package_workspaces(dir))
+                })
+                .clone()
+                .map(|members| (dir.to_path_buf(), members))
+        });
+
+        let Some((root, members)) = monorepo else {
+            standalone_paths.push(file_path.clone());
+            continue;
+        };
+
+        let package = members
+            .iter()
+            .filter(|member| path.starts_with(member))
+            .max_by_key(|member| member.components().count())
+            .unwrap_or(&root);
+        result_map
+            .entry(package.to_string_lossy().to_string())
+            .or_default()
+            .push(file_path.clone());
+    }
+
+    for (workspace, paths) in detect_from_files(&standalone_paths, marker_files).map {
+        result_map.entry(workspace).or_default().extend(paths);
+    }
 
     Workspaces { map: result_map }
 }
 
+/// Canonical form of a path for storing and comparing: forward slashes, no
+/// leading slash before a drive letter (as in `/C:/` taken from a URI) and a
+/// lowercase drive letter.
+#[must_use]
+pub fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = match path.strip_prefix('/') {
+        Some(rest) if has_drive_letter(rest) => rest,
+        _ => &path,
+    };
+    if has_drive_letter(path) {
+        path[..1].to_ascii_lowercase() + &path[1..]
+    } else {
+        path.to_string()
+    }
+}
+
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// Whether two paths name the same file, regardless of separators and, on
+/// Windows where file names are case-insensitive, of case.
+#[must_use]
+pub fn same_path(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_path(a), normalize_path(b));
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(&b)
+    } else {
+        a == b
+    }
+}
+
+/// Whether two paths lead to the same file, compared with [`same_path`] and,
+/// failing that, by their canonical forms, which resolve symlinks.
+#[must_use]
+pub fn same_file(a: &str, b: &str) -> bool {
+    same_path(a, b)
+        || matches!(
+            (std::fs::canonicalize(a), std::fs::canonicalize(b)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// Whether `path` is one of `paths`, compared with [`same_path`].
+#[must_use]
+pub fn contains_path(paths: &[String], path: &str) -> bool {
+    paths.iter().any(|candidate| same_path(candidate, path))
+}
+
 /// Resolve a relative path against a base directory, handling ../ and ./
 /// components.
 #[must_use]
@@ -230,10 +894,10 @@ pub fn resolve_path(base_dir: &Path, relative_path: &str) -> PathBuf {
             Component::ParentDir => {
                 components.pop();
             }
-            Component::Normal(_) | Component::RootDir => {
+            Component::Prefix(_) | Component::Normal(_) | Component::RootDir => {
                 components.push(component);
             }
-            _ => {}
+            Component::CurDir => {}
         }
     }
 
@@ -300,4 +964,363 @@ mod tests {
             paths[0]
         );
     }
+
+    #[test]
+    fn test_scope_to_test_kind_leaves_out_nested_projects() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for dir in ["src", "tools/gen", "web", "edge/lib"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"demo\"\n").unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{ "devDependencies": { "jest": "^29.0.0" } }"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("tools/gen/go.mod"), "module gen\n").unwrap();
+        std::fs::write(root.join("edge/deno.json"), "{}").unwrap();
+        let file = |path: &str| root.join(path).to_string_lossy().to_string();
+        let rust_files = [file("src/lib.rs"), file("tools/gen/bindings.rs")];
+        let ts_files = [
+            file("web/app.test.ts"),
+            file("edge/lib/mod_test.ts"),
+            file("edge/main_test.ts"),
+        ];
+        for path in rust_files.iter().chain(&ts_files) {
+            std::fs::write(path, "").unwrap();
+        }
+
+        let cargo = scope_to_test_kind(
+            detect_from_files(&rust_files, &["Cargo.toml"]),
+            "cargo-test",
+        );
+        assert_eq!(cargo.map.len(), 1);
+        assert_eq!(
+            cargo.map.values().next().unwrap(),
+            &vec![file("src/lib.rs")]
+        );
+
+        let jest = scope_to_test_kind(detect_from_files(&ts_files, &["package.json"]), "jest");
+        assert_eq!(
+            jest.map.values().next().unwrap(),
+            &vec![file("web/app.test.ts")]
+        );
+        let deno = scope_to_test_kind(detect_from_files(&ts_files, &["deno.json"]), "deno");
+        let mut deno_files = deno.map.values().next().unwrap().clone();
+        deno_files.sort();
+        assert_eq!(
+            deno_files,
+            vec![file("edge/lib/mod_test.ts"), file("edge/main_test.ts")]
+        );
+    }
+
+    #[test]
+    fn test_walk_files_max_depth() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("top.rs"), "").unwrap();
+        std::fs::write(root.join("a/mid.rs"), "").unwrap();
+        std::fs::write(root.join("a/b/deep.rs"), "").unwrap();
+
+        assert_eq!(walk_files(root, &["rs"], None, false).len(), 3);
+        let shallow = walk_files(root, &["rs"], Some(2), false);
+        assert_eq!(shallow.len(), 2);
+        assert!(shallow.iter().all(|f| !f.ends_with("deep.rs")));
+    }
+
+    #[test]
+    fn test_walk_files_respects_ignore_file() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        std::fs::create_dir_all(root.join("vendor")).unwrap();
+        std::fs::write(root.join("lib.rs"), "").unwrap();
+        std::fs::write(root.join("flaky.rs"), "").unwrap();
+        std::fs::write(root.join("vendor/dep.rs"), "").unwrap();
+        std::fs::write(root.join(IGNORE_FILE_NAME), "flaky.rs\nvendor/\n").unwrap();
+
+        let files = walk_files(root, &["rs"], None, false);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("lib.rs"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_files_follow_symlinks() {
+        let root = tempfile::tempdir().unwrap();
+        let linked = tempfile::tempdir().unwrap();
+        std::fs::write(linked.path().join("linked.rs"), "").unwrap();
+        std::os::unix::fs::symlink(linked.path(), root.path().join("src")).unwrap();
+
+        assert!(walk_files(root.path(), &["rs"], None, false).is_empty());
+        let files = walk_files(root.path(), &["rs"], None, true);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("src/linked.rs"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_linked_files_listed_once() {
+        let root = tempfile::tempdir().unwrap();
+        let src = root.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(root.path().join("Cargo.toml"), "").unwrap();
+        std::fs::write(src.join("lib.rs"), "").unwrap();
+        std::os::unix::fs::symlink(src.join("lib.rs"), src.join("alias.rs")).unwrap();
+        std::fs::hard_link(src.join("lib.rs"), src.join("more.rs")).unwrap();
+        std::fs::write(src.join("other.rs"), "").unwrap();
+
+        let mut files = walk_files(root.path(), &["rs"], None, false);
+        files.sort();
+        let names: Vec<&str> = files
+            .iter()
+            .map(|path| path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["lib.rs", "other.rs"]);
+
+        // The symlink is listed first, but the real path is kept
+        let alias = src.join("alias.rs").to_string_lossy().to_string();
+        let workspaces = detect_from_files(&[alias, files[0].clone()], &["Cargo.toml"]);
+        let paths: Vec<&String> = workspaces.map.values().flatten().collect();
+        assert_eq!(paths, vec![&files[0]]);
+    }
+
+    #[test]
+    fn test_nested_crate_detection() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let write = |relative: &str| {
+            let path = root.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "").unwrap();
+            path.to_string_lossy().to_string()
+        };
+        write("ws/Cargo.toml");
+        write("ws/crates/a/Cargo.toml");
+        write("ws-extra/Cargo.toml");
+        let ws_lib = write("ws/src/lib.rs");
+        let a_lib = write("ws/crates/a/src/lib.rs");
+        // A string prefix of `ws/`, but not below it
+        let extra_lib = write("ws-extra/src/lib.rs");
+
+        let workspaces = detect_from_files(
+            &[ws_lib.clone(), a_lib.clone(), extra_lib.clone()],
+            &["Cargo.toml"],
+        );
+        let dir = |relative: &str| root.join(relative).to_string_lossy().to_string();
+        assert_eq!(workspaces.map.len(), 3);
+        assert_eq!(workspaces.map[&dir("ws")], vec![ws_lib]);
+        assert_eq!(workspaces.map[&dir("ws/crates/a")], vec![a_lib]);
+        assert_eq!(workspaces.map[&dir("ws-extra")], vec![extra_lib]);
+
+        let roots = enclosing_roots(&workspaces, &["Cargo.toml"]);
+        assert_eq!(roots.len(), 1, "{roots:?}");
+        assert_eq!(roots[&dir("ws/crates/a")], dir("ws"));
+    }
+
+    #[test]
+    fn test_package_workspaces_detection() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let write = |relative: &str, content: &str| {
+            let path = root.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path.to_string_lossy().to_string()
+        };
+
+        write("package.json", r#"{ "workspaces": ["packages/*"] }"#);
+        write("packages/a/package.json", "{}");
+        write("packages/b/package.json", "{}");
+        let root_test = write("root.test.js", "");
+        let a_test = write("packages/a/a.test.js", "");
+        let b_test = write("packages/b/src/b.test.js", "");
+
+        let mut members = resolve_package_workspaces(root);
+        members.sort();
+        assert_eq!(
+            members,
+            vec![root.join("packages/a"), root.join("packages/b")]
+        );
+
+        let workspaces = detect_package_workspaces(
+            &[root_test.clone(), a_test.clone(), b_test.clone()],
+            &["package.json"],
+        );
+        let workspace_of = |dir: &Path| workspaces.map.get(dir.to_str().unwrap()).cloned();
+        assert_eq!(workspaces.map.len(), 3);
+        assert_eq!(workspace_of(root), Some(vec![root_test]));
+        assert_eq!(workspace_of(&root.join("packages/a")), Some(vec![a_test]));
+        assert_eq!(workspace_of(&root.join("packages/b")), Some(vec![b_test]));
+    }
+
+    #[test]
+    fn test_detect_cmake_project() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/ctest"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "ctest");
+
+        let config = config_from_detected(&projects[0]);
+        assert!(config.include.contains(&"**/CMakeLists.txt".to_string()));
+
+        let projects = detect_projects(&current_dir().unwrap().join("demo/catch2"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "catch2");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/*.{cpp,cc,cxx,hpp}".to_string()]);
+    }
+
+    #[test]
+    fn test_normalize_mixed_separators() {
+        assert_eq!(
+            normalize_path(r"C:\proj\src/lib.rs"),
+            "c:/proj/src/lib.rs".to_string()
+        );
+        assert_eq!(normalize_path("/C:/proj/src/lib.rs"), "c:/proj/src/lib.rs");
+        assert_eq!(normalize_path("/home/demo/lib.rs"), "/home/demo/lib.rs");
+
+        let paths = vec!["c:/proj/src/lib.rs".to_string()];
+        assert!(contains_path(&paths, r"C:\proj\src\lib.rs"));
+        assert!(contains_path(&paths, "/C:/proj/src/lib.rs"));
+        assert!(!contains_path(&paths, r"C:\proj\src\main.rs"));
+        assert!(same_path(r"src\lib.rs", "src/lib.rs"));
+    }
+
+    #[test]
+    fn test_detect_swift_package() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/swift"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "swift-test");
+        assert_eq!(extensions_for_test_kind("swift-test"), vec!["swift"]);
+    }
+
+    #[test]
+    fn test_detect_gradle_build() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/gradle"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "gradle-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(
+            config.include,
+            vec!["**/src/test/**/*.kt", "**/src/test/**/*.java"]
+        );
+        assert_eq!(extensions_for_test_kind("gradle-test"), vec!["kt", "java"]);
+    }
+
+    #[test]
+    fn test_detect_maven_project() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/maven"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "maven-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.exclude, vec!["**/target/**"]);
+        assert_eq!(extensions_for_test_kind("maven-test"), vec!["java", "kt"]);
+    }
+
+    #[test]
+    fn test_detect_dart_package() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/dart"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "dart-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/test/**/*_test.dart"]);
+        assert_eq!(extensions_for_test_kind("dart-test"), vec!["dart"]);
+    }
+
+    #[test]
+    fn test_detect_zig_build() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/zig"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "zig-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/*.zig"]);
+        assert_eq!(extensions_for_test_kind("zig-test"), vec!["zig"]);
+    }
+
+    #[test]
+    fn test_detect_dune_project() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/dune"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "dune-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.exclude, vec!["**/_build/**", "**/_opam/**"]);
+        assert_eq!(extensions_for_test_kind("dune-test"), vec!["ml"]);
+    }
+
+    #[test]
+    fn test_detect_busted() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/busted"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "busted");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/*_spec.lua"]);
+        assert_eq!(extensions_for_test_kind("busted"), vec!["lua"]);
+    }
+
+    #[test]
+    fn test_detect_haskell() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/stack"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "stack-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/test/**/*.hs"]);
+        assert_eq!(extensions_for_test_kind("cabal-test"), vec!["hs"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("demo.cabal"), "name: demo\n").unwrap();
+        let projects = detect_projects(dir.path());
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "cabal-test");
+    }
+
+    #[test]
+    fn test_detect_sbt_build() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/sbt"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "sbt-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/src/test/**/*.scala"]);
+        assert_eq!(extensions_for_test_kind("sbt-test"), vec!["scala"]);
+    }
+
+    #[test]
+    fn test_detect_nimble_package() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/nimble"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "nim-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/tests/**/*.nim"]);
+        assert_eq!(extensions_for_test_kind("nim-test"), vec!["nim"]);
+    }
+
+    #[test]
+    fn test_detect_julia_package() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/julia"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "julia-test");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/test/**/*.jl"]);
+        assert_eq!(extensions_for_test_kind("julia-test"), vec!["jl"]);
+    }
+
+    #[test]
+    fn test_detect_bats_project() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/bats"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "bats");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/*.bats"]);
+        assert_eq!(extensions_for_test_kind("bats"), vec!["bats"]);
+    }
+
+    #[test]
+    fn test_detect_pytest_project() {
+        let projects = detect_projects(&current_dir().unwrap().join("demo/pytest"));
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].test_kind, "pytest");
+        let config = config_from_detected(&projects[0]);
+        assert_eq!(config.include, vec!["**/test_*.py", "**/*_test.py"]);
+        assert_eq!(extensions_for_test_kind("pytest"), vec!["py"]);
+    }
 }
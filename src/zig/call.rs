@@ -0,0 +1,41 @@
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use crate::{
+    error::LSError,
+    log::write_result_log,
+    runner::{RunContext, test_command},
+};
+
+/// Command running the tests, with `zig build test` in a workspace with a
+/// `build.zig` and `zig test` on the given file otherwise. Only `zig test`
+/// takes a name filter; the `test` step of a build runs every test.
+pub fn zig_test_command(
+    context: &RunContext,
+    file_paths: &[String],
+    extra_args: &[String],
+    command: Option<&[String]>,
+    filter: Option<&str>,
+) -> Result<Command, LSError> {
+    if Path::new(context.workspace).join("build.zig").exists() {
+        let mut zig = test_command(context, command, &["zig", "build", "test"])?;
+        zig.args(extra_args);
+        return Ok(zig);
+    }
+    let mut zig = test_command(context, command, &["zig", "test"])?;
+    zig.args(file_paths).args(extra_args);
+    if let Some(filter) = filter {
+        zig.arg("--test-filter").arg(filter);
+    }
+    Ok(zig)
+}
+
+/// Run a `zig build test` or `zig test` command.
+pub fn run_zig_test(command: &mut Command) -> Result<Output, LSError> {
+    let output = command.output()?;
+
+    write_result_log("zig_test.log", &output)?;
+    Ok(output)
+}
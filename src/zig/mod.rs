@@ -0,0 +1,164 @@
+//! Zig tests run by `zig build test`, or `zig test` outside of a build.
+//!
+//! Tests are the `test "name" { ... }` blocks of a file, and the doctests
+//! `test decl { ... }` named after a declaration. No Zig grammar is bundled,
+//! so they are found by scanning the source, balancing braces to find where
+//! blocks end.
+
+pub mod call;
+pub mod parse;
+
+use std::{path::Path, process::Command};
+
+use lsp_types::{Position, Range};
+use regex::Regex;
+use tree_sitter::{Language, Tree};
+
+use crate::{
+    Diagnostics, DiscoveredTests, MAX_CHAR_LENGTH, TestItem, Workspaces,
+    error::LSError,
+    runner::{RunContext, Runner, discover_files},
+    source::{block_end, position_at},
+};
+
+/// Tests of a file, with ids like `adds` as written after `test`, which is
+/// how failures name them after their containers.
+fn discover_in_source(file_path: &str, source: &str) -> Vec<TestItem> {
+    let test_re =
+        Regex::new(r#"(?m)^[ \t]*test\s+(?:"((?:[^"\\\n]|\\.)*)"|([A-Za-z_]\w*))\s*\{"#).unwrap();
+
+    test_re
+        .captures_iter(source)
+        .map(|m| {
+            let found = m.get(0).unwrap();
+            let name = m.get(1).or_else(|| m.get(2)).unwrap().as_str();
+            let keyword = found.start() + found.as_str().find("test").unwrap();
+            let (line, column) = position_at(source, keyword);
+            let end = position_at(source, block_end(source, found.end() - 1));
+            TestItem {
+                id: name.to_string(),
+                name: name.to_string(),
+                path: file_path.to_string(),
+                start_position: Range::new(
+                    Position::new(line, column),
+                    Position::new(line, MAX_CHAR_LENGTH),
+                ),
+                end_position: Range::new(Position::new(end.0, 0), Position::new(end.0, end.1)),
+            }
+        })
+        .collect()
+}
+
+fn discover_tests(file_path: &str) -> Result<Vec<TestItem>, LSError> {
+    let source = std::fs::read_to_string(file_path)?;
+    Ok(discover_in_source(file_path, &source))
+}
+
+fn discover_all(file_paths: &[String]) -> Vec<TestItem> {
+    file_paths
+        .iter()
+        .filter_map(|path| discover_tests(path).ok())
+        .flatten()
+        .collect()
+}
+
+fn diagnostics(contents: &str, workspace: &str, file_paths: &[String]) -> Diagnostics {
+    parse::to_diagnostics(
+        &parse::parse_zig_output(contents),
+        Path::new(workspace),
+        file_paths,
+        &discover_all(file_paths),
+    )
+}
+
+#[derive(Eq, PartialEq, Hash, Debug)]
+pub struct ZigTestRunner;
+
+impl Runner for ZigTestRunner {
+    fn discover(&self, file_paths: &[String]) -> Result<DiscoveredTests, LSError> {
+        Ok(discover_files(file_paths, discover_tests))
+    }
+
+    fn language(&self) -> Option<Language> {
+        None
+    }
+
+    fn discover_tree(
+        &self,
+        file_path: &str,
+        source: &str,
+        _tree: &Tree,
+    ) -> Result<Vec<TestItem>, LSError> {
+        self.discover_source(file_path, source)
+    }
+
+    fn discover_source(&self, file_path: &str, source: &str) -> Result<Vec<TestItem>, LSError> {
+        Ok(discover_in_source(file_path, source))
+    }
+
+    fn build_command(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Command, LSError> {
+        call::zig_test_command(context, file_paths, extra_args, command, filter)
+    }
+
+    fn run_tests(
+        &self,
+        file_paths: &[String],
+        context: &RunContext,
+        extra_args: &[String],
+        command: Option<&[String]>,
+        filter: Option<&str>,
+    ) -> Result<Diagnostics, LSError> {
+        let output = call::run_zig_test(
+            &mut self.build_command(file_paths, context, extra_args, command, filter)?,
+        )?;
+        // Both `zig build test` and `zig test` report on stderr
+        let contents = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(diagnostics(&contents, context.workspace, file_paths))
+    }
+
+    fn parse_results(
+        &self,
+        results_path: &Path,
+        file_paths: &[String],
+        workspace: &str,
+    ) -> Result<Diagnostics, LSError> {
+        Ok(diagnostics(
+            &std::fs::read_to_string(results_path)?,
+            workspace,
+            file_paths,
+        ))
+    }
+
+    fn detect_workspaces(&self, file_paths: &[String]) -> Workspaces {
+        crate::workspace::detect_from_files(file_paths, &["build.zig"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_in_source() {
+        let items = discover_tests("demo/zig/src/main.zig").unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["adds", "fails on purpose", "nested adds", "add"]);
+        assert_eq!(items[1].start_position.start, Position::new(10, 0));
+        assert_eq!(items[1].end_position.end, Position::new(12, 1));
+        assert_eq!(items[2].start_position.start, Position::new(15, 4));
+
+        let items = discover_in_source("main.zig", "const s = \"test \\\"x\\\" {\";");
+        assert!(items.is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use std::{collections::HashMap, path::Path};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use regex::Regex;
+
+use crate::{
+    Diagnostics, FileDiagnostics, MAX_CHAR_LENGTH, TestItem,
+    workspace::{resolve_path, same_path},
+};
+
+/// A failed test, or a compile error when `name` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZigFailure {
+    /// Full name as Zig reports it, like `main.test.fails on purpose`
+    pub name: Option<String>,
+    pub message: String,
+    /// File, line and column of each stack frame, innermost first, or of the
+    /// compile error
+    pub frames: Vec<(String, u32, u32)>,
+}
+
+/// Name of a test as written after `test`, from its full name: the part
+/// after the containers and `test.` (or `decltest.` for doctests).
+#[must_use]
+pub fn test_name(full_name: &str) -> &str {
+    [".test.", ".decltest."]
+        .iter()
+        .filter_map(|marker| {
+            full_name
+                .find(marker)
+                .map(|index| (index, &full_name[index + marker.len()..]))
+        })
+        .min_by_key(|(index, _)| *index)
+        .map_or(full_name, |(_, name)| name)
+}
+
+/// Parse failed tests and compile errors from the output of `zig build test`
+/// or `zig test`.
+///
+/// A failure starts at `error: '<name>' failed:` (`zig build test`) or at
+/// `FAIL (<error>)` after the `1/4 <name>...` progress line of the test
+/// (`zig test`), and its stack trace has frames like
+/// `/src/main.zig:12:5: 0x1038e25 in test.adds (test)`. Compile errors are
+/// lines like `src/main.zig:8:46: error: <message>`.
+#[must_use]
+pub fn parse_zig_output(contents: &str) -> Vec<ZigFailure> {
+    let header_re = Regex::new(r"^error: '(.+)' failed:\s*(.*)$").unwrap();
+    let progress_re = Regex::new(r"^\d+/\d+ (.+?)\.\.\.(.*)$").unwrap();
+    let fail_re = Regex::new(r"^FAIL \((\w+)\)").unwrap();
+    let frame_re = Regex::new(r"^(\S.*\.zig):(\d+):(\d+): 0x[0-9a-f]+ in ").unwrap();
+    let compile_re = Regex::new(r"^(\S.*\.zig):(\d+):(\d+): error: (.+)$").unwrap();
+    let location = |m: &regex::Captures| -> Option<(String, u32, u32)> {
+        Some((m[1].to_string(), m[2].parse().ok()?, m[3].parse().ok()?))
+    };
+
+    let mut failures = vec![];
+    let mut current: Option<ZigFailure> = None;
+    // Name and output of the test `zig test` is running
+    let mut running: Option<(String, Vec<String>)> = None;
+
+    for line in contents.lines() {
+        if let Some(m) = header_re.captures(line) {
+            failures.extend(current.take());
+            current = Some(ZigFailure {
+                name: Some(m[1].to_string()),
+                message: m[2].to_string(),
+                frames: vec![],
+            });
+        } else if let Some(m) = progress_re.captures(line) {
+            failures.extend(current.take());
+            let output = m[2].to_string();
+            running = (!matches!(output.as_str(), "OK" | "SKIP"))
+                .then(|| (m[1].to_string(), vec![output]));
+        } else if let Some(m) = fail_re.captures(line) {
+            failures.extend(current.take());
+            let Some((name, mut output)) = running.take() else {
+                continue;
+            };
+            output.retain(|line| !line.is_empty());
+            if output.is_empty() {
+                output.push(m[1].to_string());
+            }
+            current = Some(ZigFailure {
+                name: Some(name),
+                message: output.join("\n"),
+                frames: vec![],
+            });
+        } else if let Some(frame) = frame_re.captures(line).as_ref().and_then(location) {
+            if let Some(failure) = current.as_mut() {
+                failure.frames.push(frame);
+            }
+        } else if let Some(m) = compile_re.captures(line) {
+            failures.extend(current.take());
+            if let Some(frame) = location(&m) {
+                failures.push(ZigFailure {
+                    name: None,
+                    message: m[4].to_string(),
+                    frames: vec![frame],
+                });
+            }
+        } else if line.starts_with("error: ") {
+            failures.extend(current.take());
+        } else if let Some((_, output)) = running.as_mut() {
+            output.push(line.to_string());
+        } else if let Some(failure) = current.as_mut().filter(|failure| failure.frames.is_empty()) {
+            // The expectation `zig build test` prints below the header
+            if !failure.message.is_empty() {
+                failure.message.push('\n');
+            }
+            failure.message.push_str(line.trim());
+        }
+    }
+    failures.extend(current);
+    failures
+}
+
+fn error_diagnostic(range: Range, message: String, code: &str) -> Diagnostic {
+    Diagnostic {
+        range,
+        message,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("zig-test".to_string()),
+        code: Some(NumberOrString::String(code.to_string())),
+        ..Diagnostic::default()
+    }
+}
+
+/// Diagnostics for failed tests and compile errors.
+///
+/// Failures are placed at the innermost stack frame in one of `file_paths`
+/// and linked to the definition of the test, or at the definition when no
+/// frame is in these files. Compile errors are placed where they occurred.
+#[must_use]
+pub fn to_diagnostics(
+    failures: &[ZigFailure],
+    root: &Path,
+    file_paths: &[String],
+    test_items: &[TestItem],
+) -> Diagnostics {
+    let mut result_map: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for failure in failures {
+        let frame = failure.frames.iter().find_map(|(path, line, column)| {
+            let resolved = resolve_path(root, path).to_string_lossy().to_string();
+            let path = file_paths.iter().find(|path| same_path(path, &resolved))?;
+            let range = Range::new(
+                Position::new(line.saturating_sub(1), column.saturating_sub(1)),
+                Position::new(line.saturating_sub(1), MAX_CHAR_LENGTH),
+            );
+            Some((path, range))
+        });
+
+        let Some(full_name) = &failure.name else {
+            if let Some((path, range)) = frame {
+                result_map
+                    .entry(path.clone())
+                    .or_default()
+                    .push(error_diagnostic(
+                        range,
+                        failure.message.clone(),
+                        "zig-compile-error",
+                    ));
+            }
+            continue;
+        };
+
+        let name = test_name(full_name);
+        let matching = |item: &&TestItem| item.id == name;
+        let test_item = frame
+            .and_then(|(path, _)| {
+                test_items
+                    .iter()
+                    .filter(matching)
+                    .find(|item| same_path(&item.path, path))
+            })
+            .or_else(|| test_items.iter().find(matching));
+
+        if let Some((path, range)) = frame {
+            result_map
+                .entry(path.clone())
+                .or_default()
+                .push(Diagnostic {
+                    related_information: test_item
+                        .and_then(TestItem::definition_info)
+                        .map(|info| vec![info]),
+                    ..error_diagnostic(
+                        range,
+                        format!("`{name}` failed: {}", failure.message),
+                        "zig-test-failed",
+                    )
+                });
+        } else if let Some(test_item) =
+            test_item.filter(|item| file_paths.iter().any(|path| same_path(path, &item.path)))
+        {
+            result_map
+                .entry(test_item.path.clone())
+                .or_default()
+                .push(error_diagnostic(
+                    test_item.start_position,
+                    format!("`{name}` failed: {}", failure.message),
+                    "zig-test-failed",
+                ));
+        }
+    }
+
+    Diagnostics {
+        files: result_map
+            .into_iter()
+            .map(|(path, diagnostics)| FileDiagnostics { path, diagnostics })
+            .collect(),
+        messages: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_zig_output() {
+        let contents = std::fs::read_to_string("demo/zig/test-output.txt").unwrap();
+        let failures = parse_zig_output(&contents);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].name.as_deref(),
+            Some("main.test.fails on purpose")
+        );
+        assert_eq!(failures[0].message, "expected 4, found 3");
+        assert_eq!(failures[0].frames.len(), 2);
+        assert_eq!(test_name("main.test.fails on purpose"), "fails on purpose");
+        assert_eq!(test_name("main.Nested.test.nested adds"), "nested adds");
+        assert_eq!(test_name("main.decltest.add"), "add");
+
+        let root = Path::new("/home/demo/zig");
+        let file_path = "/home/demo/zig/src/main.zig".to_string();
+        let test_item = TestItem {
+            id: "fails on purpose".to_string(),
+            name: "fails on purpose".to_string(),
+            path: file_path.clone(),
+            start_position: Range::new(Position::new(10, 0), Position::new(10, MAX_CHAR_LENGTH)),
+            end_position: Range::new(Position::new(12, 0), Position::new(12, 1)),
+        };
+        let diagnostics = to_diagnostics(
+            &failures,
+            root,
+            std::slice::from_ref(&file_path),
+            std::slice::from_ref(&test_item),
+        );
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(11, 4));
+        assert_eq!(
+            diagnostic.message,
+            "`fails on purpose` failed: expected 4, found 3"
+        );
+        assert!(diagnostic.related_information.is_some());
+
+        // `zig test` reports the test on its progress line
+        let contents = "1/2 main.test.adds...OK\n2/2 main.test.fails on purpose...expected 4, \
+                        found 3\nFAIL (TestExpectedEqual)\n/home/demo/zig/src/main.zig:12:5: 0x10 \
+                        in test.fails on purpose (test)\n1 passed; 1 failed.\n";
+        let progress_failures = parse_zig_output(contents);
+        assert_eq!(progress_failures.len(), 1);
+        assert_eq!(progress_failures[0].name, failures[0].name);
+        assert_eq!(progress_failures[0].message, failures[0].message);
+        assert_eq!(progress_failures[0].frames, failures[0].frames[1..]);
+    }
+
+    #[test]
+    fn test_parse_zig_compile_error() {
+        let contents = std::fs::read_to_string("demo/zig/compile-error.txt").unwrap();
+        let failures = parse_zig_output(&contents);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, None);
+
+        let file_path = "/home/demo/zig/src/main.zig".to_string();
+        let diagnostics = to_diagnostics(&failures, Path::new("/home/demo/zig"), &[file_path], &[]);
+        let diagnostic = &diagnostics.files[0].diagnostics[0];
+        assert_eq!(diagnostic.range.start, Position::new(7, 45));
+        assert_eq!(diagnostic.message, "use of undeclared identifier 'ad'");
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("zig-compile-error".to_string()))
+        );
+    }
+}
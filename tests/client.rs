@@ -198,6 +198,12 @@ impl SessionResult {
             self.stderr_contains("No project detected"),
             "Expected 'No project detected' message"
         );
+        assert!(
+            self.responses
+                .iter()
+                .any(|r| r.contains("window/showMessage") && r.contains("No test project detected")),
+            "Expected a 'No test project detected' notification"
+        );
     }
 
     /// Assert exact number of diagnostics